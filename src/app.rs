@@ -1,18 +1,39 @@
 use crate::{
-    commander::Commander,
-    env::Env,
+    commander::{
+        is_stale_working_copy_error,
+        log::{Head, StackEntry},
+        Commander,
+    },
+    env::{DiffFormat, DiffFormatScope, Env},
+    events,
+    health::get_health_warnings,
     ui::{
-        bookmarks_tab::BookmarksTab, command_log_tab::CommandLogTab, files_tab::FilesTab,
-        log_tab::LogTab, Component, ComponentAction,
+        bookmarks_tab::BookmarksTab,
+        command_log_tab::CommandLogTab,
+        command_palette::CommandPalette,
+        confirm_popup::ConfirmAction,
+        files_tab::FilesTab,
+        health_popup::HealthPopup,
+        log_tab::LogTab,
+        message_popup::MessagePopup,
+        sparse_reset_popup::SparseResetPopup,
+        stack_breadcrumb::get_adjacent_entry,
+        stale_view_popup::StaleViewPopup,
+        stale_workspace_popup::StaleWorkspacePopup,
+        utils::{is_on_panel_split, split_percent_at, DEFAULT_SPLIT_PERCENT},
+        Component, ComponentAction, FocusedPanel,
     },
     ComponentInputResult,
 };
 use anyhow::{anyhow, Result};
 use core::fmt;
-use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind};
+use ratatui::{layout::Rect, text::Line};
+use serde::Serialize;
 use tracing::{info, info_span};
 
-#[derive(PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Tab {
     Log,
     Files,
@@ -35,6 +56,24 @@ impl Tab {
     pub const VALUES: [Self; 4] = [Tab::Log, Tab::Files, Tab::Bookmarks, Tab::CommandLog];
 }
 
+/// How `]`/`[` move the working copy with `jj next`/`jj prev`.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum NavigationMode {
+    // Create a new change on top of the next/previous change
+    New,
+    // Edit the next/previous change directly
+    Edit,
+}
+
+impl fmt::Display for NavigationMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NavigationMode::New => write!(f, "new"),
+            NavigationMode::Edit => write!(f, "edit"),
+        }
+    }
+}
+
 pub struct App<'a> {
     pub env: Env,
     pub current_tab: Tab,
@@ -43,10 +82,96 @@ pub struct App<'a> {
     pub bookmarks: Option<BookmarksTab<'a>>,
     pub command_log: Option<CommandLogTab>,
     pub popup: Option<Box<dyn Component>>,
+    // Popup shown once the current one is closed, such as the health warnings after the config
+    // warnings at startup
+    next_popup: Option<Box<dyn Component>>,
+    // Head last selected in the log, followed by the files tab
+    pub active_head: Option<Head>,
+    // Panel shown full screen, toggled with Ctrl+z
+    pub maximized_panel: Option<(Tab, FocusedPanel)>,
+    // Width of the tabs' left panel in percent, changed by dragging the border between panels
+    pub split_percent: u16,
+    dragging_split: bool,
+    // Area of the current tab in the last draw
+    pub tab_area: Rect,
+    // Toggled with Ctrl+n
+    pub navigation_mode: NavigationMode,
+    // jj command to run with the TUI suspended, run by the main loop
+    pub external_command: Option<Vec<String>>,
+    // Output to print with the TUI suspended, printed by the main loop
+    pub print_output: Option<String>,
+    // Operation before suspending for an external command
+    suspended_operation: Option<String>,
+    // Whether the stale working copy popup was shown, since the last successful update
+    stale_prompted: bool,
+    // Changes from trunk to the working copy, shown in the header if enabled
+    pub stack: Vec<StackEntry>,
+    // Refresh the stack was fetched at, to only fetch it again on the next refresh or tab switch
+    stack_refresh_count: Option<u64>,
+    // Diff format of all tabs, when `lazyjj.diff-format-scope` is global
+    diff_format: Option<DiffFormat>,
+    // Revision to select in the log tab when it's opened, from `--revision`
+    initial_revision: Option<String>,
+    pub should_quit: bool,
+}
+
+// Whether the repository changed between two operations. Unknown operations aren't compared
+fn operation_changed(before: Option<&str>, after: Option<&str>) -> bool {
+    match (before, after) {
+        (Some(before), Some(after)) => before != after,
+        _ => false,
+    }
+}
+
+// Ctrl+Shift+R, kept distinct from refreshing with `R`. Terminals report Shift with an uppercase
+// character, the Shift modifier, or both
+fn is_sparse_reset_key(event: &Event) -> bool {
+    match event {
+        Event::Key(key) => {
+            key.kind == event::KeyEventKind::Press
+                && key.modifiers.contains(KeyModifiers::CONTROL)
+                && (key.code == KeyCode::Char('R')
+                    || (key.code == KeyCode::Char('r')
+                        && key.modifiers.contains(KeyModifiers::SHIFT)))
+        }
+        _ => false,
+    }
 }
 
 impl<'a> App<'a> {
-    pub fn new(env: Env) -> Result<App<'a>> {
+    pub fn new(env: Env, initial_revision: Option<String>) -> Result<App<'a>> {
+        // Warn once at startup about invalid config values, then about jj config which works
+        // badly with lazyjj
+        let health_warnings = get_health_warnings(&env.config);
+        let health_popup: Option<Box<dyn Component>> = if health_warnings.is_empty() {
+            None
+        } else {
+            Some(Box::new(HealthPopup::new(
+                health_warnings,
+                env.config.dismissed_warnings(),
+            )))
+        };
+        let (popup, next_popup): (Option<Box<dyn Component>>, _) =
+            if !env.config_warnings.is_empty() {
+                let config_popup = Box::new(MessagePopup {
+                    title: "Config warnings".into(),
+                    messages: std::iter::once(
+                        "Some config was skipped, so defaults are used instead:".to_owned(),
+                    )
+                    .chain(
+                        env.config_warnings
+                            .iter()
+                            .map(|warning| format!("- {warning}")),
+                    )
+                    .map(Line::raw)
+                    .collect::<Vec<_>>()
+                    .into(),
+                });
+                (Some(config_popup), health_popup)
+            } else {
+                (health_popup, None)
+            };
+
         Ok(App {
             env,
             current_tab: Tab::Log,
@@ -54,7 +179,23 @@ impl<'a> App<'a> {
             files: None,
             bookmarks: None,
             command_log: None,
-            popup: None,
+            popup,
+            next_popup,
+            active_head: None,
+            maximized_panel: None,
+            split_percent: DEFAULT_SPLIT_PERCENT,
+            dragging_split: false,
+            tab_area: Rect::default(),
+            navigation_mode: NavigationMode::New,
+            external_command: None,
+            print_output: None,
+            suspended_operation: None,
+            stale_prompted: false,
+            stack: vec![],
+            stack_refresh_count: None,
+            diff_format: None,
+            initial_revision,
+            should_quit: false,
         })
     }
 
@@ -64,6 +205,215 @@ impl<'a> App<'a> {
     ) -> Result<&mut dyn Component> {
         self.get_or_init_tab(commander, self.current_tab)
     }
+
+    /// Get the maximized panel of the current tab.
+    pub fn get_maximized_panel(&self) -> Option<FocusedPanel> {
+        self.maximized_panel
+            .filter(|(tab, _)| *tab == self.current_tab)
+            .map(|(_, panel)| panel)
+    }
+
+    fn toggle_maximized_panel(&mut self, commander: &mut Commander) -> Result<()> {
+        self.maximized_panel = match self.get_maximized_panel() {
+            Some(_) => None,
+            None => {
+                let current_tab = self.current_tab;
+                self.get_or_init_current_tab(commander)?
+                    .focused_panel()
+                    .map(|panel| (current_tab, panel))
+            }
+        };
+        Ok(())
+    }
+
+    /// Move the working copy to the next/previous change, according to the navigation mode.
+    fn navigate(&mut self, commander: &mut Commander, next: bool) -> Result<()> {
+        let edit = self.navigation_mode == NavigationMode::Edit;
+        let result = if next {
+            commander.run_next(edit)
+        } else {
+            commander.run_prev(edit)
+        };
+        if let Err(err) = result {
+            self.popup = Some(Box::new(MessagePopup {
+                title: if next { "Next error" } else { "Previous error" }.into(),
+                messages: err.into_text("")?,
+            }));
+            return Ok(());
+        }
+
+        // Select the new working copy
+        let head = commander.get_current_head()?;
+        if let Some(log) = self.log.as_mut() {
+            log.set_head(commander, head.clone());
+        }
+        self.active_head = Some(head);
+        self.set_tab(commander, self.current_tab)
+    }
+
+    /// Update the current tab. If jj fails because the working copy is stale, offer to update it
+    /// instead of failing.
+    pub fn update(&mut self, commander: &mut Commander) -> Result<()> {
+        let result = self
+            .get_or_init_current_tab(commander)
+            .and_then(|tab| tab.update(commander));
+        match result {
+            Ok(component_action) => {
+                self.stale_prompted = false;
+                if let Some(component_action) = component_action {
+                    self.handle_action(component_action, commander)?;
+                }
+            }
+            // Keep the last state until fixed, and only prompt once
+            Err(err) if is_stale_working_copy_error(&err) => {
+                if !self.stale_prompted {
+                    self.stale_prompted = true;
+                    self.popup = Some(Box::new(StaleWorkspacePopup));
+                }
+            }
+            Err(err) => return Err(err),
+        }
+
+        if let Some(popup) = self.popup.as_mut() {
+            if let Some(component_action) = popup.update(commander)? {
+                self.handle_action(component_action, commander)?;
+            }
+        }
+
+        // Shown once, the tool isn't used again
+        if self.popup.is_none() {
+            if let Some(diff_tool_error) = commander.take_diff_tool_error() {
+                self.popup = Some(Box::new(MessagePopup {
+                    title: "Diff tool error".into(),
+                    messages: diff_tool_error.into(),
+                }));
+            }
+        }
+
+        if self.env.config.stack_breadcrumb()
+            && self.stack_refresh_count != Some(commander.refresh_count())
+        {
+            self.stack_refresh_count = Some(commander.refresh_count());
+            // Keep the last stack if it can't be fetched, such as when the working copy is stale
+            if let Ok(stack) = commander.get_stack_summary() {
+                self.stack = stack;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pick up the results of work running in the background, on timer ticks. Unlike `update`,
+    /// doesn't run jj, which would snapshot the working copy.
+    pub fn poll_background(&mut self, commander: &mut Commander) -> Result<()> {
+        if let Some(component_action) = self.get_current_tab().and_then(|tab| tab.poll_background())
+        {
+            self.handle_action(component_action, commander)?;
+        }
+
+        if let Some(component_action) = self
+            .popup
+            .as_mut()
+            .and_then(|popup| popup.poll_background())
+        {
+            self.handle_action(component_action, commander)?;
+        }
+
+        Ok(())
+    }
+
+    /// Select the change next to the selected one in the stack breadcrumb, in the log tab.
+    fn select_stack_entry(
+        &mut self,
+        commander: &mut Commander,
+        towards_working_copy: bool,
+    ) -> Result<()> {
+        let Some(entry) = get_adjacent_entry(
+            &self.stack,
+            self.active_head.as_ref().map(|head| &head.change_id),
+            towards_working_copy,
+        ) else {
+            return Ok(());
+        };
+
+        // The stack is fetched without snapshotting the working copy, so may be outdated
+        let head = commander.get_head_latest(&entry.head)?;
+        self.get_log_tab(commander)?
+            .select_head(commander, head.clone());
+        self.handle_action(ComponentAction::ChangeHead(head), commander)?;
+        self.set_tab(commander, Tab::Log)
+    }
+
+    /// Remember the current operation before the terminal is handed to an external command.
+    pub fn suspend_for_external(&mut self, commander: &mut Commander) {
+        self.suspended_operation = commander.get_operation_id().ok();
+    }
+
+    /// Refresh after an external command, such as an editor or merge tool, returns. Every
+    /// command run with the TUI suspended resumes through here, after the terminal is restored.
+    pub fn resume_from_external(&mut self, commander: &mut Commander) -> Result<()> {
+        let operation = commander.get_operation_id().ok();
+        if operation_changed(
+            self.suspended_operation.take().as_deref(),
+            operation.as_deref(),
+        ) {
+            if let Some(log) = self.log.as_mut() {
+                log.set_notification("Repository changed while lazyjj was suspended".to_owned());
+            }
+        }
+
+        // The active head may have been rewritten or abandoned
+        if let Some(active_head) = self.active_head.as_ref() {
+            self.active_head = Some(
+                commander
+                    .get_head_latest(active_head)
+                    .or_else(|_| commander.get_current_head())?,
+            );
+        }
+
+        // Other tabs refresh when switched to
+        self.set_tab(commander, self.current_tab)
+    }
+
+    /// Forget the sizes of the last draw after the terminal was resized, until the next draw.
+    pub fn reset_size(&mut self) {
+        if let Some(log) = self.log.as_mut() {
+            log.reset_size();
+        }
+        if let Some(files) = self.files.as_mut() {
+            files.reset_size();
+        }
+        if let Some(bookmarks) = self.bookmarks.as_mut() {
+            bookmarks.reset_size();
+        }
+        if let Some(command_log) = self.command_log.as_mut() {
+            command_log.reset_size();
+        }
+        if let Some(popup) = self.popup.as_mut() {
+            popup.reset_size();
+        }
+    }
+
+    /// Whether work is running in the background for the current tab, whose results need to be
+    /// drawn.
+    pub fn has_background_work(&self) -> bool {
+        if self
+            .popup
+            .as_ref()
+            .is_some_and(|popup| popup.has_background_work())
+        {
+            return true;
+        }
+
+        match self.current_tab {
+            Tab::Log => self
+                .log
+                .as_ref()
+                .is_some_and(|log| log.is_fetching_decorations() || log.is_searching_change_id()),
+            _ => false,
+        }
+    }
+
     pub fn get_current_tab(&mut self) -> Option<&mut dyn Component> {
         self.get_tab(self.current_tab)
     }
@@ -71,6 +421,14 @@ impl<'a> App<'a> {
     pub fn set_tab(&mut self, commander: &mut Commander, tab: Tab) -> Result<()> {
         info!("Setting tab to {}", tab);
         self.current_tab = tab;
+        self.stack_refresh_count = None;
+        commander.emit_event(&events::Event::TabSwitched { tab });
+
+        if tab == Tab::Files {
+            if let Some(active_head) = self.active_head.clone() {
+                self.get_files_tab(commander)?.focus(&active_head);
+            }
+        }
 
         self.get_or_init_current_tab(commander)?.switch(commander)?;
         Ok(())
@@ -79,7 +437,12 @@ impl<'a> App<'a> {
     pub fn get_log_tab(&mut self, commander: &mut Commander) -> Result<&mut LogTab<'a>> {
         if self.log.is_none() {
             let span = info_span!("Initializing log tab");
-            let log_tab = span.in_scope(|| LogTab::new(commander))?;
+            let initial_revision = self.initial_revision.take();
+            let mut log_tab =
+                span.in_scope(|| LogTab::new(commander, initial_revision.as_deref()))?;
+            if let Some(diff_format) = self.diff_format.clone() {
+                log_tab.set_diff_format(commander, diff_format);
+            }
             self.log = Some(log_tab);
         }
 
@@ -91,10 +454,13 @@ impl<'a> App<'a> {
     pub fn get_files_tab(&mut self, commander: &mut Commander) -> Result<&mut FilesTab> {
         if self.files.is_none() {
             let span = info_span!("Initializing files tab");
-            let files_tab = span.in_scope(|| {
+            let mut files_tab = span.in_scope(|| {
                 let current_head = commander.get_current_head()?;
                 FilesTab::new(commander, &current_head)
             })?;
+            if let Some(diff_format) = self.diff_format.clone() {
+                files_tab.set_diff_format(commander, diff_format)?;
+            }
             self.files = Some(files_tab);
         }
 
@@ -109,7 +475,10 @@ impl<'a> App<'a> {
     ) -> Result<&mut BookmarksTab<'a>> {
         if self.bookmarks.is_none() {
             let span = info_span!("Initializing bookmarks tab");
-            let bookmarks_tab = span.in_scope(|| BookmarksTab::new(commander))?;
+            let mut bookmarks_tab = span.in_scope(|| BookmarksTab::new(commander))?;
+            if let Some(diff_format) = self.diff_format.clone() {
+                bookmarks_tab.set_diff_format(commander, diff_format);
+            }
             self.bookmarks = Some(bookmarks_tab);
         }
 
@@ -164,6 +533,8 @@ impl<'a> App<'a> {
         }
     }
 
+    /// Apply an action from a component. `Multiple` actions are applied in order, each fully
+    /// before the next, including nested ones.
     pub fn handle_action(
         &mut self,
         component_action: ComponentAction,
@@ -171,18 +542,101 @@ impl<'a> App<'a> {
     ) -> Result<()> {
         match component_action {
             ComponentAction::ViewFiles(head) => {
+                // Viewing a change explicitly unpins the files tab
+                self.active_head = Some(head);
+                self.get_files_tab(commander)?.pinned = false;
                 self.set_tab(commander, Tab::Files)?;
-                self.get_files_tab(commander)?.set_head(commander, &head)?;
             }
             ComponentAction::ViewLog(head) => {
                 self.get_log_tab(commander)?.set_head(commander, head);
                 self.set_tab(commander, Tab::Log)?;
             }
             ComponentAction::ChangeHead(head) => {
-                self.get_files_tab(commander)?.set_head(commander, &head)?;
+                commander.emit_event(&events::Event::SelectionChanged {
+                    change_id: head.change_id.as_string(),
+                    commit_id: head.commit_id.as_string(),
+                });
+                self.active_head = Some(head);
             }
             ComponentAction::SetPopup(popup) => {
-                self.popup = popup;
+                self.popup = popup.or_else(|| self.next_popup.take());
+            }
+            ComponentAction::SetTab(tab) => {
+                self.set_tab(commander, tab)?;
+            }
+            ComponentAction::SetRevset(revset) => {
+                self.get_log_tab(commander)?.set_revset(revset);
+                self.set_tab(commander, Tab::Log)?;
+            }
+            ComponentAction::SetLogPath(path) => {
+                self.get_log_tab(commander)?.set_path(path);
+                self.set_tab(commander, Tab::Log)?;
+            }
+            ComponentAction::SetDiffFormat(diff_format)
+                if self.env.config.diff_format_scope() == DiffFormatScope::Global =>
+            {
+                // Tabs not opened yet pick it up when initialized
+                self.diff_format = Some(diff_format.clone());
+                if let Some(log_tab) = self.log.as_mut() {
+                    log_tab.set_diff_format(commander, diff_format.clone());
+                }
+                if let Some(files_tab) = self.files.as_mut() {
+                    files_tab.set_diff_format(commander, diff_format.clone())?;
+                }
+                if let Some(bookmarks_tab) = self.bookmarks.as_mut() {
+                    bookmarks_tab.set_diff_format(commander, diff_format);
+                }
+            }
+            ComponentAction::SetDiffFormat(diff_format) => match self.current_tab {
+                Tab::Log => self
+                    .get_log_tab(commander)?
+                    .set_diff_format(commander, diff_format),
+                Tab::Files => self
+                    .get_files_tab(commander)?
+                    .set_diff_format(commander, diff_format)?,
+                Tab::Bookmarks => self
+                    .get_bookmarks_tab(commander)?
+                    .set_diff_format(commander, diff_format),
+                Tab::CommandLog => {}
+            },
+            ComponentAction::Confirmed(action) => {
+                // Make sure the repository didn't change since the last refresh first
+                match action
+                    .changes_repository()
+                    .then(|| commander.get_changed_operation())
+                    .flatten()
+                {
+                    Some(operations) => {
+                        self.popup = Some(Box::new(StaleViewPopup::new(operations, action)));
+                    }
+                    None => self.handle_action(ComponentAction::RunConfirmed(action), commander)?,
+                }
+            }
+            ComponentAction::RunConfirmed(action) => {
+                let component_action = match action {
+                    ConfirmAction::Log(action) => self
+                        .get_log_tab(commander)?
+                        .run_confirmed(commander, action)?,
+                    ConfirmAction::Bookmarks(action) => self
+                        .get_bookmarks_tab(commander)?
+                        .run_confirmed(commander, action)?,
+                };
+                if let Some(component_action) = component_action {
+                    self.handle_action(component_action, commander)?;
+                }
+            }
+            ComponentAction::Refresh => {
+                self.stack_refresh_count = None;
+                self.get_or_init_current_tab(commander)?.switch(commander)?;
+            }
+            ComponentAction::RunExternal(args) => {
+                self.external_command = Some(args);
+            }
+            ComponentAction::Print(output) => {
+                self.print_output = Some(output);
+            }
+            ComponentAction::Quit => {
+                self.should_quit = true;
             }
             ComponentAction::Multiple(component_actions) => {
                 for component_action in component_actions.into_iter() {
@@ -194,6 +648,38 @@ impl<'a> App<'a> {
         Ok(())
     }
 
+    /// Resize the current tab's panels by dragging the border between them with the mouse.
+    /// Returns whether the event was part of a drag.
+    fn drag_split(&mut self, event: &Event) -> bool {
+        let Event::Mouse(mouse) = event else {
+            return false;
+        };
+
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left)
+                if self.get_maximized_panel().is_none()
+                    && is_on_panel_split(
+                        self.tab_area,
+                        self.split_percent,
+                        mouse.column,
+                        mouse.row,
+                    ) =>
+            {
+                self.dragging_split = true;
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.dragging_split => {
+                self.split_percent = split_percent_at(self.tab_area, mouse.column);
+            }
+            MouseEventKind::Up(MouseButton::Left) if self.dragging_split => {
+                self.split_percent = split_percent_at(self.tab_area, mouse.column);
+                self.dragging_split = false;
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
     pub fn input(&mut self, event: Event, commander: &mut Commander) -> Result<bool> {
         if let Some(popup) = self.popup.as_mut() {
             match popup.input(commander, event.clone())? {
@@ -214,12 +700,17 @@ impl<'a> App<'a> {
                                     | KeyCode::Char('q')
                                     | KeyCode::Esc
                             ) {
-                                self.popup = None
+                                self.popup = self.next_popup.take();
                             }
                         }
                     }
                 }
             };
+        } else if self.drag_split(&event) {
+            // Handled before the tab, as dragging can leave a panel's area
+        } else if is_sparse_reset_key(&event) {
+            // Checked before the tab, which would handle it as a refresh with `R`
+            self.popup = Some(Box::new(SparseResetPopup));
         } else {
             match self
                 .get_or_init_current_tab(commander)?
@@ -240,6 +731,42 @@ impl<'a> App<'a> {
                             {
                                 return Ok(true);
                             }
+
+                            // Command palette
+                            if key.code == KeyCode::Char(':') {
+                                self.popup = Some(Box::new(CommandPalette::new()));
+                            }
+
+                            // Navigation mode
+                            if key.code == KeyCode::Char('n')
+                                && key.modifiers.contains(KeyModifiers::CONTROL)
+                            {
+                                self.navigation_mode = match self.navigation_mode {
+                                    NavigationMode::New => NavigationMode::Edit,
+                                    NavigationMode::Edit => NavigationMode::New,
+                                };
+                            }
+
+                            // Next/previous change
+                            if key.code == KeyCode::Char(']') || key.code == KeyCode::Char('[') {
+                                self.navigate(commander, key.code == KeyCode::Char(']'))?;
+                            }
+
+                            // Select a change of the stack breadcrumb
+                            if self.env.config.stack_breadcrumb()
+                                && (key.code == KeyCode::Char('<')
+                                    || key.code == KeyCode::Char('>'))
+                            {
+                                self.select_stack_entry(commander, key.code == KeyCode::Char('>'))?;
+                            }
+
+                            // Maximize panel
+                            if key.code == KeyCode::Char('z')
+                                && key.modifiers.contains(KeyModifiers::CONTROL)
+                            {
+                                self.toggle_maximized_panel(commander)?;
+                            }
+
                             //
                             // Tab switching
                             if let Some((_, tab)) = Tab::VALUES.iter().enumerate().find(|(i, _)| {
@@ -257,6 +784,142 @@ impl<'a> App<'a> {
             };
         }
 
-        Ok(false)
+        Ok(self.should_quit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commander::ids::{ChangeId, CommitId},
+        env::{Config, ConfigWarning},
+    };
+    use crossterm::event::KeyEvent;
+
+    #[test]
+    fn compare_operations() {
+        assert!(!operation_changed(Some("a1b2"), Some("a1b2")));
+        assert!(operation_changed(Some("a1b2"), Some("c3d4")));
+        // jj op log failed before or after
+        assert!(!operation_changed(None, Some("c3d4")));
+        assert!(!operation_changed(Some("a1b2"), None));
+        assert!(!operation_changed(None, None));
+    }
+
+    // App without a repository, for what doesn't run jj
+    fn app_without_repository<'a>() -> Result<(App<'a>, Commander)> {
+        let env = Env {
+            config: Config::default(),
+            root: String::new(),
+            default_revset: None,
+            jj_config: vec![],
+            jj_config_flag: "--config",
+            config_warnings: vec![],
+        };
+        let commander = Commander::new(&env);
+        Ok((App::new(env, None)?, commander))
+    }
+
+    #[test]
+    fn show_health_after_config_warnings() -> Result<()> {
+        let env = Env {
+            // Warned about by the health check
+            config: toml::from_str(r#""ui.color" = "never""#)?,
+            root: String::new(),
+            default_revset: None,
+            jj_config: vec![],
+            jj_config_flag: "--config",
+            config_warnings: vec![ConfigWarning {
+                key: "lazyjj.transient-retries".to_owned(),
+                value: "100".to_owned(),
+                message: "out of range".to_owned(),
+            }],
+        };
+        let mut commander = Commander::new(&env);
+        let mut app = App::new(env, None)?;
+        assert!(app.popup.is_some());
+        assert!(app.next_popup.is_some());
+
+        // Closing the config warnings shows the health warnings
+        app.input(
+            Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)),
+            &mut commander,
+        )?;
+        assert!(app.popup.is_some());
+        assert!(app.next_popup.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn init_tabs_lazily() -> Result<()> {
+        let (app, _) = app_without_repository()?;
+        // Tabs are created by `get_or_init_current_tab` and the `get_*_tab` methods when first
+        // used, so starting doesn't run jj for tabs which aren't shown
+        assert!(app.log.is_none());
+        assert!(app.files.is_none());
+        assert!(app.bookmarks.is_none());
+        assert!(app.command_log.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn handle_nested_actions() -> Result<()> {
+        // Only actions which don't run jj, so no repository is needed
+        let (mut app, mut commander) = app_without_repository()?;
+        let head = Head {
+            change_id: ChangeId("kxryzmor".to_owned()),
+            commit_id: CommitId("f0e1d2c3".to_owned()),
+            divergent: false,
+            immutable: false,
+            hidden: false,
+            conflict: false,
+            empty: false,
+        };
+
+        app.handle_action(
+            ComponentAction::Multiple(vec![
+                ComponentAction::Multiple(vec![ComponentAction::ChangeHead(head.clone())]),
+                ComponentAction::Print("first".to_owned()),
+                ComponentAction::Multiple(vec![ComponentAction::Multiple(vec![
+                    ComponentAction::Print("second".to_owned()),
+                    ComponentAction::Quit,
+                ])]),
+            ]),
+            &mut commander,
+        )?;
+
+        assert_eq!(app.active_head, Some(head));
+        // Later actions are applied after earlier ones
+        assert_eq!(app.print_output.as_deref(), Some("second"));
+        assert!(app.should_quit);
+        Ok(())
+    }
+
+    #[test]
+    fn match_sparse_reset_key() {
+        let key = |code, modifiers| Event::Key(event::KeyEvent::new(code, modifiers));
+
+        assert!(is_sparse_reset_key(&key(
+            KeyCode::Char('R'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT
+        )));
+        assert!(is_sparse_reset_key(&key(
+            KeyCode::Char('R'),
+            KeyModifiers::CONTROL
+        )));
+        assert!(is_sparse_reset_key(&key(
+            KeyCode::Char('r'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT
+        )));
+        // Refresh
+        assert!(!is_sparse_reset_key(&key(
+            KeyCode::Char('R'),
+            KeyModifiers::SHIFT
+        )));
+        assert!(!is_sparse_reset_key(&key(
+            KeyCode::Char('r'),
+            KeyModifiers::CONTROL
+        )));
     }
 }
@@ -0,0 +1,150 @@
+use std::{
+    env,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+// Parts of jj's errors when the repository was written in a format this jj doesn't know, such as
+// by a newer jj: unknown store backends or working copy types, and undecodable state files
+const NEWER_JJ_ERRORS: &[&str] = &[
+    "unsupported commit backend type",
+    "unsupported operation backend type",
+    "unsupported operation store type",
+    "unsupported op_heads backend type",
+    "unsupported index backend type",
+    "unsupported submodule_store backend type",
+    "unsupported working copy type",
+    "failed to decode tree state",
+    "failed to decode operation",
+    "failed to decode view",
+    "requires a newer version of jj",
+];
+
+/// Whether a jj error is the repository requiring a newer jj than the one run, such as when a
+/// repository pins its jj with direnv and the environment wasn't loaded.
+pub fn is_newer_jj_required_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    NEWER_JJ_ERRORS.iter().any(|error| message.contains(error))
+}
+
+// First executable named `program` in the directories of a `PATH`, as `which` would find it
+fn find_in_path(program: &str, path: &OsStr) -> Option<PathBuf> {
+    let file_name = format!("{program}{}", env::consts::EXE_SUFFIX);
+    env::split_paths(path)
+        .map(|directory| directory.join(&file_name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Path of the jj run by lazyjj, from `PATH`.
+pub fn jj_binary_path() -> Option<PathBuf> {
+    find_in_path("jj", &env::var_os("PATH")?)
+}
+
+/// Version of a jj binary, such as `jj 0.21.0`.
+pub fn jj_version(binary: &Path) -> Option<String> {
+    let output = Command::new(binary).arg("--version").output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+// Flag passing config to jj for a `jj --version` output. `--config NAME=VALUE` replaced
+// `--config-toml` in jj 0.25, which newer jj no longer accepts, so older jj keeps the TOML flag.
+// Both take `NAME=VALUE`, as it is also valid TOML
+fn config_flag(version: Option<&str>) -> &'static str {
+    let minor = version
+        .and_then(|version| version.strip_prefix("jj 0."))
+        .and_then(|version| version.split(['.', '-']).next())
+        .and_then(|minor| minor.parse::<u32>().ok());
+    match minor {
+        Some(minor) if minor < 25 => "--config-toml",
+        _ => "--config",
+    }
+}
+
+/// Flag passing config to the jj in `PATH`, `--config` unless it's older than 0.25.
+pub fn jj_config_flag() -> &'static str {
+    config_flag(jj_version(Path::new("jj")).as_deref())
+}
+
+/// Startup error when the repository requires a newer jj, naming the jj binary which was run and
+/// its version, so a wrong jj in `PATH` can be spotted.
+pub fn newer_jj_required_message(error: &str) -> String {
+    let binary = jj_binary_path();
+    let version = binary.as_deref().and_then(jj_version);
+    format!(
+        "This repository was written by a newer version of jj than the one lazyjj ran:
+
+    {}
+
+jj binary: {}
+jj version: {}
+
+If the repository pins a jj version, such as with direnv, make sure its environment is loaded \
+(`direnv allow`) so that jj is first in PATH, or update jj.",
+        error.trim(),
+        binary
+            .as_deref()
+            .map_or("not found in PATH".to_owned(), |binary| binary
+                .display()
+                .to_string()),
+        version.as_deref().unwrap_or("unknown"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempdir::TempDir;
+
+    #[test]
+    fn newer_jj_required_errors() {
+        // Captured from older jj run on repositories written by newer ones
+        for message in [
+            "Error: Failed to load the repo: Unsupported commit backend type 'secret'",
+            "Internal error: Failed to load an operation: Unsupported operation backend type 'simple_op_store_v2'",
+            "Error: Unsupported working copy type 'local_v2'",
+            "Internal error: Failed to decode tree state: invalid wire type value: 6",
+            "Error: The repository requires a newer version of jj",
+        ] {
+            assert!(is_newer_jj_required_error(message), "{message}");
+        }
+
+        for message in [
+            "Error: There is no jj repo in \".\"",
+            "Error: The working copy is stale (not updated since operation 1234).",
+            "Error: Revision \"nope\" doesn't exist",
+        ] {
+            assert!(!is_newer_jj_required_error(message), "{message}");
+        }
+    }
+
+    #[test]
+    fn find_binary_in_path() -> anyhow::Result<()> {
+        let empty = TempDir::new("lazyjj-path-empty")?;
+        let bin = TempDir::new("lazyjj-path-bin")?;
+        let binary = bin.path().join(format!("jj{}", env::consts::EXE_SUFFIX));
+        fs::write(&binary, "")?;
+        // A directory named like the binary isn't it
+        fs::create_dir(empty.path().join(format!("jj{}", env::consts::EXE_SUFFIX)))?;
+
+        let path = env::join_paths([empty.path(), bin.path()])?;
+        assert_eq!(find_in_path("jj", &path), Some(binary));
+        assert_eq!(find_in_path("jj", &env::join_paths([empty.path()])?), None);
+        Ok(())
+    }
+
+    #[test]
+    fn config_flag_by_version() {
+        assert_eq!(config_flag(Some("jj 0.18.0")), "--config-toml");
+        assert_eq!(config_flag(Some("jj 0.24.0-8c5a3a3b")), "--config-toml");
+        assert_eq!(config_flag(Some("jj 0.25.0")), "--config");
+        assert_eq!(config_flag(Some("jj 0.31.0-2bce6a1c")), "--config");
+        assert_eq!(config_flag(Some("jj 1.0.0")), "--config");
+        // Unknown versions are assumed to be recent
+        assert_eq!(config_flag(None), "--config");
+    }
+}
@@ -1,12 +1,13 @@
 use crate::{
-    commander::{CommandError, Commander, RemoveEndLine},
+    commander::{ids::CommitId, log::Head, CommandError, Commander, RemoveEndLine},
     env::DiffFormat,
+    ui::utils::ansi_text,
 };
-use ansi_to_tui::IntoText;
 use anyhow::Result;
+use itertools::Itertools;
 use ratatui::text::Text;
 use regex::Regex;
-use std::{fmt::Display, sync::LazyLock};
+use std::{collections::HashMap, fmt::Display, sync::LazyLock};
 use tracing::instrument;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -56,6 +57,40 @@ fn parse_bookmark(text: &str) -> Option<Bookmark> {
     })
 }
 
+// Template which outputs a commit's ID followed by its remote bookmarks, in the format of
+// BRANCH_TEMPLATE
+const REMOTE_BOOKMARKS_TEMPLATE: &str = r#"commit_id ++ remote_bookmarks.map(|b| " [" ++ b.name() ++ "@" ++ b.remote() ++ "|" ++ b.present() ++ "]").join("") ++ "\n""#;
+
+// Parse commits' remote bookmarks with REMOTE_BOOKMARKS_TEMPLATE. The Git backend's own `@git`
+// bookmarks are skipped
+fn parse_remote_bookmarks(output: &str) -> HashMap<CommitId, Vec<Bookmark>> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split(' ');
+            let commit_id = CommitId(parts.next()?.to_owned());
+            let bookmarks: Vec<Bookmark> = parts
+                .filter_map(parse_bookmark)
+                .filter(|bookmark| {
+                    bookmark
+                        .remote
+                        .as_deref()
+                        .is_some_and(|remote| remote != "git")
+                })
+                .collect();
+            (!bookmarks.is_empty()).then_some((commit_id, bookmarks))
+        })
+        .collect()
+}
+
+/// Bookmark with a summary of the change it points to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookmarkTarget {
+    pub bookmark: Bookmark,
+    pub change_id: String,
+    pub description: String,
+}
+
 #[derive(Clone, Debug)]
 pub enum BookmarkLine {
     Unparsable(String),
@@ -63,10 +98,10 @@ pub enum BookmarkLine {
 }
 
 impl BookmarkLine {
-    pub fn to_text(&self) -> Result<Text, ansi_to_tui::Error> {
+    pub fn to_text(&self) -> Result<Text<'static>, ansi_to_tui::Error> {
         match self {
-            BookmarkLine::Unparsable(text) => text.to_text(),
-            BookmarkLine::Parsed { text, .. } => text.to_text(),
+            BookmarkLine::Unparsable(text) => ansi_text(text),
+            BookmarkLine::Parsed { text, .. } => ansi_text(text),
         }
     }
 }
@@ -85,16 +120,14 @@ impl Commander {
                 vec![
                     "bookmark",
                     "list",
-                    "--config-toml",
+                    self.env.jj_config_flag,
                     // Override format_ref_targets to not list conflicts
-                    r#"
-                            template-aliases.'format_ref_targets(ref)' = '''
+                    r#"template-aliases.'format_ref_targets(ref)'='''
                                 if(ref.conflict(),
                                   " " ++ label("conflict", "(conflicted)"),
                                   ": " ++ format_commit_summary_with_refs(ref.normal_target(), ""),
                                 )
-                            '''
-                        "#,
+                            '''"#,
                 ],
                 args.clone(),
             ]
@@ -153,7 +186,102 @@ impl Commander {
         Ok(bookmarks)
     }
 
-    /// Get bookmark details.
+    /// Get all bookmarks with the change they point to. Local bookmarks are listed first.
+    /// Maps to `jj bookmark list --all-remotes`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_bookmark_targets(&mut self) -> Result<Vec<BookmarkTarget>, CommandError> {
+        let mut bookmark_targets: Vec<BookmarkTarget> = self
+            .execute_jj_command(
+                vec![
+                    "bookmark",
+                    "list",
+                    "--all-remotes",
+                    "-T",
+                    &format!(
+                        r#"if(present, if(normal_target, {} ++ "\t" ++ normal_target.change_id().shortest(8) ++ "\t" ++ normal_target.description().first_line() ++ "\n"))"#,
+                        BRANCH_TEMPLATE
+                    ),
+                ],
+                false,
+                true,
+            )?
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, '\t');
+                let bookmark = parse_bookmark(parts.next()?)?;
+                Some(BookmarkTarget {
+                    bookmark,
+                    change_id: parts.next()?.to_owned(),
+                    description: parts.next().unwrap_or_default().to_owned(),
+                })
+            })
+            // Git remote duplicates local bookmarks in colocated repos
+            .filter(|bookmark_target| bookmark_target.bookmark.remote.as_deref() != Some("git"))
+            .collect();
+
+        bookmark_targets.sort_by_key(|bookmark_target| bookmark_target.bookmark.remote.is_some());
+
+        Ok(bookmark_targets)
+    }
+
+    /// Get bookmarks pointing to a commit, including their remotes, even when in sync.
+    /// Maps to `jj bookmark list --all-remotes -r <revision>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_commit_bookmarks(
+        &mut self,
+        commit_id: &CommitId,
+    ) -> Result<Vec<Bookmark>, CommandError> {
+        let bookmarks: Vec<Bookmark> = self
+            .execute_jj_command(
+                vec![
+                    "bookmark",
+                    "list",
+                    "--all-remotes",
+                    "-r",
+                    commit_id.as_str(),
+                    "-T",
+                    &format!(r#"{} ++ "\n""#, BRANCH_TEMPLATE),
+                ],
+                false,
+                true,
+            )?
+            .lines()
+            .filter_map(parse_bookmark)
+            .collect();
+
+        Ok(bookmarks)
+    }
+
+    /// Get the remote bookmarks pointing to each of the heads, for every remote.
+    /// Maps to `jj log -r '(<heads>) & remote_bookmarks()'`
+    #[instrument(level = "trace", skip(self, heads))]
+    pub fn get_remote_bookmarks(
+        &mut self,
+        heads: &[Head],
+    ) -> Result<HashMap<CommitId, Vec<Bookmark>>, CommandError> {
+        if heads.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let revset = format!(
+            "({}) & remote_bookmarks()",
+            heads.iter().map(|head| head.commit_id.as_str()).join(" | ")
+        );
+        Ok(parse_remote_bookmarks(&self.execute_jj_command(
+            vec![
+                "log",
+                "--no-graph",
+                "--template",
+                REMOTE_BOOKMARKS_TEMPLATE,
+                "-r",
+                &revset,
+            ],
+            false,
+            true,
+        )?))
+    }
+
+    /// Get bookmark details, with the diff in a format, such as only the `--stat` summary.
     /// Maps to `jj show <bookmark>`
     #[instrument(level = "trace", skip(self))]
     pub fn get_bookmark_show(
@@ -162,11 +290,7 @@ impl Commander {
         diff_format: &DiffFormat,
     ) -> Result<String, CommandError> {
         Ok(self
-            .execute_jj_command(
-                vec!["show", &bookmark.to_string(), diff_format.get_arg()],
-                true,
-                true,
-            )?
+            .execute_diff_command(vec!["show", &bookmark.to_string()], diff_format)?
             .remove_end_line())
     }
 }
@@ -180,6 +304,36 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn parse_remote_bookmark_lines() {
+        let remote_bookmarks = parse_remote_bookmarks(
+            "a1b2c3d4 [main@origin|true] [main@upstream|true] [main@git|true]\ne5f6a7b8\n",
+        );
+
+        assert_eq!(remote_bookmarks.len(), 1);
+        assert_eq!(
+            remote_bookmarks[&CommitId("a1b2c3d4".to_owned())]
+                .iter()
+                .map(Bookmark::to_string)
+                .collect::<Vec<_>>(),
+            ["main@origin", "main@upstream"]
+        );
+    }
+
+    #[test]
+    fn get_remote_bookmarks() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let head = test_repo.commander.get_current_head()?;
+        // The test repo has no remotes
+        assert!(test_repo
+            .commander
+            .get_remote_bookmarks(&[head])?
+            .is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn get_bookmarks() -> Result<()> {
         let mut test_repo = TestRepo::new()?;
@@ -211,6 +365,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn get_bookmark_targets() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        test_repo.commander.run_describe("@", "Test\nMore")?;
+        let bookmark = test_repo.commander.create_bookmark("test")?;
+        let head = test_repo.commander.get_current_head()?;
+        let bookmark_targets = test_repo.commander.get_bookmark_targets()?;
+
+        assert_eq!(bookmark_targets.len(), 1);
+        let bookmark_target = &bookmark_targets[0];
+        assert_eq!(bookmark_target.bookmark, bookmark);
+        assert!(head
+            .change_id
+            .as_str()
+            .starts_with(&bookmark_target.change_id));
+        assert_eq!(bookmark_target.description, "Test");
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_commit_bookmarks() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let bookmark = test_repo.commander.create_bookmark("test")?;
+        let head = test_repo.commander.get_current_head()?;
+        let bookmarks = test_repo.commander.get_commit_bookmarks(&head.commit_id)?;
+
+        assert_eq!(bookmarks, [bookmark]);
+
+        Ok(())
+    }
+
     #[test]
     fn get_bookmark_show() -> Result<()> {
         let mut test_repo = TestRepo::new()?;
@@ -230,4 +418,20 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn get_bookmark_show_stat() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        std::fs::write(test_repo.directory.path().join("README"), b"AAA\nBBB\n")?;
+        let bookmark = test_repo.commander.create_bookmark("test")?;
+        let bookmark_show = test_repo
+            .commander
+            .get_bookmark_show(&bookmark, &DiffFormat::Stat)?;
+
+        assert!(bookmark_show.contains("README | 2 ++"), "{bookmark_show}");
+        assert!(bookmark_show.ends_with("1 file changed, 2 insertions(+), 0 deletions(-)"));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,331 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    process::{Command, Stdio},
+    str::FromStr,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, LazyLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use ratatui::style::Color;
+use regex::Regex;
+use tracing::{instrument, trace};
+
+use crate::{
+    commander::{get_output_args, ids::CommitId},
+    env::Env,
+    ui::utils::escape_control_chars,
+};
+
+// Maximum number of decoration commands running at once
+const MAX_RUNNING: usize = 4;
+// Decoration commands taking longer are killed, and show no decoration
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Decoration shown after a change in the log, such as a CI status.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decoration {
+    pub text: String,
+    pub color: Option<Color>,
+}
+
+/// Parse the first line of a decoration command's output, such as `✓` or `✓ green`.
+/// Returns `None` if the output is empty.
+pub fn parse_decoration(output: &str) -> Option<Decoration> {
+    // Decoration commands are user scripts, whose output could contain escapes
+    let line = escape_control_chars(output.lines().next()?.trim()).into_owned();
+    if line.is_empty() {
+        return None;
+    }
+
+    // The last word is a color if it parses as one
+    if let Some((text, color)) = line.rsplit_once(char::is_whitespace) {
+        if let Ok(color) = Color::from_str(color) {
+            return Some(Decoration {
+                text: text.trim_end().to_owned(),
+                color: Some(color),
+            });
+        }
+    }
+
+    Some(Decoration {
+        text: line,
+        color: None,
+    })
+}
+
+fn format_command(command: &str, commit_id: &CommitId) -> String {
+    command.replace("{commit_id}", commit_id.as_str())
+}
+
+// Run a decoration command with a timeout. Any failure results in no decoration
+fn run_decoration_command(command: &str, root: &str) -> Option<Decoration> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    let start = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if start.elapsed() < COMMAND_TIMEOUT => thread::sleep(COMMAND_POLL_INTERVAL),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                trace!("Decoration command timed out: {command}");
+                return None;
+            }
+        }
+    }
+
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_decoration(&String::from_utf8_lossy(&output.stdout))
+}
+
+// Example line: `3 files changed, 120 insertions(+), 45 deletions(-)`
+static STAT_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(\d+) files? changed, (\d+) insertions?\(\+\), (\d+) deletions?\(-\)").unwrap()
+});
+
+/// Parse the last line of `jj diff --stat` output into a badge, such as `(3f +120 -45)`.
+/// Returns `None` if no files changed.
+pub fn parse_stat_badge(output: &str) -> Option<Decoration> {
+    let captured = STAT_REGEX.captures(output.lines().last()?)?;
+    if &captured[1] == "0" {
+        return None;
+    }
+
+    Some(Decoration {
+        text: format!("({}f +{} -{})", &captured[1], &captured[2], &captured[3]),
+        color: Some(Color::DarkGray),
+    })
+}
+
+// Get a commit's diff stat. The working copy isn't snapshotted, as this runs alongside other
+// commands
+fn run_stat_command(commit_id: &CommitId, env: &Env) -> Option<Decoration> {
+    let output = Command::new("jj")
+        .args([
+            "diff",
+            "--stat",
+            "--ignore-working-copy",
+            "-r",
+            commit_id.as_str(),
+        ])
+        .args(get_output_args(false, true))
+        .args(env.jj_config_args())
+        .current_dir(&env.root)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_stat_badge(&String::from_utf8_lossy(&output.stdout))
+}
+
+type DecorationJob = Arc<dyn Fn(&CommitId) -> Option<Decoration> + Send + Sync>;
+
+/// Gets decorations for commits in the background, and caches the results per commit for a TTL.
+pub struct DecorationCache {
+    job: DecorationJob,
+    ttl: Duration,
+    // Cached decorations, with the time they were fetched. The time is removed when expired
+    entries: HashMap<CommitId, (Option<Instant>, Option<Decoration>)>,
+    // Commits waiting for or running a command
+    pending: HashSet<CommitId>,
+    queue: VecDeque<CommitId>,
+    running: usize,
+    tx: Sender<(CommitId, Option<Decoration>)>,
+    rx: Receiver<(CommitId, Option<Decoration>)>,
+}
+
+impl DecorationCache {
+    fn new(job: DecorationJob, ttl: Duration) -> Self {
+        let (tx, rx) = channel();
+        Self {
+            job,
+            ttl,
+            entries: HashMap::new(),
+            pending: HashSet::new(),
+            queue: VecDeque::new(),
+            running: 0,
+            tx,
+            rx,
+        }
+    }
+
+    /// Decorations from the `lazyjj.decoration-command`, run for each commit.
+    pub fn from_command(command: String, root: String, ttl: Duration) -> Self {
+        Self::new(
+            Arc::new(move |commit_id| {
+                run_decoration_command(&format_command(&command, commit_id), &root)
+            }),
+            ttl,
+        )
+    }
+
+    /// Badges with the number of files changed, insertions and deletions of each commit, from
+    /// `jj diff --stat`. Commits don't change, so badges don't expire.
+    pub fn stat_badges(env: &Env) -> Self {
+        let env = env.clone();
+        Self::new(
+            Arc::new(move |commit_id| run_stat_command(commit_id, &env)),
+            Duration::MAX,
+        )
+    }
+
+    /// Get the cached decoration for a commit, even if expired.
+    pub fn get(&self, commit_id: &CommitId) -> Option<&Decoration> {
+        self.entries
+            .get(commit_id)
+            .and_then(|(_, decoration)| decoration.as_ref())
+    }
+
+    /// Whether commands are waiting or running.
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Queue commands for commits without a fresh decoration.
+    #[instrument(level = "trace", skip_all)]
+    pub fn request<'a>(&mut self, commit_ids: impl IntoIterator<Item = &'a CommitId>) {
+        for commit_id in commit_ids {
+            let fresh = self
+                .entries
+                .get(commit_id)
+                .and_then(|(fetched, _)| *fetched)
+                .is_some_and(|fetched| fetched.elapsed() < self.ttl);
+            if !fresh && self.pending.insert(commit_id.clone()) {
+                self.queue.push_back(commit_id.clone());
+            }
+        }
+
+        self.start_commands();
+    }
+
+    /// Receive finished commands. Returns true if any decoration was updated.
+    pub fn poll(&mut self) -> bool {
+        let mut updated = false;
+        while let Ok((commit_id, decoration)) = self.rx.try_recv() {
+            self.running -= 1;
+            self.pending.remove(&commit_id);
+            self.entries
+                .insert(commit_id, (Some(Instant::now()), decoration));
+            updated = true;
+        }
+
+        self.start_commands();
+        updated
+    }
+
+    /// Expire all decorations, so they are fetched again when requested. Old decorations are still
+    /// shown until then.
+    pub fn expire(&mut self) {
+        for (fetched, _) in self.entries.values_mut() {
+            *fetched = None;
+        }
+    }
+
+    fn start_commands(&mut self) {
+        while self.running < MAX_RUNNING {
+            let Some(commit_id) = self.queue.pop_front() else {
+                break;
+            };
+
+            let job = self.job.clone();
+            let tx = self.tx.clone();
+            self.running += 1;
+            thread::spawn(move || {
+                let decoration = job(&commit_id);
+                // The cache may have been dropped
+                let _ = tx.send((commit_id, decoration));
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_decorations() {
+        assert_eq!(
+            parse_decoration("✓\n"),
+            Some(Decoration {
+                text: "✓".to_owned(),
+                color: None,
+            })
+        );
+        assert_eq!(
+            parse_decoration("✗ red\nmore output"),
+            Some(Decoration {
+                text: "✗".to_owned(),
+                color: Some(Color::Red),
+            })
+        );
+        assert_eq!(
+            parse_decoration("CI running"),
+            Some(Decoration {
+                text: "CI running".to_owned(),
+                color: None,
+            })
+        );
+        assert_eq!(
+            parse_decoration("\x1b[31mfailed\x1b[0m red"),
+            Some(Decoration {
+                text: "^[[31mfailed^[[0m".to_owned(),
+                color: Some(Color::Red),
+            })
+        );
+        assert_eq!(parse_decoration(""), None);
+        assert_eq!(parse_decoration("  \n✓"), None);
+    }
+
+    #[test]
+    fn parse_stat_badges() {
+        assert_eq!(
+            parse_stat_badge(
+                "README    | 120 ++++----\nsrc/main.rs |  45 ---\n3 files changed, 120 insertions(+), 45 deletions(-)\n"
+            ),
+            Some(Decoration {
+                text: "(3f +120 -45)".to_owned(),
+                color: Some(Color::DarkGray),
+            })
+        );
+        assert_eq!(
+            parse_stat_badge("README | 1 +\n1 file changed, 1 insertion(+), 0 deletions(-)\n"),
+            Some(Decoration {
+                text: "(1f +1 -0)".to_owned(),
+                color: Some(Color::DarkGray),
+            })
+        );
+        assert_eq!(
+            parse_stat_badge("0 files changed, 0 insertions(+), 0 deletions(-)\n"),
+            None
+        );
+        assert_eq!(parse_stat_badge(""), None);
+    }
+
+    #[test]
+    fn format_decoration_command() {
+        assert_eq!(
+            format_command("ci-status {commit_id}", &CommitId("abc123".to_owned())),
+            "ci-status abc123"
+        );
+    }
+}
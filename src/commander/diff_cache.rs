@@ -0,0 +1,235 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    process::{Command, Stdio},
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use tracing::{instrument, trace};
+
+use crate::{
+    commander::{get_output_args, ids::CommitId, RemoveEndLine},
+    env::{DiffFormat, Env},
+};
+
+// Number of `jj show` outputs kept, evicting the least recently used
+const CAPACITY: usize = 64;
+// Pause before each prefetch, so scrolling through the log doesn't start many jj processes
+const PREFETCH_INTERVAL: Duration = Duration::from_millis(100);
+
+type DiffKey = (CommitId, DiffFormat);
+type DiffJob = Arc<dyn Fn(&CommitId, &DiffFormat) -> Option<String> + Send + Sync>;
+
+// Get a commit's details like `Commander::get_commit_show`. The working copy isn't snapshotted, as
+// this runs alongside other commands
+fn run_show_command(commit_id: &CommitId, diff_format: &DiffFormat, env: &Env) -> Option<String> {
+    let output = Command::new("jj")
+        .args(["show", "--ignore-working-copy", commit_id.as_str()])
+        .args(diff_format.get_args())
+        .args(get_output_args(diff_format.color(), true))
+        .args(env.jj_config_args())
+        .current_dir(&env.root)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout)
+        .ok()
+        .map(|show| show.remove_end_line())
+}
+
+/// Details of commits from `jj show` by diff format, with the commits next to the selected change
+/// in the log prefetched in the background one at a time.
+pub struct DiffCache {
+    job: DiffJob,
+    capacity: usize,
+    interval: Duration,
+    entries: HashMap<DiffKey, String>,
+    // Keys of entries, least recently used first
+    order: VecDeque<DiffKey>,
+    queue: VecDeque<DiffKey>,
+    running: Option<DiffKey>,
+    tx: Sender<(DiffKey, Option<String>)>,
+    rx: Receiver<(DiffKey, Option<String>)>,
+}
+
+impl DiffCache {
+    fn new(job: DiffJob, capacity: usize, interval: Duration) -> Self {
+        let (tx, rx) = channel();
+        Self {
+            job,
+            capacity,
+            interval,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            queue: VecDeque::new(),
+            running: None,
+            tx,
+            rx,
+        }
+    }
+
+    pub fn from_env(env: &Env) -> Self {
+        let env = env.clone();
+        Self::new(
+            Arc::new(move |commit_id, diff_format| run_show_command(commit_id, diff_format, &env)),
+            CAPACITY,
+            PREFETCH_INTERVAL,
+        )
+    }
+
+    /// Get the cached details of a commit, marking them as recently used.
+    pub fn get(&mut self, commit_id: &CommitId, diff_format: &DiffFormat) -> Option<String> {
+        let key = (commit_id.clone(), diff_format.clone());
+        let show = self.entries.get(&key)?.clone();
+        self.touch(key);
+        Some(show)
+    }
+
+    /// Cache the details of a commit, evicting the least recently used if full.
+    pub fn insert(&mut self, commit_id: &CommitId, diff_format: &DiffFormat, show: String) {
+        let key = (commit_id.clone(), diff_format.clone());
+        self.entries.insert(key.clone(), show);
+        self.touch(key);
+        while self.order.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.entries.remove(&evicted);
+            }
+        }
+    }
+
+    /// Forget all details, such as after an operation which may have moved bookmarks.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.queue.clear();
+    }
+
+    /// Replace the commits waiting to be prefetched. Diff tools aren't prefetched, as their
+    /// failures are handled when shown.
+    #[instrument(level = "trace", skip_all)]
+    pub fn prefetch<'a>(
+        &mut self,
+        commit_ids: impl IntoIterator<Item = &'a CommitId>,
+        diff_format: &DiffFormat,
+    ) {
+        self.queue.clear();
+        if matches!(diff_format, DiffFormat::DiffTool(_)) {
+            return;
+        }
+
+        for commit_id in commit_ids {
+            let key = (commit_id.clone(), diff_format.clone());
+            if !self.entries.contains_key(&key) && self.running.as_ref() != Some(&key) {
+                self.queue.push_back(key);
+            }
+        }
+
+        self.start_prefetch();
+    }
+
+    /// Receive a finished prefetch, and start the next one.
+    pub fn poll(&mut self) {
+        while let Ok((key, show)) = self.rx.try_recv() {
+            self.running = None;
+            if let Some(show) = show {
+                self.insert(&key.0, &key.1, show);
+            }
+        }
+
+        self.start_prefetch();
+    }
+
+    fn touch(&mut self, key: DiffKey) {
+        self.order.retain(|used| used != &key);
+        self.order.push_back(key);
+    }
+
+    fn start_prefetch(&mut self) {
+        if self.running.is_some() {
+            return;
+        }
+        let Some(key) = self.queue.pop_front() else {
+            return;
+        };
+
+        let job = self.job.clone();
+        let tx = self.tx.clone();
+        let interval = self.interval;
+        self.running = Some(key.clone());
+        trace!("Prefetching details of {}", key.0);
+        thread::spawn(move || {
+            thread::sleep(interval);
+            let show = job(&key.0, &key.1);
+            // The cache may have been dropped
+            let _ = tx.send((key, show));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn commit(id: &str) -> CommitId {
+        CommitId(id.to_owned())
+    }
+
+    fn cache(capacity: usize) -> DiffCache {
+        DiffCache::new(
+            Arc::new(|commit_id, diff_format| Some(format!("{commit_id} {diff_format:?}"))),
+            capacity,
+            Duration::ZERO,
+        )
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let mut cache = cache(2);
+        cache.insert(&commit("a"), &DiffFormat::ColorWords, "a".to_owned());
+        cache.insert(&commit("b"), &DiffFormat::ColorWords, "b".to_owned());
+        // Using a makes b the least recently used
+        assert_eq!(
+            cache.get(&commit("a"), &DiffFormat::ColorWords),
+            Some("a".to_owned())
+        );
+        cache.insert(&commit("c"), &DiffFormat::ColorWords, "c".to_owned());
+
+        assert_eq!(cache.get(&commit("b"), &DiffFormat::ColorWords), None);
+        assert!(cache.get(&commit("a"), &DiffFormat::ColorWords).is_some());
+        assert!(cache.get(&commit("c"), &DiffFormat::ColorWords).is_some());
+        // Each diff format is cached separately
+        assert_eq!(cache.get(&commit("a"), &DiffFormat::Git), None);
+    }
+
+    #[test]
+    fn prefetch_in_background() {
+        let mut cache = cache(CAPACITY);
+        cache.prefetch([&commit("a"), &commit("b")], &DiffFormat::Git);
+
+        let start = Instant::now();
+        while cache.get(&commit("b"), &DiffFormat::Git).is_none() {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "prefetch timed out"
+            );
+            thread::sleep(Duration::from_millis(10));
+            cache.poll();
+        }
+        assert_eq!(
+            cache.get(&commit("a"), &DiffFormat::Git),
+            Some("a Git".to_owned())
+        );
+
+        // Diff tools aren't prefetched
+        cache.prefetch([&commit("c")], &DiffFormat::DiffTool("difft".to_owned()));
+        assert!(cache.queue.is_empty() && cache.running.is_none());
+    }
+}
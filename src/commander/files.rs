@@ -13,15 +13,25 @@ use tracing::instrument;
 #[derive(Clone, Debug, PartialEq)]
 pub struct File {
     pub line: String,
+    // Path of the file after the change, such as the new path of renamed files
     pub path: Option<String>,
     pub diff_type: Option<DiffType>,
 }
 
+/// Files changed in a change, with any warnings jj printed alongside them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FilesOutput {
+    pub files: Vec<File>,
+    pub warnings: Vec<String>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum DiffType {
     Added,
     Modified,
     Deleted,
+    Renamed,
+    Copied,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -35,6 +45,8 @@ impl DiffType {
             "A" => Some(DiffType::Added),
             "M" => Some(DiffType::Modified),
             "D" => Some(DiffType::Deleted),
+            "R" => Some(DiffType::Renamed),
+            "C" => Some(DiffType::Copied),
             _ => None,
         }
     }
@@ -44,44 +56,94 @@ impl DiffType {
             DiffType::Added => Color::Green,
             DiffType::Modified => Color::Cyan,
             DiffType::Deleted => Color::Red,
+            DiffType::Renamed => Color::Yellow,
+            DiffType::Copied => Color::Green,
         }
     }
 }
 
-// Example line: `A README.md`, `M src/main.rs`, `D Hello World`
-static FILES_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(.) (.*)").unwrap());
+// Example line: `A README.md`, `M src/main.rs`, `D Hello World`, `R src/{old.rs => new.rs}`
+static FILES_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^([A-Z]) (.+)$").unwrap());
+// Renamed or copied path, with the common prefix and suffix outside the braces, such as
+// `src/{old.rs => new.rs}` or `{a => b}/mod.rs`
+static RENAME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.*)\{(.*) => (.*)\}(.*)$").unwrap());
+// Example line: `Warning: Refused to snapshot some files:`, `Hint: ...`
+static WARNING_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(Warning|Hint|Error|Caution): ").unwrap());
 static CONFLICTS_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(.*)    .*").unwrap());
 
+/// Fileset matching a path, relative to the repository root. The path is quoted, so it may
+/// contain spaces, quotes or fileset operators.
+pub fn fileset_path(path: &str) -> String {
+    format!(
+        r#"root:"{}""#,
+        path.replace('\\', r"\\").replace('"', r#"\""#)
+    )
+}
+
+// Path of a file after the change, from its path in `jj diff --summary`. Only renames and copies
+// are shown as `{old => new}`, so other paths are kept as is, even if they contain ` => `
+fn summary_target_path(diff_type: Option<&DiffType>, path: &str) -> String {
+    if !matches!(diff_type, Some(DiffType::Renamed | DiffType::Copied)) {
+        return path.to_owned();
+    }
+
+    match RENAME_REGEX.captures(path) {
+        // A side is empty when the file moved to or from a parent directory, such as
+        // `{ => src}/main.rs`
+        Some(captured) => format!("{}{}{}", &captured[1], &captured[3], &captured[4])
+            .replace("//", "/")
+            .trim_start_matches('/')
+            .to_owned(),
+        None => path.to_owned(),
+    }
+}
+
+// Parse `jj diff --summary` output. Warning and hint lines (including their indented continuation
+// lines), and any other lines which aren't file entries, are returned as warnings
+fn parse_files(output: &str) -> FilesOutput {
+    let mut files_output = FilesOutput::default();
+    let mut in_warning = false;
+
+    for line in output.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if WARNING_REGEX.is_match(line) || (in_warning && line.starts_with(char::is_whitespace)) {
+            in_warning = true;
+            files_output.warnings.push(line.to_owned());
+            continue;
+        }
+        in_warning = false;
+
+        match FILES_REGEX.captures(line) {
+            Some(captured) => {
+                let diff_type = DiffType::parse(&captured[1]);
+                files_output.files.push(File {
+                    line: line.to_owned(),
+                    path: Some(summary_target_path(diff_type.as_ref(), &captured[2])),
+                    diff_type,
+                })
+            }
+            None => files_output.warnings.push(line.to_owned()),
+        }
+    }
+
+    files_output
+}
+
 impl Commander {
     /// Get list of changes files in a change. Parses the output.
     /// Maps to `jj diff --summary -r <revision>`
     #[instrument(level = "trace", skip(self))]
-    pub fn get_files(&mut self, head: &Head) -> Result<Vec<File>, CommandError> {
-        Ok(self
-            .execute_jj_command(
-                vec!["diff", "-r", head.commit_id.as_str(), "--summary"],
-                false,
-                true,
-            )?
-            .lines()
-            .map(|line| {
-                let captured = FILES_REGEX.captures(line);
-                let diff_type = captured
-                    .as_ref()
-                    .and_then(|captured| captured.get(1))
-                    .and_then(|inner_text| DiffType::parse(inner_text.as_str()));
-                let path = captured
-                    .as_ref()
-                    .and_then(|captured| captured.get(2))
-                    .map(|inner_text| inner_text.as_str().to_owned());
-
-                File {
-                    line: line.to_string(),
-                    path,
-                    diff_type,
-                }
-            })
-            .collect())
+    pub fn get_files(&mut self, head: &Head) -> Result<FilesOutput, CommandError> {
+        Ok(parse_files(&self.execute_jj_command(
+            vec!["diff", "-r", head.commit_id.as_str(), "--summary"],
+            false,
+            true,
+        )?))
     }
 
     /// Get list of changes files in a change. Parses the output.
@@ -109,7 +171,7 @@ impl Commander {
                     })
                     .collect())
             }
-            Err(CommandError::Status(_, Some(2))) => {
+            Err(CommandError::Status(_, Some(2), _)) => {
                 // No conflicts
                 Ok(vec![])
             }
@@ -117,7 +179,8 @@ impl Commander {
         }
     }
 
-    /// Get diff for file change in a change.
+    /// Get diff for file change in a change. The path is quoted, so it may contain spaces or
+    /// fileset operators.
     /// Maps to `jj diff -r <revision> <path>`
     #[instrument(level = "trace", skip(self))]
     pub fn get_file_diff(
@@ -126,16 +189,68 @@ impl Commander {
         current_file: &str,
         diff_format: &DiffFormat,
     ) -> Result<String, CommandError> {
-        self.execute_jj_command(
+        self.execute_diff_command(
             vec![
                 "diff",
                 "-r",
                 head.commit_id.as_str(),
-                current_file,
-                diff_format.get_arg(),
+                &fileset_path(current_file),
             ],
-            true,
-            true,
+            diff_format,
+        )
+    }
+
+    /// Get the content of a file at a revision as bytes, such as to measure a binary file.
+    /// Maps to `jj file show -r <revision> <path>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_file_content(
+        &mut self,
+        revision: &str,
+        path: &str,
+    ) -> Result<Vec<u8>, CommandError> {
+        self.execute_jj_command_bytes(["file", "show", "-r", revision, &fileset_path(path)])
+    }
+
+    /// Get diff for all files in a change.
+    /// Maps to `jj diff -r <revision>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_change_diff(
+        &mut self,
+        head: &Head,
+        diff_format: &DiffFormat,
+    ) -> Result<String, CommandError> {
+        self.execute_diff_command(vec!["diff", "-r", head.commit_id.as_str()], diff_format)
+    }
+
+    /// Get diff between two commits.
+    /// Maps to `jj diff --from <revision> --to <revision>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_diff_between(
+        &mut self,
+        from: &CommitId,
+        to: &CommitId,
+        diff_format: &DiffFormat,
+    ) -> Result<String, CommandError> {
+        self.execute_diff_command(
+            vec!["diff", "--from", from.as_str(), "--to", to.as_str()],
+            diff_format,
+        )
+    }
+
+    /// Get diff for a file between two revisions. A file missing in one of them is shown as added
+    /// or deleted.
+    /// Maps to `jj diff --from <revision> --to <revision> <path>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_file_diff_range(
+        &mut self,
+        from: &str,
+        to: &str,
+        path: &str,
+        diff_format: &DiffFormat,
+    ) -> Result<String, CommandError> {
+        self.execute_diff_command(
+            vec!["diff", "--from", from, "--to", to, &fileset_path(path)],
+            diff_format,
         )
     }
 }
@@ -143,10 +258,19 @@ impl Commander {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::commander::tests::TestRepo;
+    use crate::commander::tests::{apply_common_filters, TestRepo};
     use insta::assert_debug_snapshot;
     use std::fs;
 
+    #[test]
+    fn quote_fileset_path() {
+        assert_eq!(fileset_path("src/main.rs"), r#"root:"src/main.rs""#);
+        assert_eq!(
+            fileset_path(r#"my "quoted" file\name"#),
+            r#"root:"my \"quoted\" file\\name""#
+        );
+    }
+
     #[test]
     fn get_files() -> Result<()> {
         let mut test_repo = TestRepo::new()?;
@@ -155,7 +279,7 @@ mod tests {
         // Initial state
         {
             let head = test_repo.commander.get_current_head()?;
-            let files = test_repo.commander.get_files(&head)?;
+            let files = test_repo.commander.get_files(&head)?.files;
             assert_eq!(files, vec![]);
         }
 
@@ -164,7 +288,7 @@ mod tests {
             fs::write(&file_path, b"AAA")?;
 
             let head = test_repo.commander.get_current_head()?;
-            let files = test_repo.commander.get_files(&head)?;
+            let files = test_repo.commander.get_files(&head)?.files;
             assert_eq!(
                 files,
                 vec![File {
@@ -183,7 +307,7 @@ mod tests {
             fs::write(&file_path, b"BBB")?;
 
             let head = test_repo.commander.get_current_head()?;
-            let files = test_repo.commander.get_files(&head)?;
+            let files = test_repo.commander.get_files(&head)?.files;
             assert_eq!(
                 files,
                 vec![File {
@@ -199,7 +323,7 @@ mod tests {
             fs::remove_file(&file_path)?;
 
             let head = test_repo.commander.get_current_head()?;
-            let files = test_repo.commander.get_files(&head)?;
+            let files = test_repo.commander.get_files(&head)?.files;
             assert_eq!(
                 files,
                 vec![File {
@@ -213,6 +337,208 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn get_files_snapshots() -> Result<()> {
+        apply_common_filters!();
+
+        let mut test_repo = TestRepo::new()?;
+        let file_path = test_repo.directory.path().join("README");
+        let other_path = test_repo.directory.path().join("LICENSE");
+
+        // Empty change
+        let head = test_repo.commander.get_current_head()?;
+        assert_debug_snapshot!(test_repo.commander.get_files(&head)?);
+
+        // Added
+        fs::write(&file_path, b"AAA\n")?;
+        fs::write(&other_path, b"BBB\n")?;
+        let head = test_repo.commander.get_current_head()?;
+        assert_debug_snapshot!(test_repo.commander.get_files(&head)?);
+
+        // Modified and deleted, then renamed in a child
+        test_repo.commander.execute_void_jj_command(vec!["new"])?;
+        fs::write(&file_path, b"CCC\n")?;
+        fs::remove_file(&other_path)?;
+        test_repo.commander.execute_void_jj_command(vec!["new"])?;
+        fs::rename(&file_path, test_repo.directory.path().join("README.md"))?;
+        let head = test_repo.commander.get_current_head()?;
+        let parent = test_repo.commander.get_commit_parent(&head.commit_id)?;
+        assert_debug_snapshot!(test_repo.commander.get_files(&parent)?);
+        assert_debug_snapshot!(test_repo.commander.get_files(&head)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_files_with_warnings() {
+        let output = "\
+Warning: Refused to snapshot some files:
+  vendor/tool/.git: nested Git repository is not supported
+Hint: Add the directories to `.gitignore` to ignore them.
+A README.md
+M src/main.rs
+D vendor/old file.txt
+";
+
+        assert_eq!(
+            parse_files(output),
+            FilesOutput {
+                files: vec![
+                    File {
+                        line: "A README.md".to_owned(),
+                        path: Some("README.md".to_owned()),
+                        diff_type: Some(DiffType::Added),
+                    },
+                    File {
+                        line: "M src/main.rs".to_owned(),
+                        path: Some("src/main.rs".to_owned()),
+                        diff_type: Some(DiffType::Modified),
+                    },
+                    File {
+                        line: "D vendor/old file.txt".to_owned(),
+                        path: Some("vendor/old file.txt".to_owned()),
+                        diff_type: Some(DiffType::Deleted),
+                    },
+                ],
+                warnings: vec![
+                    "Warning: Refused to snapshot some files:".to_owned(),
+                    "  vendor/tool/.git: nested Git repository is not supported".to_owned(),
+                    "Hint: Add the directories to `.gitignore` to ignore them.".to_owned(),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn parse_files_unparsable_lines() {
+        let output = "\
+M Cargo.toml
+vendor/tool: skipping nested repository
+Warning: The working-copy commit in workspace 'default' became immutable
+
+A src/lib.rs
+";
+
+        let files_output = parse_files(output);
+        assert_eq!(
+            files_output
+                .files
+                .iter()
+                .map(|file| file.line.as_str())
+                .collect::<Vec<_>>(),
+            ["M Cargo.toml", "A src/lib.rs"]
+        );
+        assert_eq!(
+            files_output.warnings,
+            [
+                "vendor/tool: skipping nested repository",
+                "Warning: The working-copy commit in workspace 'default' became immutable",
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_renamed_files() {
+        let output = "\
+R src/{old.rs => new.rs}
+C {lib => src}/mod.rs
+R { => docs}/guide.md
+R {docs => }/faq.md
+A weird=>name.txt
+M {a => b}.txt
+";
+
+        assert_eq!(
+            parse_files(output)
+                .files
+                .iter()
+                .map(|file| (file.diff_type.clone(), file.path.as_deref()))
+                .collect::<Vec<_>>(),
+            [
+                (Some(DiffType::Renamed), Some("src/new.rs")),
+                (Some(DiffType::Copied), Some("src/mod.rs")),
+                (Some(DiffType::Renamed), Some("docs/guide.md")),
+                (Some(DiffType::Renamed), Some("faq.md")),
+                // Only renames and copies are parsed as such
+                (Some(DiffType::Added), Some("weird=>name.txt")),
+                (Some(DiffType::Modified), Some("{a => b}.txt")),
+            ]
+        );
+    }
+
+    #[test]
+    fn get_files_special_paths() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let paths = ["a b.txt", "weird=>name.txt", "ünïcödé 文件.txt"];
+        for path in paths {
+            fs::write(test_repo.directory.path().join(path), b"AAA\n")?;
+        }
+
+        let head = test_repo.commander.get_current_head()?;
+        let mut files: Vec<String> = test_repo
+            .commander
+            .get_files(&head)?
+            .files
+            .into_iter()
+            .filter_map(|file| file.path)
+            .collect();
+        files.sort();
+        assert_eq!(files, paths);
+
+        // Each path is passed to jj as is, so the diff is of that file only
+        for path in paths {
+            let diff = test_repo
+                .commander
+                .get_file_diff(&head, path, &DiffFormat::Summary)?;
+            assert_eq!(diff, format!("A {path}\n"));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_change_diff() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        fs::write(test_repo.directory.path().join("README"), b"AAA")?;
+        fs::write(test_repo.directory.path().join("LICENSE"), b"BBB")?;
+
+        let head = test_repo.commander.get_current_head()?;
+        assert_eq!(
+            test_repo
+                .commander
+                .get_change_diff(&head, &DiffFormat::Summary)?,
+            "A LICENSE\nA README\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_file_content() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        // Not UTF-8, as binary files usually aren't
+        let content = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\xff\xfe";
+        fs::write(test_repo.directory.path().join("logo.png"), content)?;
+
+        let head = test_repo.commander.get_current_head()?;
+        assert_eq!(
+            test_repo
+                .commander
+                .get_file_content(head.commit_id.as_str(), "logo.png")?,
+            content
+        );
+        // Missing before the change which added it
+        assert!(test_repo
+            .commander
+            .get_file_content(&format!("{}-", head.commit_id.as_str()), "logo.png")
+            .is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn get_file_diff() -> Result<()> {
         let mut test_repo = TestRepo::new()?;
@@ -276,8 +602,107 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn get_file_diff_formats() -> Result<()> {
+        apply_common_filters!();
+
+        let mut test_repo = TestRepo::new()?;
+        let file_path = test_repo.directory.path().join("README");
+
+        fs::write(&file_path, b"AAA\nBBB\n")?;
+        test_repo.commander.execute_void_jj_command(vec!["new"])?;
+        fs::write(&file_path, b"AAA\nCCC\n")?;
+        let head = test_repo.commander.get_current_head()?;
+
+        // Diff tools are left out, as they depend on what's installed
+        for diff_format in [
+            DiffFormat::ColorWords,
+            DiffFormat::Git,
+            DiffFormat::Summary,
+            DiffFormat::Stat,
+            DiffFormat::SideBySide,
+            DiffFormat::CharLevel,
+        ] {
+            assert_debug_snapshot!(
+                format!("get_file_diff_formats_{diff_format:?}"),
+                test_repo
+                    .commander
+                    .get_file_diff(&head, "README", &diff_format)?
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_diff_between() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let file_path = test_repo.directory.path().join("README");
+
+        let head0 = test_repo.commander.get_current_head()?;
+        fs::write(&file_path, b"AAA")?;
+        test_repo.commander.run_new("@", false)?;
+        fs::write(&file_path, b"BBB")?;
+        let head1 = test_repo.commander.get_current_head()?;
+        let head0 = test_repo.commander.get_head_latest(&head0)?;
+
+        let diff = test_repo.commander.get_diff_between(
+            &head0.commit_id,
+            &head1.commit_id,
+            &DiffFormat::Git,
+        )?;
+        assert!(diff.contains("-AAA"));
+        assert!(diff.contains("+BBB"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_file_diff_range() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let file_path = test_repo.directory.path().join("README");
+        let other_path = test_repo.directory.path().join("OTHER");
+
+        let head0 = test_repo.commander.get_current_head()?;
+        fs::write(&file_path, b"AAA")?;
+        test_repo.commander.run_new("@", false)?;
+        fs::write(&file_path, b"BBB")?;
+        fs::write(&other_path, b"CCC")?;
+        test_repo.commander.run_new("@", false)?;
+        fs::write(&file_path, b"DDD")?;
+        let head2 = test_repo.commander.get_current_head()?;
+        let head0 = test_repo.commander.get_head_latest(&head0)?;
+
+        // Only the file, across several changes
+        let diff = test_repo.commander.get_file_diff_range(
+            head0.commit_id.as_str(),
+            head2.commit_id.as_str(),
+            "README",
+            &DiffFormat::Git,
+        )?;
+        assert!(diff.contains("-AAA"));
+        assert!(diff.contains("+DDD"));
+        assert!(!diff.contains("CCC"));
+
+        // Missing in one of the revisions
+        let diff = test_repo.commander.get_file_diff_range(
+            head2.commit_id.as_str(),
+            "root()",
+            "OTHER",
+            &DiffFormat::Git,
+        )?;
+        assert!(diff.contains("deleted file"));
+        assert!(diff.contains("-CCC"));
+
+        Ok(())
+    }
+
     #[test]
     fn get_conflicts() -> Result<()> {
+        apply_common_filters!();
+
         let mut test_repo = TestRepo::new()?;
 
         let file_path = test_repo.directory.path().join("README");
@@ -285,14 +710,22 @@ mod tests {
         let head0 = test_repo.commander.get_current_head()?;
 
         // First change
-        test_repo.commander.run_new(head0.commit_id.as_str())?;
+        test_repo
+            .commander
+            .run_new(head0.commit_id.as_str(), false)?;
         let head1 = test_repo.commander.get_current_head()?;
         fs::write(&file_path, b"AAA")?;
 
-        test_repo.commander.run_new(head0.commit_id.as_str())?;
+        test_repo
+            .commander
+            .run_new(head0.commit_id.as_str(), false)?;
         let head2 = test_repo.commander.get_current_head()?;
         fs::write(&file_path, b"BBB")?;
 
+        // No conflicts before the rebase
+        let head2 = test_repo.commander.get_head_latest(&head2)?;
+        assert_eq!(test_repo.commander.get_conflicts(&head2.commit_id)?, []);
+
         test_repo.commander.execute_void_jj_command([
             "rebase",
             "-s",
@@ -304,6 +737,7 @@ mod tests {
         let head = test_repo.commander.get_current_head()?;
 
         let conflicts = test_repo.commander.get_conflicts(&head.commit_id)?;
+        assert_debug_snapshot!(conflicts);
 
         assert_eq!(
             conflicts,
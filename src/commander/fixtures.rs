@@ -0,0 +1,174 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Output,
+    sync::LazyLock,
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Emails, such as of authors. Also filtered from insta snapshots.
+pub const EMAIL_PATTERN: &str = r"[\w.+-]+@[\w-]+(\.[\w-]+)+";
+
+static EMAIL_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(EMAIL_PATTERN).unwrap());
+
+/// A command and its output, as recorded with `--record-fixtures`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub program: String,
+    pub args: Vec<String>,
+    pub status: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+// Fixtures are only replayed by tests
+#[cfg(test)]
+impl Fixture {
+    /// Result of the command, as returned by `Commander::execute_command`.
+    pub fn into_result(self) -> Result<String, crate::commander::CommandError> {
+        crate::commander::command_result(
+            self.status == Some(0),
+            self.status,
+            self.stdout.into_bytes(),
+            self.stderr.as_bytes(),
+        )
+    }
+}
+
+/// Writes each command run by the commander and its raw output to a numbered file in a
+/// directory, to harvest fixtures for parser tests from a real repository. The repository and
+/// home paths and emails are replaced, so fixtures can be committed.
+#[derive(Debug)]
+pub struct FixtureRecorder {
+    directory: PathBuf,
+    root: String,
+    home: Option<String>,
+    count: usize,
+}
+
+impl FixtureRecorder {
+    pub fn new(directory: &Path, root: &str) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+        Ok(Self {
+            directory: directory.to_owned(),
+            root: root.to_owned(),
+            home: std::env::var("HOME").ok().filter(|home| !home.is_empty()),
+            count: 0,
+        })
+    }
+
+    fn sanitize(&self, text: &str) -> String {
+        // The repository is usually inside the home directory, so is replaced first
+        let mut text = text.replace(&self.root, "[ROOT]");
+        if let Some(home) = self.home.as_ref() {
+            text = text.replace(home, "[HOME]");
+        }
+        EMAIL_REGEX.replace_all(&text, "[EMAIL]").into_owned()
+    }
+
+    fn fixture(
+        &self,
+        program: &str,
+        args: &[String],
+        status: Option<i32>,
+        stdout: &[u8],
+        stderr: &[u8],
+    ) -> Fixture {
+        Fixture {
+            program: program.to_owned(),
+            args: args.iter().map(|arg| self.sanitize(arg)).collect(),
+            status,
+            stdout: self.sanitize(&String::from_utf8_lossy(stdout)),
+            stderr: self.sanitize(&String::from_utf8_lossy(stderr)),
+        }
+    }
+
+    // Write a fixture to the next file, named by the program and subcommand, such as
+    // `0003-jj-log.json`
+    fn write(&mut self, fixture: &Fixture) -> io::Result<()> {
+        self.count += 1;
+        let name = [fixture.program.as_str()]
+            .into_iter()
+            .chain(fixture.args.first().map(String::as_str))
+            .map(|part| {
+                part.chars()
+                    .filter(|c| c.is_ascii_alphanumeric())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("-");
+        fs::write(
+            self.directory
+                .join(format!("{:04}-{name}.json", self.count)),
+            serde_json::to_string_pretty(fixture)?,
+        )
+    }
+
+    /// Record a command and its output.
+    pub fn record(&mut self, program: &str, args: &[String], output: &Output) -> io::Result<()> {
+        let fixture = self.fixture(
+            program,
+            args,
+            output.status.code(),
+            &output.stdout,
+            &output.stderr,
+        );
+        self.write(&fixture)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::{tests::load_fixture, CommandError};
+    use tempdir::TempDir;
+
+    #[test]
+    fn record_fixtures() -> anyhow::Result<()> {
+        let directory = TempDir::new("lazyjj-fixtures")?;
+        let mut recorder = FixtureRecorder::new(directory.path(), "/home/user/repo")?;
+        recorder.home = Some("/home/user".to_owned());
+
+        let fixture = recorder.fixture(
+            "jj",
+            &[
+                "log".to_owned(),
+                "-R".to_owned(),
+                "/home/user/repo".to_owned(),
+            ],
+            Some(0),
+            b"Author: Jane <jane.doe@example.com>\n/home/user/.config\n",
+            b"",
+        );
+        recorder.write(&fixture)?;
+
+        let path = directory.path().join("0001-jj-log.json");
+        let fixture: Fixture = serde_json::from_str(&fs::read_to_string(path)?)?;
+        assert_eq!(
+            fixture,
+            Fixture {
+                program: "jj".to_owned(),
+                args: vec!["log".to_owned(), "-R".to_owned(), "[ROOT]".to_owned()],
+                status: Some(0),
+                stdout: "Author: Jane <[EMAIL]>\n[HOME]/.config\n".to_owned(),
+                stderr: String::new(),
+            }
+        );
+        assert_eq!(
+            fixture.into_result()?,
+            "Author: Jane <[EMAIL]>\n[HOME]/.config\n"
+        );
+
+        // Failed commands replay as status errors
+        let fixture = recorder.fixture("jj", &["log".to_owned()], Some(1), b"", b"Error: nope\n");
+        recorder.write(&fixture)?;
+        assert!(matches!(
+            load_fixture(&directory.path().join("0002-jj-log.json")),
+            Err(CommandError::Status(_, Some(1), None))
+        ));
+
+        Ok(())
+    }
+}
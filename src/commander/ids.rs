@@ -43,9 +43,9 @@ impl CommitId {
         &self.0
     }
 
-    // pub fn as_string(&self) -> String {
-    //     self.0.to_owned()
-    // }
+    pub fn as_string(&self) -> String {
+        self.0.to_owned()
+    }
 }
 
 impl AsRef<OsStr> for CommitId {
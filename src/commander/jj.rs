@@ -1,13 +1,104 @@
-use crate::commander::{bookmarks::Bookmark, ids::CommitId, CommandError, Commander};
+use crate::commander::{
+    bookmarks::Bookmark, ids::CommitId, stream::StreamingCommand, CommandError, Commander,
+};
 
 use anyhow::{Context, Result};
+use itertools::Itertools;
 use tracing::instrument;
 
+/// Tool configured for `jj fix`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixTool {
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// Which changes `jj rebase` moves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RebaseMode {
+    /// Only the revision, with `-r`
+    Revision,
+    /// The revision and its descendants, with `-s`
+    Source,
+    /// The whole branch relative to the destination, with `-b`
+    Branch,
+}
+
+impl RebaseMode {
+    pub fn flag(&self) -> &'static str {
+        match self {
+            RebaseMode::Revision => "-r",
+            RebaseMode::Source => "-s",
+            RebaseMode::Branch => "-b",
+        }
+    }
+}
+
+// Parse `jj config list fix.tools` output, such as `fix.tools.rustfmt.command = ["rustfmt"]`
+fn parse_fix_tools(output: &str) -> Vec<FixTool> {
+    let tools: Vec<(&str, &str, &str)> = output
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            let (name, option) = key.trim().strip_prefix("fix.tools.")?.rsplit_once('.')?;
+            Some((name.trim_matches('"'), option, value.trim()))
+        })
+        .collect();
+
+    tools
+        .iter()
+        .map(|(name, _, _)| *name)
+        .unique()
+        .map(|name| FixTool {
+            name: name.to_owned(),
+            enabled: !tools.contains(&(name, "enabled", "false")),
+        })
+        .collect()
+}
+
+// Parts of jj's error when a push is rejected because a remote bookmark moved since the last
+// fetch, across jj versions
+const PUSH_REJECTED_ERRORS: &[&str] = &[
+    "not fast-forwardable",
+    "unexpectedly moved on the remote",
+    "(reason: stale info)",
+];
+
+/// If a push was rejected because remote bookmarks moved since the last fetch, such as when
+/// someone else pushed, get the names of the bookmarks. The names are empty if jj doesn't list
+/// them.
+pub fn parse_push_rejected(output: &str) -> Option<Vec<String>> {
+    if !PUSH_REJECTED_ERRORS
+        .iter()
+        .any(|error| output.contains(error))
+    {
+        return None;
+    }
+
+    Some(
+        output
+            .split_whitespace()
+            .filter_map(|word| {
+                word.trim_matches(|c: char| c == ',' || c == '`' || c == '"')
+                    .strip_prefix("refs/heads/")
+            })
+            .map(str::to_owned)
+            .unique()
+            .collect(),
+    )
+}
+
 impl Commander {
-    /// Create a new change after revision. Maps to `jj new <revision>`
+    /// Create a new change after revision. With `no_edit`, the working copy stays where it is.
+    /// Maps to `jj new [--no-edit] <revision>`
     #[instrument(level = "trace", skip(self))]
-    pub fn run_new(&mut self, revision: &str) -> Result<()> {
-        self.execute_void_jj_command(vec!["new", revision])
+    pub fn run_new(&mut self, revision: &str, no_edit: bool) -> Result<()> {
+        let mut args = vec!["new"];
+        if no_edit {
+            args.push("--no-edit");
+        }
+        args.push(revision);
+        self.execute_void_jj_command(args)
             .context("Failed executing jj new")
     }
 
@@ -18,6 +109,96 @@ impl Commander {
             .context("Failed executing jj edit")
     }
 
+    /// Move the working copy to the child change. Creates a new change on the child, or edits it
+    /// directly with `edit`.
+    /// Maps to `jj next [--edit]`
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_next(&mut self, edit: bool) -> Result<(), CommandError> {
+        let mut args = vec!["next"];
+        if edit {
+            args.push("--edit");
+        }
+        self.execute_void_jj_command(args)
+    }
+
+    /// Move the working copy to the parent change. Creates a new change on the parent, or edits it
+    /// directly with `edit`.
+    /// Maps to `jj prev [--edit]`
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_prev(&mut self, edit: bool) -> Result<(), CommandError> {
+        let mut args = vec!["prev"];
+        if edit {
+            args.push("--edit");
+        }
+        self.execute_void_jj_command(args)
+    }
+
+    /// Get fix tools configured with `fix.tools`, and whether they are enabled.
+    /// Maps to `jj config list fix.tools`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_fix_tools(&mut self) -> Result<Vec<FixTool>, CommandError> {
+        match self.execute_jj_command(vec!["config", "list", "fix.tools"], false, true) {
+            Ok(output) => Ok(parse_fix_tools(&output)),
+            // No tools configured
+            Err(CommandError::Status(_, Some(1), _)) => Ok(vec![]),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Run fix tools on a change and its descendants. Configured tools which aren't in
+    /// `enabled_tools` are disabled for this run.
+    /// Maps to `jj fix -s <commit> [--include-unchanged-files]`
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_fix(
+        &mut self,
+        commit_id: &CommitId,
+        include_unchanged: bool,
+        enabled_tools: &[String],
+    ) -> Result<(), CommandError> {
+        let disabled_tools_config: Vec<String> = self
+            .get_fix_tools()?
+            .into_iter()
+            .filter(|tool| !enabled_tools.contains(&tool.name))
+            .map(|tool| format!(r#"fix.tools."{}".enabled=false"#, tool.name))
+            .collect();
+
+        let mut args = vec!["fix", "-s", commit_id.as_str()];
+        if include_unchanged {
+            args.push("--include-unchanged-files");
+        }
+        for config in disabled_tools_config.iter() {
+            args.push(self.env.jj_config_flag);
+            args.push(config);
+        }
+
+        self.execute_void_jj_command(args)
+    }
+
+    /// Move changes from a revision into another change.
+    /// Maps to `jj squash --from <revision> --into <commit>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_squash(&mut self, from: &str, into: &CommitId) -> Result<(), CommandError> {
+        self.execute_void_jj_command(vec!["squash", "--from", from, "--into", into.as_str()])
+    }
+
+    /// Move changes onto a destination.
+    /// Maps to `jj rebase <-r|-s|-b> <revision> -d <destination>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_rebase(
+        &mut self,
+        mode: RebaseMode,
+        revision: &str,
+        destination: &CommitId,
+    ) -> Result<(), CommandError> {
+        self.execute_void_jj_command(vec![
+            "rebase",
+            mode.flag(),
+            revision,
+            "-d",
+            destination.as_str(),
+        ])
+    }
+
     /// Abandon change. Maps to `jj abandon <revision>`
     #[instrument(level = "trace", skip(self))]
     pub fn run_abandon(&mut self, commit_id: &CommitId) -> Result<()> {
@@ -136,19 +317,113 @@ impl Commander {
 
         self.execute_jj_command(args, true, true)
     }
+
+    /// Get the names of the Git remotes. Maps to `jj git remote list`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_git_remotes(&mut self) -> Result<Vec<String>, CommandError> {
+        Ok(self
+            .execute_jj_command(vec!["git", "remote", "list"], false, true)?
+            .lines()
+            .filter_map(|line| line.split_whitespace().next())
+            .map(str::to_owned)
+            .collect())
+    }
+
+    /// Git export, updating Git refs from jj bookmarks. Maps to `jj git export`
+    #[instrument(level = "trace", skip(self))]
+    pub fn git_export(&mut self) -> Result<String, CommandError> {
+        self.execute_jj_command(vec!["git", "export"], true, true)
+    }
+
+    /// Git import, updating jj bookmarks from Git refs. Maps to `jj git import`
+    #[instrument(level = "trace", skip(self))]
+    pub fn git_import(&mut self) -> Result<String, CommandError> {
+        self.execute_jj_command(vec!["git", "import"], true, true)
+    }
+
+    /// Run a shell command for each commit in a revset, streaming its output.
+    /// Maps to `jj run <command> -r <revset>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_exec(
+        &mut self,
+        revset: &str,
+        command: &str,
+    ) -> Result<StreamingCommand, CommandError> {
+        self.spawn_jj_command(vec!["run", command, "-r", revset], true, true)
+    }
+
+    /// Update a stale working copy, such as after another workspace rewrote its commit.
+    /// Maps to `jj workspace update-stale`
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_workspace_update_stale(&mut self) -> Result<String, CommandError> {
+        self.execute_jj_command(vec!["workspace", "update-stale"], true, true)
+    }
+
+    /// Set a value in the user config. The value is parsed as TOML if valid, such as an array.
+    /// Maps to `jj config set --user <name> <value>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn set_user_config(&mut self, name: &str, value: &str) -> Result<(), CommandError> {
+        self.execute_void_jj_command(vec!["config", "set", "--user", name, value])
+    }
+
+    /// Reset a sparse checkout, restoring all files to the working copy.
+    /// Maps to `jj sparse set --reset`
+    #[instrument(level = "trace", skip(self))]
+    pub fn reset_sparse(&mut self) -> Result<(), CommandError> {
+        self.execute_void_jj_command(vec!["sparse", "set", "--reset"])
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::commander::tests::TestRepo;
+    use std::fs;
+
+    #[test]
+    fn detect_push_rejected() {
+        // jj 0.12
+        assert_eq!(
+            parse_push_rejected(
+                "Error: The push conflicts with changes made on the remote (it is not fast-forwardable).
+Hint: Try fetching from the remote, then make the branch point to where you want it to be, and push again."
+            ),
+            Some(vec![])
+        );
+        // jj 0.15
+        assert_eq!(
+            parse_push_rejected(
+                "Error: Refusing to push a branch that unexpectedly moved on the remote. Affected refs: refs/heads/main
+Hint: Try fetching from the remote, then make the branch point to where you want it to be, and push again."
+            ),
+            Some(vec!["main".to_owned()])
+        );
+        // jj 0.25
+        assert_eq!(
+            parse_push_rejected(
+                "Error: Failed to push some bookmarks
+Hint: The following references unexpectedly moved on the remote:
+  refs/heads/main (reason: stale info)
+  refs/heads/feature/login (reason: stale info)
+Hint: Try fetching from the remote, then make the bookmark point to where you want it to be, and push again."
+            ),
+            Some(vec!["main".to_owned(), "feature/login".to_owned()])
+        );
+
+        assert_eq!(
+            parse_push_rejected("Error: Won't push commit 1234abcd since it has no description"),
+            None
+        );
+    }
 
     #[test]
     fn run_new() -> Result<()> {
         let mut test_repo = TestRepo::new()?;
 
         let head = test_repo.commander.get_current_head()?;
-        test_repo.commander.run_new(head.commit_id.as_str())?;
+        test_repo
+            .commander
+            .run_new(head.commit_id.as_str(), false)?;
         assert_eq!(
             test_repo
                 .commander
@@ -165,12 +440,69 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn get_git_remotes() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        assert!(test_repo.commander.get_git_remotes()?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_new_no_edit() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let head = test_repo.commander.get_current_head()?;
+        test_repo.commander.run_new(head.commit_id.as_str(), true)?;
+        assert_eq!(head, test_repo.commander.get_current_head()?);
+
+        let child = test_repo.commander.get_latest_child(&head.commit_id)?;
+        assert_ne!(head, child);
+        assert_eq!(
+            test_repo.commander.get_commit_parent(&child.commit_id)?,
+            head
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_rebase() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let head0 = test_repo.commander.get_current_head()?;
+        test_repo
+            .commander
+            .run_new(head0.commit_id.as_str(), false)?;
+        let head1 = test_repo.commander.get_current_head()?;
+        test_repo
+            .commander
+            .run_new(head0.commit_id.as_str(), false)?;
+        let head2 = test_repo.commander.get_current_head()?;
+
+        test_repo.commander.run_rebase(
+            RebaseMode::Revision,
+            head2.change_id.as_str(),
+            &head1.commit_id,
+        )?;
+        let head2 = test_repo.commander.get_head_latest(&head2)?;
+        assert_eq!(
+            test_repo.commander.get_commit_parent(&head2.commit_id)?,
+            head1
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn run_edit() -> Result<()> {
         let mut test_repo = TestRepo::new()?;
 
         let head = test_repo.commander.get_current_head()?;
-        test_repo.commander.run_new(head.commit_id.as_str())?;
+        test_repo
+            .commander
+            .run_new(head.commit_id.as_str(), false)?;
         assert_ne!(head, test_repo.commander.get_current_head()?);
         test_repo.commander.run_edit(head.commit_id.as_str())?;
         assert_eq!(
@@ -189,6 +521,53 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn run_prev_next() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        test_repo.commander.run_new("@", false)?;
+        let head1 = test_repo.commander.get_current_head()?;
+        test_repo.commander.run_new("@", false)?;
+        // Prevent the change from being abandoned when moving away from it
+        fs::write(test_repo.directory.path().join("README"), b"AAA")?;
+        let head2 = test_repo.commander.get_current_head()?;
+
+        test_repo.commander.run_prev(true)?;
+        assert_eq!(
+            test_repo.commander.get_current_head()?.change_id,
+            head1.change_id
+        );
+
+        test_repo.commander.run_next(true)?;
+        assert_eq!(
+            test_repo.commander.get_current_head()?.change_id,
+            head2.change_id
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_squash() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let target = test_repo.commander.get_current_head()?;
+        test_repo.commander.run_new("@", false)?;
+        fs::write(test_repo.directory.path().join("README"), b"AAA")?;
+
+        test_repo.commander.run_squash("@", &target.commit_id)?;
+        let target = test_repo.commander.get_head_latest(&target)?;
+        assert_eq!(test_repo.commander.get_files(&target)?.files.len(), 1);
+        let current_head = test_repo.commander.get_current_head()?;
+        assert!(test_repo
+            .commander
+            .get_files(&current_head)?
+            .files
+            .is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn run_abandon() -> Result<()> {
         let mut test_repo = TestRepo::new()?;
@@ -255,7 +634,9 @@ mod tests {
 
         // Create new change, since by default `jj bookmark create` uses current change
         let head = test_repo.commander.get_current_head()?;
-        test_repo.commander.run_new(head.commit_id.as_str())?;
+        test_repo
+            .commander
+            .run_new(head.commit_id.as_str(), false)?;
         assert_ne!(head, test_repo.commander.get_current_head()?);
 
         let bookmark = test_repo
@@ -288,7 +669,9 @@ mod tests {
 
         // Create new change, since by default `jj bookmark create` uses current change
         let old_head = test_repo.commander.get_current_head()?;
-        test_repo.commander.run_new(old_head.commit_id.as_str())?;
+        test_repo
+            .commander
+            .run_new(old_head.commit_id.as_str(), false)?;
         let new_head = test_repo.commander.get_current_head()?;
         assert_ne!(old_head, new_head);
 
@@ -394,4 +777,56 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn run_workspace_update_stale() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        // Not stale, so there is nothing to update
+        test_repo.commander.run_workspace_update_stale()?;
+        test_repo.commander.get_current_head()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn reset_sparse() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        fs::write(test_repo.directory.path().join("README"), b"AAA")?;
+        test_repo
+            .commander
+            .execute_void_jj_command(vec!["sparse", "set", "--clear"])?;
+        assert!(!test_repo.directory.path().join("README").exists());
+
+        test_repo.commander.reset_sparse()?;
+        assert!(test_repo.directory.path().join("README").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_fix_tools_config() {
+        let output = r#"fix.tools.rustfmt.command = ["rustfmt", "--emit", "stdout"]
+fix.tools.rustfmt.patterns = ["glob:'**/*.rs'"]
+fix.tools."black-py".command = ["black", "-"]
+fix.tools."black-py".patterns = ["glob:'**/*.py'"]
+fix.tools."black-py".enabled = false
+"#;
+
+        assert_eq!(
+            parse_fix_tools(output),
+            [
+                FixTool {
+                    name: "rustfmt".to_owned(),
+                    enabled: true,
+                },
+                FixTool {
+                    name: "black-py".to_owned(),
+                    enabled: false,
+                },
+            ]
+        );
+        assert_eq!(parse_fix_tools(""), []);
+    }
 }
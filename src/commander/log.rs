@@ -1,16 +1,20 @@
 use crate::{
     commander::{
         bookmarks::Bookmark,
+        files::fileset_path,
         ids::{ChangeId, CommitId},
         CommandError, Commander, RemoveEndLine,
     },
-    env::DiffFormat,
+    env::{DiffFormat, GraphStyle},
+    ui::utils::{escape_control_chars, graph::unicode_graph},
 };
 
 use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
 use itertools::Itertools;
 use regex::Regex;
-use std::{fmt::Display, sync::LazyLock};
+use serde::Serialize;
+use std::{collections::HashSet, fmt::Display, sync::LazyLock};
 use thiserror::Error;
 use tracing::instrument;
 
@@ -20,6 +24,9 @@ pub struct Head {
     pub commit_id: CommitId,
     pub divergent: bool,
     pub immutable: bool,
+    pub hidden: bool,
+    pub conflict: bool,
+    pub empty: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -27,9 +34,111 @@ pub struct LogOutput {
     pub graph: String,
     // Maps graph line -> heads
     pub graph_heads: Vec<Option<Head>>,
+    // Maps graph line -> committer timestamp, on the first line of each change
+    pub graph_timestamps: Vec<Option<DateTime<Utc>>>,
     pub heads: Vec<Head>,
 }
 
+/// Change of the log with all its details, exported as JSON for scripts.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HeadInfo {
+    pub change_id: String,
+    pub commit_id: String,
+    // Commit IDs
+    pub parents: Vec<String>,
+    pub author_name: String,
+    pub author_email: String,
+    // Seconds since epoch
+    pub author_timestamp: i64,
+    pub committer_timestamp: i64,
+    // Local bookmarks
+    pub bookmarks: Vec<String>,
+    pub divergent: bool,
+    pub immutable: bool,
+    pub hidden: bool,
+    pub conflict: bool,
+    pub empty: bool,
+    pub current_working_copy: bool,
+    pub description: String,
+}
+
+/// Change in the working copy's stack, from `Commander::get_stack_summary`, or a relative of a
+/// change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StackEntry {
+    pub head: Head,
+    // First line of the description
+    pub title: String,
+}
+
+/// Parents and children of a change, from `Commander::get_relatives`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Relatives {
+    pub parents: Vec<StackEntry>,
+    pub children: Vec<StackEntry>,
+}
+
+/// Syntax error in a revset, parsed from jj's error output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RevsetError {
+    // 1-based position of the error in the revset
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+static REVSET_ERROR_POSITION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:--> (\d+):(\d+)|at line (\d+), column (\d+))").unwrap());
+
+/// Parse a revset syntax error, such as:
+///
+/// ```text
+/// Error: Failed to parse revset: Syntax error
+/// Caused by:  --> 1:8
+///   |
+/// 1 | trunk()|
+///   |        ^---
+///   |
+///   = expected <expression>
+/// Hint: ...
+/// ```
+///
+/// Returns None for other errors, such as a revision which doesn't exist.
+pub fn parse_revset_error(output: &str) -> Option<RevsetError> {
+    let message = output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Error: Failed to parse revset: "))?;
+    let captures = REVSET_ERROR_POSITION_REGEX.captures(output)?;
+    let number = |first: usize, second: usize| {
+        captures
+            .get(first)
+            .or(captures.get(second))
+            .and_then(|number| number.as_str().parse().ok())
+    };
+    let detail = output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("= "));
+    let hint = output
+        .lines()
+        .filter_map(|line| line.strip_prefix("Hint: "))
+        .collect::<Vec<_>>();
+
+    Some(RevsetError {
+        line: number(1, 3)?,
+        column: number(2, 4)?,
+        message: match detail {
+            Some(detail) => format!("{message}: {detail}"),
+            None => message.to_owned(),
+        },
+        hint: if hint.is_empty() {
+            None
+        } else {
+            Some(hint.join(" "))
+        },
+    })
+}
+
 #[derive(Error, Debug)]
 pub struct HeadParseError(String);
 
@@ -39,13 +148,125 @@ impl Display for HeadParseError {
     }
 }
 
-// Template which outputs `[change_id|commit_id|divergent]`. Used to parse data from log and other
-// commands which supports templating.
-const HEAD_TEMPLATE: &str =
-    r#""[" ++ change_id ++ "|" ++ commit_id ++ "|" ++ divergent ++ "|" ++ immutable ++ "]""#;
+// Template which outputs `[change_id|commit_id|divergent|immutable|hidden|conflict|empty]`. Used to
+// parse data from log and other commands which supports templating.
+const HEAD_TEMPLATE: &str = r#""[" ++ change_id ++ "|" ++ commit_id ++ "|" ++ divergent ++ "|" ++ immutable ++ "|" ++ hidden ++ "|" ++ conflict ++ "|" ++ empty ++ "]""#;
 // Regex to parse HEAD_TEMPLATE
 static HEAD_TEMPLATE_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"\[(.*)\|(.*)\|(.*)\|(.*)\]").unwrap());
+    LazyLock::new(|| Regex::new(r"\[(.*)\|(.*)\|(.*)\|(.*)\|(.*)\|(.*)\|(.*)\]").unwrap());
+
+// Template which outputs `{timestamp}`, with the committer timestamp in seconds since epoch
+const TIMESTAMP_TEMPLATE: &str = r#""{" ++ committer.timestamp().format("%s") ++ "}""#;
+// Regex to parse TIMESTAMP_TEMPLATE at the end of a line
+static TIMESTAMP_TEMPLATE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{(\d+)\}$").unwrap());
+
+// Separators of HEAD_INFO_TEMPLATE's fields, and after each change. The description is last, as it
+// may contain new lines
+const HEAD_INFO_FIELD_SEPARATOR: char = '␟';
+const HEAD_INFO_END_MARKER: char = '␞';
+// Template which outputs the fields of `HeadInfo`, in order
+static HEAD_INFO_TEMPLATE: LazyLock<String> = LazyLock::new(|| {
+    [
+        "change_id",
+        "commit_id",
+        r#"parents.map(|c| c.commit_id()).join(",")"#,
+        "author.name()",
+        "author.email()",
+        r#"author.timestamp().format("%s")"#,
+        r#"committer.timestamp().format("%s")"#,
+        r#"local_bookmarks.map(|b| b.name()).join(",")"#,
+        "divergent",
+        "immutable",
+        "hidden",
+        "conflict",
+        "empty",
+        "current_working_copy",
+        "description",
+    ]
+    .join(&format!(r#" ++ "{HEAD_INFO_FIELD_SEPARATOR}" ++ "#))
+        + &format!(r#" ++ "{HEAD_INFO_END_MARKER}""#)
+});
+
+// Parse changes output with HEAD_INFO_TEMPLATE
+fn parse_head_infos(output: &str) -> Result<Vec<HeadInfo>> {
+    let list = |field: &str| -> Vec<String> {
+        field
+            .split(',')
+            .filter(|item| !item.is_empty())
+            .map(str::to_owned)
+            .collect()
+    };
+
+    output
+        .split(HEAD_INFO_END_MARKER)
+        .map(|text| text.trim_start_matches('\n'))
+        .filter(|text| !text.is_empty())
+        .map(|text| {
+            // Fields are read in the order of the struct
+            let mut fields = text.splitn(15, HEAD_INFO_FIELD_SEPARATOR);
+            let mut next = || fields.next().ok_or_else(|| HeadParseError(text.to_owned()));
+            let timestamp = |field: &str| {
+                field
+                    .parse::<i64>()
+                    .map_err(|_| HeadParseError(text.to_owned()))
+            };
+
+            Ok(HeadInfo {
+                change_id: next()?.to_owned(),
+                commit_id: next()?.to_owned(),
+                parents: list(next()?),
+                author_name: next()?.to_owned(),
+                author_email: next()?.to_owned(),
+                author_timestamp: timestamp(next()?)?,
+                committer_timestamp: timestamp(next()?)?,
+                bookmarks: list(next()?),
+                divergent: next()? == "true",
+                immutable: next()? == "true",
+                hidden: next()? == "true",
+                conflict: next()? == "true",
+                empty: next()? == "true",
+                current_working_copy: next()? == "true",
+                description: next()?.trim_end().to_owned(),
+            })
+        })
+        .collect()
+}
+
+// Markers around the head and timestamp of each change, and after each change, when using a custom
+// log template. The end marker also separates multi-line descriptions
+const LOG_START_MARKER: char = '␞';
+const LOG_END_MARKER: char = '␟';
+// Output is sanitized, so only contains SGR escape codes
+pub(crate) static ANSI_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\x1b\[[0-9;:]*m").unwrap());
+
+// Parse a committer timestamp with TIMESTAMP_TEMPLATE.
+fn parse_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    TIMESTAMP_TEMPLATE_REGEX
+        .captures(text.trim_end())
+        .and_then(|captured| captured.get(1))
+        .and_then(|timestamp| timestamp.as_str().parse().ok())
+        .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+}
+
+// Template which outputs a change's head and the first line of its description
+static TITLED_HEAD_TEMPLATE: LazyLock<String> =
+    LazyLock::new(|| format!(r#"{HEAD_TEMPLATE} ++ " " ++ description.first_line() ++ "\n""#));
+
+// Parse changes output with TITLED_HEAD_TEMPLATE
+fn parse_titled_heads(output: &str) -> Vec<StackEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (head, title) = line.split_once("] ").unwrap_or((line, ""));
+            parse_head(&format!("{head}]")).ok().map(|head| StackEntry {
+                head,
+                title: escape_control_chars(title.trim()).into_owned(),
+            })
+        })
+        .collect()
+}
 
 // Parse a head with HEAD_TEMPLATE.
 fn parse_head(text: &str) -> Result<Head> {
@@ -53,17 +274,31 @@ fn parse_head(text: &str) -> Result<Head> {
     captured
         .as_ref()
         .map_or(Err(anyhow!(HeadParseError(text.to_owned()))), |captured| {
-            if let (Some(change_id), Some(commit_id), Some(divergent), Some(immutable)) = (
+            if let (
+                Some(change_id),
+                Some(commit_id),
+                Some(divergent),
+                Some(immutable),
+                Some(hidden),
+                Some(conflict),
+                Some(empty),
+            ) = (
                 captured.get(1),
                 captured.get(2),
                 captured.get(3),
                 captured.get(4),
+                captured.get(5),
+                captured.get(6),
+                captured.get(7),
             ) {
                 Ok(Head {
                     change_id: ChangeId(change_id.as_str().to_string()),
                     commit_id: CommitId(commit_id.as_str().to_string()),
                     divergent: divergent.as_str() == "true",
                     immutable: immutable.as_str() == "true",
+                    hidden: hidden.as_str() == "true",
+                    conflict: conflict.as_str() == "true",
+                    empty: empty.as_str() == "true",
                 })
             } else {
                 bail!(HeadParseError(text.to_owned()))
@@ -71,11 +306,79 @@ fn parse_head(text: &str) -> Result<Head> {
         })
 }
 
+// Quote text as a string literal for revsets and templates
+fn string_literal(text: &str) -> String {
+    format!(r#""{}""#, text.replace('\\', r"\\").replace('"', r#"\""#))
+}
+
+// Whether a line only continues the graph's edges, without text
+fn is_graph_continuation(line: &str) -> bool {
+    ANSI_REGEX
+        .replace_all(line, "")
+        .chars()
+        .all(|c| matches!(c, ' ' | '│' | '|' | ':' | '┆'))
+}
+
+type MarkedLog = (String, Vec<Option<Head>>, Vec<Option<DateTime<Utc>>>);
+
+// Parse log output from a custom template, with each change's output wrapped in markers. Lines
+// from a change's start marker to its end marker are mapped to the change, and the markers are
+// removed. An end marker on its own line, after a template ending with a new line, is removed with
+// its line
+fn parse_marked_log(output: &str) -> MarkedLog {
+    let mut graph = String::new();
+    let mut graph_heads = vec![];
+    let mut graph_timestamps = vec![];
+    let mut current_head: Option<Head> = None;
+
+    for line in output.lines() {
+        let mut line = line.to_owned();
+        let mut timestamp = None;
+
+        let marked = line
+            .split_once(LOG_START_MARKER)
+            .and_then(|(before, rest)| {
+                rest.split_once(LOG_START_MARKER)
+                    .map(|(marked, after)| (before.to_owned() + after, marked.to_owned()))
+            });
+        if let Some((unmarked_line, marked)) = marked {
+            let marked = ANSI_REGEX.replace_all(&marked, "");
+            current_head = parse_head(&marked).ok();
+            timestamp = parse_timestamp(&marked);
+            line = unmarked_line;
+        }
+
+        let head = current_head.clone();
+        if line.contains(LOG_END_MARKER) {
+            line = line.replace(LOG_END_MARKER, "");
+            current_head = None;
+            if is_graph_continuation(&line) {
+                continue;
+            }
+        }
+
+        graph.push_str(&line);
+        graph.push('\n');
+        graph_heads.push(head);
+        graph_timestamps.push(timestamp);
+    }
+
+    (graph, graph_heads, graph_timestamps)
+}
+
 impl Commander {
-    /// Get log. Returns human readable log and mapping to log line to head.
-    /// Maps to `jj log`
+    /// Get log. Returns human readable log and mapping to log line to head. Without the graph,
+    /// each change uses 1 line. A custom template can use any number of lines per change. With a
+    /// path, only changes touching it are shown.
+    /// Maps to `jj log [--no-graph] [--template <template>] [-- <path>]`
     #[instrument(level = "trace", skip(self))]
-    pub fn get_log(&mut self, revset: &Option<String>) -> Result<LogOutput, CommandError> {
+    pub fn get_log(
+        &mut self,
+        revset: &Option<String>,
+        no_graph: bool,
+        template: &Option<String>,
+        path: &Option<String>,
+    ) -> Result<LogOutput, CommandError> {
         let mut args = vec![];
 
         if let Some(revset) = revset {
@@ -83,41 +386,101 @@ impl Commander {
             args.push(revset);
         }
 
-        // Force builtin_log_compact which uses 2 lines per change
+        // Passed as a fileset argument rather than in the revset, so it doesn't need escaping
+        let fileset = path.as_deref().map(fileset_path);
+        if let Some(fileset) = fileset.as_ref() {
+            args.push("--");
+            args.push(fileset);
+        }
+
+        if no_graph {
+            args.push("--no-graph");
+        } else if self.env.config.graph_style() == GraphStyle::Ascii {
+            args.push(self.env.jj_config_flag);
+            args.push(r#"ui.graph.style="ascii""#);
+        }
+
+        if let Some(template) = template {
+            let marked_template = format!(
+                r#""{LOG_START_MARKER}" ++ {HEAD_TEMPLATE} ++ " " ++ {TIMESTAMP_TEMPLATE} ++ "{LOG_START_MARKER}" ++ ({template}) ++ "{LOG_END_MARKER}""#
+            );
+            let output = self.execute_jj_command(
+                [vec!["log", "--template", &marked_template], args].concat(),
+                true,
+                true,
+            )?;
+            let (graph, graph_heads, graph_timestamps) = parse_marked_log(&output);
+            let graph = if !no_graph && self.env.config.unicode_graph() {
+                unicode_graph(&graph)
+            } else {
+                graph
+            };
+            let heads = graph_heads.clone().into_iter().flatten().unique().collect();
+
+            return Ok(LogOutput {
+                graph,
+                graph_heads,
+                graph_timestamps,
+                heads,
+            });
+        }
+
+        // Force builtin_log_compact which uses 2 lines per change, or builtin_log_oneline which
+        // uses 1 line per change without the graph
         let graph = self.execute_jj_command(
             [
-                vec!["log", "--template", "builtin_log_compact"],
+                vec![
+                    "log",
+                    "--template",
+                    if no_graph {
+                        "builtin_log_oneline"
+                    } else {
+                        "builtin_log_compact"
+                    },
+                ],
                 args.clone(),
             ]
             .concat(),
             true,
             true,
         )?;
+        let graph = if !no_graph && self.env.config.unicode_graph() {
+            unicode_graph(&graph)
+        } else {
+            graph
+        };
 
-        let graph_heads: Vec<Option<Head>> = self
-            .execute_jj_command(
-                [
-                    vec![
-                        "log",
-                        "--template",
-                        // Match builtin_log_compact with 2 lines per change
-                        &format!(r#"{0} ++ " " ++ bookmarks ++"\n" ++ {0}"#, HEAD_TEMPLATE),
-                    ],
-                    args,
-                ]
-                .concat(),
-                false,
-                true,
-            )?
+        let heads_template = if no_graph {
+            // Match builtin_log_oneline with 1 line per change
+            format!(
+                r#"{} ++ " " ++ {} ++ "\n""#,
+                HEAD_TEMPLATE, TIMESTAMP_TEMPLATE
+            )
+        } else {
+            // Match builtin_log_compact with 2 lines per change
+            format!(
+                r#"{0} ++ " " ++ bookmarks ++ " " ++ {1} ++ "\n" ++ {0}"#,
+                HEAD_TEMPLATE, TIMESTAMP_TEMPLATE
+            )
+        };
+        let heads_output = self.execute_jj_command(
+            [vec!["log", "--template", &heads_template], args].concat(),
+            false,
+            true,
+        )?;
+
+        let graph_heads: Vec<Option<Head>> = heads_output
             .lines()
             .map(|line| parse_head(line).ok())
             .collect();
+        let graph_timestamps = heads_output.lines().map(parse_timestamp).collect();
 
         let heads = graph_heads.clone().into_iter().flatten().unique().collect();
 
         Ok(LogOutput {
             graph,
             graph_heads,
+            graph_timestamps,
             heads,
         })
     }
@@ -131,14 +494,88 @@ impl Commander {
         diff_format: &DiffFormat,
     ) -> Result<String, CommandError> {
         Ok(self
-            .execute_jj_command(
-                vec!["show", commit_id.as_str(), diff_format.get_arg()],
-                true,
-                true,
-            )?
+            .execute_diff_command(vec!["show", commit_id.as_str()], diff_format)?
             .remove_end_line())
     }
 
+    /// Get the first changes of a revset rendered with a template, to preview the template.
+    /// Maps to `jj log --no-graph --limit 3 --template <template>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_log_preview(
+        &mut self,
+        revset: &Option<String>,
+        template: &str,
+    ) -> Result<String, CommandError> {
+        let mut args = vec!["log", "--no-graph", "--limit", "3", "--template", template];
+        if let Some(revset) = revset {
+            args.push("-r");
+            args.push(revset);
+        }
+        self.execute_jj_command(args, true, true)
+    }
+
+    /// Check that a revset is valid, without rendering the log.
+    /// Maps to `jj log -r <revset> --limit 1`
+    #[instrument(level = "trace", skip(self))]
+    pub fn check_revset(&mut self, revset: &str) -> Result<(), CommandError> {
+        self.execute_jj_command(
+            vec![
+                "log",
+                "--no-graph",
+                "--ignore-working-copy",
+                "--limit",
+                "1",
+                "--template",
+                "\"\"",
+                "-r",
+                revset,
+            ],
+            false,
+            true,
+        )?;
+        Ok(())
+    }
+
+    /// Show how jj parses and evaluates a revset, to debug revsets matching unexpected changes.
+    /// Maps to `jj debug revset <revset>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn debug_revset(&mut self, revset: &str) -> Result<String, CommandError> {
+        self.execute_jj_command(
+            vec!["debug", "revset", "--ignore-working-copy", revset],
+            false,
+            true,
+        )
+    }
+
+    /// Get the changes of the log with all their details, such as to export them as JSON. With a
+    /// path, only changes touching it are included.
+    /// Maps to `jj log --no-graph --template <template> [-r <revset>] [-- <path>]`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_head_infos(
+        &mut self,
+        revset: &Option<String>,
+        path: &Option<String>,
+    ) -> Result<Vec<HeadInfo>> {
+        let mut args = vec!["log", "--no-graph", "--template", &HEAD_INFO_TEMPLATE];
+
+        if let Some(revset) = revset {
+            args.push("-r");
+            args.push(revset);
+        }
+
+        let fileset = path.as_deref().map(fileset_path);
+        if let Some(fileset) = fileset.as_ref() {
+            args.push("--");
+            args.push(fileset);
+        }
+
+        parse_head_infos(
+            &self
+                .execute_jj_command(args, false, true)
+                .context("Failed getting log")?,
+        )
+    }
+
     /// Get the current head.
     /// Maps to `jj log -r @`
     #[instrument(level = "trace", skip(self))]
@@ -253,6 +690,31 @@ impl Commander {
         )
     }
 
+    /// Get the latest created child of a commit, such as one created with `jj new --no-edit`.
+    /// Maps to `jj log -r 'latest(<revision>+)'`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_latest_child(&mut self, commit_id: &CommitId) -> Result<Head> {
+        parse_head(
+            &self
+                .execute_jj_command(
+                    vec![
+                        "log",
+                        "--no-graph",
+                        "--template",
+                        &format!(r#"{} ++ "\n""#, HEAD_TEMPLATE),
+                        "-r",
+                        &format!("latest({}+)", commit_id),
+                        "--limit",
+                        "1",
+                    ],
+                    false,
+                    true,
+                )
+                .with_context(|| format!("Failed getting latest commit child: {commit_id}"))?
+                .remove_end_line(),
+        )
+    }
+
     /// Get commit's description.
     /// Maps to `jj log -r <revision> -T description`
     #[instrument(level = "trace", skip(self))]
@@ -276,6 +738,84 @@ impl Commander {
             .remove_end_line())
     }
 
+    /// Get the heads and descriptions of a revset's changes, oldest first.
+    /// Maps to `jj log --reversed -r <revset> -T description`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_descriptions(&mut self, revset: &str) -> Result<Vec<(Head, String)>> {
+        self.execute_jj_command(
+            vec![
+                "log",
+                "--no-graph",
+                "--reversed",
+                "--template",
+                &format!(r#"{HEAD_TEMPLATE} ++ "\n" ++ description ++ "{LOG_END_MARKER}""#),
+                "-r",
+                revset,
+            ],
+            false,
+            true,
+        )
+        .with_context(|| format!("Failed getting descriptions: {revset}"))?
+        .split(LOG_END_MARKER)
+        .filter(|change| !change.trim().is_empty())
+        .map(|change| {
+            let (head, description) = change.split_once('\n').unwrap_or((change, ""));
+            Ok((
+                parse_head(head.trim_start())?,
+                description.trim_end().to_owned(),
+            ))
+        })
+        .collect()
+    }
+
+    /// Get the changes from trunk to the working copy, oldest first. Empty if the working copy
+    /// isn't a descendant of trunk.
+    /// Maps to `jj log --reversed -r 'trunk()::@'`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_stack_summary(&mut self) -> Result<Vec<StackEntry>, CommandError> {
+        Ok(parse_titled_heads(&self.execute_jj_command(
+            vec![
+                "log",
+                "--no-graph",
+                "--reversed",
+                "--ignore-working-copy",
+                "--template",
+                &TITLED_HEAD_TEMPLATE,
+                "-r",
+                "trunk()::@",
+            ],
+            false,
+            true,
+        )?))
+    }
+
+    /// Get the parents and direct children of a change, with their titles.
+    /// Maps to `jj log -r <revision>-` and `jj log -r <revision>+`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_relatives(&mut self, commit_id: &CommitId) -> Result<Relatives, CommandError> {
+        let mut get_heads = |revset: String| -> Result<Vec<StackEntry>, CommandError> {
+            Ok(parse_titled_heads(&self.execute_jj_command(
+                vec![
+                    "log",
+                    "--no-graph",
+                    "--reversed",
+                    "--ignore-working-copy",
+                    "--template",
+                    &TITLED_HEAD_TEMPLATE,
+                    "-r",
+                    &revset,
+                ],
+                false,
+                true,
+            )?))
+        };
+
+        Ok(Relatives {
+            parents: get_heads(format!("{commit_id}-"))?,
+            children: get_heads(format!("{commit_id}+"))?,
+        })
+    }
+
     /// Check if a revision is immutable
     /// Maps to `jj log -r <revision> -T immutable`
     #[instrument(level = "trace", skip(self))]
@@ -300,6 +840,130 @@ impl Commander {
             == "true")
     }
 
+    /// Get changes with an empty description, or one starting with `wip_prefix`, which would be
+    /// pushed by `jj git push`. Limited to a few results since it runs before every push.
+    /// Maps to `jj log -r '(remote_bookmarks()..<revision>) & (description(exact:"") | description(substring:<wip_prefix>))'`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_push_empty_description_heads(
+        &mut self,
+        all_bookmarks: bool,
+        commit_id: &CommitId,
+        wip_prefix: &str,
+    ) -> Result<Vec<Head>> {
+        let target = if all_bookmarks {
+            "bookmarks()".to_owned()
+        } else {
+            commit_id.to_string()
+        };
+        // The revset can only match the prefix anywhere, so the template checks it's at the start
+        let (description_revset, template) = if wip_prefix.is_empty() {
+            (
+                r#"description(exact:"")"#.to_owned(),
+                format!(r#"{} ++ "\n""#, HEAD_TEMPLATE),
+            )
+        } else {
+            let wip_prefix = string_literal(wip_prefix);
+            (
+                format!(r#"(description(exact:"") | description(substring:{wip_prefix}))"#),
+                format!(
+                    r#"if(description == "" || description.starts_with({wip_prefix}), {} ++ "\n")"#,
+                    HEAD_TEMPLATE
+                ),
+            )
+        };
+
+        self.execute_jj_command(
+            vec![
+                "log",
+                "--no-graph",
+                "--template",
+                &template,
+                "-r",
+                &format!(
+                    r#"((remote_bookmarks()..{}) ~ root()) & {}"#,
+                    target, description_revset
+                ),
+                "--limit",
+                "10",
+            ],
+            false,
+            true,
+        )
+        .context("Failed getting heads with empty description")?
+        .lines()
+        .map(parse_head)
+        .collect()
+    }
+
+    /// Get which of the heads have a description starting with `wip_prefix`, so are work in
+    /// progress.
+    /// Maps to `jj log -r '(<heads>) & description(substring:<wip_prefix>)'`
+    #[instrument(level = "trace", skip(self, heads))]
+    pub fn get_wip_heads(&mut self, heads: &[Head], wip_prefix: &str) -> Result<HashSet<CommitId>> {
+        if heads.is_empty() || wip_prefix.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let wip_prefix = string_literal(wip_prefix);
+        let revset = format!(
+            "({}) & description(substring:{wip_prefix})",
+            heads.iter().map(|head| head.commit_id.as_str()).join(" | ")
+        );
+        Ok(self
+            .execute_jj_command(
+                vec![
+                    "log",
+                    "--no-graph",
+                    "--template",
+                    &format!(r#"if(description.starts_with({wip_prefix}), commit_id ++ "\n")"#),
+                    "-r",
+                    &revset,
+                ],
+                false,
+                true,
+            )
+            .context("Failed getting WIP heads")?
+            .lines()
+            .map(|commit_id| CommitId(commit_id.to_owned()))
+            .collect())
+    }
+
+    /// Get which of the heads are ancestors of a remote bookmark, so exist on a remote. Returns
+    /// `None` when there are no remotes, as then nothing can be pushed.
+    /// Maps to `jj log -r '(<heads>) & ::remote_bookmarks()'`
+    #[instrument(level = "trace", skip(self, heads))]
+    pub fn get_pushed_heads(&mut self, heads: &[Head]) -> Result<Option<HashSet<CommitId>>> {
+        if self.get_git_remotes()?.is_empty() {
+            return Ok(None);
+        }
+        if heads.is_empty() {
+            return Ok(Some(HashSet::new()));
+        }
+
+        let revset = format!(
+            "({}) & ::remote_bookmarks()",
+            heads.iter().map(|head| head.commit_id.as_str()).join(" | ")
+        );
+        Ok(Some(
+            self.execute_jj_command(
+                vec![
+                    "log",
+                    "--no-graph",
+                    "--template",
+                    r#"commit_id ++ "\n""#,
+                    "-r",
+                    &revset,
+                ],
+                false,
+                true,
+            )
+            .context("Failed getting pushed heads")?
+            .lines()
+            .map(|commit_id| CommitId(commit_id.to_owned()))
+            .collect(),
+        ))
+    }
+
     /// Get bookmark head
     /// Maps to `jj log -r <bookmark>[@<remote>]`
     #[instrument(level = "trace", skip(self))]
@@ -324,33 +988,458 @@ impl Commander {
                 .remove_end_line(),
         )
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use std::fs;
-
-    use super::*;
-    use crate::commander::tests::TestRepo;
-    use insta::assert_debug_snapshot;
-
-    #[test]
-    fn get_log() -> Result<()> {
-        let mut test_repo = TestRepo::new()?;
 
-        let log = test_repo.commander.get_log(&None)?;
-
-        let mut settings = insta::Settings::clone_current();
-        settings.add_filter(r"[k-z]{8} .*? [0-9a-fA-F]{8}", "[LINE]");
-        let _bound = settings.bind_to_scope();
-
-        assert_debug_snapshot!(log.graph);
-
-        assert!(log.graph_heads.iter().all(|graph_head| graph_head
-            .as_ref()
-            .map_or(true, |graph_head| log.heads.contains(graph_head))));
+    /// Get the latest commit of a change.
+    /// Maps to `jj log -r <change id>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_change_head(&mut self, change_id: &ChangeId) -> Result<Head> {
+        parse_head(
+            &self
+                .execute_jj_command(
+                    vec![
+                        "log",
+                        "--no-graph",
+                        "--template",
+                        &format!(r#"{} ++ "\n""#, HEAD_TEMPLATE),
+                        "-r",
+                        change_id.as_str(),
+                        "--limit",
+                        "1",
+                    ],
+                    false,
+                    true,
+                )
+                .with_context(|| format!("Failed getting change head: {}", change_id.as_str()))?
+                .remove_end_line(),
+        )
+    }
 
-        Ok(())
+    /// Get the head of a revision, such as a bookmark or `@-`. The newest if it resolves to
+    /// several changes.
+    /// Maps to `jj log -r <revision> --limit 1`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_head_by_revision(&mut self, revision: &str) -> Result<Head> {
+        parse_head(
+            &self
+                .execute_jj_command(
+                    vec![
+                        "log",
+                        "--no-graph",
+                        "--template",
+                        &format!(r#"{} ++ "\n""#, HEAD_TEMPLATE),
+                        "-r",
+                        revision,
+                        "--limit",
+                        "1",
+                    ],
+                    false,
+                    true,
+                )
+                .with_context(|| format!("Failed getting head of revision: {revision}"))?
+                .remove_end_line(),
+        )
+    }
+
+    /// Get conflicted changes in a revset, in log order. Uses the default log revset if none.
+    /// Maps to `jj log -r 'conflicts() & (<revset>)'`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_conflicted_heads(
+        &mut self,
+        revset: &Option<String>,
+    ) -> Result<Vec<Head>, CommandError> {
+        let revset = match revset {
+            Some(revset) => revset.to_owned(),
+            None => self.get_default_log_revset(),
+        };
+
+        Ok(self
+            .execute_jj_command(
+                vec![
+                    "log",
+                    "--no-graph",
+                    "--template",
+                    &format!(r#"{} ++ "\n""#, HEAD_TEMPLATE),
+                    "-r",
+                    &format!("conflicts() & ({})", revset),
+                ],
+                false,
+                true,
+            )?
+            .lines()
+            // Lines are always heads, since the template is only heads
+            .filter_map(|line| parse_head(line).ok())
+            .collect())
+    }
+
+    /// Get the changes only reachable through a merge's non-first parents, such as a merged
+    /// branch. Returns no heads if the change isn't a merge.
+    /// Maps to `jj log -r '::<merge>- ~ ::<first parent>'`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_merge_side_heads(&mut self, merge: &CommitId) -> Result<Vec<Head>, CommandError> {
+        let parents = self.execute_jj_command(
+            vec![
+                "log",
+                "--no-graph",
+                "--template",
+                r#"parents.map(|c| c.commit_id()).join(" ")"#,
+                "-r",
+                merge.as_str(),
+            ],
+            false,
+            true,
+        )?;
+        let parents: Vec<&str> = parents.split_whitespace().collect();
+        if parents.len() < 2 {
+            return Ok(vec![]);
+        }
+
+        Ok(self
+            .execute_jj_command(
+                vec![
+                    "log",
+                    "--no-graph",
+                    "--template",
+                    &format!(r#"{} ++ "\n""#, HEAD_TEMPLATE),
+                    "-r",
+                    &format!("::{}- ~ ::{}", merge, parents[0]),
+                ],
+                false,
+                true,
+            )?
+            .lines()
+            // Lines are always heads, since the template is only heads
+            .filter_map(|line| parse_head(line).ok())
+            .collect())
+    }
+
+    /// Get the revset used for the log by default.
+    /// Maps to `jj config get revsets.log`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_default_log_revset(&mut self) -> String {
+        self.execute_jj_command(vec!["config", "get", "revsets.log"], false, true)
+            .map(|revset| revset.trim().to_owned())
+            .ok()
+            .filter(|revset| !revset.is_empty())
+            // jj's builtin default
+            .unwrap_or(
+                "present(@) | ancestors(immutable_heads().., 2) | present(trunk())".to_owned(),
+            )
+    }
+
+    /// Get the head of a bookmark's remote.
+    /// Maps to `jj log -r <bookmark>@<remote>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_bookmark_remote_head(&mut self, name: &str, remote: &str) -> Result<Head> {
+        self.get_bookmark_head(&Bookmark {
+            name: name.to_owned(),
+            remote: Some(remote.to_owned()),
+            present: true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        commander::{
+            fixtures::FixtureRecorder,
+            tests::{load_fixture, recorded_fixtures, TestRepo},
+        },
+        ui::utils::ansi_text,
+    };
+    use insta::assert_debug_snapshot;
+    use ratatui::text::Text;
+    use tempdir::TempDir;
+
+    #[test]
+    fn parse_revset_errors() {
+        let output = "\
+Error: Failed to parse revset: Syntax error
+Caused by:  --> 1:8
+  |
+1 | trunk()|
+  |        ^---
+  |
+  = expected <expression>
+Hint: See https://jj-vcs.github.io/jj/latest/revsets/ for revsets syntax
+";
+        assert_eq!(
+            parse_revset_error(output),
+            Some(RevsetError {
+                line: 1,
+                column: 8,
+                message: "Syntax error: expected <expression>".to_owned(),
+                hint: Some(
+                    "See https://jj-vcs.github.io/jj/latest/revsets/ for revsets syntax".to_owned()
+                ),
+            })
+        );
+
+        assert_eq!(
+            parse_revset_error(
+                "Error: Failed to parse revset: Syntax error in revset at line 2, column 3"
+            ),
+            Some(RevsetError {
+                line: 2,
+                column: 3,
+                message: "Syntax error in revset at line 2, column 3".to_owned(),
+                hint: None,
+            })
+        );
+
+        assert_eq!(
+            parse_revset_error("Error: Revision `missing` doesn't exist"),
+            None
+        );
+    }
+
+    #[test]
+    fn replay_recorded_revset_error() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+        let fixtures = TempDir::new("lazyjj-fixtures")?;
+        let root = test_repo.commander.env.root.clone();
+        test_repo
+            .commander
+            .record_fixtures(FixtureRecorder::new(fixtures.path(), &root)?);
+
+        let result = test_repo.commander.check_revset("trunk()|");
+        let paths = recorded_fixtures(fixtures.path())?;
+        assert_eq!(paths.len(), 1);
+        let replayed = load_fixture(&paths[0]);
+        assert_eq!(
+            format!("{:?}", replayed.as_ref().map(|_| ())),
+            format!("{result:?}")
+        );
+
+        // The replayed error parses like jj's
+        let Err(CommandError::Status(output, _, _)) = replayed else {
+            panic!("Expected the recorded command to fail");
+        };
+        assert!(matches!(
+            parse_revset_error(&output),
+            Some(RevsetError {
+                line: 1,
+                column: 8,
+                ..
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn quote_string_literal() {
+        assert_eq!(string_literal("wip: "), r#""wip: ""#);
+        assert_eq!(string_literal(r#"[\"wip\"]"#), r#""[\\\"wip\\\"]""#);
+    }
+
+    #[test]
+    fn check_revset() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        assert!(test_repo.commander.check_revset("trunk()::@").is_ok());
+        match test_repo.commander.check_revset("trunk()|") {
+            Err(CommandError::Status(output, _, _)) => {
+                let revset_error = parse_revset_error(&output).unwrap();
+                assert_eq!((revset_error.line, revset_error.column), (1, 8));
+            }
+            result => panic!("Expected revset error, got {result:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn debug_revset() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let head = test_repo.commander.get_current_head()?;
+        let output = test_repo.commander.debug_revset("@")?;
+        assert!(output.contains(head.commit_id.as_str()), "{output}");
+        assert!(test_repo.commander.debug_revset("trunk()|").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_log() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let log = test_repo.commander.get_log(&None, false, &None, &None)?;
+
+        let mut settings = insta::Settings::clone_current();
+        settings.add_filter(r"[k-z]{8} .*? [0-9a-fA-F]{8}", "[LINE]");
+        let _bound = settings.bind_to_scope();
+
+        assert_debug_snapshot!(log.graph);
+
+        assert!(log.graph_heads.iter().all(|graph_head| graph_head
+            .as_ref()
+            .map_or(true, |graph_head| log.heads.contains(graph_head))));
+
+        assert_eq!(log.graph_timestamps.len(), log.graph_heads.len());
+        assert!(log.graph_timestamps.iter().any(Option::is_some));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_head_info() -> Result<()> {
+        let output = [
+            "kxryzmor␟a1b2c3d4␟e5f6a7b8,c9d0e1f2␟Jane Doe␟jane@example.com␟1700000000␟1700000100␟main,feature␟false␟false␟false␟true␟false␟true␟Fix parser\n\nWith details\n␞",
+            "vruxwmqv␟e5f6a7b8␟␟␟␟0␟0␟␟false␟true␟false␟false␟true␟false␟␞",
+        ]
+        .concat();
+
+        let head_infos = parse_head_infos(&output)?;
+        assert_eq!(head_infos.len(), 2);
+        assert_eq!(
+            head_infos[0],
+            HeadInfo {
+                change_id: "kxryzmor".to_owned(),
+                commit_id: "a1b2c3d4".to_owned(),
+                parents: vec!["e5f6a7b8".to_owned(), "c9d0e1f2".to_owned()],
+                author_name: "Jane Doe".to_owned(),
+                author_email: "jane@example.com".to_owned(),
+                author_timestamp: 1700000000,
+                committer_timestamp: 1700000100,
+                bookmarks: vec!["main".to_owned(), "feature".to_owned()],
+                divergent: false,
+                immutable: false,
+                hidden: false,
+                conflict: true,
+                empty: false,
+                current_working_copy: true,
+                description: "Fix parser\n\nWith details".to_owned(),
+            }
+        );
+        assert!(head_infos[1].parents.is_empty());
+        assert!(head_infos[1].immutable);
+        assert_eq!(head_infos[1].description, "");
+
+        assert!(parse_head_infos("kxryzmor␟a1b2c3d4␞").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_head_infos() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let head_infos = test_repo.commander.get_head_infos(&None, &None)?;
+        let current_head = test_repo.commander.get_current_head()?;
+
+        assert!(head_infos
+            .iter()
+            .any(|head_info| head_info.current_working_copy
+                && head_info.commit_id == current_head.commit_id.as_str()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_log_path() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let path = r#"my "quoted" file"#;
+        fs::write(test_repo.directory.path().join(path), "Hello")?;
+        let head = test_repo.commander.get_current_head()?;
+        test_repo
+            .commander
+            .run_new(head.commit_id.as_str(), false)?;
+
+        let log = test_repo.commander.get_log(
+            &Some("all()".to_owned()),
+            true,
+            &None,
+            &Some(path.to_owned()),
+        )?;
+        assert_eq!(
+            log.heads
+                .iter()
+                .map(|head| head.change_id.clone())
+                .collect::<Vec<_>>(),
+            vec![head.change_id]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_log_no_graph() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let log = test_repo.commander.get_log(&None, true, &None, &None)?;
+
+        // Each line is a change
+        assert_eq!(log.graph.lines().count(), log.heads.len());
+        assert_eq!(log.graph_heads.len(), log.heads.len());
+        assert!(log.graph_heads.iter().all(Option::is_some));
+        assert!(log.graph_timestamps.iter().all(Option::is_some));
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_log_template() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let template = Some(r#"change_id.short() ++ "\n" ++ "line 2\n" ++ "line 3\n""#.to_owned());
+        let log = test_repo
+            .commander
+            .get_log(&None, false, &template, &None)?;
+
+        // 3 lines for each change
+        assert_eq!(log.heads.len(), 2);
+        assert_eq!(log.graph.lines().count(), log.graph_heads.len());
+        assert_eq!(
+            log.graph_heads.iter().flatten().count(),
+            log.heads.len() * 3
+        );
+        assert!(!log.graph.contains(LOG_START_MARKER));
+        assert!(!log.graph.contains(LOG_END_MARKER));
+
+        let preview = test_repo
+            .commander
+            .get_log_preview(&None, r#""preview\n""#)?;
+        assert_eq!(preview, "preview\npreview\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_marked_logs() {
+        let head = |id: &str| format!("[{id}|{id}|false|false|false|false|false] {{1700000000}}");
+        let output = format!(
+            "@  ␞{}␞\x1b[1mkxryzmor\x1b[0m\n│  first\n│  ␟\n│ ○  ␞{}␞vruxwmqv␟\n├─╯\n◆  ␞{}␞zzzzzzzz\n   root␟\n",
+            head("a"),
+            head("b"),
+            head("c")
+        );
+
+        let (graph, graph_heads, graph_timestamps) = parse_marked_log(&output);
+        assert_eq!(
+            graph,
+            "@  \x1b[1mkxryzmor\x1b[0m\n│  first\n│ ○  vruxwmqv\n├─╯\n◆  zzzzzzzz\n   root\n"
+        );
+        let change_ids: Vec<Option<&str>> = graph_heads
+            .iter()
+            .map(|head| head.as_ref().map(|head| head.change_id.as_str()))
+            .collect();
+        assert_eq!(
+            change_ids,
+            vec![Some("a"), Some("a"), Some("b"), None, Some("c"), Some("c")]
+        );
+        assert_eq!(
+            graph_timestamps
+                .iter()
+                .map(Option::is_some)
+                .collect::<Vec<bool>>(),
+            vec![true, false, true, false, true, false]
+        );
     }
 
     #[test]
@@ -388,6 +1477,9 @@ mod tests {
                 change_id: ChangeId("zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz".to_owned()),
                 divergent: false,
                 immutable: true,
+                hidden: false,
+                conflict: false,
+                empty: true,
             }
         );
 
@@ -420,6 +1512,237 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn get_descriptions() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        test_repo
+            .commander
+            .execute_void_jj_command(vec!["describe", "-m", "First\n\nBody"])?;
+        test_repo
+            .commander
+            .execute_void_jj_command(vec!["new", "-m", "Second"])?;
+
+        let descriptions = test_repo.commander.get_descriptions("root()..@")?;
+        assert_eq!(
+            descriptions
+                .iter()
+                .map(|(_, description)| description.as_str())
+                .collect::<Vec<_>>(),
+            vec!["First\n\nBody", "Second"]
+        );
+        assert_eq!(descriptions[1].0, test_repo.commander.get_current_head()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_stack_summary() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        test_repo.commander.execute_void_jj_command(vec![
+            "describe",
+            "-m",
+            "First change\n\nBody",
+        ])?;
+        test_repo
+            .commander
+            .execute_void_jj_command(vec!["new", "-m", "Second change"])?;
+
+        // Trunk is the root without remote bookmarks
+        let stack = test_repo.commander.get_stack_summary()?;
+        assert_eq!(
+            stack
+                .iter()
+                .map(|entry| entry.title.as_str())
+                .collect::<Vec<_>>(),
+            vec!["", "First change", "Second change"]
+        );
+        assert_eq!(
+            stack.last().map(|entry| &entry.head),
+            Some(&test_repo.commander.get_current_head()?)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_relatives() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        test_repo
+            .commander
+            .execute_void_jj_command(vec!["describe", "-m", "Parent"])?;
+        let parent = test_repo.commander.get_current_head()?;
+        test_repo
+            .commander
+            .execute_void_jj_command(vec!["new", "-m", "First child"])?;
+        let first_child = test_repo.commander.get_current_head()?;
+        test_repo.commander.execute_void_jj_command(vec![
+            "new",
+            "-m",
+            "Second child",
+            parent.change_id.as_str(),
+        ])?;
+
+        let relatives = test_repo.commander.get_relatives(&parent.commit_id)?;
+        assert_eq!(
+            relatives
+                .parents
+                .iter()
+                .map(|entry| entry.title.as_str())
+                .collect::<Vec<_>>(),
+            [""]
+        );
+        assert_eq!(
+            relatives
+                .children
+                .iter()
+                .map(|entry| entry.title.as_str())
+                .collect::<Vec<_>>(),
+            ["First child", "Second child"]
+        );
+
+        let relatives = test_repo.commander.get_relatives(&first_child.commit_id)?;
+        assert_eq!(
+            relatives
+                .parents
+                .iter()
+                .map(|entry| &entry.head)
+                .collect::<Vec<_>>(),
+            [&parent]
+        );
+        assert!(relatives.children.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_log_escapes_descriptions() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        // A description documenting escape codes, with literal escape bytes
+        let description = "Use \x1b[31m for red \x1b]0;title\x07 [0m\x08";
+        test_repo
+            .commander
+            .execute_void_jj_command(vec!["describe", "-m", description])?;
+
+        let inert = |text: &Text| {
+            text.lines
+                .iter()
+                .flat_map(|line| line.spans.iter())
+                .all(|span| !span.content.chars().any(|c| c.is_control() && c != '\t'))
+        };
+        for template in [None, Some("description".to_owned())] {
+            let log = test_repo
+                .commander
+                .get_log(&None, false, &template, &None)?;
+            let text = ansi_text(&log.graph)?;
+            assert!(inert(&text), "{template:?}: {text:?}");
+            assert!(text.to_string().contains("for red"));
+        }
+
+        let stack = test_repo.commander.get_stack_summary()?;
+        assert_eq!(
+            stack.last().map(|entry| entry.title.as_str()),
+            Some("Use ^[[31m for red ^[]0;title^G [0m^H")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_push_empty_description_heads() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let head = test_repo.commander.get_current_head()?;
+        let heads = test_repo.commander.get_push_empty_description_heads(
+            false,
+            &head.commit_id,
+            "wip: ",
+        )?;
+        test_repo
+            .commander
+            .run_describe(head.commit_id.as_str(), "AAA")?;
+        assert_eq!(heads, [head]);
+
+        let head = test_repo.commander.get_current_head()?;
+        assert_eq!(
+            test_repo.commander.get_push_empty_description_heads(
+                false,
+                &head.commit_id,
+                "wip: "
+            )?,
+            []
+        );
+
+        // Changes marked as WIP are listed too, unless the prefix is only in the middle
+        test_repo
+            .commander
+            .run_describe(head.commit_id.as_str(), "wip: AAA")?;
+        let head = test_repo.commander.get_current_head()?;
+        assert_eq!(
+            test_repo.commander.get_push_empty_description_heads(
+                false,
+                &head.commit_id,
+                "wip: "
+            )?,
+            std::slice::from_ref(&head)
+        );
+        assert_eq!(
+            test_repo
+                .commander
+                .get_wip_heads(std::slice::from_ref(&head), "wip: ")?,
+            HashSet::from([head.commit_id.clone()])
+        );
+        assert_eq!(
+            test_repo
+                .commander
+                .get_push_empty_description_heads(false, &head.commit_id, "")?,
+            []
+        );
+
+        test_repo
+            .commander
+            .run_describe(head.commit_id.as_str(), "AAA wip: ")?;
+        let head = test_repo.commander.get_current_head()?;
+        assert_eq!(
+            test_repo.commander.get_push_empty_description_heads(
+                false,
+                &head.commit_id,
+                "wip: "
+            )?,
+            []
+        );
+        assert_eq!(
+            test_repo.commander.get_wip_heads(&[head], "wip: ")?,
+            HashSet::new()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_pushed_heads() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let head = test_repo.commander.get_current_head()?;
+        // The test repo has no remotes
+        assert_eq!(test_repo.commander.get_pushed_heads(&[head])?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_change_head() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let head = test_repo.commander.get_current_head()?;
+        assert_eq!(test_repo.commander.get_change_head(&head.change_id)?, head);
+
+        Ok(())
+    }
+
     #[test]
     fn get_bookmark_head() -> Result<()> {
         let mut test_repo = TestRepo::new()?;
@@ -432,4 +1755,92 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn get_head_by_revision() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let head = test_repo.commander.get_current_head()?;
+        test_repo.commander.create_bookmark("main")?;
+
+        assert_eq!(test_repo.commander.get_head_by_revision("@")?, head);
+        assert_eq!(test_repo.commander.get_head_by_revision("main")?, head);
+        assert!(test_repo.commander.get_head_by_revision("missing").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_conflicted_heads() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+        let file_path = test_repo.directory.path().join("README");
+
+        let head0 = test_repo.commander.get_current_head()?;
+        assert_eq!(test_repo.commander.get_conflicted_heads(&None)?, []);
+
+        test_repo
+            .commander
+            .run_new(head0.commit_id.as_str(), false)?;
+        let head1 = test_repo.commander.get_current_head()?;
+        fs::write(&file_path, b"AAA")?;
+
+        test_repo
+            .commander
+            .run_new(head0.commit_id.as_str(), false)?;
+        let head2 = test_repo.commander.get_current_head()?;
+        fs::write(&file_path, b"BBB")?;
+
+        test_repo.commander.execute_void_jj_command([
+            "rebase",
+            "-s",
+            head2.change_id.as_str(),
+            "-d",
+            head1.change_id.as_str(),
+        ])?;
+
+        let heads = test_repo
+            .commander
+            .get_conflicted_heads(&Some("all()".to_owned()))?;
+        assert_eq!(heads.len(), 1);
+        assert_eq!(heads[0].change_id, head2.change_id);
+        assert!(heads[0].conflict);
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_merge_side_heads() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let head0 = test_repo.commander.get_current_head()?;
+        assert_eq!(
+            test_repo.commander.get_merge_side_heads(&head0.commit_id)?,
+            []
+        );
+
+        test_repo
+            .commander
+            .run_new(head0.commit_id.as_str(), false)?;
+        fs::write(test_repo.directory.path().join("A"), b"AAA")?;
+        let head1 = test_repo.commander.get_current_head()?;
+
+        test_repo
+            .commander
+            .run_new(head0.commit_id.as_str(), false)?;
+        fs::write(test_repo.directory.path().join("B"), b"BBB")?;
+        let head2 = test_repo.commander.get_current_head()?;
+
+        test_repo.commander.execute_void_jj_command([
+            "new",
+            head1.change_id.as_str(),
+            head2.change_id.as_str(),
+        ])?;
+        let merge = test_repo.commander.get_current_head()?;
+
+        let heads = test_repo.commander.get_merge_side_heads(&merge.commit_id)?;
+        assert_eq!(heads.len(), 1);
+        assert_eq!(heads[0].change_id, head2.change_id);
+
+        Ok(())
+    }
 }
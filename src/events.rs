@@ -0,0 +1,151 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    sync::mpsc::{sync_channel, SyncSender},
+    thread,
+};
+
+use serde::Serialize;
+use tracing::trace;
+
+use crate::app::Tab;
+
+// Events waiting to be written. Further events are dropped until the writer catches up
+const EVENT_QUEUE_SIZE: usize = 64;
+
+/// Event written for external tools, such as a status bar, as a line of JSON.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    SelectionChanged {
+        change_id: String,
+        commit_id: String,
+    },
+    CommandStarted {
+        name: String,
+    },
+    CommandFinished {
+        name: String,
+        duration_ms: i64,
+        success: bool,
+        // Exit code, if the command ran
+        status: Option<i32>,
+    },
+    TabSwitched {
+        tab: Tab,
+    },
+}
+
+/// Writes events to a file or FIFO, from `lazyjj.event-fifo` or `LAZYJJ_EVENTS`.
+///
+/// Events are written by a background thread, and dropped if it falls behind, so a slow or absent
+/// reader never stalls the UI.
+#[derive(Debug)]
+pub struct EventSink {
+    tx: SyncSender<String>,
+}
+
+impl EventSink {
+    pub fn new(path: PathBuf) -> Self {
+        let (tx, rx) = sync_channel::<String>(EVENT_QUEUE_SIZE);
+        thread::spawn(move || {
+            let mut file = None;
+            for line in rx {
+                // Opening a FIFO waits for a reader. Reopen after the reader goes away
+                if file.is_none() {
+                    file = OpenOptions::new()
+                        .append(true)
+                        .create(true)
+                        .open(&path)
+                        .ok();
+                }
+                if let Some(writer) = file.as_mut() {
+                    if writer.write_all(line.as_bytes()).is_err() {
+                        file = None;
+                    }
+                }
+            }
+        });
+
+        Self { tx }
+    }
+
+    pub fn emit(&self, event: &Event) {
+        let Ok(mut line) = serde_json::to_string(event) else {
+            return;
+        };
+        line.push('\n');
+
+        if self.tx.try_send(line).is_err() {
+            trace!("Dropped event: {event:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, time::Duration};
+    use tempdir::TempDir;
+
+    #[test]
+    fn serialize_events() -> serde_json::Result<()> {
+        assert_eq!(
+            serde_json::to_string(&Event::SelectionChanged {
+                change_id: "kxryzmor".to_owned(),
+                commit_id: "4e9b3b2c".to_owned(),
+            })?,
+            r#"{"event":"selection-changed","change_id":"kxryzmor","commit_id":"4e9b3b2c"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Event::CommandStarted {
+                name: "jj git push".to_owned(),
+            })?,
+            r#"{"event":"command-started","name":"jj git push"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Event::CommandFinished {
+                name: "jj git push".to_owned(),
+                duration_ms: 1200,
+                success: false,
+                status: Some(1),
+            })?,
+            r#"{"event":"command-finished","name":"jj git push","duration_ms":1200,"success":false,"status":1}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Event::TabSwitched {
+                tab: Tab::CommandLog
+            })?,
+            r#"{"event":"tab-switched","tab":"command-log"}"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_events() -> anyhow::Result<()> {
+        let directory = TempDir::new("lazyjj-events")?;
+        let path = directory.path().join("events");
+
+        let event_sink = EventSink::new(path.clone());
+        event_sink.emit(&Event::TabSwitched { tab: Tab::Log });
+        event_sink.emit(&Event::TabSwitched { tab: Tab::Files });
+
+        // Wait for the background writer
+        let mut content = String::new();
+        for _ in 0..100 {
+            content = fs::read_to_string(&path).unwrap_or_default();
+            if content.lines().count() == 2 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(
+            content,
+            "{\"event\":\"tab-switched\",\"tab\":\"log\"}\n{\"event\":\"tab-switched\",\"tab\":\"files\"}\n"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use crate::env::Config;
+
+/// Known jj config combination which works badly when jj is run by lazyjj.
+pub struct HealthCheck {
+    // Stable ID, stored in `lazyjj.dismissed-warnings` to not show the warning again
+    pub id: &'static str,
+    // Whether the config has the issue
+    detect: fn(&Config) -> bool,
+    // Effect of the issue
+    pub message: &'static str,
+    // Config change which fixes it
+    pub fix: &'static str,
+}
+
+// Whether a pager command waits for input even if the output fits on one screen. Only `less`
+// without `-F`/`--quit-if-one-screen` is known to
+fn pager_waits(pager: &str) -> bool {
+    let mut args = pager.split_whitespace();
+    let is_less = args
+        .next()
+        .is_some_and(|program| program == "less" || program.ends_with("/less"));
+    is_less
+        && !args.any(|arg| {
+            arg == "--quit-if-one-screen"
+                || (arg.starts_with('-') && !arg.starts_with("--") && arg.contains('F'))
+        })
+}
+
+pub const HEALTH_CHECKS: &[HealthCheck] = &[
+    HealthCheck {
+        id: "pager-waits",
+        detect: |config| {
+            config.ui_paginate() != Some("never")
+                && config.ui_pager().is_some_and(|pager| pager_waits(&pager))
+        },
+        message: "ui.pager runs less without -F, so jj commands run with ! from the command palette wait for q before returning to lazyjj, even for short output.",
+        fix: r#"jj config set --user ui.pager "less -FRX""#,
+    },
+    HealthCheck {
+        id: "color-never",
+        detect: |config| config.ui_color() == Some("never"),
+        message: "ui.color is never. lazyjj asks jj for colors, but jj commands run with ! from the command palette and older jj versions will have no colors.",
+        fix: "jj config set --user ui.color auto",
+    },
+];
+
+/// Health checks whose issue is in the config, except those dismissed with "don't show again".
+pub fn get_health_warnings(config: &Config) -> Vec<&'static HealthCheck> {
+    HEALTH_CHECKS
+        .iter()
+        .filter(|check| (check.detect)(config))
+        .filter(|check| {
+            !config
+                .dismissed_warnings()
+                .iter()
+                .any(|dismissed| dismissed == check.id)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Parse config in the `jj config list` format read at startup
+    fn config(toml: &str) -> Config {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn warning_ids(toml: &str) -> Vec<&'static str> {
+        get_health_warnings(&config(toml))
+            .iter()
+            .map(|check| check.id)
+            .collect()
+    }
+
+    #[test]
+    fn detect_pager() {
+        assert!(pager_waits("less"));
+        assert!(pager_waits("/usr/bin/less -R"));
+        assert!(!pager_waits("less -FRX"));
+        assert!(!pager_waits("less --quit-if-one-screen"));
+        assert!(!pager_waits(":builtin"));
+        assert!(!pager_waits("delta"));
+    }
+
+    #[test]
+    fn health_warnings() {
+        for (toml, expected) in [
+            ("", vec![]),
+            (r#""ui.pager" = "less""#, vec!["pager-waits"]),
+            (r#""ui.pager" = ["less", "-R"]"#, vec!["pager-waits"]),
+            (
+                r#""ui.pager" = { command = ["less"] }"#,
+                vec!["pager-waits"],
+            ),
+            (r#""ui.pager" = "less -FRX""#, vec![]),
+            (
+                "\"ui.pager\" = \"less\"\n\"ui.paginate\" = \"never\"",
+                vec![],
+            ),
+            (r#""ui.color" = "never""#, vec!["color-never"]),
+            (r#""ui.color" = "auto""#, vec![]),
+        ] {
+            assert_eq!(warning_ids(toml), expected, "{toml}");
+        }
+    }
+
+    #[test]
+    fn dismissed_health_warnings() {
+        assert_eq!(
+            warning_ids(
+                "\"ui.color\" = \"never\"\n\"lazyjj.dismissed-warnings\" = [\"color-never\"]"
+            ),
+            Vec::<&str>::new()
+        );
+    }
+}
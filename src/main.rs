@@ -4,14 +4,18 @@ use std::{
     env::current_dir,
     fs::{canonicalize, OpenOptions},
     io::{self, ErrorKind},
+    path::PathBuf,
     process::Command,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
 };
 
 use anyhow::{bail, Context, Result};
 use chrono::Utc;
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use clap_complete::Shell;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -23,21 +27,27 @@ use ratatui::{
 };
 use tracing::{info, trace_span};
 use tracing_chrome::ChromeLayerBuilder;
-use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{layer::SubscriberExt, EnvFilter, Layer};
 
 mod app;
 mod commander;
 mod env;
+mod events;
+mod health;
 mod ui;
 
 use crate::{
     app::App,
-    commander::Commander,
+    commander::{
+        binary::{is_newer_jj_required_error, newer_jj_required_message},
+        fixtures::FixtureRecorder,
+        Commander,
+    },
     env::Env,
-    ui::{ui, ComponentAction},
+    ui::{message_popup::MessagePopup, ui, ComponentAction},
 };
 
-/// Simple program to greet a person
+/// TUI for Jujutsu/jj
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
@@ -45,37 +55,104 @@ struct Args {
     #[arg(short, long)]
     path: Option<String>,
 
-    // Default revset
+    /// Default revset of the log. Defaults to `lazyjj.default-revset`, then jj's `revsets.log`
+    /// config
     #[arg(short, long)]
     revisions: Option<String>,
+
+    /// Revision to select in the log at startup, such as a bookmark. Defaults to the working copy
+    #[arg(long)]
+    revision: Option<String>,
+
+    /// jj config as `NAME=VALUE`, passed to every jj command with `--config` (`--config-toml`
+    /// before jj 0.25), such as `--jj-config 'ui.diff.format="git"'`. Can be repeated. Also read
+    /// from `LAZYJJ_JJ_CONFIG`, which the flags override
+    #[arg(long, value_name = "TOML")]
+    jj_config: Vec<String>,
+
+    /// Don't start the TUI, only print the output requested by other flags
+    #[arg(long, requires = "output_json")]
+    no_tui: bool,
+
+    /// Print the changes of the log as JSON, for scripts. Uses the default revset
+    #[arg(long, requires = "no_tui")]
+    output_json: bool,
+
+    /// Don't capture the mouse, such as inside tmux where it would take mouse events from the
+    /// multiplexer. Can also be set with `LAZYJJ_NO_MOUSE=1`
+    #[arg(long)]
+    no_mouse: bool,
+
+    /// Print shell completions for lazyjj to stdout, for packaging
+    #[arg(
+        long,
+        value_name = "SHELL",
+        hide = true,
+        conflicts_with = "generate_manpage"
+    )]
+    generate_completions: Option<Shell>,
+
+    /// Print the man page of lazyjj in roff format to stdout, for packaging
+    #[arg(long, hide = true)]
+    generate_manpage: bool,
+
+    /// Write every command run and its output to numbered files in a directory, to harvest
+    /// fixtures for parser tests. Paths and emails are replaced
+    #[arg(long, value_name = "DIR", hide = true)]
+    record_fixtures: Option<PathBuf>,
+}
+
+fn generate_completions(shell: Shell, out: &mut impl io::Write) {
+    clap_complete::generate(shell, &mut Args::command(), "lazyjj", out);
+}
+
+fn generate_manpage(out: &mut impl io::Write) -> Result<()> {
+    clap_mangen::Man::new(Args::command()).render(out)?;
+    Ok(())
+}
+
+// Whether an environment variable is set to `1` or `true`
+fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
 }
 
 fn main() -> Result<()> {
-    let should_log = std::env::var("LAZYJJ_LOG")
-        .map(|log| log == "1" || log.eq_ignore_ascii_case("true"))
-        .unwrap_or(false);
+    // `LAZYJJ_LOG_LEVEL` filters logs by level and module, such as `debug` or
+    // `lazyjj::commander=debug,lazyjj::ui=warn`. `LAZYJJ_LOG` logs everything
+    let log_level = std::env::var("LAZYJJ_LOG_LEVEL")
+        .ok()
+        .filter(|log_level| !log_level.is_empty());
+    let should_log = log_level.is_some() || env_flag("LAZYJJ_LOG");
 
     let log_layer = if should_log {
+        let log_filter = EnvFilter::try_new(log_level.as_deref().unwrap_or("trace"))
+            .context("Invalid LAZYJJ_LOG_LEVEL")?;
+
+        let log_path = std::env::var("LAZYJJ_LOG_FILE")
+            .ok()
+            .filter(|log_path| !log_path.is_empty())
+            .unwrap_or("lazyjj.log".to_owned());
         let log_file = OpenOptions::new()
             .append(true)
             .create(true)
-            .open("lazyjj.log")
-            .unwrap();
+            .open(&log_path)
+            .with_context(|| format!("Could not open log file {log_path}"))?;
 
         Some(
             tracing_subscriber::fmt::layer()
                 .compact()
                 .with_writer(log_file)
                 // Add log when span ends with their duration
-                .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE),
+                .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+                .with_filter(log_filter),
         )
     } else {
         None
     };
 
-    let should_trace = std::env::var("LAZYJJ_TRACE")
-        .map(|log| log == "1" || log.eq_ignore_ascii_case("true"))
-        .unwrap_or(false);
+    let should_trace = env_flag("LAZYJJ_TRACE");
     let (trace_layer, _guard) = if should_trace {
         let (chrome_layer, _guard) = ChromeLayerBuilder::new().build();
         (Some(chrome_layer), Some(_guard))
@@ -92,6 +169,14 @@ fn main() -> Result<()> {
 
     // Parse arguments and determine path
     let args = Args::parse();
+    if let Some(shell) = args.generate_completions {
+        generate_completions(shell, &mut io::stdout());
+        return Ok(());
+    }
+    if args.generate_manpage {
+        generate_manpage(&mut io::stdout())?;
+        return Ok(());
+    }
     let path = match args.path {
         Some(path) => {
             canonicalize(&path).with_context(|| format!("Could not find path {}", &path))?
@@ -107,15 +192,43 @@ fn main() -> Result<()> {
     }
 
     // Setup environment
-    let env = Env::new(path, args.revisions)?;
+    let jj_config = std::env::var("LAZYJJ_JJ_CONFIG")
+        .ok()
+        .filter(|jj_config| !jj_config.is_empty())
+        .into_iter()
+        .chain(args.jj_config)
+        .collect();
+    let env = Env::new(path, args.revisions, jj_config)?;
     let mut commander = Commander::new(&env);
+    if let Some(directory) = args.record_fixtures.as_ref() {
+        commander.record_fixtures(
+            FixtureRecorder::new(directory, &env.root)
+                .with_context(|| format!("Could not create {}", directory.display()))?,
+        );
+    }
+    ui::styles::set_graph_style(env.config.graph_style());
 
     // Check that `jj status` works
-    commander.init()?;
+    if let Err(err) = commander.init() {
+        let message = format!("{err:#}");
+        if is_newer_jj_required_error(&message) {
+            bail!(newer_jj_required_message(&message));
+        }
+        return Err(err);
+    }
+
+    if args.no_tui {
+        let head_infos = commander.get_head_infos(&env.default_revset, &None)?;
+        println!("{}", serde_json::to_string_pretty(&head_infos)?);
+        return Ok(());
+    }
 
     // Setup app
-    let mut app = App::new(env.clone())?;
+    let mut app = App::new(env.clone(), args.revision)?;
 
+    if args.no_mouse || env_flag("LAZYJJ_NO_MOUSE") {
+        MOUSE_CAPTURE.store(false, Ordering::Relaxed);
+    }
     let mut terminal = setup_terminal()?;
 
     // Run app
@@ -126,26 +239,29 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+const IDLE_REDRAW_INTERVAL: Duration = Duration::from_secs(30);
+const BACKGROUND_REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+
 fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
     commander: &mut Commander,
 ) -> Result<()> {
     let mut start_time = Utc::now().time();
+    // Whether the draw is for a timer tick or a resize rather than an input
+    let mut redraw_only = false;
     loop {
         // Draw
         terminal.draw(|f| {
-            // Update current tab
+            // Update current tab. Redraws only pick up background work, as updating runs jj
             let update_span = trace_span!("update");
             update_span
-                .in_scope(|| -> Result<()> {
-                    if let Some(component_action) =
-                        app.get_or_init_current_tab(commander)?.update(commander)?
-                    {
-                        app.handle_action(component_action, commander)?;
+                .in_scope(|| {
+                    if redraw_only {
+                        app.poll_background(commander)
+                    } else {
+                        app.update(commander)
                     }
-
-                    Ok(())
                 })
                 .unwrap();
 
@@ -175,9 +291,28 @@ fn run_app<B: Backend>(
 
         start_time = Utc::now().time();
 
+        // Redraw periodically when idle, to keep relative times up to date. Redraw more often while
+        // background work is running, to show its results
+        let redraw_interval = if app.has_background_work() {
+            BACKGROUND_REDRAW_INTERVAL
+        } else {
+            IDLE_REDRAW_INTERVAL
+        };
+        redraw_only = true;
+        if !event::poll(redraw_interval)? {
+            continue;
+        }
+
         // Input
         let input_spawn = trace_span!("input");
         let event = event::read()?;
+        if let Event::Resize(_, _) = event {
+            // Redraw at the new size right away, rather than on the next input
+            terminal.autoresize()?;
+            app.reset_size();
+            continue;
+        }
+        redraw_only = false;
         let should_stop = input_spawn.in_scope(|| -> Result<bool> {
             if app.input(event, commander)? {
                 return Ok(true);
@@ -189,26 +324,113 @@ fn run_app<B: Backend>(
         if should_stop {
             return Ok(());
         }
+
+        if let Some(args) = app.external_command.take() {
+            run_external(terminal, app, commander, args)?;
+        }
+
+        if let Some(output) = app.print_output.take() {
+            print_output(terminal, &output)?;
+        }
+    }
+}
+
+// Whether the TUI captures the mouse, unless disabled with `--no-mouse` or `LAZYJJ_NO_MOUSE`
+static MOUSE_CAPTURE: AtomicBool = AtomicBool::new(true);
+
+/// Terminal operations used to hand the terminal to an external command and take it back.
+trait TerminalHandle {
+    // Leave raw mode and the alternate screen, like when quitting
+    fn suspend(&mut self) -> Result<()>;
+    // Enter raw mode and the alternate screen again, like when starting
+    fn resume(&mut self) -> Result<()>;
+    // Clear the screen, so the next draw redraws everything
+    fn force_redraw(&mut self) -> Result<()>;
+}
+
+impl<B: Backend> TerminalHandle for Terminal<B> {
+    fn suspend(&mut self) -> Result<()> {
+        disable_raw_mode()?;
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+        if MOUSE_CAPTURE.load(Ordering::Relaxed) {
+            execute!(io::stdout(), DisableMouseCapture)?;
+        }
+        self.show_cursor()?;
+        Ok(())
+    }
+
+    fn resume(&mut self) -> Result<()> {
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        if MOUSE_CAPTURE.load(Ordering::Relaxed) {
+            execute!(io::stdout(), EnableMouseCapture)?;
+        }
+        Ok(())
+    }
+
+    fn force_redraw(&mut self) -> Result<()> {
+        self.clear()?;
+        Ok(())
     }
 }
 
+/// Run a function with the terminal released, such as to run an editor, and take it back after.
+fn with_suspended_terminal<T: TerminalHandle, R>(
+    terminal: &mut T,
+    run: impl FnOnce() -> R,
+) -> Result<R> {
+    terminal.suspend()?;
+    let result = run();
+    terminal.resume()?;
+    terminal.force_redraw()?;
+    Ok(result)
+}
+
+/// Run a jj command which uses the terminal, such as one opening an editor or merge tool, with
+/// the TUI suspended.
+fn run_external<T: TerminalHandle>(
+    terminal: &mut T,
+    app: &mut App,
+    commander: &mut Commander,
+    args: Vec<String>,
+) -> Result<()> {
+    info!("Running external command: jj {}", args.join(" "));
+    app.suspend_for_external(commander);
+    let result =
+        with_suspended_terminal(terminal, || commander.execute_interactive_jj_command(&args))?;
+    app.resume_from_external(commander)?;
+
+    if let Err(err) = result {
+        app.popup = Some(Box::new(MessagePopup {
+            title: "Command error".into(),
+            messages: err.into_text("")?,
+        }));
+    }
+
+    Ok(())
+}
+
+/// Print output with the TUI suspended, such as to copy it or pipe it from the scrollback, and wait
+/// for Enter before taking the terminal back.
+fn print_output<T: TerminalHandle>(terminal: &mut T, output: &str) -> Result<()> {
+    with_suspended_terminal(terminal, || -> io::Result<()> {
+        println!("{output}");
+        eprintln!("Press Enter to return to lazyjj");
+        io::stdin().read_line(&mut String::new())?;
+        Ok(())
+    })??;
+
+    Ok(())
+}
+
 fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    Ok(Terminal::new(backend)?)
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    terminal.resume()?;
+    Ok(terminal)
 }
 
 fn restore_terminal(mut terminal: Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-    Ok(())
+    terminal.suspend()
 }
 
 enum ComponentInputResult {
@@ -216,3 +438,135 @@ enum ComponentInputResult {
     HandledAction(ComponentAction),
     NotHandled,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    // Records calls, shared with the command run while suspended
+    struct MockTerminal {
+        calls: Rc<RefCell<Vec<&'static str>>>,
+    }
+
+    impl TerminalHandle for MockTerminal {
+        fn suspend(&mut self) -> Result<()> {
+            self.calls.borrow_mut().push("suspend");
+            Ok(())
+        }
+
+        fn resume(&mut self) -> Result<()> {
+            self.calls.borrow_mut().push("resume");
+            Ok(())
+        }
+
+        fn force_redraw(&mut self) -> Result<()> {
+            self.calls.borrow_mut().push("force_redraw");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn suspend_terminal_order() -> Result<()> {
+        let calls = Rc::new(RefCell::new(vec![]));
+        let mut terminal = MockTerminal {
+            calls: calls.clone(),
+        };
+
+        let result = with_suspended_terminal(&mut terminal, || {
+            calls.borrow_mut().push("run");
+            Err::<(), _>("editor failed")
+        })?;
+
+        assert_eq!(result, Err("editor failed"));
+        // The terminal is taken back even if the command failed
+        assert_eq!(
+            *calls.borrow(),
+            vec!["suspend", "run", "resume", "force_redraw"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_args() -> Result<()> {
+        let args = Args::try_parse_from(["lazyjj", "--path", "repo", "-r", "::@"])?;
+        assert_eq!(args.path.as_deref(), Some("repo"));
+        assert_eq!(args.revisions.as_deref(), Some("::@"));
+        assert_eq!(args.revision, None);
+        assert!(!args.no_mouse);
+
+        let args = Args::try_parse_from(["lazyjj", "--no-mouse"])?;
+        assert!(args.no_mouse);
+        assert!(args.jj_config.is_empty());
+        assert_eq!(args.record_fixtures, None);
+
+        let args = Args::try_parse_from(["lazyjj", "--record-fixtures", "fixtures"])?;
+        assert_eq!(args.record_fixtures, Some(PathBuf::from("fixtures")));
+
+        let args = Args::try_parse_from([
+            "lazyjj",
+            "--jj-config",
+            r#"ui.diff.format="git""#,
+            "--jj-config",
+            r#"revset-aliases."mine()"="mine() & ~empty()""#,
+        ])?;
+        assert_eq!(
+            args.jj_config,
+            [
+                r#"ui.diff.format="git""#,
+                r#"revset-aliases."mine()"="mine() & ~empty()""#
+            ]
+        );
+
+        let args = Args::try_parse_from(["lazyjj", "--revision", "main"])?;
+        assert_eq!(args.revision.as_deref(), Some("main"));
+        assert_eq!(args.revisions, None);
+        assert_eq!(args.generate_completions, None);
+        assert!(!args.generate_manpage);
+
+        let args = Args::try_parse_from(["lazyjj", "--generate-completions", "zsh"])?;
+        assert_eq!(args.generate_completions, Some(Shell::Zsh));
+        assert_eq!(args.path, None);
+
+        assert!(Args::try_parse_from(["lazyjj", "--generate-completions", "ksh"]).is_err());
+        assert!(Args::try_parse_from([
+            "lazyjj",
+            "--generate-completions",
+            "bash",
+            "--generate-manpage"
+        ])
+        .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn completions() {
+        for (shell, expected) in [
+            (Shell::Bash, "complete -F _lazyjj"),
+            (Shell::Zsh, "#compdef lazyjj"),
+            (Shell::Fish, "complete -c lazyjj"),
+            (Shell::PowerShell, "Register-ArgumentCompleter"),
+        ] {
+            let mut out = vec![];
+            generate_completions(shell, &mut out);
+            let completions = String::from_utf8(out).unwrap();
+            assert!(completions.contains(expected), "{shell}: {completions}");
+            assert!(completions.contains("path"), "{shell}: {completions}");
+        }
+    }
+
+    #[test]
+    fn manpage() -> Result<()> {
+        let mut out = vec![];
+        generate_manpage(&mut out)?;
+        let manpage = String::from_utf8(out)?;
+        assert!(manpage.contains(".TH lazyjj 1"), "{manpage}");
+        assert!(manpage.contains("Path to jj repo"), "{manpage}");
+        // Packaging flags aren't documented
+        assert!(!manpage.contains("generate"), "{manpage}");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph},
+    Frame,
+};
+use std::collections::HashMap;
+use tui_textarea::TextArea;
+
+use crate::{
+    commander::{ids::ChangeId, Commander},
+    env::Config,
+    ui::{
+        styles::{border_set, create_popup_block},
+        utils::centered_rect,
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+/// Change to the log tab's aliases, sent by `AliasPopup`.
+pub enum AliasAction {
+    Set(ChangeId, String),
+    Remove(ChangeId),
+    Jump(ChangeId),
+}
+
+// Get indexes of aliases matching the filter, case-insensitively
+fn filter_aliases(aliases: &[(String, ChangeId)], filter: &str) -> Vec<usize> {
+    let filter = filter.trim().to_lowercase();
+    aliases
+        .iter()
+        .enumerate()
+        .filter(|(_, (alias, _))| alias.to_lowercase().contains(&filter))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Popup to name the selected change with an alias for the session, and to list, go to and
+/// remove aliases.
+pub struct AliasPopup<'a> {
+    // Sorted by alias
+    aliases: Vec<(String, ChangeId)>,
+    filtered: Vec<usize>,
+    textarea: TextArea<'a>,
+    list_state: ListState,
+    change_id: ChangeId,
+    config: Config,
+    tx: std::sync::mpsc::Sender<AliasAction>,
+}
+
+impl AliasPopup<'_> {
+    pub fn new(
+        aliases: &HashMap<ChangeId, String>,
+        change_id: ChangeId,
+        config: Config,
+        tx: std::sync::mpsc::Sender<AliasAction>,
+    ) -> Self {
+        let mut aliases: Vec<(String, ChangeId)> = aliases
+            .iter()
+            .map(|(change_id, alias)| (alias.clone(), change_id.clone()))
+            .collect();
+        aliases.sort_by(|(alias, _), (other, _)| alias.cmp(other));
+        let filtered = filter_aliases(&aliases, "");
+
+        Self {
+            aliases,
+            filtered,
+            textarea: TextArea::default(),
+            list_state: ListState::default().with_selected(Some(0)),
+            change_id,
+            config,
+            tx,
+        }
+    }
+
+    fn get_text(&self) -> String {
+        self.textarea.lines().join("").trim().to_owned()
+    }
+
+    fn refresh_filter(&mut self) {
+        self.filtered = filter_aliases(&self.aliases, &self.get_text());
+        self.list_state.select(Some(0));
+    }
+
+    fn scroll(&mut self, scroll: isize) {
+        self.list_state.select(Some(
+            self.list_state
+                .selected()
+                .map(|selected| selected.saturating_add_signed(scroll))
+                .unwrap_or(0)
+                .min(self.filtered.len().saturating_sub(1)),
+        ));
+    }
+
+    fn get_selected(&self) -> Option<&(String, ChangeId)> {
+        let index = self.filtered.get(self.list_state.selected()?)?;
+        self.aliases.get(*index)
+    }
+
+    fn close() -> ComponentInputResult {
+        ComponentInputResult::HandledAction(ComponentAction::SetPopup(None))
+    }
+}
+
+impl Component for AliasPopup<'_> {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let block = create_popup_block("Aliases");
+        let area = centered_rect(area, 50, 50);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Fill(1),
+                Constraint::Length(2),
+            ])
+            .split(block.inner(area));
+
+        self.textarea.set_block(
+            Block::bordered()
+                .title(format!(" Alias for {} ", self.change_id.as_str()))
+                .border_set(border_set())
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+        f.render_widget(&self.textarea, popup_chunks[0]);
+
+        if self.aliases.is_empty() {
+            f.render_widget(
+                Paragraph::new("No aliases yet")
+                    .fg(Color::DarkGray)
+                    .italic(),
+                popup_chunks[1],
+            );
+        } else {
+            let list_items = self.filtered.iter().map(|index| {
+                let (alias, change_id) = &self.aliases[*index];
+                Line::from(vec![
+                    Span::raw(alias.clone()).fg(Color::Yellow),
+                    Span::raw(" "),
+                    Span::raw(change_id.as_string()).fg(Color::Blue),
+                ])
+            });
+            let list = List::new(list_items)
+                .scroll_padding(3)
+                .highlight_style(Style::default().bg(self.config.highlight_color()));
+            f.render_stateful_widget(list, popup_chunks[1], &mut self.list_state);
+        }
+
+        let help = Paragraph::new(vec![
+            "Ctrl+s: name change | Enter: go to alias | Ctrl+d: remove alias | Escape: cancel"
+                .into(),
+        ])
+        .fg(Color::DarkGray)
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_set(border_set())
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+        f.render_widget(help, popup_chunks[2]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            match key.code {
+                KeyCode::Down => self.scroll(1),
+                KeyCode::Up => self.scroll(-1),
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    let alias = self.get_text();
+                    if !alias.is_empty() {
+                        self.tx
+                            .send(AliasAction::Set(self.change_id.clone(), alias))?;
+                        return Ok(Self::close());
+                    }
+                }
+                KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    if let Some((_, change_id)) = self.get_selected() {
+                        self.tx.send(AliasAction::Remove(change_id.clone()))?;
+                        return Ok(Self::close());
+                    }
+                }
+                KeyCode::Enter => {
+                    match self.get_selected() {
+                        Some((_, change_id)) => {
+                            self.tx.send(AliasAction::Jump(change_id.clone()))?
+                        }
+                        // Name the change when no alias matches
+                        None if !self.get_text().is_empty() => self
+                            .tx
+                            .send(AliasAction::Set(self.change_id.clone(), self.get_text()))?,
+                        None => {}
+                    }
+                    return Ok(Self::close());
+                }
+                KeyCode::Esc => return Ok(Self::close()),
+                _ => {
+                    if self.textarea.input(event) {
+                        self.refresh_filter();
+                    }
+                }
+            }
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_alias_names() {
+        let aliases = [
+            ("fix".to_owned(), ChangeId("kxryzmor".to_owned())),
+            ("refactor".to_owned(), ChangeId("vruxwmqv".to_owned())),
+        ];
+
+        assert_eq!(filter_aliases(&aliases, ""), [0, 1]);
+        assert_eq!(filter_aliases(&aliases, " Fac"), [1]);
+        assert!(filter_aliases(&aliases, "docs").is_empty());
+    }
+}
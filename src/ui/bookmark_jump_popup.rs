@@ -0,0 +1,236 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph},
+    Frame,
+};
+use tui_textarea::TextArea;
+
+use crate::{
+    commander::{bookmarks::BookmarkTarget, log::Head, CommandError, Commander},
+    env::Config,
+    ui::{
+        styles::{border_set, create_popup_block},
+        utils::centered_rect,
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+// Get indexes of bookmarks matching the filter, case-insensitively
+fn filter_bookmark_targets(bookmark_targets: &[BookmarkTarget], filter: &str) -> Vec<usize> {
+    let filter = filter.to_lowercase();
+    bookmark_targets
+        .iter()
+        .enumerate()
+        .filter(|(_, bookmark_target)| {
+            bookmark_target
+                .bookmark
+                .to_string()
+                .to_lowercase()
+                .contains(&filter)
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Popup to jump to a bookmark's change in the log.
+pub struct BookmarkJumpPopup<'a> {
+    bookmark_targets: Result<Vec<BookmarkTarget>, CommandError>,
+    filtered: Vec<usize>,
+    filter_textarea: TextArea<'a>,
+    list_state: ListState,
+    list_height: u16,
+    config: Config,
+    tx: std::sync::mpsc::Sender<Head>,
+}
+
+impl BookmarkJumpPopup<'_> {
+    pub fn new(
+        commander: &mut Commander,
+        config: Config,
+        tx: std::sync::mpsc::Sender<Head>,
+    ) -> Self {
+        let bookmark_targets = commander.get_bookmark_targets();
+        let filtered = bookmark_targets
+            .as_ref()
+            .map(|bookmark_targets| filter_bookmark_targets(bookmark_targets, ""))
+            .unwrap_or_default();
+
+        Self {
+            bookmark_targets,
+            filtered,
+            filter_textarea: TextArea::default(),
+            list_state: ListState::default().with_selected(Some(0)),
+            list_height: 0,
+            config,
+            tx,
+        }
+    }
+
+    fn refresh_filter(&mut self) {
+        if let Ok(bookmark_targets) = self.bookmark_targets.as_ref() {
+            self.filtered =
+                filter_bookmark_targets(bookmark_targets, &self.filter_textarea.lines().join(""));
+        }
+        self.list_state.select(Some(0));
+    }
+
+    fn scroll(&mut self, scroll: isize) {
+        self.list_state.select(Some(
+            self.list_state
+                .selected()
+                .map(|selected| selected.saturating_add_signed(scroll))
+                .unwrap_or(0)
+                .min(self.filtered.len().saturating_sub(1)),
+        ));
+    }
+
+    fn get_selected(&self) -> Option<&BookmarkTarget> {
+        let index = self.filtered.get(self.list_state.selected()?)?;
+        self.bookmark_targets.as_ref().ok()?.get(*index)
+    }
+}
+
+impl Component for BookmarkJumpPopup<'_> {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let block = create_popup_block("Go to bookmark");
+        let area = centered_rect(area, 60, 60);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Fill(1),
+                Constraint::Length(2),
+            ])
+            .split(block.inner(area));
+
+        self.filter_textarea.set_block(
+            Block::bordered()
+                .title(" Filter ")
+                .border_set(border_set())
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+        f.render_widget(&self.filter_textarea, popup_chunks[0]);
+
+        match self.bookmark_targets.as_ref() {
+            Ok(bookmark_targets) => {
+                let list_items = self.filtered.iter().map(|index| {
+                    let bookmark_target = &bookmark_targets[*index];
+                    let description = if bookmark_target.description.is_empty() {
+                        Span::raw("(no description set)").fg(Color::DarkGray)
+                    } else {
+                        Span::raw(bookmark_target.description.clone())
+                    };
+                    Line::from(vec![
+                        Span::raw(bookmark_target.bookmark.to_string()).fg(Color::Magenta),
+                        Span::raw(" "),
+                        Span::raw(bookmark_target.change_id.clone()).fg(Color::Blue),
+                        Span::raw(" "),
+                        description,
+                    ])
+                });
+                let list = List::new(list_items)
+                    .scroll_padding(3)
+                    .highlight_style(Style::default().bg(self.config.highlight_color()));
+                f.render_stateful_widget(list, popup_chunks[1], &mut self.list_state);
+            }
+            Err(err) => {
+                f.render_widget(
+                    Paragraph::new(err.into_text("Error getting bookmarks")?),
+                    popup_chunks[1],
+                );
+            }
+        }
+        self.list_height = popup_chunks[1].height;
+
+        let help = Paragraph::new(vec![
+            "Up/Down: select | Enter: go to bookmark | Escape: cancel".into(),
+        ])
+        .fg(Color::DarkGray)
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_set(border_set())
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+        f.render_widget(help, popup_chunks[2]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            match key.code {
+                KeyCode::Down => self.scroll(1),
+                KeyCode::Up => self.scroll(-1),
+                KeyCode::PageDown => self.scroll(self.list_height as isize / 2),
+                KeyCode::PageUp => self.scroll((self.list_height as isize / 2).saturating_neg()),
+                KeyCode::Enter => {
+                    if let Some(bookmark_target) = self.get_selected() {
+                        let head = commander.get_bookmark_head(&bookmark_target.bookmark)?;
+                        self.tx.send(head)?;
+                    }
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                _ => {
+                    if self.filter_textarea.input(event) {
+                        self.refresh_filter();
+                    }
+                }
+            }
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::bookmarks::Bookmark;
+
+    fn bookmark_target(name: &str, remote: Option<&str>) -> BookmarkTarget {
+        BookmarkTarget {
+            bookmark: Bookmark {
+                name: name.to_owned(),
+                remote: remote.map(str::to_owned),
+                present: true,
+            },
+            change_id: "abc".to_owned(),
+            description: "".to_owned(),
+        }
+    }
+
+    #[test]
+    fn filter_bookmarks() {
+        let bookmark_targets = [
+            bookmark_target("main", None),
+            bookmark_target("feature-foo", None),
+            bookmark_target("feature-foo", Some("origin")),
+        ];
+
+        assert_eq!(filter_bookmark_targets(&bookmark_targets, ""), [0, 1, 2]);
+        assert_eq!(filter_bookmark_targets(&bookmark_targets, "Foo"), [1, 2]);
+        assert_eq!(filter_bookmark_targets(&bookmark_targets, "@origin"), [2]);
+        assert!(filter_bookmark_targets(&bookmark_targets, "bar").is_empty());
+    }
+}
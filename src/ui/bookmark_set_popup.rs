@@ -1,12 +1,12 @@
 use ansi_to_tui::IntoText;
 use anyhow::bail;
 use anyhow::Result;
-use crossterm::event::{Event, KeyCode, KeyModifiers};
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Style, Stylize},
     text::{Span, Text},
-    widgets::{Block, BorderType, Borders, Clear, List, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph},
 };
 use tui_textarea::TextArea;
 
@@ -18,8 +18,8 @@ use crate::{
     },
     env::Config,
     ui::{
-        styles::create_popup_block,
-        utils::{centered_rect, centered_rect_line_height},
+        styles::{border_set, create_popup_block},
+        utils::{centered_rect, centered_rect_line_height, fuzzy::fuzzy_filter},
         Component, ComponentAction,
     },
     ComponentInputResult,
@@ -37,6 +37,10 @@ pub struct BookmarkSetPopup<'a> {
     pub change_id: Option<ChangeId>,
     commit_id: CommitId,
     options: Vec<BookmarkSetOption>,
+    // Indexes of options shown in the list, after filtering
+    visible: Vec<usize>,
+    filter_textarea: TextArea<'a>,
+    filtering: bool,
     list_state: ListState,
     list_height: u16,
     config: Config,
@@ -83,6 +87,33 @@ fn generate_options(
     options
 }
 
+// Get indexes of options to show. Options other than bookmarks are always shown first, followed by
+// bookmarks fuzzy-matching the filter, best match first
+fn filter_options(options: &[BookmarkSetOption], filter: &str) -> Vec<usize> {
+    let mut visible: Vec<usize> = options
+        .iter()
+        .enumerate()
+        .filter(|(_, option)| !matches!(option, BookmarkSetOption::Bookmark(_)))
+        .map(|(i, _)| i)
+        .collect();
+
+    let bookmarks: Vec<(usize, &str)> = options
+        .iter()
+        .enumerate()
+        .filter_map(|(i, option)| match option {
+            BookmarkSetOption::Bookmark(bookmark) => Some((i, bookmark.name.as_str())),
+            _ => None,
+        })
+        .collect();
+    visible.extend(
+        fuzzy_filter(filter, bookmarks.iter().map(|(_, name)| *name))
+            .into_iter()
+            .map(|i| bookmarks[i].0),
+    );
+
+    visible
+}
+
 fn generate_name(git_push_bookmark_prefix: &str, change_id: &ChangeId) -> String {
     let mut change_id = change_id.to_string();
     change_id.truncate(12);
@@ -97,8 +128,12 @@ impl BookmarkSetPopup<'_> {
         commit_id: CommitId,
         tx: std::sync::mpsc::Sender<bool>,
     ) -> Self {
-        Self {
-            options: generate_options(commander, change_id.as_ref()),
+        let options = generate_options(commander, change_id.as_ref());
+        let mut popup = Self {
+            visible: filter_options(&options, ""),
+            options,
+            filter_textarea: TextArea::default(),
+            filtering: false,
             change_id,
             list_state: ListState::default().with_selected(Some(0)),
             list_height: 0,
@@ -106,7 +141,9 @@ impl BookmarkSetPopup<'_> {
             commit_id,
             creating: None,
             tx,
-        }
+        };
+        popup.set_filtering(false);
+        popup
     }
 
     fn scroll(&mut self, scroll: isize) {
@@ -115,10 +152,24 @@ impl BookmarkSetPopup<'_> {
                 .selected()
                 .map(|selected| selected.saturating_add_signed(scroll))
                 .unwrap_or(0)
-                .min(self.options.len().saturating_sub(1)),
+                .min(self.visible.len().saturating_sub(1)),
         ));
     }
 
+    fn refresh_filter(&mut self) {
+        self.visible = filter_options(&self.options, &self.filter_textarea.lines().join(""));
+        self.list_state.select(Some(0));
+    }
+
+    fn set_filtering(&mut self, filtering: bool) {
+        self.filtering = filtering;
+        self.filter_textarea.set_cursor_style(if filtering {
+            Style::default().reversed()
+        } else {
+            Style::default()
+        });
+    }
+
     fn on_creating(&mut self) {
         self.creating = Some(TextArea::default());
     }
@@ -175,7 +226,7 @@ impl Component for BookmarkSetPopup<'_> {
                 .block(
                     Block::default()
                         .borders(Borders::TOP)
-                        .border_type(BorderType::Rounded)
+                        .border_set(border_set())
                         .border_style(Style::default().fg(Color::DarkGray)),
                 );
 
@@ -187,7 +238,7 @@ impl Component for BookmarkSetPopup<'_> {
                     Style::new().bold().cyan(),
                 ))
                 .title_alignment(Alignment::Center)
-                .border_type(BorderType::Rounded)
+                .border_set(border_set())
                 .border_style(Style::default().fg(Color::Green));
             let area = centered_rect(area, 40, 60);
             f.render_widget(Clear, area);
@@ -195,10 +246,27 @@ impl Component for BookmarkSetPopup<'_> {
 
             let popup_chunks = Layout::default()
                 .direction(Direction::Vertical)
-                .constraints([Constraint::Fill(1), Constraint::Length(2)])
+                .constraints([
+                    Constraint::Length(3),
+                    Constraint::Fill(1),
+                    Constraint::Length(2),
+                ])
                 .split(block.inner(area));
 
-            let list_items = self.options.iter().map(|option| match option {
+            self.filter_textarea.set_block(
+                Block::bordered()
+                    .title(" Filter ")
+                    .border_set(border_set())
+                    .border_style(Style::default().fg(if self.filtering {
+                        Color::Green
+                    } else {
+                        Color::DarkGray
+                    })),
+            );
+            f.render_widget(&self.filter_textarea, popup_chunks[0]);
+
+            let options = &self.options;
+            let list_items = self.visible.iter().map(|index| match &options[*index] {
                 BookmarkSetOption::CreateBookmark => {
                     Text::raw("(C)reate bookmark").fg(Color::Yellow)
                 }
@@ -219,20 +287,24 @@ impl Component for BookmarkSetPopup<'_> {
                 .scroll_padding(3)
                 .highlight_style(Style::default().bg(self.config.highlight_color()));
 
-            f.render_stateful_widget(list, popup_chunks[0], &mut self.list_state);
-            self.list_height = popup_chunks[0].height;
+            f.render_stateful_widget(list, popup_chunks[1], &mut self.list_state);
+            self.list_height = popup_chunks[1].height;
 
-            let help = Paragraph::new(vec!["j/k: scroll down/up | Escape: cancel".into()])
-                .fg(Color::DarkGray)
-                .alignment(Alignment::Center)
-                .block(
-                    Block::default()
-                        .borders(Borders::TOP)
-                        .border_type(BorderType::Rounded)
-                        .border_style(Style::default().fg(Color::DarkGray)),
-                );
+            let help = Paragraph::new(vec![if self.filtering {
+                "Up/Down: scroll down/up | Enter: select | Escape: stop filtering".into()
+            } else {
+                "j/k: scroll down/up | /: filter | Escape: cancel".into()
+            }])
+            .fg(Color::DarkGray)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .border_set(border_set())
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            );
 
-            f.render_widget(help, popup_chunks[1]);
+            f.render_widget(help, popup_chunks[2]);
         }
 
         Ok(())
@@ -273,8 +345,40 @@ impl Component for BookmarkSetPopup<'_> {
             return Ok(ComponentInputResult::Handled);
         }
 
+        if self.filtering {
+            if let Event::Key(key) = event {
+                if key.kind != KeyEventKind::Press {
+                    return Ok(ComponentInputResult::Handled);
+                }
+
+                match key.code {
+                    KeyCode::Down => self.scroll(1),
+                    KeyCode::Up => self.scroll(-1),
+                    KeyCode::PageDown => self.scroll(self.list_height as isize / 2),
+                    KeyCode::PageUp => {
+                        self.scroll((self.list_height as isize / 2).saturating_neg())
+                    }
+                    KeyCode::Esc => self.set_filtering(false),
+                    // Select with the same handling as outside of filtering
+                    KeyCode::Enter => {}
+                    _ => {
+                        if self.filter_textarea.input(event.clone()) {
+                            self.refresh_filter();
+                        }
+                    }
+                }
+
+                if key.code != KeyCode::Enter {
+                    return Ok(ComponentInputResult::Handled);
+                }
+            }
+        }
+
         if let Event::Key(key) = event {
             match key.code {
+                KeyCode::Char('/') => {
+                    self.set_filtering(true);
+                }
                 KeyCode::Char('j') | KeyCode::Down => {
                     self.scroll(1);
                 }
@@ -301,7 +405,8 @@ impl Component for BookmarkSetPopup<'_> {
                     if let Some(action) = self
                         .list_state
                         .selected()
-                        .and_then(|index| self.options.get(index))
+                        .and_then(|index| self.visible.get(index))
+                        .and_then(|index| self.options.get(*index))
                     {
                         match action {
                             BookmarkSetOption::CreateBookmark => {
@@ -323,6 +428,7 @@ impl Component for BookmarkSetPopup<'_> {
                             }
                             BookmarkSetOption::Error(_) => {
                                 self.options = generate_options(commander, self.change_id.as_ref());
+                                self.refresh_filter();
                             }
                         }
                     }
@@ -342,3 +448,30 @@ impl Component for BookmarkSetPopup<'_> {
         Ok(ComponentInputResult::NotHandled)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark_option(name: &str) -> BookmarkSetOption {
+        BookmarkSetOption::Bookmark(Bookmark {
+            name: name.to_owned(),
+            remote: None,
+            present: true,
+        })
+    }
+
+    #[test]
+    fn filter_options_keeps_actions_first() {
+        let options = [
+            BookmarkSetOption::CreateBookmark,
+            bookmark_option("main"),
+            bookmark_option("feature-bar"),
+            bookmark_option("fix-bug"),
+        ];
+
+        assert_eq!(filter_options(&options, ""), [0, 1, 2, 3]);
+        assert_eq!(filter_options(&options, "fb"), [0, 3, 2]);
+        assert_eq!(filter_options(&options, "xyz"), [0]);
+    }
+}
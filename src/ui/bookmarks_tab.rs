@@ -1,13 +1,21 @@
-#![allow(clippy::borrow_interior_mutable_const)]
 use crate::{
     commander::{bookmarks::BookmarkLine, ids::ChangeId, CommandError, Commander},
     env::{Config, DiffFormat},
     ui::{
+        confirm_popup::{ConfirmAction, ConfirmPopup},
         details_panel::DetailsPanel,
         help_popup::HelpPopup,
         message_popup::MessagePopup,
-        utils::{centered_rect, centered_rect_line_height, tabs_to_spaces},
-        Component, ComponentAction,
+        styles::{border_set, highlight_line},
+        utils::{
+            centered_rect, centered_rect_line_height,
+            char_diff::char_diff_text,
+            fit_diff_lines, keep_last_ok, next_diff_format, panel_constraints, panel_hint,
+            recover_selection,
+            refresh::{LastRefresh, RefreshInputs},
+            stale_banner, tabs_to_spaces, DEFAULT_SPLIT_PERCENT,
+        },
+        Component, ComponentAction, FocusedPanel,
     },
     ComponentInputResult,
 };
@@ -16,7 +24,6 @@ use anyhow::Result;
 use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
 use ratatui::{prelude::*, widgets::*};
 use tracing::instrument;
-use tui_confirm_dialog::{ButtonLabel, ConfirmDialog, ConfirmDialogState, Listener};
 use tui_textarea::{CursorMove, TextArea};
 
 struct CreateBookmark<'a> {
@@ -30,22 +37,31 @@ struct RenameBookmark<'a> {
     error: Option<anyhow::Error>,
 }
 
-struct DeleteBookmark {
-    name: String,
+/// Action of a confirmation popup opened by the bookmarks tab, on a bookmark.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BookmarksConfirmAction {
+    Delete(String),
+    Forget(String),
+    // Create a new change on the bookmark, and describe it right away with `describe`
+    New { bookmark: String, describe: bool },
+    Edit(String),
 }
 
-struct ForgetBookmark {
-    name: String,
+/// What the details panel shows of the selected bookmark's change, toggled with `s`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DetailsMode {
+    /// Diff in the current diff format
+    #[default]
+    Diff,
+    /// `--stat` summary of the changed files
+    Stat,
 }
 
-const DELETE_BRANCH_POPUP_ID: u16 = 1;
-const FORGET_BRANCH_POPUP_ID: u16 = 2;
-const NEW_POPUP_ID: u16 = 3;
-const EDIT_POPUP_ID: u16 = 4;
-
 /// Bookmarks tab. Shows bookmarks in left panel and selected bookmark current change in right panel.
 pub struct BookmarksTab<'a> {
     bookmarks_output: Result<Vec<BookmarkLine>, CommandError>,
+    // Error from the last refresh, while showing the last successful bookmarks
+    bookmarks_error: Option<CommandError>,
     bookmarks_list_state: ListState,
     bookmarks_height: u16,
 
@@ -58,18 +74,22 @@ pub struct BookmarksTab<'a> {
 
     create: Option<CreateBookmark<'a>>,
     rename: Option<RenameBookmark<'a>>,
-    delete: Option<DeleteBookmark>,
-    forget: Option<ForgetBookmark>,
 
     describe_textarea: Option<TextArea<'a>>,
-    describe_after_new: bool,
     describe_after_new_change: Option<ChangeId>,
 
-    popup: ConfirmDialogState,
-    popup_tx: std::sync::mpsc::Sender<Listener>,
-    popup_rx: std::sync::mpsc::Receiver<Listener>,
-
     diff_format: DiffFormat,
+    details_mode: DetailsMode,
+
+    // Shown below the bookmarks until the next key press
+    notification: Option<String>,
+    // Last successful refresh with `R`, to skip the next one if nothing changed
+    last_refresh: Option<LastRefresh>,
+
+    // Panel last interacted with, panel shown full screen, and width of the left panel in percent
+    focused_panel: FocusedPanel,
+    maximized_panel: Option<FocusedPanel>,
+    split_percent: u16,
 
     config: Config,
 }
@@ -132,10 +152,11 @@ impl BookmarksTab<'_> {
             _ => None,
         });
 
-        let (popup_tx, popup_rx) = std::sync::mpsc::channel();
+        commander.capture_operation();
 
         Ok(Self {
             bookmarks_output,
+            bookmarks_error: None,
             bookmark,
             bookmarks_list_state,
             bookmarks_height: 0,
@@ -147,18 +168,19 @@ impl BookmarksTab<'_> {
 
             create: None,
             rename: None,
-            delete: None,
-            forget: None,
 
-            describe_after_new: false,
             describe_textarea: None,
             describe_after_new_change: None,
 
-            popup: ConfirmDialogState::default(),
-            popup_tx,
-            popup_rx,
-
             diff_format,
+            details_mode: DetailsMode::default(),
+
+            notification: None,
+            last_refresh: None,
+
+            focused_panel: FocusedPanel::Left,
+            maximized_panel: None,
+            split_percent: DEFAULT_SPLIT_PERCENT,
 
             config: commander.env.config.clone(),
         })
@@ -168,21 +190,113 @@ impl BookmarksTab<'_> {
         get_current_bookmark_index(self.bookmark.as_ref(), &self.bookmarks_output)
     }
 
+    // Select the bookmark at an index clamped to the bookmarks, such as where a deleted bookmark was
+    fn select_bookmark_near(&mut self, index: Option<usize>) {
+        self.bookmark = self.bookmarks_output.as_ref().ok().and_then(|bookmarks| {
+            recover_selection(index, bookmarks.len()).map(|index| bookmarks[index].to_owned())
+        });
+    }
+
+    fn refresh_inputs(&self) -> RefreshInputs {
+        RefreshInputs {
+            revset: None,
+            diff_format: self.details_format(),
+            show_all: self.show_all,
+            filters: vec![],
+        }
+    }
+
     pub fn refresh_bookmarks(&mut self, commander: &mut Commander) {
-        self.bookmarks_output = commander.get_bookmarks(self.show_all);
+        let bookmarks_output = commander.get_bookmarks(self.show_all);
+        self.bookmarks_error = keep_last_ok(&mut self.bookmarks_output, bookmarks_output);
+        commander.capture_operation();
+    }
+
+    /// Run the action of a confirmation popup after it was accepted.
+    pub fn run_confirmed(
+        &mut self,
+        commander: &mut Commander,
+        action: BookmarksConfirmAction,
+    ) -> Result<Option<ComponentAction>> {
+        match action {
+            BookmarksConfirmAction::Delete(name) => match commander.delete_bookmark(&name) {
+                Ok(_) => {
+                    let previous_index = self.get_current_bookmark_index();
+                    self.refresh_bookmarks(commander);
+                    self.select_bookmark_near(previous_index);
+                    self.refresh_bookmark(commander);
+                }
+                Err(err) => {
+                    return Ok(Some(ComponentAction::SetPopup(Some(Box::new(
+                        MessagePopup {
+                            title: "Delete error".into(),
+                            messages: err.to_string().into_text()?,
+                        },
+                    )))));
+                }
+            },
+            BookmarksConfirmAction::Forget(name) => match commander.forget_bookmark(&name) {
+                Ok(_) => {
+                    let previous_index = self.get_current_bookmark_index();
+                    self.refresh_bookmarks(commander);
+                    self.select_bookmark_near(previous_index);
+                    self.refresh_bookmark(commander);
+                }
+                Err(err) => {
+                    return Ok(Some(ComponentAction::SetPopup(Some(Box::new(
+                        MessagePopup {
+                            title: "Forget error".into(),
+                            messages: err.to_string().into_text()?,
+                        },
+                    )))));
+                }
+            },
+            BookmarksConfirmAction::New { bookmark, describe } => {
+                commander.run_new(&bookmark, false)?;
+                let head = commander.get_current_head()?;
+                if describe {
+                    self.describe_after_new_change = Some(head.change_id);
+                    let textarea = TextArea::default();
+                    self.describe_textarea = Some(textarea);
+                } else {
+                    return Ok(Some(ComponentAction::ViewLog(head)));
+                }
+            }
+            BookmarksConfirmAction::Edit(bookmark) => {
+                commander.run_edit(&bookmark)?;
+                let head = commander.get_current_head()?;
+                return Ok(Some(ComponentAction::ViewLog(head)));
+            }
+        }
+
+        Ok(None)
     }
 
     pub fn refresh_bookmark(&mut self, commander: &mut Commander) {
         self.bookmark_output = self.bookmark.as_ref().and_then(|bookmark| match bookmark {
             BookmarkLine::Parsed { bookmark, .. } => Some(
                 commander
-                    .get_bookmark_show(bookmark, &self.diff_format)
+                    .get_bookmark_show(bookmark, &self.details_format())
                     .map(|diff| tabs_to_spaces(&diff)),
             ),
             _ => None,
         });
 
         self.bookmark_panel.scroll = 0;
+        self.bookmark_panel.h_scroll = 0;
+    }
+
+    // Format of the bookmark's change in the details panel
+    fn details_format(&self) -> DiffFormat {
+        match self.details_mode {
+            DetailsMode::Diff => self.diff_format.clone(),
+            DetailsMode::Stat => DiffFormat::Stat,
+        }
+    }
+
+    pub fn set_diff_format(&mut self, commander: &mut Commander, diff_format: DiffFormat) {
+        self.diff_format = diff_format;
+        self.refresh_bookmark(commander);
     }
 
     fn scroll_bookmarks(&mut self, commander: &mut Commander, scroll: isize) {
@@ -193,7 +307,7 @@ impl BookmarksTab<'_> {
             Some(current_bookmark_index) => bookmarks.get(
                 current_bookmark_index
                     .saturating_add_signed(scroll)
-                    .min(bookmarks.len() - 1),
+                    .min(bookmarks.len().saturating_sub(1)),
             ),
             None => bookmarks.first(),
         }
@@ -213,87 +327,21 @@ impl Component for BookmarksTab<'_> {
         Ok(())
     }
 
-    fn update(&mut self, commander: &mut Commander) -> Result<Option<ComponentAction>> {
-        // Check for popup action
-        if let Ok(res) = self.popup_rx.try_recv() {
-            if res.1.unwrap_or(false) {
-                match res.0 {
-                    DELETE_BRANCH_POPUP_ID => {
-                        if let Some(delete) = self.delete.as_ref() {
-                            match commander.delete_bookmark(&delete.name) {
-                                Ok(_) => {
-                                    self.refresh_bookmarks(commander);
-                                    let bookmarks = Vec::new();
-                                    let bookmarks =
-                                        self.bookmarks_output.as_ref().unwrap_or(&bookmarks);
-                                    self.bookmark =
-                                        bookmarks.first().map(|bookmark| bookmark.to_owned());
-                                    self.refresh_bookmark(commander);
-                                }
-                                Err(err) => {
-                                    return Ok(Some(ComponentAction::SetPopup(Some(Box::new(
-                                        MessagePopup {
-                                            title: "Delete error".into(),
-                                            messages: err.to_string().into_text()?,
-                                        },
-                                    )))));
-                                }
-                            }
-                        }
-                    }
-                    FORGET_BRANCH_POPUP_ID => {
-                        if let Some(forget) = self.forget.as_ref() {
-                            match commander.forget_bookmark(&forget.name) {
-                                Ok(_) => {
-                                    self.refresh_bookmarks(commander);
-                                    let bookmarks = Vec::new();
-                                    let bookmarks =
-                                        self.bookmarks_output.as_ref().unwrap_or(&bookmarks);
-                                    self.bookmark =
-                                        bookmarks.first().map(|bookmark| bookmark.to_owned());
-                                    self.refresh_bookmark(commander);
-                                }
-                                Err(err) => {
-                                    return Ok(Some(ComponentAction::SetPopup(Some(Box::new(
-                                        MessagePopup {
-                                            title: "Forget error".into(),
-                                            messages: err.to_string().into_text()?,
-                                        },
-                                    )))));
-                                }
-                            }
-                        }
-                    }
-                    NEW_POPUP_ID => {
-                        if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref()
-                        {
-                            commander.run_new(&bookmark.to_string())?;
-                            let head = commander.get_current_head()?;
-                            if self.describe_after_new {
-                                self.describe_after_new_change = Some(head.change_id);
-                                self.describe_after_new = false;
-                                let textarea = TextArea::default();
-                                self.describe_textarea = Some(textarea);
-                                return Ok(None);
-                            } else {
-                                return Ok(Some(ComponentAction::ViewLog(head)));
-                            }
-                        }
-                    }
-                    EDIT_POPUP_ID => {
-                        if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref()
-                        {
-                            commander.run_edit(&bookmark.to_string())?;
-                            let head = commander.get_current_head()?;
-                            return Ok(Some(ComponentAction::ViewLog(head)));
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
+    fn focused_panel(&self) -> Option<FocusedPanel> {
+        Some(self.focused_panel)
+    }
 
-        Ok(None)
+    fn set_maximized_panel(&mut self, maximized_panel: Option<FocusedPanel>) {
+        self.maximized_panel = maximized_panel;
+    }
+
+    fn set_split_percent(&mut self, split_percent: u16) {
+        self.split_percent = split_percent;
+    }
+
+    fn reset_size(&mut self) {
+        self.bookmarks_height = 0;
+        self.bookmark_panel.reset_size();
     }
 
     fn draw(
@@ -303,7 +351,7 @@ impl Component for BookmarksTab<'_> {
     ) -> Result<()> {
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .constraints(panel_constraints(self.maximized_panel, self.split_percent))
             .split(area);
 
         // Draw bookmarks
@@ -327,15 +375,7 @@ impl Component for BookmarksTab<'_> {
                                 if current_bookmark_index.map_or(false, |current_bookmark_index| {
                                     i == current_bookmark_index
                                 }) {
-                                    line = line.bg(self.config.highlight_color());
-
-                                    line.spans = line
-                                        .spans
-                                        .iter_mut()
-                                        .map(|span| {
-                                            span.to_owned().bg(self.config.highlight_color())
-                                        })
-                                        .collect();
+                                    line = highlight_line(line, &self.config);
                                 }
 
                                 line
@@ -349,7 +389,7 @@ impl Component for BookmarksTab<'_> {
                 Err(err) => [
                     vec![Line::raw("Error getting bookmarks").bold().fg(Color::Red)],
                     // TODO: Remove when jj 0.20 is released
-                    if let CommandError::Status(output, _) = err {
+                    if let CommandError::Status(output, _, _) = err {
                         if output.contains("unexpected argument '-T' found") {
                             vec![
                                 Line::raw(""),
@@ -375,13 +415,55 @@ impl Component for BookmarksTab<'_> {
                 bookmark_lines
             };
 
-            let bookmarks_block = Block::bordered()
-                .title(" Bookmarks ")
-                .border_type(BorderType::Rounded);
-            self.bookmarks_height = bookmarks_block.inner(chunks[0]).height;
-            let bookmarks = List::new(lines).block(bookmarks_block).scroll_padding(3);
+            let mut bookmarks_block = Block::bordered()
+                .title(if self.bookmarks_error.is_some() {
+                    " Bookmarks (stale) "
+                } else {
+                    " Bookmarks "
+                })
+                .border_set(border_set());
+            if let Some(notification) = self.notification.as_ref() {
+                bookmarks_block = bookmarks_block.title_bottom(
+                    Line::raw(format!(" {notification} "))
+                        .fg(Color::Yellow)
+                        .right_aligned(),
+                );
+            } else if let (true, Some(BookmarkLine::Parsed { bookmark, .. })) =
+                (self.config.panel_hints(), self.bookmark.as_ref())
+            {
+                // Enter only views bookmarks which point to a change
+                if let Some(hint) = bookmark
+                    .present
+                    .then(|| panel_hint(&format!("Enter: view {bookmark} in log"), chunks[0].width))
+                    .flatten()
+                {
+                    bookmarks_block = bookmarks_block.title_bottom(hint);
+                }
+            }
+            let bookmarks_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(if self.bookmarks_error.is_some() { 1 } else { 0 }),
+                    Constraint::Fill(1),
+                ])
+                .split(bookmarks_block.inner(chunks[0]));
+            f.render_widget(bookmarks_block, chunks[0]);
+
+            if let Some(bookmarks_error) = self.bookmarks_error.as_ref() {
+                f.render_widget(
+                    stale_banner(bookmarks_error, "Press R to refresh"),
+                    bookmarks_chunks[0],
+                );
+            }
+
+            self.bookmarks_height = bookmarks_chunks[1].height;
+            let bookmarks = List::new(lines).scroll_padding(3);
             *self.bookmarks_list_state.selected_mut() = current_bookmark_index;
-            f.render_stateful_widget(bookmarks, chunks[0], &mut self.bookmarks_list_state);
+            f.render_stateful_widget(
+                bookmarks,
+                bookmarks_chunks[1],
+                &mut self.bookmarks_list_state,
+            );
         }
 
         // Draw bookmark
@@ -392,35 +474,47 @@ impl Component for BookmarksTab<'_> {
             } else {
                 " Bookmark ".to_owned()
             };
+            let title = match self.details_mode {
+                DetailsMode::Diff => title,
+                DetailsMode::Stat => format!("{title}[stat] "),
+            };
+            let details_format = self.details_format();
 
             let bookmark_block = Block::bordered()
                 .title(title)
-                .border_type(BorderType::Rounded)
+                .border_set(border_set())
                 .padding(Padding::horizontal(1));
-            let bookmark_content: Vec<Line> = match self.bookmark_output.as_ref() {
-                Some(Ok(bookmark_output)) => bookmark_output.into_text()?.lines,
-                Some(Err(err)) => err.into_text("Error getting bookmark")?.lines,
-                None => vec![],
-            };
-            let bookmark = self
-                .bookmark_panel
-                .render(bookmark_content, bookmark_block.inner(chunks[1]))
-                .block(bookmark_block);
-            f.render_widget(bookmark, chunks[1]);
-        }
-
-        // Draw popup
-        if self.popup.is_opened() {
-            let popup = ConfirmDialog::default()
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(Color::Green))
-                .selected_button_style(
-                    Style::default()
-                        .bg(self.config.highlight_color())
-                        .underlined(),
-                );
-            f.render_stateful_widget(popup, area, &mut self.popup);
+            match (&details_format, self.bookmark_output.as_ref()) {
+                (DiffFormat::SideBySide, Some(Ok(bookmark_output))) => {
+                    self.bookmark_panel.render_side_by_side(
+                        f,
+                        bookmark_output,
+                        bookmark_block,
+                        chunks[1],
+                    );
+                }
+                _ => {
+                    let bookmark_content: Vec<Line> = match self.bookmark_output.as_ref() {
+                        Some(Ok(bookmark_output)) if details_format == DiffFormat::CharLevel => {
+                            char_diff_text(bookmark_output).lines
+                        }
+                        Some(Ok(bookmark_output)) => fit_diff_lines(
+                            bookmark_output,
+                            &details_format,
+                            bookmark_block.inner(chunks[1]).width as usize,
+                            self.config.shorten_paths(),
+                        )?,
+                        Some(Err(err)) => err.into_text("Error getting bookmark")?.lines,
+                        None => vec![],
+                    };
+                    let bookmark = self
+                        .bookmark_panel
+                        .render(bookmark_content, bookmark_block.inner(chunks[1]))
+                        .block(bookmark_block);
+                    f.render_widget(bookmark, chunks[1]);
+                    self.bookmark_panel.render_h_scrollbar(f, chunks[1]);
+                }
+            }
         }
 
         // Draw create textarea
@@ -432,7 +526,7 @@ impl Component for BookmarksTab<'_> {
                         Style::new().bold().cyan(),
                     ))
                     .title_alignment(Alignment::Center)
-                    .border_type(BorderType::Rounded)
+                    .border_set(border_set())
                     .border_style(Style::default().fg(Color::Green));
                 let error_lines = create
                     .error
@@ -462,7 +556,7 @@ impl Component for BookmarksTab<'_> {
                     let help = Paragraph::new(error_lines).block(
                         Block::default()
                             .borders(Borders::TOP)
-                            .border_type(BorderType::Rounded)
+                            .border_set(border_set())
                             .border_style(Style::default().fg(Color::DarkGray)),
                     );
 
@@ -475,7 +569,7 @@ impl Component for BookmarksTab<'_> {
                     .block(
                         Block::default()
                             .borders(Borders::TOP)
-                            .border_type(BorderType::Rounded)
+                            .border_set(border_set())
                             .border_style(Style::default().fg(Color::DarkGray)),
                     );
 
@@ -492,7 +586,7 @@ impl Component for BookmarksTab<'_> {
                         Style::new().bold().cyan(),
                     ))
                     .title_alignment(Alignment::Center)
-                    .border_type(BorderType::Rounded)
+                    .border_set(border_set())
                     .border_style(Style::default().fg(Color::Green));
                 let error_lines = rename
                     .error
@@ -522,7 +616,7 @@ impl Component for BookmarksTab<'_> {
                     let help = Paragraph::new(error_lines).block(
                         Block::default()
                             .borders(Borders::TOP)
-                            .border_type(BorderType::Rounded)
+                            .border_set(border_set())
                             .border_style(Style::default().fg(Color::DarkGray)),
                     );
 
@@ -535,7 +629,7 @@ impl Component for BookmarksTab<'_> {
                     .block(
                         Block::default()
                             .borders(Borders::TOP)
-                            .border_type(BorderType::Rounded)
+                            .border_set(border_set())
                             .border_style(Style::default().fg(Color::DarkGray)),
                     );
 
@@ -549,7 +643,7 @@ impl Component for BookmarksTab<'_> {
                 let block = Block::bordered()
                     .title(Span::styled(" Describe ", Style::new().bold().cyan()))
                     .title_alignment(Alignment::Center)
-                    .border_type(BorderType::Rounded)
+                    .border_set(border_set())
                     .border_style(Style::default().fg(Color::Green));
                 let area = centered_rect(area, 50, 50);
                 f.render_widget(Clear, area);
@@ -568,7 +662,7 @@ impl Component for BookmarksTab<'_> {
                     .block(
                         Block::default()
                             .borders(Borders::TOP)
-                            .border_type(BorderType::Rounded)
+                            .border_set(border_set())
                             .border_style(Style::default().fg(Color::DarkGray)),
                     );
 
@@ -725,19 +819,19 @@ impl Component for BookmarksTab<'_> {
             if key.kind != KeyEventKind::Press {
                 return Ok(ComponentInputResult::Handled);
             }
-            if self.popup.is_opened() {
-                if key.code == KeyCode::Char('q') || key.code == KeyCode::Esc {
-                    self.popup = ConfirmDialogState::default();
-                } else {
-                    self.popup.handle(key);
-                }
 
-                return Ok(ComponentInputResult::Handled);
-            }
+            self.notification = None;
 
             if self.bookmark_panel.input(key) {
+                self.focused_panel = FocusedPanel::Right;
                 return Ok(ComponentInputResult::Handled);
             }
+            if matches!(
+                key.code,
+                KeyCode::Char('j' | 'k' | 'J' | 'K') | KeyCode::Down | KeyCode::Up
+            ) {
+                self.focused_panel = FocusedPanel::Left;
+            }
 
             match key.code {
                 KeyCode::Char('j') | KeyCode::Down => self.scroll_bookmarks(commander, 1),
@@ -752,15 +846,40 @@ impl Component for BookmarksTab<'_> {
                     );
                 }
                 KeyCode::Char('w') => {
-                    self.diff_format = match self.diff_format {
-                        DiffFormat::ColorWords => DiffFormat::Git,
-                        _ => DiffFormat::ColorWords,
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetDiffFormat(next_diff_format(
+                            &self.diff_format,
+                            &commander.available_diff_tools(),
+                        )),
+                    ));
+                }
+                KeyCode::Char('s') => {
+                    self.details_mode = match self.details_mode {
+                        DetailsMode::Diff => DetailsMode::Stat,
+                        DetailsMode::Stat => DetailsMode::Diff,
                     };
                     self.refresh_bookmark(commander);
                 }
                 KeyCode::Char('R') | KeyCode::F(5) => {
+                    // Alt+R refreshes even if nothing changed
+                    let inputs = self.refresh_inputs();
+                    if !key.modifiers.contains(KeyModifiers::ALT)
+                        && LastRefresh::is_up_to_date(
+                            self.last_refresh.as_ref(),
+                            commander,
+                            &inputs,
+                        )
+                    {
+                        self.notification = Some("Up to date".to_owned());
+                        return Ok(ComponentInputResult::Handled);
+                    }
+
                     self.refresh_bookmarks(commander);
                     self.refresh_bookmark(commander);
+                    self.last_refresh = match (&self.bookmarks_error, &self.bookmark_output) {
+                        (None, None | Some(Ok(_))) => LastRefresh::capture(commander, &inputs),
+                        _ => None,
+                    };
                 }
                 KeyCode::Char('a') => {
                     self.show_all = !self.show_all;
@@ -788,40 +907,36 @@ impl Component for BookmarksTab<'_> {
                 }
                 KeyCode::Char('d') => {
                     if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref() {
-                        self.delete = Some(DeleteBookmark {
-                            name: bookmark.name.clone(),
-                        });
-                        self.popup = ConfirmDialogState::new(
-                            DELETE_BRANCH_POPUP_ID,
-                            Span::styled(" Delete ", Style::new().bold().cyan()),
-                            Text::from(vec![Line::from(format!(
-                                "Are you sure you want to delete the {} bookmark?",
-                                bookmark.name
-                            ))]),
-                        )
-                        .with_yes_button(ButtonLabel::YES.clone())
-                        .with_no_button(ButtonLabel::NO.clone())
-                        .with_listener(Some(self.popup_tx.clone()))
-                        .open();
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetPopup(Some(Box::new(ConfirmPopup::new(
+                                self.config.clone(),
+                                "Delete",
+                                vec![format!(
+                                    "Are you sure you want to delete the {} bookmark?",
+                                    bookmark.name
+                                )],
+                                ConfirmAction::Bookmarks(BookmarksConfirmAction::Delete(
+                                    bookmark.name.clone(),
+                                )),
+                            )))),
+                        ));
                     }
                 }
                 KeyCode::Char('f') => {
                     if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref() {
-                        self.forget = Some(ForgetBookmark {
-                            name: bookmark.name.clone(),
-                        });
-                        self.popup = ConfirmDialogState::new(
-                            FORGET_BRANCH_POPUP_ID,
-                            Span::styled(" Forget ", Style::new().bold().cyan()),
-                            Text::from(vec![Line::from(format!(
-                                "Are you sure you want to forget the {} bookmark?",
-                                bookmark.name
-                            ))]),
-                        )
-                        .with_yes_button(ButtonLabel::YES.clone())
-                        .with_no_button(ButtonLabel::NO.clone())
-                        .with_listener(Some(self.popup_tx.clone()))
-                        .open();
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetPopup(Some(Box::new(ConfirmPopup::new(
+                                self.config.clone(),
+                                "Forget",
+                                vec![format!(
+                                    "Are you sure you want to forget the {} bookmark?",
+                                    bookmark.name
+                                )],
+                                ConfirmAction::Bookmarks(BookmarksConfirmAction::Forget(
+                                    bookmark.name.clone(),
+                                )),
+                            )))),
+                        ));
                     }
                 }
                 // TODO: Ask for confirmation?
@@ -843,23 +958,25 @@ impl Component for BookmarksTab<'_> {
                         }
                     }
                 }
-                KeyCode::Char('n') | KeyCode::Char('N') => {
+                KeyCode::Char('n') | KeyCode::Char('N')
+                    if !key.modifiers.contains(KeyModifiers::CONTROL) =>
+                {
                     if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref() {
                         if bookmark.present {
-                            self.popup = ConfirmDialogState::new(
-                                NEW_POPUP_ID,
-                                Span::styled(" New ", Style::new().bold().cyan()),
-                                Text::from(vec![
-                                    Line::from("Are you sure you want to create a new change?"),
-                                    Line::from(format!("Bookmark: {}", bookmark)),
-                                ]),
-                            )
-                            .with_yes_button(ButtonLabel::YES.clone())
-                            .with_no_button(ButtonLabel::NO.clone())
-                            .with_listener(Some(self.popup_tx.clone()))
-                            .open();
-
-                            self.describe_after_new = key.code == KeyCode::Char('N');
+                            return Ok(ComponentInputResult::HandledAction(
+                                ComponentAction::SetPopup(Some(Box::new(ConfirmPopup::new(
+                                    self.config.clone(),
+                                    "New",
+                                    vec![
+                                        "Are you sure you want to create a new change?".to_owned(),
+                                        format!("Bookmark: {}", bookmark),
+                                    ],
+                                    ConfirmAction::Bookmarks(BookmarksConfirmAction::New {
+                                        bookmark: bookmark.to_string(),
+                                        describe: key.code == KeyCode::Char('N'),
+                                    }),
+                                )))),
+                            ));
                         }
                     }
                 }
@@ -878,20 +995,20 @@ impl Component for BookmarksTab<'_> {
                                     }))),
                                 ));
                             } else {
-                                self.popup = ConfirmDialogState::new(
-                                    EDIT_POPUP_ID,
-                                    Span::styled(" Edit ", Style::new().bold().cyan()),
-                                    Text::from(vec![
-                                        Line::from(
-                                            "Are you sure you want to edit an existing change?",
-                                        ),
-                                        Line::from(format!("Bookmark: {}", bookmark)),
-                                    ]),
-                                )
-                                .with_yes_button(ButtonLabel::YES.clone())
-                                .with_no_button(ButtonLabel::NO.clone())
-                                .with_listener(Some(self.popup_tx.clone()))
-                                .open();
+                                return Ok(ComponentInputResult::HandledAction(
+                                    ComponentAction::SetPopup(Some(Box::new(ConfirmPopup::new(
+                                        self.config.clone(),
+                                        "Edit",
+                                        vec![
+                                            "Are you sure you want to edit an existing change?"
+                                                .to_owned(),
+                                            format!("Bookmark: {}", bookmark),
+                                        ],
+                                        ConfirmAction::Bookmarks(BookmarksConfirmAction::Edit(
+                                            bookmark.to_string(),
+                                        )),
+                                    )))),
+                                ));
                             }
                         }
                     }
@@ -923,6 +1040,10 @@ impl Component for BookmarksTab<'_> {
                             ],
                             vec![
                                 ("Ctrl+e/Ctrl+y".to_owned(), "scroll down/up".to_owned()),
+                                (
+                                    "Ctrl+Left/Ctrl+Right".to_owned(),
+                                    "scroll left/right".to_owned(),
+                                ),
                                 (
                                     "Ctrl+d/Ctrl+u".to_owned(),
                                     "scroll down/up by ½ page".to_owned(),
@@ -931,7 +1052,11 @@ impl Component for BookmarksTab<'_> {
                                     "Ctrl+f/Ctrl+b".to_owned(),
                                     "scroll down/up by page".to_owned(),
                                 ),
-                                ("w".to_owned(), "toggle diff format".to_owned()),
+                                (
+                                    "w".to_owned(),
+                                    "cycle diff format (words/git/summary)".to_owned(),
+                                ),
+                                ("s".to_owned(), "toggle diff/stat summary".to_owned()),
                                 ("W".to_owned(), "toggle wrapping".to_owned()),
                             ],
                         )))),
@@ -2,7 +2,7 @@ use std::borrow::Borrow;
 
 use anyhow::Result;
 
-use ansi_to_tui::IntoText;
+use chrono::TimeDelta;
 use crossterm::event::{Event, KeyCode, KeyEventKind};
 use ratatui::{prelude::*, widgets::*};
 use tracing::instrument;
@@ -11,12 +11,48 @@ use crate::{
     commander::{CommandLogItem, Commander},
     env::Config,
     ui::{
-        details_panel::DetailsPanel, help_popup::HelpPopup, utils::tabs_to_spaces, Component,
-        ComponentAction,
+        details_panel::DetailsPanel,
+        help_popup::HelpPopup,
+        styles::border_set,
+        utils::{ansi_text, panel_constraints, tabs_to_spaces, DEFAULT_SPLIT_PERCENT},
+        Component, ComponentAction, FocusedPanel,
     },
     ComponentInputResult,
 };
 
+// Slowest commands pinned above the list
+const SLOWEST_COMMANDS: usize = 5;
+
+// Upper bounds of the duration histogram's buckets in milliseconds, and their labels. The last
+// bucket has no bound
+const DURATION_BUCKETS: [(i64, &str); 5] = [
+    (10, "<10ms"),
+    (100, "10–100ms"),
+    (1000, "100ms–1s"),
+    (5000, "1s–5s"),
+    (i64::MAX, ">5s"),
+];
+
+// Number of commands in each bucket of `DURATION_BUCKETS`
+fn duration_histogram(durations: impl Iterator<Item = TimeDelta>) -> [u64; 5] {
+    let mut counts = [0; 5];
+    for duration in durations {
+        let milliseconds = duration.num_milliseconds();
+        if let Some(bucket) = DURATION_BUCKETS
+            .iter()
+            .position(|(bound, _)| milliseconds < *bound)
+        {
+            counts[bucket] += 1;
+        }
+    }
+    counts
+}
+
+// Text of a command in the list, such as `jj log -r @`
+fn command_text(command: &CommandLogItem) -> String {
+    format!("{} {}", command.program, command.args.join(" "))
+}
+
 /// Command log tab. Shows list of commands exectured by lazyjj in left panel and selected command
 /// output in right panel
 pub struct CommandLogTab {
@@ -26,6 +62,11 @@ pub struct CommandLogTab {
 
     output_panel: DetailsPanel,
 
+    // Panel last interacted with, panel shown full screen, and width of the left panel in percent
+    focused_panel: FocusedPanel,
+    maximized_panel: Option<FocusedPanel>,
+    split_percent: u16,
+
     config: Config,
 }
 
@@ -41,6 +82,10 @@ impl CommandLogTab {
             commands_list_state,
             command_history,
             output_panel: DetailsPanel::new(),
+            focused_panel: FocusedPanel::Left,
+            maximized_panel: None,
+            split_percent: DEFAULT_SPLIT_PERCENT,
+
             config: commander.env.config.clone(),
         })
     }
@@ -96,7 +141,7 @@ impl CommandLogTab {
                         );
                         output_lines.push(Line::default());
                         output_lines
-                            .append(&mut tabs_to_spaces(&stdout.to_string()).into_text()?.lines);
+                            .append(&mut ansi_text(&tabs_to_spaces(&stdout.to_string()))?.lines);
                         has_output = true;
                     }
 
@@ -112,7 +157,7 @@ impl CommandLogTab {
                                 .spans([Span::raw("Error output:").fg(Color::Green).bold()]),
                         );
                         output_lines.push(Line::default());
-                        output_lines.append(&mut stderr.as_ref().into_text()?.lines);
+                        output_lines.append(&mut ansi_text(stderr)?.lines);
                         has_output = true;
                     }
 
@@ -139,10 +184,10 @@ impl CommandLogTab {
                 .selected()
                 .map(|selected_index| selected_index.saturating_add_signed(scroll))
                 .unwrap_or(0))
-            .min(self.command_history.len() - 1)
-            .max(0),
+            .min(self.command_history.len().saturating_sub(1)),
         );
         self.output_panel.scroll = 0;
+        self.output_panel.h_scroll = 0;
     }
 }
 
@@ -156,6 +201,23 @@ impl Component for CommandLogTab {
         Ok(())
     }
 
+    fn focused_panel(&self) -> Option<FocusedPanel> {
+        Some(self.focused_panel)
+    }
+
+    fn set_maximized_panel(&mut self, maximized_panel: Option<FocusedPanel>) {
+        self.maximized_panel = maximized_panel;
+    }
+
+    fn set_split_percent(&mut self, split_percent: u16) {
+        self.split_percent = split_percent;
+    }
+
+    fn reset_size(&mut self) {
+        self.commands_height = 0;
+        self.output_panel.reset_size();
+    }
+
     fn draw(
         &mut self,
         f: &mut ratatui::prelude::Frame<'_>,
@@ -163,9 +225,43 @@ impl Component for CommandLogTab {
     ) -> Result<()> {
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .constraints(panel_constraints(self.maximized_panel, self.split_percent))
             .split(area);
 
+        let left_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(self.command_history.len().min(SLOWEST_COMMANDS) as u16 + 2),
+                Constraint::Fill(1),
+                Constraint::Length(DURATION_BUCKETS.len() as u16 + 2),
+            ])
+            .split(chunks[0]);
+
+        // Draw slowest commands
+        {
+            let mut slowest: Vec<&CommandLogItem> = self.command_history.iter().collect();
+            slowest.sort_by_key(|command| std::cmp::Reverse(command.duration));
+            let slowest_lines = slowest
+                .into_iter()
+                .take(SLOWEST_COMMANDS)
+                .map(|command| {
+                    Line::from(vec![
+                        Span::raw(format!(" {:>6}ms ", command.duration.num_milliseconds()))
+                            .fg(Color::Cyan),
+                        Span::raw(command_text(command)),
+                    ])
+                })
+                .collect::<Vec<Line>>();
+            f.render_widget(
+                Paragraph::new(slowest_lines).block(
+                    Block::bordered()
+                        .title(" Slowest commands ")
+                        .border_set(border_set()),
+                ),
+                left_chunks[0],
+            );
+        }
+
         // Draw commands
         {
             let command_lines = self
@@ -175,12 +271,7 @@ impl Component for CommandLogTab {
                 .enumerate()
                 .map(|(i, command)| {
                     let mut line = Line::default()
-                        .spans([
-                            " ".into(),
-                            Span::raw(command.program.clone()),
-                            " ".into(),
-                            command.args.join(" ").into(),
-                        ])
+                        .spans([" ".into(), Span::raw(command_text(command))])
                         .fg(
                             if command
                                 .output
@@ -206,19 +297,46 @@ impl Component for CommandLogTab {
                 .block(
                     Block::bordered()
                         .title(" Commands ")
-                        .border_type(BorderType::Rounded),
+                        .border_set(border_set()),
                 )
                 .scroll_padding(3);
 
-            f.render_stateful_widget(commands, chunks[0], &mut self.commands_list_state);
-            self.commands_height = chunks[0].height.saturating_sub(2);
+            f.render_stateful_widget(commands, left_chunks[1], &mut self.commands_list_state);
+            self.commands_height = left_chunks[1].height.saturating_sub(2);
+        }
+
+        // Draw durations histogram
+        {
+            let counts =
+                duration_histogram(self.command_history.iter().map(|command| command.duration));
+            let bars = DURATION_BUCKETS
+                .iter()
+                .zip(counts)
+                .map(|((_, label), count)| {
+                    Bar::default()
+                        .value(count)
+                        .label(Line::raw(*label))
+                        .style(Style::default().fg(Color::Cyan))
+                })
+                .collect::<Vec<Bar>>();
+            let histogram = BarChart::default()
+                .block(
+                    Block::bordered()
+                        .title(" Durations ")
+                        .border_set(border_set()),
+                )
+                .direction(Direction::Horizontal)
+                .bar_width(1)
+                .bar_gap(0)
+                .data(BarGroup::default().bars(&bars));
+            f.render_widget(histogram, left_chunks[2]);
         }
 
         // Draw output
         {
             let output_block = Block::bordered()
                 .title(" Output ")
-                .border_type(BorderType::Rounded)
+                .border_set(border_set())
                 .padding(Padding::horizontal(1));
             let output = self
                 .output_panel
@@ -226,6 +344,7 @@ impl Component for CommandLogTab {
                 .block(output_block);
 
             f.render_widget(output, chunks[1]);
+            self.output_panel.render_h_scrollbar(f, chunks[1]);
         }
 
         Ok(())
@@ -239,8 +358,15 @@ impl Component for CommandLogTab {
             }
 
             if self.output_panel.input(key) {
+                self.focused_panel = FocusedPanel::Right;
                 return Ok(ComponentInputResult::Handled);
             }
+            if matches!(
+                key.code,
+                KeyCode::Char('j' | 'k' | 'J' | 'K') | KeyCode::Down | KeyCode::Up
+            ) {
+                self.focused_panel = FocusedPanel::Left;
+            }
 
             match key.code {
                 KeyCode::Char('j') | KeyCode::Down => {
@@ -268,6 +394,10 @@ impl Component for CommandLogTab {
                             ],
                             vec![
                                 ("Ctrl+e/Ctrl+y".to_owned(), "scroll down/up".to_owned()),
+                                (
+                                    "Ctrl+Left/Ctrl+Right".to_owned(),
+                                    "scroll left/right".to_owned(),
+                                ),
                                 (
                                     "Ctrl+d/Ctrl+u".to_owned(),
                                     "scroll down/up by ½ page".to_owned(),
@@ -288,3 +418,17 @@ impl Component for CommandLogTab {
         Ok(ComponentInputResult::Handled)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duration_buckets() {
+        let durations = [0, 9, 10, 99, 150, 999, 1000, 4999, 5000, 60_000]
+            .into_iter()
+            .map(TimeDelta::milliseconds);
+        assert_eq!(duration_histogram(durations), [2, 2, 2, 2, 2]);
+        assert_eq!(duration_histogram(std::iter::empty()), [0; 5]);
+    }
+}
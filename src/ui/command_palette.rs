@@ -0,0 +1,535 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use thiserror::Error;
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::{
+    app::Tab,
+    commander::{CommandError, Commander},
+    env::{DiffFormat, Env},
+    ui::{
+        message_popup::MessagePopup,
+        styles::{border_set, create_popup_block},
+        utils::{ansi_text, centered_rect_line_height, escape_control_chars},
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+// Prefix used to run lazyjj meta-commands instead of jj commands
+const META_PREFIX: char = '>';
+// Prefix used to run jj commands which use the terminal, such as opening an editor
+const EXTERNAL_PREFIX: char = '!';
+
+// Meta-command names with their usage, used for completion and help
+const META_COMMANDS: [(&str, &str); 8] = [
+    ("tab", "tab <log|files|bookmarks|command-log>"),
+    ("revset", "revset [revset]"),
+    (
+        "diff-format",
+        "diff-format <color-words|git|summary|stat|side-by-side|char-level>",
+    ),
+    ("refresh", "refresh"),
+    ("git-export", "git-export"),
+    ("git-import", "git-import"),
+    ("info", "info"),
+    ("quit", "quit"),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum MetaCommand {
+    Tab(Tab),
+    Revset(Option<String>),
+    DiffFormat(DiffFormat),
+    Refresh,
+    GitExport,
+    GitImport,
+    Info,
+    Quit,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum CommandParseError {
+    #[error("Unknown command: {0}")]
+    UnknownCommand(String),
+    #[error("Missing argument. Usage: {0}")]
+    MissingArgument(&'static str),
+    #[error("Invalid argument \"{0}\". Usage: {1}")]
+    InvalidArgument(String, &'static str),
+    #[error("Unclosed quote")]
+    UnclosedQuote,
+}
+
+fn get_usage(name: &str) -> &'static str {
+    META_COMMANDS
+        .iter()
+        .find(|(command, _)| *command == name)
+        .map_or("", |(_, usage)| usage)
+}
+
+/// Parse a meta-command, without the leading `>`.
+pub fn parse_meta_command(text: &str) -> Result<MetaCommand, CommandParseError> {
+    let text = text.trim();
+    let (name, argument) = match text.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, argument.trim()),
+        None => (text, ""),
+    };
+
+    match name {
+        "tab" => {
+            let tab = match argument {
+                "" => return Err(CommandParseError::MissingArgument(get_usage(name))),
+                "log" | "1" => Tab::Log,
+                "files" | "2" => Tab::Files,
+                "bookmarks" | "3" => Tab::Bookmarks,
+                "command-log" | "4" => Tab::CommandLog,
+                _ => {
+                    return Err(CommandParseError::InvalidArgument(
+                        argument.to_owned(),
+                        get_usage(name),
+                    ))
+                }
+            };
+            Ok(MetaCommand::Tab(tab))
+        }
+        "revset" => Ok(MetaCommand::Revset(if argument.is_empty() {
+            None
+        } else {
+            Some(argument.to_owned())
+        })),
+        "diff-format" => {
+            let diff_format = match argument {
+                "" => return Err(CommandParseError::MissingArgument(get_usage(name))),
+                "color-words" => DiffFormat::ColorWords,
+                "git" => DiffFormat::Git,
+                "summary" => DiffFormat::Summary,
+                "stat" => DiffFormat::Stat,
+                "side-by-side" => DiffFormat::SideBySide,
+                "char-level" => DiffFormat::CharLevel,
+                _ => {
+                    return Err(CommandParseError::InvalidArgument(
+                        argument.to_owned(),
+                        get_usage(name),
+                    ))
+                }
+            };
+            Ok(MetaCommand::DiffFormat(diff_format))
+        }
+        "refresh" => Ok(MetaCommand::Refresh),
+        "git-export" => Ok(MetaCommand::GitExport),
+        "git-import" => Ok(MetaCommand::GitImport),
+        "info" => Ok(MetaCommand::Info),
+        "quit" => Ok(MetaCommand::Quit),
+        _ => Err(CommandParseError::UnknownCommand(name.to_owned())),
+    }
+}
+
+/// Split command arguments by whitespace, keeping quoted strings together.
+pub fn split_args(text: &str) -> Result<Vec<String>, CommandParseError> {
+    let mut args = vec![];
+    let mut current: Option<String> = None;
+    let mut quote: Option<char> = None;
+
+    for c in text.chars() {
+        match quote {
+            Some(quote_char) if c == quote_char => quote = None,
+            Some(_) => current.get_or_insert_with(String::new).push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            None if c.is_whitespace() => {
+                if let Some(arg) = current.take() {
+                    args.push(arg);
+                }
+            }
+            None => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err(CommandParseError::UnclosedQuote);
+    }
+    if let Some(arg) = current {
+        args.push(arg);
+    }
+
+    Ok(args)
+}
+
+/// Get meta-command names which can complete the current text.
+fn get_completions(text: &str) -> Vec<&'static str> {
+    match text.strip_prefix(META_PREFIX) {
+        Some(name) if !name.contains(char::is_whitespace) => META_COMMANDS
+            .iter()
+            .map(|(command, _)| *command)
+            .filter(|command| command.starts_with(name))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Refresh after a command, and show its output.
+fn get_output_action(
+    title: &str,
+    result: Result<String, CommandError>,
+) -> Result<ComponentAction, ansi_to_tui::Error> {
+    let popup = match result {
+        Ok(output) if output.trim().is_empty() => MessagePopup {
+            title: title.to_owned().into(),
+            messages: vec!["Nothing changed.".into()].into(),
+        },
+        Ok(output) => MessagePopup {
+            title: title.to_owned().into(),
+            messages: ansi_text(&output)?,
+        },
+        Err(err) => MessagePopup {
+            title: format!("{title} error").into(),
+            messages: err.into_text("")?,
+        },
+    };
+
+    Ok(ComponentAction::Multiple(vec![
+        ComponentAction::Refresh,
+        ComponentAction::SetPopup(Some(Box::new(popup))),
+    ]))
+}
+
+// Repository and jj config used by lazyjj, including the `--jj-config` overrides
+fn get_repo_info(env: &Env) -> Text<'static> {
+    let mut lines = vec![
+        Line::from(vec![
+            Span::raw("Repository: ").bold(),
+            Span::raw(env.root.clone()),
+        ]),
+        Line::from(vec![
+            Span::raw("Default revset: ").bold(),
+            Span::raw(
+                env.default_revset
+                    .clone()
+                    .unwrap_or("from jj's revsets.log".to_owned()),
+            ),
+        ]),
+        Line::default(),
+        Line::raw("jj config overrides (--jj-config, LAZYJJ_JJ_CONFIG):").bold(),
+    ];
+    if env.jj_config.is_empty() {
+        lines.push(Line::raw("None").fg(Color::DarkGray));
+    }
+    for jj_config in env.jj_config.iter() {
+        lines.extend(
+            escape_control_chars(jj_config)
+                .lines()
+                .map(|line| Line::raw(line.to_owned()).fg(Color::Cyan)),
+        );
+    }
+    Text::from(lines)
+}
+
+/// Command palette. Runs jj commands, or lazyjj meta-commands when prefixed with `>`. jj
+/// commands prefixed with `!` run with the TUI suspended, such as to use an editor.
+pub struct CommandPalette<'a> {
+    textarea: TextArea<'a>,
+    error: Option<String>,
+}
+
+impl CommandPalette<'_> {
+    pub fn new() -> Self {
+        Self {
+            textarea: TextArea::default(),
+            error: None,
+        }
+    }
+
+    fn get_text(&self) -> String {
+        self.textarea.lines().join(" ")
+    }
+
+    fn complete(&mut self) {
+        let completions = get_completions(&self.get_text());
+        let completion = match completions.as_slice() {
+            [] => return,
+            [completion] => format!("{META_PREFIX}{completion} "),
+            [first, rest @ ..] => {
+                // Complete to the longest common prefix
+                let mut prefix = first.to_string();
+                for completion in rest {
+                    while !completion.starts_with(&prefix) {
+                        prefix.pop();
+                    }
+                }
+                format!("{META_PREFIX}{prefix}")
+            }
+        };
+
+        let mut textarea = TextArea::new(vec![completion]);
+        textarea.move_cursor(CursorMove::End);
+        self.textarea = textarea;
+    }
+
+    fn run(&mut self, commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        let text = self.get_text();
+        let text = text.trim();
+
+        if let Some(meta_command) = text.strip_prefix(META_PREFIX) {
+            return Ok(match parse_meta_command(meta_command) {
+                Ok(meta_command) => Some(match meta_command {
+                    MetaCommand::Tab(tab) => ComponentAction::SetTab(tab),
+                    MetaCommand::Revset(revset) => ComponentAction::SetRevset(revset),
+                    MetaCommand::DiffFormat(diff_format) => {
+                        ComponentAction::SetDiffFormat(diff_format)
+                    }
+                    MetaCommand::Refresh => ComponentAction::Refresh,
+                    MetaCommand::GitExport => {
+                        get_output_action("Git export", commander.git_export())?
+                    }
+                    MetaCommand::GitImport => {
+                        get_output_action("Git import", commander.git_import())?
+                    }
+                    MetaCommand::Info => ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                        title: "Repository info".into(),
+                        messages: get_repo_info(&commander.env),
+                    }))),
+                    MetaCommand::Quit => ComponentAction::Quit,
+                }),
+                Err(err) => {
+                    self.error = Some(err.to_string());
+                    None
+                }
+            });
+        }
+
+        let (external, text) = match text.strip_prefix(EXTERNAL_PREFIX) {
+            Some(text) => (true, text.trim_start()),
+            None => (false, text),
+        };
+
+        let args = match split_args(text.strip_prefix("jj ").unwrap_or(text)) {
+            Ok(args) if args.is_empty() => return Ok(None),
+            Ok(args) => args,
+            Err(err) => {
+                self.error = Some(err.to_string());
+                return Ok(None);
+            }
+        };
+
+        if external {
+            return Ok(Some(ComponentAction::RunExternal(args)));
+        }
+
+        let popup = match commander.execute_jj_command(args, true, true) {
+            Ok(output) if output.is_empty() => None,
+            Ok(output) => Some(MessagePopup {
+                title: "Command output".into(),
+                messages: ansi_text(&output)?,
+            }),
+            Err(err) => Some(MessagePopup {
+                title: "Command error".into(),
+                messages: err.into_text("")?,
+            }),
+        };
+
+        Ok(Some(ComponentAction::Multiple(vec![
+            ComponentAction::Refresh,
+            ComponentAction::SetPopup(popup.map(|popup| Box::new(popup) as Box<dyn Component>)),
+        ])))
+    }
+}
+
+impl Component for CommandPalette<'_> {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let block = create_popup_block("Command");
+        let area = centered_rect_line_height(area, 50, 8);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Fill(1),
+                Constraint::Length(2),
+            ])
+            .split(block.inner(area));
+
+        f.render_widget(&self.textarea, popup_chunks[0]);
+
+        let hint = match self.error.as_ref() {
+            Some(error) => Paragraph::new(Line::raw(error.to_owned()).fg(Color::Red)),
+            None => {
+                let text = self.get_text();
+                let completions = get_completions(&text);
+                if completions.is_empty() {
+                    Paragraph::new(Line::raw(format!(
+                        "jj command, {EXTERNAL_PREFIX}command to use an editor, or {META_PREFIX}command for lazyjj"
+                    )))
+                    .fg(Color::DarkGray)
+                } else {
+                    Paragraph::new(
+                        completions
+                            .iter()
+                            .map(|completion| Line::raw(get_usage(completion)))
+                            .collect::<Vec<Line>>(),
+                    )
+                    .fg(Color::DarkGray)
+                }
+            }
+        };
+        f.render_widget(hint, popup_chunks[1]);
+
+        let help = Paragraph::new(vec!["Enter: run | Tab: complete | Escape: cancel".into()])
+            .fg(Color::DarkGray)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .border_set(border_set())
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            );
+        f.render_widget(help, popup_chunks[2]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            match key.code {
+                KeyCode::Enter => {
+                    return Ok(match self.run(commander)? {
+                        Some(component_action) => {
+                            ComponentInputResult::HandledAction(ComponentAction::Multiple(vec![
+                                ComponentAction::SetPopup(None),
+                                component_action,
+                            ]))
+                        }
+                        None => ComponentInputResult::Handled,
+                    });
+                }
+                KeyCode::Tab => {
+                    self.complete();
+                    return Ok(ComponentInputResult::Handled);
+                }
+                KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        self.error = None;
+        self.textarea.input(event);
+        Ok(ComponentInputResult::Handled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_meta_commands() {
+        assert_eq!(
+            parse_meta_command("tab files"),
+            Ok(MetaCommand::Tab(Tab::Files))
+        );
+        assert_eq!(
+            parse_meta_command(" tab  4 "),
+            Ok(MetaCommand::Tab(Tab::CommandLog))
+        );
+        assert_eq!(
+            parse_meta_command("revset trunk()..@"),
+            Ok(MetaCommand::Revset(Some("trunk()..@".to_owned())))
+        );
+        assert_eq!(
+            parse_meta_command("revset description(\"a b\")"),
+            Ok(MetaCommand::Revset(Some("description(\"a b\")".to_owned())))
+        );
+        assert_eq!(parse_meta_command("revset"), Ok(MetaCommand::Revset(None)));
+        assert_eq!(
+            parse_meta_command("diff-format git"),
+            Ok(MetaCommand::DiffFormat(DiffFormat::Git))
+        );
+        assert_eq!(parse_meta_command("refresh"), Ok(MetaCommand::Refresh));
+        assert_eq!(parse_meta_command("quit"), Ok(MetaCommand::Quit));
+        assert_eq!(parse_meta_command("git-export"), Ok(MetaCommand::GitExport));
+        assert_eq!(parse_meta_command("git-import"), Ok(MetaCommand::GitImport));
+        assert_eq!(parse_meta_command("info"), Ok(MetaCommand::Info));
+    }
+
+    #[test]
+    fn parse_meta_command_errors() {
+        assert_eq!(
+            parse_meta_command("foo"),
+            Err(CommandParseError::UnknownCommand("foo".to_owned()))
+        );
+        assert_eq!(
+            parse_meta_command("tab"),
+            Err(CommandParseError::MissingArgument(get_usage("tab")))
+        );
+        assert_eq!(
+            parse_meta_command("tab foo"),
+            Err(CommandParseError::InvalidArgument(
+                "foo".to_owned(),
+                get_usage("tab")
+            ))
+        );
+        assert_eq!(
+            parse_meta_command("diff-format foo"),
+            Err(CommandParseError::InvalidArgument(
+                "foo".to_owned(),
+                get_usage("diff-format")
+            ))
+        );
+    }
+
+    #[test]
+    fn split_command_args() {
+        assert_eq!(
+            split_args("log -r  @"),
+            Ok(vec!["log".to_owned(), "-r".to_owned(), "@".to_owned()])
+        );
+        assert_eq!(
+            split_args(r#"describe -m "hello world" -r 'a b'"#),
+            Ok(vec![
+                "describe".to_owned(),
+                "-m".to_owned(),
+                "hello world".to_owned(),
+                "-r".to_owned(),
+                "a b".to_owned(),
+            ])
+        );
+        assert_eq!(
+            split_args(r#"describe -m """#),
+            Ok(vec!["describe".to_owned(), "-m".to_owned(), "".to_owned()])
+        );
+        assert_eq!(
+            split_args(r#"describe -m "hello"#),
+            Err(CommandParseError::UnclosedQuote)
+        );
+    }
+
+    #[test]
+    fn complete_meta_commands() {
+        assert_eq!(
+            get_completions(">"),
+            META_COMMANDS.map(|(command, _)| command)
+        );
+        assert_eq!(get_completions(">re"), ["revset", "refresh"]);
+        assert_eq!(get_completions(">ta"), ["tab"]);
+        assert!(get_completions(">tab ").is_empty());
+        assert!(get_completions("log").is_empty());
+    }
+}
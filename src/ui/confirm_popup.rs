@@ -0,0 +1,226 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    commander::Commander,
+    env::Config,
+    ui::{
+        bookmarks_tab::BookmarksConfirmAction,
+        log_tab::LogConfirmAction,
+        styles::{border_set, create_popup_block},
+        utils::centered_rect_line_height,
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+/// Action of a confirmation popup, run by the tab which opened it once accepted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfirmAction {
+    Log(LogConfirmAction),
+    Bookmarks(BookmarksConfirmAction),
+}
+
+impl ConfirmAction {
+    /// Whether the action changes the repository, so is only run if the repository didn't change
+    /// since the last refresh, or the user proceeds anyway.
+    pub fn changes_repository(&self) -> bool {
+        !matches!(self, ConfirmAction::Log(LogConfirmAction::ClearRevset))
+    }
+}
+
+/// Popup asking to confirm an action with yes/no buttons. Sends the action with
+/// `ComponentAction::Confirmed` when accepted, and nothing when cancelled.
+pub struct ConfirmPopup {
+    title: String,
+    messages: Vec<String>,
+    // Taken when the popup is answered, so the action is sent at most once
+    action: Option<ConfirmAction>,
+    yes_selected: bool,
+    config: Config,
+}
+
+impl ConfirmPopup {
+    pub fn new(config: Config, title: &str, messages: Vec<String>, action: ConfirmAction) -> Self {
+        Self {
+            title: title.to_owned(),
+            messages,
+            action: Some(action),
+            yes_selected: true,
+            config,
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> ComponentInputResult {
+        if key.kind != KeyEventKind::Press {
+            return ComponentInputResult::Handled;
+        }
+
+        let accepted = match key.code {
+            KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Tab
+            | KeyCode::BackTab
+            | KeyCode::Char('h')
+            | KeyCode::Char('l') => {
+                self.yes_selected = !self.yes_selected;
+                return ComponentInputResult::Handled;
+            }
+            KeyCode::Enter => self.yes_selected,
+            KeyCode::Char('y') => true,
+            KeyCode::Char('n') | KeyCode::Char('q') | KeyCode::Esc => false,
+            _ => return ComponentInputResult::Handled,
+        };
+
+        let close = ComponentAction::SetPopup(None);
+        ComponentInputResult::HandledAction(match self.action.take().filter(|_| accepted) {
+            Some(action) => {
+                ComponentAction::Multiple(vec![close, ComponentAction::Confirmed(action)])
+            }
+            None => close,
+        })
+    }
+}
+
+impl Component for ConfirmPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let block = create_popup_block(&self.title);
+        let area = centered_rect_line_height(area, 50, self.messages.len() as u16 + 5);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Fill(1), Constraint::Length(2)])
+            .split(block.inner(area));
+
+        f.render_widget(
+            Paragraph::new(
+                self.messages
+                    .iter()
+                    .map(|message| Line::raw(message.as_str()))
+                    .collect::<Vec<Line>>(),
+            )
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true }),
+            popup_chunks[0],
+        );
+
+        let button = |label: &'static str, selected: bool| {
+            if selected {
+                Span::styled(
+                    label,
+                    Style::default()
+                        .bg(self.config.highlight_color())
+                        .underlined(),
+                )
+            } else {
+                Span::raw(label)
+            }
+        };
+        let buttons = Paragraph::new(Line::from(vec![
+            button(" Yes ", self.yes_selected),
+            Span::raw("   "),
+            button(" No ", !self.yes_selected),
+        ]))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_set(border_set())
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+        f.render_widget(buttons, popup_chunks[1]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            return Ok(self.handle_key(key));
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn abandon_popup() -> ConfirmPopup {
+        ConfirmPopup::new(
+            Config::default(),
+            "Abandon",
+            vec!["Are you sure you want to abandon this change?".to_owned()],
+            ConfirmAction::Log(LogConfirmAction::Abandon),
+        )
+    }
+
+    fn press(popup: &mut ConfirmPopup, code: KeyCode) -> ComponentInputResult {
+        popup.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    // Actions confirmed by an input, if it closed the popup
+    fn confirmed(result: ComponentInputResult) -> Option<Vec<ConfirmAction>> {
+        match result {
+            ComponentInputResult::HandledAction(ComponentAction::SetPopup(None)) => Some(vec![]),
+            ComponentInputResult::HandledAction(ComponentAction::Multiple(actions)) => Some(
+                actions
+                    .into_iter()
+                    .filter_map(|action| match action {
+                        ComponentAction::Confirmed(action) => Some(action),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn accept_confirms_once() {
+        for code in [KeyCode::Enter, KeyCode::Char('y')] {
+            let mut popup = abandon_popup();
+            assert_eq!(
+                confirmed(press(&mut popup, code)),
+                Some(vec![ConfirmAction::Log(LogConfirmAction::Abandon)])
+            );
+            // Repeated keys before the popup is closed don't run it again
+            assert_eq!(confirmed(press(&mut popup, code)), Some(vec![]));
+        }
+    }
+
+    #[test]
+    fn cancel_skips_action() {
+        for code in [KeyCode::Esc, KeyCode::Char('n'), KeyCode::Char('q')] {
+            let mut popup = abandon_popup();
+            assert_eq!(confirmed(press(&mut popup, code)), Some(vec![]));
+            assert_eq!(
+                confirmed(press(&mut popup, KeyCode::Char('y'))),
+                Some(vec![])
+            );
+        }
+
+        // Enter on the no button
+        let mut popup = abandon_popup();
+        assert_eq!(confirmed(press(&mut popup, KeyCode::Tab)), None);
+        assert_eq!(confirmed(press(&mut popup, KeyCode::Enter)), Some(vec![]));
+
+        // Other keys keep the popup open
+        let mut popup = abandon_popup();
+        assert_eq!(confirmed(press(&mut popup, KeyCode::Char('x'))), None);
+        assert_eq!(
+            confirmed(press(&mut popup, KeyCode::Enter)),
+            Some(vec![ConfirmAction::Log(LogConfirmAction::Abandon)])
+        );
+    }
+}
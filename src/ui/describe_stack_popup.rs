@@ -0,0 +1,302 @@
+use anyhow::{bail, Result};
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use tui_textarea::TextArea;
+
+use crate::{
+    commander::{ids::ChangeId, log::Head, Commander},
+    ui::{
+        styles::{border_set, create_popup_block},
+        utils::centered_rect,
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+// Separator lines are `JJ: describe <change id> -------`. Other lines starting with `JJ:` are
+// ignored, like in jj's editor
+const COMMENT_PREFIX: &str = "JJ:";
+const SEPARATOR_PREFIX: &str = "JJ: describe ";
+const SEPARATOR_SUFFIX: &str = " -------";
+
+// Change ID shown in separators
+fn short_change_id(change_id: &ChangeId) -> &str {
+    &change_id.as_str()[..change_id.as_str().len().min(12)]
+}
+
+/// Join descriptions into a single buffer, each after a separator with its change ID.
+pub fn format_descriptions(descriptions: &[(ChangeId, String)]) -> String {
+    descriptions
+        .iter()
+        .map(|(change_id, description)| {
+            format!(
+                "{SEPARATOR_PREFIX}{}{SEPARATOR_SUFFIX}\n{description}\n",
+                short_change_id(change_id)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Split a buffer created by `format_descriptions` into the description of each change, in the
+/// order of `change_ids`. Fails if a separator was deleted, duplicated or changed, so that no
+/// change gets another's description.
+pub fn parse_descriptions(text: &str, change_ids: &[ChangeId]) -> Result<Vec<String>> {
+    let mut descriptions: Vec<Option<Vec<&str>>> = vec![None; change_ids.len()];
+    let mut current: Option<usize> = None;
+
+    for line in text.lines() {
+        if let Some(id) = line
+            .strip_prefix(SEPARATOR_PREFIX)
+            .and_then(|rest| rest.strip_suffix(SEPARATOR_SUFFIX))
+        {
+            let Some(index) = change_ids
+                .iter()
+                .position(|change_id| short_change_id(change_id) == id.trim())
+            else {
+                bail!("Unknown change ID in separator: {}", id.trim());
+            };
+            if descriptions[index].is_some() {
+                bail!("Separator for change {} appears more than once", id.trim());
+            }
+            descriptions[index] = Some(vec![]);
+            current = Some(index);
+        } else if line.starts_with(COMMENT_PREFIX) {
+            continue;
+        } else if let Some(index) = current {
+            if let Some(lines) = descriptions[index].as_mut() {
+                lines.push(line);
+            }
+        } else if !line.trim().is_empty() {
+            bail!("Text before the first separator. Was a separator deleted?");
+        }
+    }
+
+    descriptions
+        .into_iter()
+        .zip(change_ids)
+        .map(|(lines, change_id)| match lines {
+            Some(lines) => Ok(lines.join("\n").trim().to_owned()),
+            None => bail!(
+                "Missing separator for change {}. Was it deleted?",
+                short_change_id(change_id)
+            ),
+        })
+        .collect()
+}
+
+/// Popup to edit the descriptions of a stack of changes in a single buffer, then describe each
+/// changed one with `jj describe`.
+pub struct DescribeStackPopup<'a> {
+    heads: Vec<Head>,
+    descriptions: Vec<String>,
+    textarea: TextArea<'a>,
+    error: Option<String>,
+}
+
+impl DescribeStackPopup<'_> {
+    pub fn new(descriptions: Vec<(Head, String)>) -> Self {
+        let textarea = TextArea::new(
+            format_descriptions(
+                &descriptions
+                    .iter()
+                    .map(|(head, description)| (head.change_id.clone(), description.clone()))
+                    .collect::<Vec<_>>(),
+            )
+            .lines()
+            .map(String::from)
+            .collect(),
+        );
+        let (heads, descriptions) = descriptions.into_iter().unzip();
+
+        Self {
+            heads,
+            descriptions,
+            textarea,
+            error: None,
+        }
+    }
+
+    // Describe changes whose description was edited. Nothing is described if the buffer can't be
+    // parsed
+    fn save(&mut self, commander: &mut Commander) -> Result<()> {
+        let change_ids: Vec<ChangeId> = self
+            .heads
+            .iter()
+            .map(|head| head.change_id.clone())
+            .collect();
+        let descriptions = parse_descriptions(&self.textarea.lines().join("\n"), &change_ids)?;
+
+        // Describe by change ID, since describing a change rewrites its descendants
+        for ((change_id, description), old_description) in
+            change_ids.iter().zip(descriptions).zip(&self.descriptions)
+        {
+            if description != *old_description {
+                commander.run_describe(change_id.as_str(), &description)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Component for DescribeStackPopup<'_> {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let title = format!("Describe {} changes", self.heads.len());
+        let block = create_popup_block(&title);
+        let area = centered_rect(area, 80, 80);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Length(if self.error.is_some() { 2 } else { 0 }),
+                Constraint::Length(2),
+            ])
+            .split(block.inner(area));
+
+        f.render_widget(&self.textarea, popup_chunks[0]);
+
+        if let Some(error) = self.error.as_ref() {
+            f.render_widget(
+                Paragraph::new(Line::raw(error.as_str()))
+                    .fg(Color::Red)
+                    .wrap(Wrap { trim: false }),
+                popup_chunks[1],
+            );
+        }
+
+        let help = Paragraph::new(vec![
+            "Ctrl+s: save | Escape: cancel | Don't edit the JJ: describe lines".into(),
+        ])
+        .fg(Color::DarkGray)
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_set(border_set())
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+        f.render_widget(help, popup_chunks[2]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            match key.code {
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(match self.save(commander) {
+                        Ok(()) => {
+                            ComponentInputResult::HandledAction(ComponentAction::Multiple(vec![
+                                ComponentAction::SetPopup(None),
+                                ComponentAction::Refresh,
+                            ]))
+                        }
+                        Err(err) => {
+                            self.error = Some(format!("{err:#}"));
+                            ComponentInputResult::Handled
+                        }
+                    });
+                }
+                KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        self.textarea.input(event);
+        Ok(ComponentInputResult::Handled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change_ids() -> Vec<ChangeId> {
+        vec![
+            ChangeId("kxryzmorwvtzvkkktpxyvyxkmnvqrlsz".to_owned()),
+            ChangeId("vruxwmqvtpmxsqmurtlsnsvrqqtlyvnp".to_owned()),
+        ]
+    }
+
+    #[test]
+    fn round_trip_descriptions() -> Result<()> {
+        let text = format_descriptions(
+            &change_ids()
+                .into_iter()
+                .zip(["First\n\nBody".to_owned(), "".to_owned()])
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(
+            text,
+            "\
+JJ: describe kxryzmorwvtz -------
+First
+
+Body
+
+JJ: describe vruxwmqvtpmx -------
+
+"
+        );
+        assert_eq!(
+            parse_descriptions(&text, &change_ids())?,
+            vec!["First\n\nBody".to_owned(), "".to_owned()]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_edited_descriptions() -> Result<()> {
+        let text = "\
+JJ: Lines starting with JJ: are ignored
+JJ: describe vruxwmqvtpmx -------
+Second
+JJ: describe kxryzmorwvtz -------
+First
+";
+        assert_eq!(
+            parse_descriptions(text, &change_ids())?,
+            vec!["First".to_owned(), "Second".to_owned()]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_descriptions_errors() {
+        let missing = "JJ: describe kxryzmorwvtz -------\nFirst\nSecond\n";
+        assert!(parse_descriptions(missing, &change_ids()).is_err());
+
+        let before = "First\nJJ: describe vruxwmqvtpmx -------\nSecond\n";
+        assert!(parse_descriptions(before, &change_ids()).is_err());
+
+        let duplicate = "\
+JJ: describe kxryzmorwvtz -------
+JJ: describe vruxwmqvtpmx -------
+JJ: describe vruxwmqvtpmx -------
+";
+        assert!(parse_descriptions(duplicate, &change_ids()).is_err());
+
+        let unknown = "\
+JJ: describe kxryzmorwvtz -------
+JJ: describe vruxwmqvtpmx -------
+JJ: describe zzzzzzzzzzzz -------
+";
+        assert!(parse_descriptions(unknown, &change_ids()).is_err());
+    }
+}
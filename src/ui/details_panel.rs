@@ -1,16 +1,28 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
-    layout::Rect,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
     text::Text,
-    widgets::{Paragraph, Wrap},
+    widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
+    Frame,
 };
 
+use crate::ui::utils::{escape_control_chars, side_by_side::SideBySideDiff};
+
+// Columns scrolled horizontally per key press
+const H_SCROLL_STEP: u16 = 8;
+
 /// Details panel used for the right side of each tab.
 /// This handles scrolling and wrapping.
 pub struct DetailsPanel {
     pub scroll: u16,
+    // Columns scrolled to the right, for long lines when not wrapping
+    pub h_scroll: u16,
     height: u16,
     lines: u16,
+    width: u16,
+    // Width of the longest line, as of the last render
+    line_width: u16,
     wrap: bool,
 }
 
@@ -18,8 +30,11 @@ impl DetailsPanel {
     pub fn new() -> Self {
         Self {
             scroll: 0,
+            h_scroll: 0,
             height: 0,
             lines: 0,
+            width: 0,
+            line_width: 0,
             wrap: true,
         }
     }
@@ -29,6 +44,15 @@ impl DetailsPanel {
     where
         T: Into<Text<'a>>,
     {
+        let content = content.into();
+        self.width = area.width;
+        // Wrapped lines never extend beyond the area
+        self.line_width = if self.wrap {
+            area.width
+        } else {
+            content.width() as u16
+        };
+
         let mut paragraph = Paragraph::new(content);
 
         if self.wrap {
@@ -38,15 +62,98 @@ impl DetailsPanel {
         self.height = area.height;
         self.lines = paragraph.line_count(area.width) as u16;
 
-        paragraph = paragraph.scroll((self.scroll.min(self.lines.saturating_sub(1)), 0));
+        paragraph = paragraph.scroll((
+            self.scroll.min(self.lines.saturating_sub(1)),
+            self.h_scroll.min(self.max_h_scroll()),
+        ));
 
         paragraph
     }
 
+    /// Render a horizontal scrollbar on the bottom border of the area, when lines extend beyond
+    /// the width of the last render.
+    pub fn render_h_scrollbar(&self, f: &mut Frame<'_>, area: Rect) {
+        if self.max_h_scroll() == 0 {
+            return;
+        }
+
+        let mut state = ScrollbarState::new(self.max_h_scroll() as usize)
+            .viewport_content_length(self.width as usize)
+            .position(self.h_scroll.min(self.max_h_scroll()) as usize);
+        f.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::HorizontalBottom)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .style(Style::default().fg(Color::DarkGray)),
+            area,
+            &mut state,
+        );
+    }
+
+    /// Render a Git diff side-by-side into the area, with both columns scrolling together.
+    /// Wrapping is disabled to keep the columns aligned.
+    pub fn render_side_by_side(&mut self, f: &mut Frame<'_>, diff: &str, block: Block, area: Rect) {
+        let inner_area = block.inner(area);
+        f.render_widget(block, area);
+
+        let side_by_side = SideBySideDiff::new(&escape_control_chars(diff));
+
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(inner_area);
+
+        self.height = inner_area.height;
+        self.lines = (side_by_side.left.lines.len() as u16).max(1);
+        // The right column's border takes a column
+        self.width = chunks[0].width.min(chunks[1].width.saturating_sub(1));
+        self.line_width = side_by_side.left.width().max(side_by_side.right.width()) as u16;
+        let scroll = (
+            self.scroll.min(self.lines.saturating_sub(1)),
+            self.h_scroll.min(self.max_h_scroll()),
+        );
+
+        f.render_widget(Paragraph::new(side_by_side.left).scroll(scroll), chunks[0]);
+        f.render_widget(
+            Paragraph::new(side_by_side.right).scroll(scroll).block(
+                Block::default()
+                    .borders(Borders::LEFT)
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            ),
+            chunks[1],
+        );
+        self.render_h_scrollbar(f, area);
+    }
+
     pub fn scroll(&mut self, scroll: isize) {
         self.scroll = (self.scroll.saturating_add_signed(scroll as i16)).min(self.lines - 1)
     }
 
+    // Columns the longest line extends beyond the area
+    fn max_h_scroll(&self) -> u16 {
+        self.line_width.saturating_sub(self.width)
+    }
+
+    pub fn h_scroll(&mut self, scroll: isize) {
+        self.h_scroll = self
+            .h_scroll
+            .min(self.max_h_scroll())
+            .saturating_add_signed(scroll as i16)
+            .min(self.max_h_scroll());
+    }
+
+    /// Forget the size of the last render, such as after the terminal was resized.
+    pub fn reset_size(&mut self) {
+        self.height = 0;
+        self.width = 0;
+        self.line_width = 0;
+    }
+
+    /// Scroll so the last line is at the bottom, as of the last render.
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll = self.lines.saturating_sub(self.height);
+    }
+
     /// Handle input. Returns bool of if event was handled
     pub fn input(&mut self, key: KeyEvent) -> bool {
         match key.code {
@@ -64,6 +171,12 @@ impl DetailsPanel {
             KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                 self.scroll((self.height as isize).saturating_neg())
             }
+            KeyCode::Left if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.h_scroll(-(H_SCROLL_STEP as isize))
+            }
+            KeyCode::Right if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.h_scroll(H_SCROLL_STEP as isize)
+            }
             KeyCode::Char('W') => {
                 self.wrap = !self.wrap;
             }
@@ -73,3 +186,29 @@ impl DetailsPanel {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn h_scroll_within_long_lines() {
+        let mut panel = DetailsPanel::new();
+        let area = Rect::new(0, 0, 10, 5);
+        let content = || Text::from(vec!["short".into(), "a line of 24 characters.".into()]);
+
+        // Wrapped lines fit, so there's nothing to scroll
+        let _ = panel.render(content(), area);
+        panel.h_scroll(H_SCROLL_STEP as isize);
+        assert_eq!(panel.h_scroll, 0);
+
+        panel.wrap = false;
+        let _ = panel.render(content(), area);
+        panel.h_scroll(H_SCROLL_STEP as isize);
+        assert_eq!(panel.h_scroll, 8);
+        panel.h_scroll(H_SCROLL_STEP as isize);
+        assert_eq!(panel.h_scroll, 14);
+        panel.h_scroll(-(H_SCROLL_STEP as isize) * 2);
+        assert_eq!(panel.h_scroll, 0);
+    }
+}
@@ -0,0 +1,101 @@
+use ansi_to_tui::IntoText;
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{layout::Rect, text::Text, widgets::Clear, Frame};
+
+use crate::{
+    commander::{CommandError, Commander},
+    env::DiffFormat,
+    ui::{
+        details_panel::DetailsPanel,
+        styles::create_popup_block,
+        utils::{centered_rect, char_diff::char_diff_text, fit_diff_lines},
+        Component,
+    },
+    ComponentInputResult,
+};
+
+/// Popup showing the diff between two commits, such as a bookmark and its remote.
+pub struct DiffBetweenPopup {
+    title: String,
+    diff_output: Result<String, CommandError>,
+    diff_format: DiffFormat,
+    diff_panel: DetailsPanel,
+}
+
+impl DiffBetweenPopup {
+    pub fn new(
+        title: String,
+        diff_output: Result<String, CommandError>,
+        diff_format: DiffFormat,
+    ) -> Self {
+        Self {
+            title,
+            diff_output,
+            diff_format,
+            diff_panel: DetailsPanel::new(),
+        }
+    }
+}
+
+impl Component for DiffBetweenPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 80, 80);
+        f.render_widget(Clear, area);
+
+        let block = create_popup_block(&self.title);
+        if let (DiffFormat::SideBySide, Ok(diff_output)) =
+            (&self.diff_format, self.diff_output.as_ref())
+        {
+            if !diff_output.is_empty() {
+                self.diff_panel
+                    .render_side_by_side(f, diff_output, block, area);
+                return Ok(());
+            }
+        }
+
+        let diff_content = match self.diff_output.as_ref() {
+            Ok(diff_output) if diff_output.is_empty() => "No changes".into_text()?,
+            Ok(diff_output) if self.diff_format == DiffFormat::CharLevel => {
+                char_diff_text(diff_output)
+            }
+            Ok(diff_output) => Text::from(fit_diff_lines(
+                diff_output,
+                &self.diff_format,
+                block.inner(area).width as usize,
+                false,
+            )?),
+            Err(err) => err.into_text("Error getting diff")?,
+        };
+        let diff = self
+            .diff_panel
+            .render(diff_content, block.inner(area))
+            .block(block);
+        f.render_widget(diff, area);
+        self.diff_panel.render_h_scrollbar(f, area);
+
+        Ok(())
+    }
+
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            if self.diff_panel.input(key) {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => self.diff_panel.scroll(1),
+                KeyCode::Char('k') | KeyCode::Up => self.diff_panel.scroll(-1),
+                _ => return Ok(ComponentInputResult::NotHandled),
+            }
+
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        Ok(ComponentInputResult::NotHandled)
+    }
+}
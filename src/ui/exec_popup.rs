@@ -0,0 +1,340 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Text},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::{
+    commander::{
+        stream::{StreamEvent, StreamingCommand},
+        CommandError, Commander,
+    },
+    ui::{
+        details_panel::DetailsPanel,
+        styles::{border_set, create_popup_block},
+        utils::{ansi_text, centered_rect, centered_rect_line_height, tabs_to_spaces},
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+#[derive(PartialEq)]
+enum ExecField {
+    Revset,
+    Command,
+}
+
+enum ExecStatus {
+    Running(StreamingCommand),
+    // Exit code, `None` if interrupted
+    Exited(Option<i32>),
+    // Failed to start
+    Error(CommandError),
+}
+
+/// Popup to run a shell command for each commit in a revset with `jj run`, then show its output
+/// live while it runs.
+pub struct ExecPopup<'a> {
+    revset_textarea: TextArea<'a>,
+    command_textarea: TextArea<'a>,
+    focus: ExecField,
+    status: Option<ExecStatus>,
+    output: Vec<String>,
+    output_panel: DetailsPanel,
+    // Keep the last output line in view, until scrolling
+    follow: bool,
+}
+
+impl ExecPopup<'_> {
+    pub fn new(revset: &str) -> Self {
+        let mut revset_textarea = TextArea::new(vec![revset.to_owned()]);
+        revset_textarea.move_cursor(CursorMove::End);
+
+        let mut popup = Self {
+            revset_textarea,
+            command_textarea: TextArea::default(),
+            focus: ExecField::Command,
+            status: None,
+            output: vec![],
+            output_panel: DetailsPanel::new(),
+            follow: true,
+        };
+        popup.update_cursors();
+        popup
+    }
+
+    // Only show the cursor in the focused text area
+    fn update_cursors(&mut self) {
+        for (textarea, focused) in [
+            (&mut self.revset_textarea, self.focus == ExecField::Revset),
+            (&mut self.command_textarea, self.focus == ExecField::Command),
+        ] {
+            textarea.set_cursor_style(if focused {
+                Style::default().reversed()
+            } else {
+                Style::default()
+            });
+        }
+    }
+
+    fn get_output_text(&self) -> Result<Text<'static>> {
+        let mut text = ansi_text(&tabs_to_spaces(&self.output.join("\n")))?;
+        let status_line = match self.status.as_ref() {
+            Some(ExecStatus::Running(_)) => None,
+            Some(ExecStatus::Exited(Some(0))) => {
+                Some(Line::raw("Command succeeded").bold().fg(Color::Green))
+            }
+            Some(ExecStatus::Exited(Some(code))) => Some(
+                Line::raw(format!("Command failed with exit code {code}"))
+                    .bold()
+                    .fg(Color::Red),
+            ),
+            Some(ExecStatus::Exited(None)) => {
+                Some(Line::raw("Command interrupted").bold().fg(Color::Red))
+            }
+            Some(ExecStatus::Error(err)) => {
+                return Ok(err.into_text("Error running command")?);
+            }
+            None => None,
+        };
+        if let Some(status_line) = status_line {
+            if !self.output.is_empty() {
+                text.lines.push(Line::default());
+            }
+            text.lines.push(status_line);
+        }
+
+        Ok(text)
+    }
+
+    fn draw_output(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 80, 80);
+        f.render_widget(Clear, area);
+
+        let block = create_popup_block("Run output");
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Fill(1), Constraint::Length(2)])
+            .split(block.inner(area));
+        f.render_widget(&block, area);
+
+        let output_text = self.get_output_text()?;
+        let mut output = self
+            .output_panel
+            .render(output_text.clone(), popup_chunks[0]);
+        if self.follow {
+            self.output_panel.scroll_to_bottom();
+            output = self.output_panel.render(output_text, popup_chunks[0]);
+        }
+        f.render_widget(output, popup_chunks[0]);
+
+        let help = if matches!(self.status, Some(ExecStatus::Running(_))) {
+            "Ctrl+c: interrupt | j/k: scroll | G: follow output"
+        } else {
+            "j/k: scroll | Escape: close"
+        };
+        let help = Paragraph::new(vec![help.into()])
+            .fg(Color::DarkGray)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .border_set(border_set())
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            );
+        f.render_widget(help, popup_chunks[1]);
+
+        Ok(())
+    }
+}
+
+impl Component for ExecPopup<'_> {
+    fn update(&mut self, _commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        Ok(self.poll_background())
+    }
+
+    fn poll_background(&mut self) -> Option<ComponentAction> {
+        let Some(ExecStatus::Running(command)) = self.status.as_mut() else {
+            return None;
+        };
+
+        let mut exit = None;
+        for event in command.poll() {
+            match event {
+                StreamEvent::Line(line) => self.output.push(line),
+                StreamEvent::Exit(code) => exit = Some(code),
+            }
+        }
+
+        match exit {
+            Some(code) => {
+                self.status = Some(ExecStatus::Exited(code));
+                // Commits may have been rewritten
+                Some(ComponentAction::Refresh)
+            }
+            None => None,
+        }
+    }
+
+    fn has_background_work(&self) -> bool {
+        matches!(self.status, Some(ExecStatus::Running(_)))
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        if self.status.is_some() {
+            return self.draw_output(f, area);
+        }
+
+        let block = create_popup_block("Run command");
+        let area = centered_rect_line_height(area, 50, 11);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Fill(1),
+                Constraint::Length(2),
+            ])
+            .split(block.inner(area));
+
+        for (textarea, title, focused, chunk) in [
+            (
+                &mut self.revset_textarea,
+                " Revset ",
+                self.focus == ExecField::Revset,
+                popup_chunks[0],
+            ),
+            (
+                &mut self.command_textarea,
+                " Shell command ",
+                self.focus == ExecField::Command,
+                popup_chunks[1],
+            ),
+        ] {
+            textarea.set_block(
+                Block::bordered()
+                    .title(title)
+                    .border_set(border_set())
+                    .border_style(Style::default().fg(if focused {
+                        Color::Green
+                    } else {
+                        Color::DarkGray
+                    })),
+            );
+            f.render_widget(&*textarea, chunk);
+        }
+
+        f.render_widget(
+            Paragraph::new(Line::raw(
+                "Commits will be rewritten if the command modifies files",
+            ))
+            .fg(Color::Yellow),
+            popup_chunks[2],
+        );
+
+        let help = Paragraph::new(vec![
+            "Tab: switch field | Enter/Ctrl+s: run | Escape: cancel".into(),
+        ])
+        .fg(Color::DarkGray)
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_set(border_set())
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+        f.render_widget(help, popup_chunks[3]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            // Showing output
+            if let Some(status) = self.status.as_ref() {
+                let running = matches!(status, ExecStatus::Running(_));
+                if let (ExecStatus::Running(command), KeyCode::Char('c')) = (status, key.code) {
+                    if key.modifiers.contains(KeyModifiers::CONTROL) {
+                        command.interrupt()?;
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                }
+
+                if self.output_panel.input(key) {
+                    self.follow = false;
+                    return Ok(ComponentInputResult::Handled);
+                }
+
+                match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        self.follow = false;
+                        self.output_panel.scroll(1)
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        self.follow = false;
+                        self.output_panel.scroll(-1)
+                    }
+                    KeyCode::Char('G') => self.follow = true,
+                    // Only close once the command is done, or interrupted
+                    _ if running => {}
+                    _ => return Ok(ComponentInputResult::NotHandled),
+                }
+
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            match key.code {
+                KeyCode::Enter => {}
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {}
+                KeyCode::Tab | KeyCode::BackTab => {
+                    self.focus = match self.focus {
+                        ExecField::Revset => ExecField::Command,
+                        ExecField::Command => ExecField::Revset,
+                    };
+                    self.update_cursors();
+                    return Ok(ComponentInputResult::Handled);
+                }
+                KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                _ => {
+                    match self.focus {
+                        ExecField::Revset => self.revset_textarea.input(event),
+                        ExecField::Command => self.command_textarea.input(event),
+                    };
+                    return Ok(ComponentInputResult::Handled);
+                }
+            }
+
+            // Run
+            let revset = self.revset_textarea.lines().join("");
+            let command = self.command_textarea.lines().join("");
+            if revset.trim().is_empty() || command.trim().is_empty() {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            // Output is received in `update`
+            self.status = Some(match commander.run_exec(revset.trim(), command.trim()) {
+                Ok(command) => ExecStatus::Running(command),
+                Err(err) => ExecStatus::Error(err),
+            });
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use tui_textarea::TextArea;
+
+use crate::{
+    commander::Commander,
+    ui::{
+        styles::{border_set, create_popup_block},
+        utils::centered_rect_line_height,
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+/// Popup to enter a revision to compare the selected file of the files tab with. Opened with `c`
+/// in the files tab.
+pub struct FileComparePopup<'a> {
+    textarea: TextArea<'a>,
+    path: String,
+    tx: std::sync::mpsc::Sender<String>,
+}
+
+impl FileComparePopup<'_> {
+    pub fn new(path: String, tx: std::sync::mpsc::Sender<String>) -> Self {
+        let mut textarea = TextArea::default();
+        textarea.set_placeholder_text("Revision, such as @- or a change ID");
+
+        Self { textarea, path, tx }
+    }
+}
+
+impl Component for FileComparePopup<'_> {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let block = create_popup_block("Compare across revisions");
+        let area = centered_rect_line_height(area, 50, 8);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Fill(1),
+                Constraint::Length(2),
+            ])
+            .split(block.inner(area));
+
+        self.textarea.set_block(
+            Block::bordered()
+                .title(" Compare from ")
+                .border_set(border_set())
+                .border_style(Style::default().fg(Color::Green)),
+        );
+        f.render_widget(&self.textarea, popup_chunks[0]);
+
+        f.render_widget(
+            Paragraph::new(Line::raw(self.path.clone())).fg(Color::DarkGray),
+            popup_chunks[1],
+        );
+
+        let help = Paragraph::new(vec!["Enter/Ctrl+s: compare | Escape: cancel".into()])
+            .fg(Color::DarkGray)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .border_set(border_set())
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            );
+        f.render_widget(help, popup_chunks[2]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            match key.code {
+                KeyCode::Enter => {}
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {}
+                KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                _ => {
+                    self.textarea.input(event);
+                    return Ok(ComponentInputResult::Handled);
+                }
+            }
+
+            let revision = self.textarea.lines().join("").trim().to_owned();
+            if revision.is_empty() {
+                return Ok(ComponentInputResult::Handled);
+            }
+            self.tx.send(revision)?;
+            return Ok(ComponentInputResult::HandledAction(
+                ComponentAction::SetPopup(None),
+            ));
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+}
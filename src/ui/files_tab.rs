@@ -3,46 +3,90 @@ use tracing::instrument;
 
 use crate::{
     commander::{
-        files::{Conflict, File},
+        files::{Conflict, FilesOutput},
+        ids::ChangeId,
         log::Head,
         CommandError, Commander,
     },
     env::{Config, DiffFormat},
     ui::{
-        details_panel::DetailsPanel, help_popup::HelpPopup, utils::tabs_to_spaces, Component,
-        ComponentAction,
+        bookmark_set_popup::BookmarkSetPopup,
+        details_panel::DetailsPanel,
+        file_compare_popup::FileComparePopup,
+        fix_popup::FixPopup,
+        help_popup::HelpPopup,
+        scroll_memory::ScrollMemory,
+        styles::{border_set, highlight_line},
+        utils::{
+            ansi_text,
+            binary::{is_binary_diff, is_binary_path, BinaryFile, BinaryVersion},
+            char_diff::char_diff_text,
+            fit_diff_lines, keep_last_ok, next_diff_format, panel_constraints, panel_hint,
+            paths::shorten_path,
+            recover_selection,
+            refresh::{LastRefresh, RefreshInputs},
+            stale_banner, tabs_to_spaces, DEFAULT_SPLIT_PERCENT,
+        },
+        Component, ComponentAction, FocusedPanel,
     },
     ComponentInputResult,
 };
 
-use ansi_to_tui::IntoText;
-use crossterm::event::{Event, KeyCode, KeyEventKind};
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
 use ratatui::{prelude::*, widgets::*};
 
 /// Files tab. Shows files in selected change in left panel and selected file diff in right panel
 pub struct FilesTab {
     head: Head,
     is_current_head: bool,
-
-    files_output: Result<Vec<File>, CommandError>,
+    // Stop following the head selected in other tabs
+    pub pinned: bool,
+
+    files_output: Result<FilesOutput, CommandError>,
+    // Change of files_output, and error from the last refresh while showing the last successful
+    // files of the same change
+    files_change_id: ChangeId,
+    files_error: Option<CommandError>,
     conflicts_output: Vec<Conflict>,
     files_list_state: ListState,
     files_height: u16,
 
     pub file: Option<String>,
+    // Shown instead of the diff when the file is binary, as its diff is only a notice, or raw
+    // bytes from some diff tools
+    binary_file: Option<BinaryFile>,
     diff_panel: DetailsPanel,
+    // Scroll of the diffs of files left, to restore when coming back. Only for the same change
+    scroll_memory: ScrollMemory<String>,
     diff_output: Result<Option<String>, CommandError>,
     diff_format: DiffFormat,
+    // Revision the selected file is compared from, instead of the diff of the change
+    compare_revision: Option<String>,
+
+    // Shown below the files until the next key press
+    notification: Option<String>,
+    // Last successful refresh with `R`, to skip the next one if nothing changed
+    last_refresh: Option<LastRefresh>,
+
+    bookmark_set_popup_tx: std::sync::mpsc::Sender<bool>,
+    bookmark_set_popup_rx: std::sync::mpsc::Receiver<bool>,
+    file_compare_popup_tx: std::sync::mpsc::Sender<String>,
+    file_compare_popup_rx: std::sync::mpsc::Receiver<String>,
+
+    // Panel last interacted with, panel shown full screen, and width of the left panel in percent
+    focused_panel: FocusedPanel,
+    maximized_panel: Option<FocusedPanel>,
+    split_percent: u16,
 
     config: Config,
 }
 
 fn get_current_file_index(
     current_file: Option<&String>,
-    files_output: Result<&Vec<File>, &CommandError>,
+    files_output: Result<&FilesOutput, &CommandError>,
 ) -> Option<usize> {
     if let (Some(current_file), Ok(files_output)) = (current_file, files_output) {
-        files_output.iter().position(|file| {
+        files_output.files.iter().position(|file| {
             file.path
                 .as_ref()
                 .map_or(false, |path| path == current_file)
@@ -52,6 +96,11 @@ fn get_current_file_index(
     }
 }
 
+// Whether the files tab should switch to the active head
+fn should_follow(pinned: bool, head: &Head, active_head: &Head) -> bool {
+    !pinned && head != active_head
+}
+
 impl FilesTab {
     #[instrument(level = "trace", skip(commander))]
     pub fn new(commander: &mut Commander, head: &Head) -> Result<Self> {
@@ -62,10 +111,12 @@ impl FilesTab {
 
         let files_output = commander.get_files(&head);
         let conflicts_output = commander.get_conflicts(&head.commit_id)?;
-        let current_file = files_output
-            .as_ref()
-            .ok()
-            .and_then(|files_output| files_output.first().and_then(|change| change.path.clone()));
+        let current_file = files_output.as_ref().ok().and_then(|files_output| {
+            files_output
+                .files
+                .first()
+                .and_then(|change| change.path.clone())
+        });
         let diff_output = current_file
             .as_ref()
             .map(|current_change| commander.get_file_diff(&head, current_change, &diff_format))
@@ -76,11 +127,17 @@ impl FilesTab {
             files_output.as_ref(),
         ));
 
-        Ok(Self {
+        let (bookmark_set_popup_tx, bookmark_set_popup_rx) = std::sync::mpsc::channel();
+        let (file_compare_popup_tx, file_compare_popup_rx) = std::sync::mpsc::channel();
+
+        let mut files_tab = Self {
+            files_change_id: head.change_id.clone(),
             head,
             is_current_head,
+            pinned: false,
 
             files_output,
+            files_error: None,
             file: current_file,
             files_list_state,
             files_height: 0,
@@ -89,62 +146,209 @@ impl FilesTab {
 
             diff_output,
             diff_format,
+            binary_file: None,
             diff_panel: DetailsPanel::new(),
+            scroll_memory: ScrollMemory::new(),
+            compare_revision: None,
+
+            notification: None,
+            last_refresh: None,
+
+            bookmark_set_popup_tx,
+            bookmark_set_popup_rx,
+            file_compare_popup_tx,
+            file_compare_popup_rx,
+
+            focused_panel: FocusedPanel::Left,
+            maximized_panel: None,
+            split_percent: DEFAULT_SPLIT_PERCENT,
 
             config: commander.env.config.clone(),
-        })
+        };
+        files_tab.refresh_binary_file(commander);
+        Ok(files_tab)
     }
 
     pub fn set_head(&mut self, commander: &mut Commander, head: &Head) -> Result<()> {
         self.head = head.clone();
+        self.scroll_memory.clear();
         self.is_current_head = self.head == commander.get_current_head()?;
 
         self.refresh_files(commander)?;
-        self.file =
-            self.files_output.as_ref().ok().and_then(|files_output| {
-                files_output.first().and_then(|change| change.path.clone())
-            });
+        self.select_first_file();
         self.refresh_diff(commander)?;
 
         Ok(())
     }
 
+    /// Follow the active head, unless pinned. Files are refreshed when switching to the tab.
+    pub fn focus(&mut self, head: &Head) {
+        if should_follow(self.pinned, &self.head, head) {
+            self.head = head.clone();
+            self.file = None;
+            self.scroll_memory.clear();
+        }
+    }
+
+    fn select_first_file(&mut self) {
+        self.select_file_near(None);
+    }
+
+    // Select the file at an index clamped to the files, such as where the selected file was before
+    // it was removed from the change
+    fn select_file_near(&mut self, index: Option<usize>) {
+        self.file = self.files_output.as_ref().ok().and_then(|files_output| {
+            recover_selection(index, files_output.files.len())
+                .and_then(|index| files_output.files[index].path.clone())
+        });
+    }
+
     pub fn get_current_file_index(&self) -> Option<usize> {
         get_current_file_index(self.file.as_ref(), self.files_output.as_ref())
     }
 
+    fn refresh_inputs(&self) -> RefreshInputs {
+        RefreshInputs {
+            revset: Some(self.head.commit_id.as_string()),
+            diff_format: self.diff_format.clone(),
+            show_all: false,
+            filters: vec![self.compare_revision.clone()],
+        }
+    }
+
+    /// Refresh the files of the change. If the selected file is gone, such as after it was squashed
+    /// or restored, the file at the same position is selected instead.
     pub fn refresh_files(&mut self, commander: &mut Commander) -> Result<()> {
-        self.files_output = commander.get_files(&self.head);
+        let previous_index = self.get_current_file_index();
+        let files_output = commander.get_files(&self.head);
+        // Files of another change would be misleading, so only keep them for the same change
+        self.files_error = if self.files_change_id == self.head.change_id {
+            keep_last_ok(&mut self.files_output, files_output)
+        } else {
+            self.files_output = files_output;
+            None
+        };
+        self.files_change_id = self.head.change_id.clone();
         self.conflicts_output = commander.get_conflicts(&self.head.commit_id)?;
+        if self.get_current_file_index().is_none() {
+            self.select_file_near(previous_index);
+        }
         Ok(())
     }
 
     pub fn refresh_diff(&mut self, commander: &mut Commander) -> Result<()> {
-        self.diff_output = self
-            .file
-            .as_ref()
-            .map(|current_file| {
-                commander.get_file_diff(&self.head, current_file, &self.diff_format)
-            })
-            .map_or(Ok(None), |r| r.map(|diff| Some(tabs_to_spaces(&diff))));
-        self.diff_panel.scroll = 0;
+        self.diff_output = if let (Some(compare_revision), Some(current_file)) =
+            (self.compare_revision.as_ref(), self.file.as_ref())
+        {
+            commander
+                .get_file_diff_range(
+                    compare_revision,
+                    self.head.commit_id.as_str(),
+                    current_file,
+                    &self.diff_format,
+                )
+                .map(|diff| Some(tabs_to_spaces(&diff)))
+        } else if self.diff_format == DiffFormat::Summary {
+            // A summary of one file is a single line, so show all files of the change
+            commander
+                .get_change_diff(&self.head, &self.diff_format)
+                .map(Some)
+        } else {
+            self.file
+                .as_ref()
+                .map(|current_file| {
+                    commander.get_file_diff(&self.head, current_file, &self.diff_format)
+                })
+                .map_or(Ok(None), |r| r.map(|diff| Some(tabs_to_spaces(&diff))))
+        };
+        self.refresh_binary_file(commander);
+        self.diff_panel.scroll = match (self.file.as_ref(), self.diff_output.as_ref()) {
+            (Some(file), Ok(Some(diff))) => self.scroll_memory.restore(file, diff.lines().count()),
+            _ => 0,
+        };
+        self.diff_panel.h_scroll = 0;
         Ok(())
     }
 
+    // Check whether the selected file's diff is of a binary file, to show its sizes instead. The
+    // summary and stat formats are readable for binary files
+    fn refresh_binary_file(&mut self, commander: &mut Commander) {
+        self.binary_file = None;
+        if matches!(self.diff_format, DiffFormat::Summary | DiffFormat::Stat) {
+            return;
+        }
+        let Some(file) = self.file.clone() else {
+            return;
+        };
+        let is_binary = match self.diff_output.as_ref() {
+            Ok(Some(diff)) => is_binary_diff(diff),
+            // Raw bytes which aren't UTF-8
+            Err(CommandError::FromUtf8(_)) => true,
+            _ => false,
+        };
+        if !is_binary && !is_binary_path(&file) {
+            return;
+        }
+
+        let before = self
+            .compare_revision
+            .clone()
+            .unwrap_or_else(|| format!("{}-", self.head.commit_id.as_str()));
+        let mut version = |revision: &str| {
+            commander
+                .get_file_content(revision, &file)
+                .ok()
+                .map(|content| BinaryVersion::new(&content))
+        };
+        let before = version(&before);
+        let after = version(self.head.commit_id.as_str());
+        let diff_type = self.files_output.as_ref().ok().and_then(|files_output| {
+            files_output
+                .files
+                .iter()
+                .find(|entry| entry.path.as_ref() == Some(&file))
+                .and_then(|entry| entry.diff_type.clone())
+        });
+        self.binary_file = Some(BinaryFile {
+            path: file,
+            diff_type,
+            before,
+            after,
+        });
+    }
+
+    pub fn set_diff_format(
+        &mut self,
+        commander: &mut Commander,
+        diff_format: DiffFormat,
+    ) -> Result<()> {
+        self.diff_format = diff_format;
+        // Positions are of the previous format's lines
+        self.scroll_memory.clear();
+        self.refresh_diff(commander)
+    }
+
     fn scroll_files(&mut self, commander: &mut Commander, scroll: isize) -> Result<()> {
-        if let Ok(files) = self.files_output.as_ref() {
+        if let Ok(files) = self
+            .files_output
+            .as_ref()
+            .map(|files_output| &files_output.files)
+        {
             let current_file_index = self.get_current_file_index();
             let next_file = match current_file_index {
                 Some(current_file_index) => files.get(
                     current_file_index
                         .saturating_add_signed(scroll)
-                        .min(files.len() - 1),
+                        .min(files.len().saturating_sub(1)),
                 ),
                 None => files.first(),
             }
             .map(|x| x.to_owned());
             if let Some(next_file) = next_file {
                 if next_file.path.is_some() {
+                    if let Some(file) = self.file.take() {
+                        self.scroll_memory.remember(file, self.diff_panel.scroll);
+                    }
                     self.file.clone_from(&next_file.path);
                     self.refresh_diff(commander)?;
                 }
@@ -162,6 +366,36 @@ impl Component for FilesTab {
         Ok(())
     }
 
+    fn update(&mut self, commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        if let Ok(true) = self.bookmark_set_popup_rx.try_recv() {
+            self.refresh_files(commander)?;
+        }
+
+        if let Ok(compare_revision) = self.file_compare_popup_rx.try_recv() {
+            self.compare_revision = Some(compare_revision);
+            self.refresh_diff(commander)?;
+        }
+
+        Ok(None)
+    }
+
+    fn focused_panel(&self) -> Option<FocusedPanel> {
+        Some(self.focused_panel)
+    }
+
+    fn set_maximized_panel(&mut self, maximized_panel: Option<FocusedPanel>) {
+        self.maximized_panel = maximized_panel;
+    }
+
+    fn set_split_percent(&mut self, split_percent: u16) {
+        self.split_percent = split_percent;
+    }
+
+    fn reset_size(&mut self) {
+        self.files_height = 0;
+        self.diff_panel.reset_size();
+    }
+
     fn draw(
         &mut self,
         f: &mut ratatui::prelude::Frame<'_>,
@@ -169,21 +403,47 @@ impl Component for FilesTab {
     ) -> Result<()> {
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .constraints(panel_constraints(self.maximized_panel, self.split_percent))
             .split(area);
 
         // Draw files
         {
             let current_file_index = self.get_current_file_index();
+            // Warnings are shown above files, so offset the selected line
+            let warnings_count = self
+                .files_output
+                .as_ref()
+                .map_or(0, |files_output| files_output.warnings.len());
+            // Width left for paths, after borders, padding and the change type
+            let path_width = chunks[0].width.saturating_sub(5) as usize;
 
             let mut lines: Vec<Line> = match self.files_output.as_ref() {
                 Ok(files_output) => {
+                    let warnings_lines = files_output
+                        .warnings
+                        .iter()
+                        .map(|warning| Line::raw(format!(" {warning}")).fg(Color::Yellow));
                     let files_lines = files_output
+                        .files
                         .iter()
                         .enumerate()
                         .flat_map(|(i, file)| {
-                            file.line
-                                .to_text()
+                            let is_current = current_file_index
+                                .map_or(false, |current_file_index| i == current_file_index);
+                            // The selected file's full path is shown below the list
+                            // Renames are shortened as shown, such as `src/{old => new}`
+                            let file_line = match file.line.split_once(' ') {
+                                Some((status, path))
+                                    if file.path.is_some()
+                                        && self.config.shorten_paths()
+                                        && !is_current =>
+                                {
+                                    format!("{status} {}", shorten_path(path, path_width))
+                                }
+                                _ => file.line.clone(),
+                            };
+
+                            ansi_text(&file_line)
                                 .unwrap()
                                 .iter()
                                 .map(|line| {
@@ -200,18 +460,8 @@ impl Component for FilesTab {
                                             .collect();
                                     }
 
-                                    if current_file_index
-                                        .map_or(false, |current_file_index| i == current_file_index)
-                                    {
-                                        line = line.bg(self.config.highlight_color());
-
-                                        line.spans = line
-                                            .spans
-                                            .iter_mut()
-                                            .map(|span| {
-                                                span.to_owned().bg(self.config.highlight_color())
-                                            })
-                                            .collect();
+                                    if is_current {
+                                        line = highlight_line(line, &self.config);
                                     }
 
                                     line
@@ -220,22 +470,27 @@ impl Component for FilesTab {
                         })
                         .collect::<Vec<Line>>();
 
-                    if files_lines.is_empty() {
+                    let files_lines = if files_lines.is_empty() {
                         vec![Line::from(" No changed files in change")
                             .fg(Color::DarkGray)
                             .italic()]
                     } else {
                         files_lines
-                    }
+                    };
+
+                    warnings_lines.chain(files_lines).collect()
                 }
                 Err(err) => err.into_text("Error getting files")?.lines,
             };
 
-            let title_change = if self.is_current_head {
+            let mut title_change = if self.is_current_head {
                 format!("@ ({})", self.head.change_id)
             } else {
                 self.head.change_id.as_string()
             };
+            if self.pinned {
+                title_change.push_str(" (pinned)");
+            }
 
             if !self.conflicts_output.is_empty() {
                 lines.push(Line::default());
@@ -245,34 +500,110 @@ impl Component for FilesTab {
                 }
             }
 
-            let files = List::new(lines)
-                .block(
-                    Block::bordered()
-                        .title(" Files for ".to_owned() + &title_change + " ")
-                        .border_type(BorderType::Rounded),
-                )
-                .scroll_padding(3);
-            *self.files_list_state.selected_mut() = current_file_index;
-            f.render_stateful_widget(files, chunks[0], &mut self.files_list_state);
-            self.files_height = chunks[0].height - 2;
+            if self.files_error.is_some() {
+                title_change.push_str(" (stale)");
+            }
+
+            let mut files_block = Block::bordered()
+                .title(" Files for ".to_owned() + &title_change + " ")
+                .border_set(border_set());
+            if let (true, Some(file)) = (self.config.shorten_paths(), self.file.as_ref()) {
+                files_block = files_block.title_bottom(format!(" {file} "));
+            }
+            if let Some(notification) = self.notification.as_ref() {
+                files_block = files_block.title_bottom(
+                    Line::raw(format!(" {notification} "))
+                        .fg(Color::Yellow)
+                        .right_aligned(),
+                );
+            } else if self.config.panel_hints() {
+                let change_id = self.head.change_id.as_str();
+                let hint = if self.pinned {
+                    "pinned, p to follow the log".to_owned()
+                } else {
+                    format!("from log: change {}", &change_id[..change_id.len().min(8)])
+                };
+                if let Some(hint) = panel_hint(&hint, chunks[0].width) {
+                    files_block = files_block.title_bottom(hint);
+                }
+            }
+            let files_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(if self.files_error.is_some() { 1 } else { 0 }),
+                    Constraint::Fill(1),
+                ])
+                .split(files_block.inner(chunks[0]));
+            f.render_widget(files_block, chunks[0]);
+
+            if let Some(files_error) = self.files_error.as_ref() {
+                f.render_widget(
+                    stale_banner(files_error, "Press R to refresh"),
+                    files_chunks[0],
+                );
+            }
+
+            let files = List::new(lines).scroll_padding(3);
+            *self.files_list_state.selected_mut() =
+                current_file_index.map(|current_file_index| current_file_index + warnings_count);
+            f.render_stateful_widget(files, files_chunks[1], &mut self.files_list_state);
+            self.files_height = files_chunks[1].height;
         }
 
         // Draw diff
         {
+            let diff_title = match (self.compare_revision.as_ref(), self.file.as_ref()) {
+                (Some(compare_revision), Some(file)) => format!(
+                    " Diff of {file} from {compare_revision} to {} (Esc to go back) ",
+                    self.head.change_id
+                ),
+                _ if self.diff_format == DiffFormat::Summary => " Diff summary ".to_owned(),
+                _ => " Diff ".to_owned(),
+            };
             let diff_block = Block::bordered()
-                .title(" Diff ")
-                .border_type(BorderType::Rounded)
+                .title(diff_title)
+                .border_set(border_set())
                 .padding(Padding::horizontal(1));
-            let diff_content = match self.diff_output.as_ref() {
-                Ok(Some(diff_content)) => diff_content.into_text()?,
-                Ok(None) => Text::default(),
-                Err(err) => err.into_text("Error getting diff")?,
-            };
-            let diff = self
-                .diff_panel
-                .render(diff_content, diff_block.inner(chunks[1]))
-                .block(diff_block);
-            f.render_widget(diff, chunks[1]);
+            match (&self.diff_format, self.diff_output.as_ref()) {
+                // Never parsed, as it may be raw bytes
+                _ if self.binary_file.is_some() => {
+                    let lines = self
+                        .binary_file
+                        .as_ref()
+                        .map(BinaryFile::lines)
+                        .unwrap_or_default();
+                    let diff = self
+                        .diff_panel
+                        .render(lines, diff_block.inner(chunks[1]))
+                        .block(diff_block);
+                    f.render_widget(diff, chunks[1]);
+                }
+                (DiffFormat::SideBySide, Ok(Some(diff_content))) => {
+                    self.diff_panel
+                        .render_side_by_side(f, diff_content, diff_block, chunks[1]);
+                }
+                _ => {
+                    let diff_content = match self.diff_output.as_ref() {
+                        Ok(Some(diff_content)) if self.diff_format == DiffFormat::CharLevel => {
+                            char_diff_text(diff_content)
+                        }
+                        Ok(Some(diff_content)) => Text::from(fit_diff_lines(
+                            diff_content,
+                            &self.diff_format,
+                            diff_block.inner(chunks[1]).width as usize,
+                            self.config.shorten_paths(),
+                        )?),
+                        Ok(None) => Text::default(),
+                        Err(err) => err.into_text("Error getting diff")?,
+                    };
+                    let diff = self
+                        .diff_panel
+                        .render(diff_content, diff_block.inner(chunks[1]))
+                        .block(diff_block);
+                    f.render_widget(diff, chunks[1]);
+                    self.diff_panel.render_h_scrollbar(f, chunks[1]);
+                }
+            }
         }
 
         Ok(())
@@ -284,9 +615,18 @@ impl Component for FilesTab {
                 return Ok(ComponentInputResult::Handled);
             }
 
+            self.notification = None;
+
             if self.diff_panel.input(key) {
+                self.focused_panel = FocusedPanel::Right;
                 return Ok(ComponentInputResult::Handled);
             }
+            if matches!(
+                key.code,
+                KeyCode::Char('j' | 'k' | 'J' | 'K') | KeyCode::Down | KeyCode::Up
+            ) {
+                self.focused_panel = FocusedPanel::Left;
+            }
 
             match key.code {
                 KeyCode::Char('j') | KeyCode::Down => self.scroll_files(commander, 1)?,
@@ -301,21 +641,92 @@ impl Component for FilesTab {
                     )?;
                 }
                 KeyCode::Char('w') => {
-                    self.diff_format = match self.diff_format {
-                        DiffFormat::ColorWords => DiffFormat::Git,
-                        _ => DiffFormat::ColorWords,
-                    };
-                    self.refresh_diff(commander)?;
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetDiffFormat(next_diff_format(
+                            &self.diff_format,
+                            &commander.available_diff_tools(),
+                        )),
+                    ));
                 }
                 KeyCode::Char('R') | KeyCode::F(5) => {
+                    // Alt+R refreshes even if nothing changed
+                    let inputs = self.refresh_inputs();
+                    if !key.modifiers.contains(KeyModifiers::ALT)
+                        && LastRefresh::is_up_to_date(
+                            self.last_refresh.as_ref(),
+                            commander,
+                            &inputs,
+                        )
+                    {
+                        self.notification = Some("Up to date".to_owned());
+                        return Ok(ComponentInputResult::Handled);
+                    }
+
                     self.head = commander.get_head_latest(&self.head)?;
+                    self.scroll_memory.clear();
                     self.refresh_files(commander)?;
                     self.refresh_diff(commander)?;
+                    self.last_refresh = match (&self.files_error, &self.diff_output) {
+                        (None, Ok(_)) => LastRefresh::capture(commander, &self.refresh_inputs()),
+                        _ => None,
+                    };
                 }
                 KeyCode::Char('@') => {
                     let head = &commander.get_current_head()?;
                     self.set_head(commander, head)?;
                 }
+                KeyCode::Char('p') => {
+                    self.pinned = !self.pinned;
+                }
+                KeyCode::Char('c') => {
+                    if let Some(file) = self.file.as_ref() {
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetPopup(Some(Box::new(FileComparePopup::new(
+                                file.clone(),
+                                self.file_compare_popup_tx.clone(),
+                            )))),
+                        ));
+                    }
+                }
+                // Back to the diff of the change
+                KeyCode::Esc if self.compare_revision.is_some() => {
+                    self.compare_revision = None;
+                    self.refresh_diff(commander)?;
+                }
+                KeyCode::Char('b') => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(Box::new(BookmarkSetPopup::new(
+                            self.config.clone(),
+                            commander,
+                            Some(self.head.change_id.clone()),
+                            self.head.commit_id.clone(),
+                            self.bookmark_set_popup_tx.clone(),
+                        )))),
+                    ));
+                }
+                KeyCode::Char('l') | KeyCode::Char('L') => {
+                    // Filter by the file, or by its directory
+                    let path = self.file.as_ref().and_then(|file| {
+                        if key.code == KeyCode::Char('l') {
+                            Some(file.as_str())
+                        } else {
+                            file.rsplit_once('/').map(|(directory, _)| directory)
+                        }
+                    });
+                    if let Some(path) = path {
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetLogPath(Some(path.to_owned())),
+                        ));
+                    }
+                }
+                KeyCode::Char('F') => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(Box::new(FixPopup::new(
+                            commander,
+                            self.head.commit_id.clone(),
+                        )))),
+                    ));
+                }
                 KeyCode::Char('h') | KeyCode::Char('?') => {
                     return Ok(ComponentInputResult::HandledAction(
                         ComponentAction::SetPopup(Some(Box::new(HelpPopup::new(
@@ -323,9 +734,19 @@ impl Component for FilesTab {
                                 ("j/k".to_owned(), "scroll down/up".to_owned()),
                                 ("J/K".to_owned(), "scroll down by ½ page".to_owned()),
                                 ("@".to_owned(), "view current change files".to_owned()),
+                                ("p".to_owned(), "pin/unpin change".to_owned()),
+                                ("b".to_owned(), "set bookmark".to_owned()),
+                                ("F".to_owned(), "fix change".to_owned()),
+                                ("l".to_owned(), "log changes touching file".to_owned()),
+                                ("L".to_owned(), "log changes touching directory".to_owned()),
+                                ("c".to_owned(), "compare file across revisions".to_owned()),
                             ],
                             vec![
                                 ("Ctrl+e/Ctrl+y".to_owned(), "scroll down/up".to_owned()),
+                                (
+                                    "Ctrl+Left/Ctrl+Right".to_owned(),
+                                    "scroll left/right".to_owned(),
+                                ),
                                 (
                                     "Ctrl+d/Ctrl+u".to_owned(),
                                     "scroll down/up by ½ page".to_owned(),
@@ -334,7 +755,10 @@ impl Component for FilesTab {
                                     "Ctrl+f/Ctrl+b".to_owned(),
                                     "scroll down/up by page".to_owned(),
                                 ),
-                                ("w".to_owned(), "toggle diff format".to_owned()),
+                                (
+                                    "w".to_owned(),
+                                    "cycle diff format (words/git/summary)".to_owned(),
+                                ),
                                 ("W".to_owned(), "toggle wrapping".to_owned()),
                             ],
                         )))),
@@ -347,3 +771,29 @@ impl Component for FilesTab {
         Ok(ComponentInputResult::Handled)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::ids::{ChangeId, CommitId};
+
+    fn head(id: &str) -> Head {
+        Head {
+            change_id: ChangeId(id.to_owned()),
+            commit_id: CommitId(id.to_owned()),
+            divergent: false,
+            immutable: false,
+            hidden: false,
+            conflict: false,
+            empty: false,
+        }
+    }
+
+    #[test]
+    fn follow_active_head() {
+        assert!(should_follow(false, &head("a"), &head("b")));
+        assert!(!should_follow(false, &head("a"), &head("a")));
+        assert!(!should_follow(true, &head("a"), &head("b")));
+        assert!(!should_follow(true, &head("a"), &head("a")));
+    }
+}
@@ -0,0 +1,194 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph},
+    Frame,
+};
+
+use crate::{
+    commander::{ids::CommitId, jj::FixTool, CommandError, Commander},
+    env::Config,
+    ui::{
+        styles::{border_set, create_popup_block},
+        utils::centered_rect,
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+/// Popup to run `jj fix` on a change, choosing which fix tools to run.
+pub struct FixPopup {
+    commit_id: CommitId,
+    include_unchanged: bool,
+    tools: Result<Vec<FixTool>, CommandError>,
+    // First item is the include unchanged files option, followed by tools
+    list_state: ListState,
+    error: Option<CommandError>,
+    config: Config,
+}
+
+fn checkbox(checked: bool) -> &'static str {
+    if checked {
+        "[x] "
+    } else {
+        "[ ] "
+    }
+}
+
+impl FixPopup {
+    pub fn new(commander: &mut Commander, commit_id: CommitId) -> Self {
+        Self {
+            commit_id,
+            include_unchanged: false,
+            tools: commander.get_fix_tools(),
+            list_state: ListState::default().with_selected(Some(0)),
+            error: None,
+            config: commander.env.config.clone(),
+        }
+    }
+
+    fn scroll(&mut self, scroll: isize) {
+        let tools_len = self.tools.as_ref().map_or(0, |tools| tools.len());
+        self.list_state.select(Some(
+            self.list_state
+                .selected()
+                .map(|selected| selected.saturating_add_signed(scroll))
+                .unwrap_or(0)
+                .min(tools_len),
+        ));
+    }
+
+    fn toggle(&mut self) {
+        match self.list_state.selected() {
+            Some(0) => self.include_unchanged = !self.include_unchanged,
+            Some(index) => {
+                if let Some(tool) = self
+                    .tools
+                    .as_mut()
+                    .ok()
+                    .and_then(|tools| tools.get_mut(index - 1))
+                {
+                    tool.enabled = !tool.enabled;
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+impl Component for FixPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let block = create_popup_block("Fix");
+        let area = centered_rect(area, 50, 50);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let error_height = if self.error.is_some() { 4 } else { 0 };
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Length(error_height),
+                Constraint::Length(2),
+            ])
+            .split(block.inner(area));
+
+        let mut items = vec![Line::from(vec![
+            Span::raw(checkbox(self.include_unchanged)),
+            Span::raw("Include unchanged files"),
+        ])
+        .fg(Color::Yellow)];
+        match self.tools.as_ref() {
+            Ok(tools) if tools.is_empty() => items.push(
+                Line::raw("    No fix tools configured in fix.tools")
+                    .fg(Color::DarkGray)
+                    .italic(),
+            ),
+            Ok(tools) => items.extend(tools.iter().map(|tool| {
+                Line::from(vec![
+                    Span::raw(checkbox(tool.enabled)),
+                    Span::raw(tool.name.clone()).fg(Color::Magenta),
+                ])
+            })),
+            Err(err) => items.extend(err.into_text("Error getting fix tools")?.lines),
+        }
+
+        let list = List::new(items)
+            .scroll_padding(3)
+            .highlight_style(Style::default().bg(self.config.highlight_color()));
+        f.render_stateful_widget(list, popup_chunks[0], &mut self.list_state);
+
+        if let Some(error) = self.error.as_ref() {
+            f.render_widget(
+                Paragraph::new(error.into_text("Error running fix")?),
+                popup_chunks[1],
+            );
+        }
+
+        let help = Paragraph::new(vec![
+            "j/k: scroll down/up | Space: toggle | Enter: run | Escape: cancel".into(),
+        ])
+        .fg(Color::DarkGray)
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_set(border_set())
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+        f.render_widget(help, popup_chunks[2]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => self.scroll(1),
+                KeyCode::Char('k') | KeyCode::Up => self.scroll(-1),
+                KeyCode::Char(' ') => self.toggle(),
+                KeyCode::Enter => {
+                    let enabled_tools: Vec<String> = self
+                        .tools
+                        .as_ref()
+                        .map(|tools| {
+                            tools
+                                .iter()
+                                .filter(|tool| tool.enabled)
+                                .map(|tool| tool.name.clone())
+                                .collect()
+                        })
+                        .unwrap_or_default();
+
+                    match commander.run_fix(&self.commit_id, self.include_unchanged, &enabled_tools)
+                    {
+                        Ok(()) => {
+                            return Ok(ComponentInputResult::HandledAction(
+                                ComponentAction::Multiple(vec![
+                                    ComponentAction::SetPopup(None),
+                                    ComponentAction::Refresh,
+                                ]),
+                            ))
+                        }
+                        Err(err) => self.error = Some(err),
+                    }
+                }
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                _ => return Ok(ComponentInputResult::NotHandled),
+            }
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+}
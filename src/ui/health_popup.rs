@@ -0,0 +1,125 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    commander::{CommandError, Commander},
+    health::HealthCheck,
+    ui::{
+        message_popup::MessagePopup,
+        styles::{border_set, create_popup_block},
+        utils::centered_rect,
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+/// Popup shown at startup when the jj config has known issues with lazyjj, with how to fix
+/// them.
+pub struct HealthPopup {
+    warnings: Vec<&'static HealthCheck>,
+    // Previously dismissed warnings, kept when dismissing more
+    dismissed: Vec<String>,
+}
+
+impl HealthPopup {
+    pub fn new(warnings: Vec<&'static HealthCheck>, dismissed: &[String]) -> Self {
+        Self {
+            warnings,
+            dismissed: dismissed.to_vec(),
+        }
+    }
+
+    // Store the warnings in `lazyjj.dismissed-warnings`, as a TOML array
+    fn dismiss(&self, commander: &mut Commander) -> Result<(), CommandError> {
+        let ids = self
+            .dismissed
+            .iter()
+            .map(String::as_str)
+            .chain(self.warnings.iter().map(|warning| warning.id))
+            .map(|id| format!("{id:?}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        commander.set_user_config("lazyjj.dismissed-warnings", &format!("[{ids}]"))
+    }
+}
+
+impl Component for HealthPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let block = create_popup_block("jj config warnings");
+        let area = centered_rect(area, 60, 50);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Fill(1), Constraint::Length(2)])
+            .split(block.inner(area));
+
+        let mut lines = vec![];
+        for warning in self.warnings.iter() {
+            if !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            lines.push(Line::raw(warning.message).fg(Color::Yellow));
+            lines.push(Line::from(vec![
+                Span::raw("Fix: "),
+                Span::raw(warning.fix).fg(Color::Cyan),
+            ]));
+        }
+        f.render_widget(
+            Paragraph::new(lines).wrap(Wrap { trim: false }),
+            popup_chunks[0],
+        );
+
+        let help = Paragraph::new(vec!["Enter/Escape: close | d: don't show again".into()])
+            .fg(Color::DarkGray)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .border_set(border_set())
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            );
+        f.render_widget(help, popup_chunks[1]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            match key.code {
+                KeyCode::Char('d') => {
+                    let popup: Option<Box<dyn Component>> = match self.dismiss(commander) {
+                        Ok(()) => None,
+                        Err(err) => Some(Box::new(MessagePopup {
+                            title: "Dismiss warnings error".into(),
+                            messages: err.into_text("")?,
+                        })),
+                    };
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(popup),
+                    ));
+                }
+                KeyCode::Enter | KeyCode::Char('q') | KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+}
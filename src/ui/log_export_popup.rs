@@ -0,0 +1,145 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use tui_textarea::TextArea;
+
+use crate::{
+    commander::Commander,
+    ui::{
+        message_popup::MessagePopup,
+        styles::{border_set, create_popup_block},
+        utils::centered_rect_line_height,
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+/// Popup to export the changes of the log as JSON, to a file or to the terminal with the TUI
+/// suspended, for scripts. Opened with `Ctrl+j` in the log tab.
+pub struct LogExportPopup<'a> {
+    textarea: TextArea<'a>,
+    revset: Option<String>,
+    path: Option<String>,
+}
+
+impl LogExportPopup<'_> {
+    pub fn new(revset: Option<String>, path: Option<String>) -> Self {
+        let mut textarea = TextArea::default();
+        textarea.set_placeholder_text("Empty to print to the terminal");
+
+        Self {
+            textarea,
+            revset,
+            path,
+        }
+    }
+
+    fn export(&self, commander: &mut Commander) -> Result<ComponentAction> {
+        let json = match commander.get_head_infos(&self.revset, &self.path) {
+            Ok(head_infos) => serde_json::to_string_pretty(&head_infos)?,
+            Err(err) => {
+                return Ok(ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                    title: "Export error".into(),
+                    messages: format!("{err:?}").into(),
+                }))));
+            }
+        };
+
+        let file = self.textarea.lines().join("").trim().to_owned();
+        if file.is_empty() {
+            return Ok(ComponentAction::Multiple(vec![
+                ComponentAction::SetPopup(None),
+                ComponentAction::Print(json),
+            ]));
+        }
+
+        let message = match std::fs::write(&file, json + "\n") {
+            Ok(()) => format!("Exported the log to {file}"),
+            Err(err) => format!("Could not write {file}: {err}"),
+        };
+        Ok(ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+            title: "Export log".into(),
+            messages: message.into(),
+        }))))
+    }
+}
+
+impl Component for LogExportPopup<'_> {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let block = create_popup_block("Export log as JSON");
+        let area = centered_rect_line_height(area, 50, 8);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Fill(1),
+                Constraint::Length(2),
+            ])
+            .split(block.inner(area));
+
+        self.textarea.set_block(
+            Block::bordered()
+                .title(" File ")
+                .border_set(border_set())
+                .border_style(Style::default().fg(Color::Green)),
+        );
+        f.render_widget(&self.textarea, popup_chunks[0]);
+
+        f.render_widget(
+            Paragraph::new(Line::raw(format!(
+                "Changes of {}",
+                self.revset.as_deref().unwrap_or("the default revset")
+            )))
+            .fg(Color::DarkGray),
+            popup_chunks[1],
+        );
+
+        let help = Paragraph::new(vec!["Enter/Ctrl+s: export | Escape: cancel".into()])
+            .fg(Color::DarkGray)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .border_set(border_set())
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            );
+        f.render_widget(help, popup_chunks[2]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            match key.code {
+                KeyCode::Enter => {}
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {}
+                KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                _ => {
+                    self.textarea.input(event);
+                    return Ok(ComponentInputResult::Handled);
+                }
+            }
+
+            return Ok(ComponentInputResult::HandledAction(self.export(commander)?));
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+}
@@ -1,59 +1,170 @@
-#![allow(clippy::borrow_interior_mutable_const)]
-
-use ansi_to_tui::IntoText;
 use anyhow::Result;
+use chrono::Utc;
 use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
 use ratatui::{prelude::*, widgets::*};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    time::{Duration, Instant},
+};
 use tracing::instrument;
-use tui_confirm_dialog::{ButtonLabel, ConfirmDialog, ConfirmDialogState, Listener};
 use tui_textarea::{CursorMove, TextArea};
 
 use crate::{
     commander::{
-        log::{Head, LogOutput},
+        bookmarks::Bookmark,
+        decorations::DecorationCache,
+        diff_cache::DiffCache,
+        ids::{ChangeId, CommitId},
+        jj::{parse_push_rejected, RebaseMode},
+        log::{parse_revset_error, Head, LogOutput, Relatives, RevsetError},
         CommandError, Commander,
     },
-    env::{Config, DiffFormat},
+    env::{Config, DiffFormat, PushStatus},
     ui::{
+        alias_popup::{AliasAction, AliasPopup},
+        bookmark_jump_popup::BookmarkJumpPopup,
         bookmark_set_popup::BookmarkSetPopup,
+        confirm_popup::{ConfirmAction, ConfirmPopup},
+        describe_stack_popup::DescribeStackPopup,
         details_panel::DetailsPanel,
+        diff_between_popup::DiffBetweenPopup,
+        exec_popup::ExecPopup,
+        fix_popup::FixPopup,
         help_popup::HelpPopup,
+        log_export_popup::LogExportPopup,
         message_popup::MessagePopup,
-        utils::{centered_rect, centered_rect_line_height, tabs_to_spaces},
-        Component, ComponentAction,
+        output_popup::OutputPopup,
+        push_check_popup::{PushCheckAction, PushCheckPopup},
+        push_rejected_popup::PushRejectedPopup,
+        relatives::{numbered_relatives, relatives_lines},
+        scroll_memory::ScrollMemory,
+        styles::{
+            border_set, check_mark, highlight_line, lock, remote_color, right_arrow, up_arrow,
+        },
+        utils::{
+            ansi_text, centered_rect, centered_rect_line_height,
+            char_diff::char_diff_text,
+            fit_diff_lines, format_relative_time,
+            graph::replace_graph_nodes,
+            keep_last_ok, next_diff_format, panel_constraints, panel_hint,
+            refresh::{LastRefresh, RefreshInputs},
+            revset_completions::{
+                complete, dropdown_rect, get_argument_hint, get_completions, get_word_before_cursor,
+            },
+            stale_banner, tabs_to_spaces, toggle_prefix,
+            web_view::expand_url_template,
+            DEFAULT_SPLIT_PERCENT,
+        },
+        web_view_popup::{open_web_view, WebViewPopup},
+        Component, ComponentAction, FocusedPanel,
     },
     ComponentInputResult,
 };
 
-const NEW_POPUP_ID: u16 = 1;
-const EDIT_POPUP_ID: u16 = 2;
-const ABANDON_POPUP_ID: u16 = 3;
+/// Action of a confirmation popup opened by the log tab, on the selected change.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LogConfirmAction {
+    // Describe the new change right away with `describe`
+    New { describe: bool },
+    Edit,
+    Abandon,
+    // Clear the revset and path filter to show @
+    ClearRevset,
+}
+
+// Changes whose descriptions are edited together with `S`
+const DESCRIBE_STACK_REVSET: &str = "trunk()..@";
+// Revset toggled with `M`, for reviewing merges
+const MERGES_REVSET: &str = "merges()";
+
+// Changes above and below the selected one whose details are prefetched
+const PREFETCH_DISTANCE: usize = 2;
 
 /// Log tab. Shows `jj log` in left panel and shows selected change details of in right panel.
 pub struct LogTab<'a> {
     log_output: Result<LogOutput, CommandError>,
+    // Error from the last refresh, while showing the last successful log
+    log_error: Option<CommandError>,
     log_output_text: Text<'a>,
+    log_stats: LogStats,
     log_list_state: ListState,
     log_height: u16,
 
     log_revset: Option<String>,
-    log_revset_textarea: Option<TextArea<'a>>,
+    // Show all commits with `all()`, instead of log_revset
+    show_all: bool,
+    // Show the log as a list, without the graph
+    no_graph: bool,
+    // Create new changes with `--no-edit`, keeping the working copy where it is
+    new_no_edit: bool,
+    // Folded merges, with the changes only reachable through their other parents
+    folded_merges: HashMap<ChangeId, HashSet<ChangeId>>,
+    log_revset_popup: Option<RevsetPopup<'a>>,
+    // Custom `jj log` template, set in the revset popup
+    log_template: Option<String>,
+    // Only show changes touching this path, set from the files tab
+    log_path: Option<String>,
 
     head_panel: DetailsPanel,
     head_output: Result<String, CommandError>,
     head: Head,
+    // Parents and children of the head, when `lazyjj.show-relatives` is set
+    relatives: Option<Relatives>,
+    // Last head reported with ChangeHead
+    reported_head: Option<Head>,
 
     diff_format: DiffFormat,
 
-    popup: ConfirmDialogState,
-    popup_tx: std::sync::mpsc::Sender<Listener>,
-    popup_rx: std::sync::mpsc::Receiver<Listener>,
-
     bookmark_set_popup_tx: std::sync::mpsc::Sender<bool>,
     bookmark_set_popup_rx: std::sync::mpsc::Receiver<bool>,
 
+    push_check_popup_tx: std::sync::mpsc::Sender<PushCheckAction>,
+    push_check_popup_rx: std::sync::mpsc::Receiver<PushCheckAction>,
+
+    bookmark_jump_popup_tx: std::sync::mpsc::Sender<Head>,
+    bookmark_jump_popup_rx: std::sync::mpsc::Receiver<Head>,
+
+    // Names given to changes for the session. Keyed by change ID, to survive rewrites
+    aliases: HashMap<ChangeId, String>,
+    alias_popup_tx: std::sync::mpsc::Sender<AliasAction>,
+    alias_popup_rx: std::sync::mpsc::Receiver<AliasAction>,
+    push_all_bookmarks: bool,
+
     describe_textarea: Option<TextArea<'a>>,
-    describe_after_new: bool,
+
+    rebase_selection: Option<RebaseSelection>,
+    change_search: Option<ChangeSearch>,
+
+    // Only set when `lazyjj.decoration-command` is configured
+    decorations: Option<DecorationCache>,
+    // Only set when `lazyjj.log-stat-badges` is enabled
+    stat_badges: Option<DecorationCache>,
+    // Details of changes shown, and of the changes around the selected one prefetched in the
+    // background. Cleared when the log refreshes, as bookmarks may have moved
+    diff_cache: DiffCache,
+    // Scroll of the details of changes left, to restore when coming back. Keyed by change ID, to
+    // survive rewrites such as snapshots of the working copy
+    scroll_memory: ScrollMemory<ChangeId>,
+    // Changes of the log which exist on a remote. Only set when `lazyjj.push-status` is enabled
+    // and there are remotes
+    pushed_heads: Option<HashSet<CommitId>>,
+    // Remote bookmarks of changes of the log. Only set when `lazyjj.show-all-remote-bookmarks` is
+    // enabled
+    remote_bookmarks: HashMap<CommitId, Vec<Bookmark>>,
+    // Changes of the log whose description starts with `lazyjj.wip-prefix`. Only set when
+    // `lazyjj.log-wip-chips` is enabled
+    wip_heads: HashSet<CommitId>,
+
+    // Shown below the log until the next key press
+    notification: Option<String>,
+    // Last successful refresh with `R`, to skip the next one if nothing changed
+    last_refresh: Option<LastRefresh>,
+
+    // Panel last interacted with, panel shown full screen, and width of the left panel in percent
+    focused_panel: FocusedPanel,
+    maximized_panel: Option<FocusedPanel>,
+    split_percent: u16,
 
     config: Config,
 }
@@ -74,79 +185,519 @@ fn get_head_index(head: &Head, log_output: &Result<LogOutput, CommandError>) ->
     }
 }
 
+// Get the index in conflict_positions of the next (or previous) conflict after the selected head,
+// wrapping around. Positions are indexes of conflicted heads in the log, in order
+fn get_next_conflict(
+    conflict_positions: &[usize],
+    current_head_index: Option<usize>,
+    forward: bool,
+) -> Option<usize> {
+    if conflict_positions.is_empty() {
+        return None;
+    }
+
+    let next = match (current_head_index, forward) {
+        (Some(current), true) => conflict_positions
+            .iter()
+            .position(|position| *position > current),
+        (Some(current), false) => conflict_positions
+            .iter()
+            .rposition(|position| *position < current),
+        (None, _) => None,
+    };
+
+    Some(next.unwrap_or(if forward {
+        0
+    } else {
+        conflict_positions.len() - 1
+    }))
+}
+
+// Index of the first change of the log whose change ID starts with a prefix, skipping folded
+// changes
+fn find_change_id_prefix(
+    heads: &[Head],
+    folded: &HashSet<ChangeId>,
+    prefix: &str,
+) -> Option<usize> {
+    heads.iter().position(|head| {
+        head.change_id.as_str().starts_with(prefix) && !folded.contains(&head.change_id)
+    })
+}
+
+// Counts of changes in the log, shown below it
+#[derive(Debug, Default, PartialEq)]
+struct LogStats {
+    total: usize,
+    immutable: usize,
+    empty: usize,
+    conflicted: usize,
+}
+
+impl LogStats {
+    fn new(log_output: &Result<LogOutput, CommandError>) -> Self {
+        let heads = match log_output {
+            Ok(log_output) => &log_output.heads,
+            Err(_) => return Self::default(),
+        };
+
+        Self {
+            total: heads.len(),
+            immutable: heads.iter().filter(|head| head.immutable).count(),
+            empty: heads.iter().filter(|head| head.empty).count(),
+            conflicted: heads.iter().filter(|head| head.conflict).count(),
+        }
+    }
+}
+
+// Row of the log: a line of the graph, or a placeholder for a number of folded changes
+#[derive(Debug, PartialEq)]
+enum LogRow {
+    Line(usize),
+    Folded(usize),
+}
+
+// Map graph lines to rows, replacing each run of lines of folded changes with a placeholder. Lines
+// without a change (such as graph edges) are folded if they are between folded changes
+fn fold_log_lines(
+    line_count: usize,
+    graph_heads: &[Option<Head>],
+    folded: &HashSet<ChangeId>,
+) -> Vec<LogRow> {
+    let is_folded = |head: &Head| folded.contains(&head.change_id);
+
+    let mut rows = vec![];
+    // Index of the current run's placeholder row, and the changes in the run
+    let mut run: Option<(usize, HashSet<&ChangeId>)> = None;
+    for i in 0..line_count {
+        let head = graph_heads.get(i).and_then(Option::as_ref);
+        let fold = match head {
+            Some(head) => is_folded(head),
+            None => {
+                run.is_some()
+                    && graph_heads
+                        .iter()
+                        .skip(i + 1)
+                        .flatten()
+                        .next()
+                        .is_some_and(is_folded)
+            }
+        };
+
+        if fold {
+            let (_, changes) = run.get_or_insert_with(|| {
+                rows.push(LogRow::Folded(0));
+                (rows.len() - 1, HashSet::new())
+            });
+            if let Some(head) = head {
+                changes.insert(&head.change_id);
+            }
+        } else {
+            if let Some((row, changes)) = run.take() {
+                rows[row] = LogRow::Folded(changes.len());
+            }
+            rows.push(LogRow::Line(i));
+        }
+    }
+    if let Some((row, changes)) = run {
+        rows[row] = LogRow::Folded(changes.len());
+    }
+
+    rows
+}
+
+// Rows the list may show once scrolled to the selection. The list keeps its offset if the
+// selection is visible, and otherwise scrolls just enough to show it, so rendering this range
+// covers both without knowing the scroll padding
+fn visible_log_rows(offset: usize, selected: Option<usize>, height: usize) -> Range<usize> {
+    let selected = selected.unwrap_or(offset);
+    offset.min((selected + 1).saturating_sub(height))..offset.max(selected) + height
+}
+
+/// Change being rebased while its destination is selected in the log, opened with `m`.
+struct RebaseSelection {
+    // Selected change when the selection started, restored when cancelled
+    source: Head,
+    mode: RebaseMode,
+}
+
+// Keys of actions which can't apply to an immutable change, hidden from the help when one is
+// selected with `lazyjj.hide-immutable-actions`
+const IMMUTABLE_ACTION_KEYS: [&str; 7] = ["d", "E", "Ctrl+w", "e", "a", "A", "Ctrl+a"];
+
+// Time without input after which the change ID search ends
+const CHANGE_SEARCH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Change ID prefix typed after `/`, selecting the first change of the log which starts with it.
+struct ChangeSearch {
+    prefix: String,
+    // Whether a change of the log starts with the prefix
+    found: bool,
+    last_input: Instant,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum RevsetPopupTab {
+    Revset,
+    Template,
+}
+
+// Text of a textarea, or None if empty
+fn get_textarea_value(textarea: &TextArea) -> Option<String> {
+    let value = textarea.lines().join("\n");
+    if value.trim().is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+fn new_textarea<'a>(value: &Option<String>) -> TextArea<'a> {
+    let mut textarea = TextArea::new(
+        value
+            .as_deref()
+            .unwrap_or("")
+            .lines()
+            .map(String::from)
+            .collect(),
+    );
+    textarea.move_cursor(CursorMove::Bottom);
+    textarea.move_cursor(CursorMove::End);
+    textarea
+}
+
+/// Popup to edit the log's revset, and its template in a second tab. Opened with `r`.
+struct RevsetPopup<'a> {
+    tab: RevsetPopupTab,
+    revset_textarea: TextArea<'a>,
+    template_textarea: TextArea<'a>,
+    // Template and revset the preview was rendered with, and the first changes rendered with it
+    preview: Option<(String, Option<String>, Result<String, CommandError>)>,
+    // Syntax error of the revset when saving, marked in the revset textarea until it's edited
+    revset_error: Option<RevsetError>,
+}
+
+impl RevsetPopup<'_> {
+    fn new(revset: &Option<String>, template: &Option<String>) -> Self {
+        Self {
+            tab: RevsetPopupTab::Revset,
+            revset_textarea: new_textarea(revset),
+            template_textarea: new_textarea(template),
+            preview: None,
+            revset_error: None,
+        }
+    }
+
+    fn get_revset(&self) -> Option<String> {
+        get_textarea_value(&self.revset_textarea)
+    }
+
+    fn get_template(&self) -> Option<String> {
+        get_textarea_value(&self.template_textarea)
+    }
+
+    // Check the revset's syntax before saving, to mark the error in the textarea
+    fn check_revset(&mut self, commander: &mut Commander) -> bool {
+        self.revset_error = match self.get_revset() {
+            Some(revset) => match commander.check_revset(&revset) {
+                Err(CommandError::Status(output, _, _)) => parse_revset_error(&output),
+                _ => None,
+            },
+            None => None,
+        };
+        self.revset_error.is_none()
+    }
+
+    // Render the first changes with the template, when the template or revset changed. Done when
+    // switching to the template tab or pressing F5, not while typing
+    fn refresh_preview(&mut self, commander: &mut Commander) {
+        if self.tab != RevsetPopupTab::Template {
+            return;
+        }
+
+        let revset = self.get_revset();
+        let Some(template) = self.get_template() else {
+            self.preview = None;
+            return;
+        };
+        if self
+            .preview
+            .as_ref()
+            .is_some_and(|(preview_template, preview_revset, _)| {
+                *preview_template == template && *preview_revset == revset
+            })
+        {
+            return;
+        }
+
+        let preview = commander.get_log_preview(&revset, &template);
+        self.preview = Some((template, revset, preview));
+    }
+}
+
 impl LogTab<'_> {
     #[instrument(level = "trace", skip(commander))]
-    pub fn new(commander: &mut Commander) -> Result<Self> {
+    /// Log tab, with a revision selected instead of the working copy if given, such as from
+    /// `--revision`. Falls back to the working copy with a notification if it isn't found.
+    pub fn new(commander: &mut Commander, revision: Option<&str>) -> Result<Self> {
         let diff_format = commander.env.config.diff_format();
 
         let log_revset = commander.env.default_revset.clone();
-        let log_output = commander.get_log(&log_revset);
-        let head = commander.get_current_head()?;
+        let log_output = commander.get_log(&log_revset, false, &None, &None);
+        let (head, notification) = match revision {
+            Some(revision) => match commander.get_head_by_revision(revision) {
+                Ok(head) => (head, None),
+                Err(_) => (
+                    commander.get_current_head()?,
+                    Some(format!("Revision {revision} not found, selected @ instead")),
+                ),
+            },
+            None => (commander.get_current_head()?, None),
+        };
 
         let log_list_state = ListState::default().with_selected(get_head_index(&head, &log_output));
 
         let head_output = commander
             .get_commit_show(&head.commit_id, &diff_format)
             .map(|text| tabs_to_spaces(&text));
+        let relatives = commander
+            .env
+            .config
+            .show_relatives()
+            .then(|| commander.get_relatives(&head.commit_id).ok())
+            .flatten();
 
-        let (popup_tx, popup_rx) = std::sync::mpsc::channel();
         let (bookmark_set_popup_tx, bookmark_set_popup_rx) = std::sync::mpsc::channel();
+        let (push_check_popup_tx, push_check_popup_rx) = std::sync::mpsc::channel();
+        let (bookmark_jump_popup_tx, bookmark_jump_popup_rx) = std::sync::mpsc::channel();
+        let (alias_popup_tx, alias_popup_rx) = std::sync::mpsc::channel();
 
-        Ok(Self {
-            log_output_text: match log_output.as_ref() {
-                Ok(log_output) => log_output
-                    .graph
-                    .into_text()
-                    .unwrap_or(Text::from("Could not turn text into TUI text (coloring)")),
-                Err(_) => Text::default(),
-            },
+        let mut log_tab = Self {
+            log_output_text: Text::default(),
+            log_stats: LogStats::new(&log_output),
             log_output,
+            log_error: None,
             log_list_state,
             log_height: 0,
 
             log_revset,
-            log_revset_textarea: None,
+            show_all: false,
+            no_graph: false,
+            new_no_edit: false,
+            folded_merges: HashMap::new(),
+            log_revset_popup: None,
+            log_template: None,
+            log_path: None,
 
             head,
+            relatives,
+            reported_head: None,
             head_panel: DetailsPanel::new(),
             head_output,
 
             diff_format,
 
-            popup: ConfirmDialogState::default(),
-            popup_tx,
-            popup_rx,
-
             bookmark_set_popup_tx,
             bookmark_set_popup_rx,
 
+            push_check_popup_tx,
+            push_check_popup_rx,
+
+            bookmark_jump_popup_tx,
+            bookmark_jump_popup_rx,
+            aliases: HashMap::new(),
+            alias_popup_tx,
+            alias_popup_rx,
+            push_all_bookmarks: false,
+
             describe_textarea: None,
-            describe_after_new: false,
+            rebase_selection: None,
+            change_search: None,
+
+            decorations: commander.env.config.decoration_command().map(|command| {
+                DecorationCache::from_command(
+                    command,
+                    commander.env.root.clone(),
+                    commander.env.config.decoration_ttl(),
+                )
+            }),
+            stat_badges: commander
+                .env
+                .config
+                .log_stat_badges()
+                .then(|| DecorationCache::stat_badges(&commander.env)),
+            diff_cache: DiffCache::from_env(&commander.env),
+            scroll_memory: ScrollMemory::new(),
+            pushed_heads: None,
+            remote_bookmarks: HashMap::new(),
+            wip_heads: HashSet::new(),
+
+            notification,
+            last_refresh: None,
+
+            focused_panel: FocusedPanel::Left,
+            maximized_panel: None,
+            split_percent: DEFAULT_SPLIT_PERCENT,
 
             config: commander.env.config.clone(),
-        })
+        };
+        log_tab.refresh_log_output_text();
+        log_tab.refresh_remotes(commander);
+        log_tab.refresh_wip_heads(commander);
+        commander.capture_operation();
+
+        Ok(log_tab)
     }
 
     fn get_current_head_index(&self) -> Option<usize> {
         get_head_index(&self.head, &self.log_output)
     }
 
+    fn get_revset(&self) -> Option<String> {
+        if self.show_all {
+            Some("all()".to_owned())
+        } else {
+            self.log_revset.clone()
+        }
+    }
+
+    fn refresh_inputs(&self) -> RefreshInputs {
+        RefreshInputs {
+            revset: self.get_revset(),
+            diff_format: self.diff_format.clone(),
+            show_all: self.show_all,
+            filters: vec![
+                self.log_path.clone(),
+                self.log_template.clone(),
+                self.no_graph.then(|| "no-graph".to_owned()),
+            ],
+        }
+    }
+
     fn refresh_log_output(&mut self, commander: &mut Commander) {
-        self.log_output = commander.get_log(&self.log_revset);
+        let log_output = commander.get_log(
+            &self.get_revset(),
+            self.no_graph,
+            &self.log_template,
+            &self.log_path,
+        );
+        self.log_error = keep_last_ok(&mut self.log_output, log_output);
+        self.refresh_log_output_text();
+        self.log_stats = LogStats::new(&self.log_output);
+        self.diff_cache.clear();
+        self.refresh_remotes(commander);
+        self.refresh_wip_heads(commander);
+        commander.capture_operation();
+    }
+
+    // Get which changes of the log are marked as WIP, if enabled. Once per refresh, skipped when
+    // `lazyjj.wip-prefix` is empty
+    fn refresh_wip_heads(&mut self, commander: &mut Commander) {
+        self.wip_heads = match self.log_output.as_ref() {
+            Ok(_) if !self.config.log_wip_chips() => HashSet::new(),
+            Ok(log_output) => commander
+                .get_wip_heads(&log_output.heads, self.config.wip_prefix())
+                .unwrap_or_default(),
+            Err(_) => HashSet::new(),
+        };
+    }
+
+    // Get which changes are pushed and their remote bookmarks, if enabled. Once per refresh, for
+    // the changes of the log only
+    fn refresh_remotes(&mut self, commander: &mut Commander) {
+        let Ok(log_output) = self.log_output.as_ref() else {
+            self.pushed_heads = None;
+            self.remote_bookmarks = HashMap::new();
+            return;
+        };
+
+        self.pushed_heads = match self.config.push_status() {
+            PushStatus::None => None,
+            _ => commander.get_pushed_heads(&log_output.heads).ok().flatten(),
+        };
+        self.remote_bookmarks = if self.config.show_all_remote_bookmarks() {
+            commander
+                .get_remote_bookmarks(&log_output.heads)
+                .unwrap_or_default()
+        } else {
+            HashMap::new()
+        };
+    }
+
+    // Changes of the log which don't exist on a remote, if known
+    fn get_unpushed_heads(&self) -> Vec<Head> {
+        match (self.pushed_heads.as_ref(), self.log_output.as_ref()) {
+            (Some(pushed_heads), Ok(log_output)) => log_output
+                .heads
+                .iter()
+                .filter(|head| !pushed_heads.contains(&head.commit_id))
+                .cloned()
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    // Render the log output, with the graph's nodes replaced if configured
+    fn refresh_log_output_text(&mut self) {
+        let graph_nodes = self.config.graph_nodes();
         self.log_output_text = match self.log_output.as_ref() {
-            Ok(log_output) => log_output
-                .graph
-                .into_text()
+            Ok(log_output) if !self.no_graph && !graph_nodes.is_empty() => ansi_text(
+                &replace_graph_nodes(&log_output.graph, &log_output.graph_heads, &graph_nodes),
+            )
+            .unwrap_or(Text::from("Could not turn text into TUI text (coloring)")),
+            Ok(log_output) => ansi_text(&log_output.graph)
                 .unwrap_or(Text::from("Could not turn text into TUI text (coloring)")),
             Err(_) => Text::default(),
         };
     }
 
     fn refresh_head_output(&mut self, commander: &mut Commander) {
-        self.head_output = commander
-            .get_commit_show(&self.head.commit_id, &self.diff_format)
-            .map(|text| tabs_to_spaces(&text));
-        self.head_panel.scroll = 0;
+        let commit_id = &self.head.commit_id;
+        let head_output = match self.diff_cache.get(commit_id, &self.diff_format) {
+            Some(head_output) => Ok(head_output),
+            None => commander
+                .get_commit_show(commit_id, &self.diff_format)
+                .inspect(|head_output| {
+                    self.diff_cache
+                        .insert(commit_id, &self.diff_format, head_output.clone())
+                }),
+        };
+        self.head_output = head_output.map(|text| tabs_to_spaces(&text));
+        self.relatives = if self.config.show_relatives() {
+            commander.get_relatives(commit_id).ok()
+        } else {
+            None
+        };
+        let lines = self
+            .head_output
+            .as_ref()
+            .map_or(0, |text| text.lines().count())
+            + self
+                .relatives
+                .as_ref()
+                .map_or(0, |relatives| relatives_lines(relatives).len());
+        self.head_panel.scroll = self.scroll_memory.restore(&self.head.change_id, lines);
+        self.head_panel.h_scroll = 0;
+        self.prefetch_adjacent_heads();
+    }
+
+    // Prefetch the details of the changes around the selected one, for scrolling through the log
+    fn prefetch_adjacent_heads(&mut self) {
+        let Some(index) = self.get_current_head_index() else {
+            return;
+        };
+        let Ok(log_output) = self.log_output.as_ref() else {
+            return;
+        };
+
+        let start = index.saturating_sub(PREFETCH_DISTANCE);
+        let end = (index + PREFETCH_DISTANCE + 1).min(log_output.heads.len());
+        self.diff_cache.prefetch(
+            (start..end)
+                .filter(|i| *i != index)
+                .map(|i| &log_output.heads[i].commit_id),
+            &self.diff_format,
+        );
     }
 
     fn scroll_log(&mut self, commander: &mut Commander, scroll: isize) {
@@ -157,24 +708,520 @@ impl LogTab<'_> {
 
         let heads: &Vec<Head> = log_output.heads.as_ref();
 
-        let current_head_index = self.get_current_head_index();
-        let next_head = match current_head_index {
-            Some(current_head_index) => heads.get(
-                current_head_index
-                    .saturating_add_signed(scroll)
-                    .min(heads.len() - 1),
-            ),
-            None => heads.first(),
+        if heads.is_empty() {
+            return;
+        }
+
+        let mut next_index = match self.get_current_head_index() {
+            Some(current_head_index) => current_head_index
+                .saturating_add_signed(scroll)
+                .min(heads.len() - 1),
+            None => 0,
         };
-        if let Some(next_head) = next_head {
-            self.set_head(commander, next_head.clone());
+
+        // Skip folded changes in the scroll direction
+        let folded = self.get_folded_changes();
+        while folded.contains(&heads[next_index].change_id) {
+            if scroll < 0 && next_index > 0 {
+                next_index -= 1;
+            } else if scroll >= 0 && next_index + 1 < heads.len() {
+                next_index += 1;
+            } else {
+                return;
+            }
         }
+
+        self.set_head(commander, heads[next_index].clone());
     }
 
     pub fn set_head(&mut self, commander: &mut Commander, head: Head) {
+        self.scroll_memory
+            .remember(self.head.change_id.clone(), self.head_panel.scroll);
         head.clone_into(&mut self.head);
         self.refresh_head_output(commander);
     }
+
+    /// Show a notification below the log, until the next key press.
+    pub fn set_notification(&mut self, notification: String) {
+        self.notification = Some(notification);
+    }
+
+    /// Select a head, adding it to the revset if it isn't in the log.
+    pub fn select_head(&mut self, commander: &mut Commander, head: Head) {
+        let in_log = self
+            .log_output
+            .as_ref()
+            .is_ok_and(|log_output| log_output.heads.contains(&head));
+        if !in_log {
+            let log_revset = match self.get_revset() {
+                Some(log_revset) => log_revset,
+                None => commander.get_default_log_revset(),
+            };
+            self.log_revset = Some(format!("({}) | {}", log_revset, head.commit_id));
+            self.show_all = false;
+            self.refresh_log_output(commander);
+        }
+
+        self.set_head(commander, head);
+    }
+
+    pub fn set_revset(&mut self, revset: Option<String>) {
+        self.log_revset = revset;
+        self.show_all = false;
+    }
+
+    pub fn set_path(&mut self, path: Option<String>) {
+        self.log_path = path;
+    }
+
+    // Changes hidden by folded merges
+    fn get_folded_changes(&self) -> HashSet<ChangeId> {
+        self.folded_merges.values().flatten().cloned().collect()
+    }
+
+    /// Fold (or unfold) the changes only reachable through the selected merge's other parents.
+    fn toggle_fold(&mut self, commander: &mut Commander) -> Result<()> {
+        if self.folded_merges.remove(&self.head.change_id).is_some() {
+            return Ok(());
+        }
+
+        let side_heads = commander.get_merge_side_heads(&self.head.commit_id)?;
+        if side_heads.is_empty() {
+            self.notification = Some("Only merges can be folded".to_owned());
+            return Ok(());
+        }
+
+        self.notification = Some(format!("Folded {} changes", side_heads.len()));
+        self.folded_merges.insert(
+            self.head.change_id.clone(),
+            side_heads.into_iter().map(|head| head.change_id).collect(),
+        );
+
+        Ok(())
+    }
+
+    /// Select the next (or previous) conflicted change in the log.
+    fn select_next_conflict(&mut self, commander: &mut Commander, forward: bool) -> Result<()> {
+        let conflicted_heads = commander.get_conflicted_heads(&self.get_revset())?;
+        let heads = match self.log_output.as_ref() {
+            Ok(log_output) => &log_output.heads,
+            Err(_) => return Ok(()),
+        };
+
+        let folded = self.get_folded_changes();
+        let mut conflict_positions: Vec<usize> = conflicted_heads
+            .iter()
+            .filter(|conflicted_head| !folded.contains(&conflicted_head.change_id))
+            .filter_map(|conflicted_head| {
+                heads
+                    .iter()
+                    .position(|head| head.commit_id == conflicted_head.commit_id)
+            })
+            .collect();
+        conflict_positions.sort();
+
+        match get_next_conflict(&conflict_positions, self.get_current_head_index(), forward) {
+            Some(next) => {
+                let head = heads[conflict_positions[next]].clone();
+                self.notification = Some(format!(
+                    "Conflict {} of {}",
+                    next + 1,
+                    conflict_positions.len()
+                ));
+                self.set_head(commander, head);
+            }
+            None => {
+                self.notification = Some("No conflicts in current revset".to_owned());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Refuse an action on the selected change because it is immutable, with a popup, or with a
+    /// notification if `lazyjj.hide-immutable-actions` is set.
+    fn refuse_immutable(&mut self, title: &str, message: &str) -> ComponentInputResult {
+        if self.config.hide_immutable_actions() {
+            self.notification = Some("Change is immutable".to_owned());
+            return ComponentInputResult::Handled;
+        }
+
+        ComponentInputResult::HandledAction(ComponentAction::SetPopup(Some(Box::new(
+            MessagePopup {
+                title: title.to_owned().into(),
+                messages: vec![message.to_owned().into()].into(),
+            },
+        ))))
+    }
+
+    /// Squash the working copy's changes into the selected change, keeping it selected. With
+    /// `describe`, the describe textarea is then opened with both changes' descriptions, to
+    /// edit the combined description.
+    fn amend_into_head(
+        &mut self,
+        commander: &mut Commander,
+        describe: bool,
+    ) -> Result<ComponentInputResult> {
+        let amend_error = |message: &str| {
+            ComponentInputResult::HandledAction(ComponentAction::SetPopup(Some(Box::new(
+                MessagePopup {
+                    title: "Amend".into(),
+                    messages: vec![message.to_owned().into()].into(),
+                },
+            ))))
+        };
+
+        let current_head = commander.get_current_head()?;
+        if current_head.change_id == self.head.change_id {
+            return Ok(amend_error(
+                "The working copy cannot be amended into itself.",
+            ));
+        }
+        if self.head.immutable {
+            return Ok(self.refuse_immutable(
+                "Amend",
+                "The working copy cannot be amended into this change because it is immutable.",
+            ));
+        }
+        let files = commander.get_files(&current_head)?.files;
+        if files.is_empty() {
+            return Ok(amend_error("The working copy has no changes to amend."));
+        }
+
+        let descriptions = if describe {
+            Some([
+                commander.get_commit_description(&self.head.commit_id)?,
+                commander.get_commit_description(&current_head.commit_id)?,
+            ])
+        } else {
+            None
+        };
+
+        if let Err(err) = commander.run_squash("@", &self.head.commit_id) {
+            return Ok(ComponentInputResult::HandledAction(
+                ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                    title: "Amend error".into(),
+                    messages: err.into_text("")?,
+                }))),
+            ));
+        }
+
+        self.head = commander.get_head_latest(&self.head)?;
+        self.refresh_log_output(commander);
+        self.refresh_head_output(commander);
+        self.notification = Some(format!(
+            "Amended {} file{} into {}",
+            files.len(),
+            if files.len() == 1 { "" } else { "s" },
+            self.head.change_id.as_str()
+        ));
+
+        if let Some(descriptions) = descriptions {
+            let description = descriptions
+                .iter()
+                .map(|description| description.trim())
+                .filter(|description| !description.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            let mut textarea = TextArea::new(description.lines().map(String::from).collect());
+            textarea.move_cursor(CursorMove::Bottom);
+            textarea.move_cursor(CursorMove::End);
+            self.describe_textarea = Some(textarea);
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+
+    /// Rebase the change of the rebase selection onto the selected change, then select the
+    /// rebased change.
+    fn rebase_onto_head(&mut self, commander: &mut Commander) -> Result<ComponentInputResult> {
+        let Some(rebase_selection) = self.rebase_selection.take() else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        let source = rebase_selection.source;
+
+        if source.change_id == self.head.change_id {
+            self.head = source;
+            self.refresh_head_output(commander);
+            return Ok(ComponentInputResult::HandledAction(
+                ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                    title: "Rebase".into(),
+                    messages: vec!["A change cannot be rebased onto itself.".into()].into(),
+                }))),
+            ));
+        }
+
+        let destination = self.head.clone();
+        if let Err(err) = commander.run_rebase(
+            rebase_selection.mode,
+            source.change_id.as_str(),
+            &destination.commit_id,
+        ) {
+            self.head = source;
+            self.refresh_head_output(commander);
+            return Ok(ComponentInputResult::HandledAction(
+                ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                    title: "Rebase error".into(),
+                    messages: err.into_text("")?,
+                }))),
+            ));
+        }
+
+        self.head = commander.get_head_latest(&source)?;
+        self.refresh_log_output(commander);
+        self.refresh_head_output(commander);
+        self.notification = Some(format!(
+            "Rebased {} onto {}",
+            source.change_id.as_str(),
+            destination.change_id.as_str()
+        ));
+
+        Ok(ComponentInputResult::Handled)
+    }
+
+    /// Whether decorations are being fetched in the background.
+    pub fn is_fetching_decorations(&self) -> bool {
+        [self.decorations.as_ref(), self.stat_badges.as_ref()]
+            .into_iter()
+            .flatten()
+            .any(|decorations| decorations.is_pending())
+    }
+
+    /// Whether a change ID search is shown, to redraw when it times out.
+    pub fn is_searching_change_id(&self) -> bool {
+        self.change_search.is_some()
+    }
+
+    // End the change ID search after a while without input
+    fn expire_change_search(&mut self) {
+        if self
+            .change_search
+            .as_ref()
+            .is_some_and(|search| search.last_input.elapsed() >= CHANGE_SEARCH_TIMEOUT)
+        {
+            self.change_search = None;
+        }
+    }
+
+    /// Select the first change of the log whose change ID starts with the searched prefix.
+    fn search_change_id(&mut self, commander: &mut Commander) {
+        let folded = self.get_folded_changes();
+        let (Some(search), Ok(log_output)) =
+            (self.change_search.as_mut(), self.log_output.as_ref())
+        else {
+            return;
+        };
+
+        search.last_input = Instant::now();
+        if search.prefix.is_empty() {
+            search.found = true;
+            return;
+        }
+        let index = find_change_id_prefix(&log_output.heads, &folded, &search.prefix);
+        search.found = index.is_some();
+        if let Some(index) = index {
+            let head = log_output.heads[index].clone();
+            self.set_head(commander, head);
+        }
+    }
+
+    pub fn set_diff_format(&mut self, commander: &mut Commander, diff_format: DiffFormat) {
+        self.diff_format = diff_format;
+        // Positions are of the previous format's lines
+        self.scroll_memory.clear();
+        self.refresh_head_output(commander);
+    }
+
+    fn open_describe(&mut self, commander: &mut Commander) -> Result<()> {
+        let mut textarea = TextArea::new(
+            commander
+                .get_commit_description(&self.head.commit_id)?
+                .split("\n")
+                .map(|line| line.to_string())
+                .collect(),
+        );
+        textarea.move_cursor(CursorMove::End);
+        self.describe_textarea = Some(textarea);
+        Ok(())
+    }
+
+    /// Run the action of a confirmation popup after it was accepted.
+    pub fn run_confirmed(
+        &mut self,
+        commander: &mut Commander,
+        action: LogConfirmAction,
+    ) -> Result<Option<ComponentAction>> {
+        match action {
+            LogConfirmAction::New { describe } => {
+                commander.run_new(self.head.commit_id.as_str(), self.new_no_edit)?;
+                // Select the new change, which isn't the working copy with `--no-edit`
+                self.head = if self.new_no_edit {
+                    commander.get_latest_child(&self.head.commit_id)?
+                } else {
+                    commander.get_current_head()?
+                };
+                self.refresh_log_output(commander);
+                self.refresh_head_output(commander);
+                if describe {
+                    let textarea = TextArea::default();
+                    self.describe_textarea = Some(textarea);
+                }
+                return Ok(Some(ComponentAction::ChangeHead(self.head.clone())));
+            }
+            LogConfirmAction::ClearRevset => {
+                self.set_revset(None);
+                self.log_path = None;
+                self.refresh_log_output(commander);
+                self.head = commander.get_current_head()?;
+                self.refresh_head_output(commander);
+            }
+            LogConfirmAction::Edit => {
+                commander.run_edit(self.head.commit_id.as_str())?;
+                self.refresh_log_output(commander);
+                self.refresh_head_output(commander);
+                return Ok(Some(ComponentAction::ChangeHead(self.head.clone())));
+            }
+            LogConfirmAction::Abandon => {
+                if self.head == commander.get_current_head()? {
+                    commander.run_abandon(&self.head.commit_id)?;
+                    self.refresh_log_output(commander);
+                    self.head = commander.get_current_head()?;
+                    self.refresh_head_output(commander);
+                    return Ok(Some(ComponentAction::ChangeHead(self.head.clone())));
+                } else {
+                    let head_parent = commander.get_commit_parent(&self.head.commit_id)?;
+                    commander.run_abandon(&self.head.commit_id)?;
+                    self.refresh_log_output(commander);
+                    self.head = head_parent;
+                    self.refresh_head_output(commander);
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Open the selected change in a browser, picking the URL template if several are configured
+    fn open_web_view(&self, commander: &mut Commander) -> Result<ComponentAction> {
+        let templates = self.config.web_view_url_templates();
+        if templates.is_empty() {
+            return Ok(ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                title: "Open in browser".into(),
+                messages: vec![
+                    "No URL templates configured. Set lazyjj.web-view-url-templates, such as:"
+                        .into(),
+                    r#"["https://github.com/owner/repo/commit/{commit_id}"]"#.into(),
+                ]
+                .into(),
+            }))));
+        }
+
+        let bookmark = if templates
+            .iter()
+            .any(|template| template.contains("{bookmark}"))
+        {
+            commander
+                .get_commit_bookmarks(&self.head.commit_id)?
+                .into_iter()
+                .find(|bookmark| bookmark.remote.is_none())
+                .map(|bookmark| bookmark.name)
+        } else {
+            None
+        };
+        let urls: Vec<String> = templates
+            .iter()
+            .filter_map(|template| {
+                expand_url_template(
+                    template,
+                    self.head.commit_id.as_str(),
+                    self.head.change_id.as_str(),
+                    bookmark.as_deref(),
+                )
+            })
+            .collect();
+
+        Ok(match urls.as_slice() {
+            [] => ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                title: "Open in browser".into(),
+                messages: vec!["The change has no bookmark for the URL templates.".into()].into(),
+            }))),
+            [url] => open_web_view(url),
+            _ => ComponentAction::SetPopup(Some(Box::new(WebViewPopup::new(
+                urls,
+                self.config.clone(),
+            )))),
+        })
+    }
+
+    fn git_push(
+        &mut self,
+        commander: &mut Commander,
+        all_bookmarks: bool,
+    ) -> Result<Option<ComponentAction>> {
+        let unpushed_heads = self.get_unpushed_heads();
+        let result = commander.git_push(all_bookmarks, &self.head.commit_id);
+        // Only after a successful push. A failed export doesn't fail the push, so is shown below it
+        let export_result = match result {
+            Ok(_) if self.config.auto_git_export() => Some(commander.git_export()),
+            _ => None,
+        };
+        self.refresh_log_output(commander);
+        self.refresh_head_output(commander);
+
+        // Changes of the log which were pushed, such as ancestors of the pushed bookmarks
+        let newly_pushed = match result {
+            Ok(_) if !unpushed_heads.is_empty() => commander
+                .get_pushed_heads(&unpushed_heads)
+                .ok()
+                .flatten()
+                .map_or(0, |pushed_heads| pushed_heads.len()),
+            _ => 0,
+        };
+
+        Ok(match result {
+            Ok(result)
+                if !result.is_empty()
+                    || newly_pushed > 0
+                    || export_result.as_ref().is_some_and(|export| {
+                        export.as_ref().map_or(true, |output| !output.is_empty())
+                    }) =>
+            {
+                let mut messages = ansi_text(&result)?;
+                if newly_pushed > 0 {
+                    messages.lines.push(Line::raw(format!(
+                        "{newly_pushed} change{} of the log now on the remote",
+                        if newly_pushed == 1 { "" } else { "s" }
+                    )));
+                }
+                match export_result {
+                    Some(Ok(export_output)) => messages.extend(ansi_text(&export_output)?),
+                    Some(Err(err)) => {
+                        messages.lines.push(Line::default());
+                        messages.extend(err.into_text("Pushed, but the Git export failed")?);
+                    }
+                    None => {}
+                }
+                Some(ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                    title: "Push message".into(),
+                    messages,
+                }))))
+            }
+            Err(err) => {
+                let rejected = match &err {
+                    CommandError::Status(output, _, _) => parse_push_rejected(output),
+                    _ => None,
+                };
+                Some(ComponentAction::SetPopup(Some(match rejected {
+                    Some(bookmarks) => Box::new(PushRejectedPopup::new(commander, bookmarks)),
+                    None => Box::new(MessagePopup {
+                        title: "Push error".into(),
+                        messages: err.into_text("")?,
+                    }),
+                })))
+            }
+            _ => None,
+        })
+    }
 }
 
 #[allow(clippy::invisible_characters)]
@@ -193,56 +1240,86 @@ impl Component for LogTab<'_> {
             self.refresh_head_output(commander);
         }
 
-        // Check for popup action
-        if let Ok(res) = self.popup_rx.try_recv() {
-            if res.1.unwrap_or(false) {
-                match res.0 {
-                    NEW_POPUP_ID => {
-                        commander.run_new(self.head.commit_id.as_str())?;
-                        self.head = commander.get_current_head()?;
-                        self.refresh_log_output(commander);
-                        self.refresh_head_output(commander);
-                        if self.describe_after_new {
-                            self.describe_after_new = false;
-                            let textarea = TextArea::default();
-                            self.describe_textarea = Some(textarea);
-                        }
-                        return Ok(Some(ComponentAction::ChangeHead(self.head.clone())));
-                    }
-                    EDIT_POPUP_ID => {
-                        commander.run_edit(self.head.commit_id.as_str())?;
-                        self.refresh_log_output(commander);
-                        self.refresh_head_output(commander);
-                        return Ok(Some(ComponentAction::ChangeHead(self.head.clone())));
-                    }
-                    ABANDON_POPUP_ID => {
-                        if self.head == commander.get_current_head()? {
-                            commander.run_abandon(&self.head.commit_id)?;
-                            self.refresh_log_output(commander);
-                            self.head = commander.get_current_head()?;
-                            self.refresh_head_output(commander);
-                            return Ok(Some(ComponentAction::ChangeHead(self.head.clone())));
-                        } else {
-                            let head_parent = commander.get_commit_parent(&self.head.commit_id)?;
-                            commander.run_abandon(&self.head.commit_id)?;
-                            self.refresh_log_output(commander);
-                            self.head = head_parent;
-                            self.refresh_head_output(commander);
-                        }
+        self.poll_background();
+
+        if let Ok(true) = self.bookmark_set_popup_rx.try_recv() {
+            self.refresh_log_output(commander);
+            self.refresh_head_output(commander)
+        }
+
+        if let Ok(head) = self.bookmark_jump_popup_rx.try_recv() {
+            self.select_head(commander, head);
+        }
+
+        if let Ok(action) = self.alias_popup_rx.try_recv() {
+            match action {
+                AliasAction::Set(change_id, alias) => {
+                    // An alias names a single change
+                    self.aliases.retain(|_, existing| *existing != alias);
+                    self.aliases.insert(change_id, alias);
+                }
+                AliasAction::Remove(change_id) => {
+                    self.aliases.remove(&change_id);
+                }
+                AliasAction::Jump(change_id) => match commander.get_change_head(&change_id) {
+                    Ok(head) => self.select_head(commander, head),
+                    Err(_) => {
+                        self.notification =
+                            Some(format!("Change {} no longer exists", change_id.as_str()));
                     }
-                    _ => {}
+                },
+            }
+        }
+
+        if let Ok(action) = self.push_check_popup_rx.try_recv() {
+            match action {
+                PushCheckAction::Describe(head) => {
+                    self.set_head(commander, head);
+                    self.open_describe(commander)?;
+                }
+                PushCheckAction::Push => {
+                    return self.git_push(commander, self.push_all_bookmarks);
                 }
             }
         }
 
-        if let Ok(true) = self.bookmark_set_popup_rx.try_recv() {
-            self.refresh_log_output(commander);
-            self.refresh_head_output(commander)
+        // Report selected head changes, so the files tab can follow it
+        if self.reported_head.as_ref() != Some(&self.head) {
+            self.reported_head = Some(self.head.clone());
+            return Ok(Some(ComponentAction::ChangeHead(self.head.clone())));
         }
 
         Ok(None)
     }
 
+    fn poll_background(&mut self) -> Option<ComponentAction> {
+        for decorations in [self.decorations.as_mut(), self.stat_badges.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            decorations.poll();
+        }
+        self.diff_cache.poll();
+        None
+    }
+
+    fn focused_panel(&self) -> Option<FocusedPanel> {
+        Some(self.focused_panel)
+    }
+
+    fn set_maximized_panel(&mut self, maximized_panel: Option<FocusedPanel>) {
+        self.maximized_panel = maximized_panel;
+    }
+
+    fn set_split_percent(&mut self, split_percent: u16) {
+        self.split_percent = split_percent;
+    }
+
+    fn reset_size(&mut self) {
+        self.log_height = 0;
+        self.head_panel.reset_size();
+    }
+
     fn draw(
         &mut self,
         f: &mut ratatui::prelude::Frame<'_>,
@@ -250,20 +1327,60 @@ impl Component for LogTab<'_> {
     ) -> Result<()> {
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .constraints(panel_constraints(self.maximized_panel, self.split_percent))
             .split(area);
 
         // Draw log
         {
             let mut scroll_offset = 0;
+            let mut log_rows = vec![];
             let log_lines = match self.log_output.as_ref() {
                 Ok(log_output) => {
-                    let log_lines: Vec<Line> = self
-                        .log_output_text
+                    log_rows = fold_log_lines(
+                        self.log_output_text.lines.len(),
+                        &log_output.graph_heads,
+                        &self.get_folded_changes(),
+                    );
+                    self.log_list_state.select(log_rows.iter().position(|row| {
+                        matches!(row, LogRow::Line(i) if log_output
+                            .graph_heads
+                            .get(*i)
+                            .unwrap_or(&None)
+                            .as_ref()
+                            .is_some_and(|line_change| line_change == &self.head))
+                    }));
+
+                    // Only style rows which may be visible, keeping the others as placeholders
+                    // so the list length stays the same. The panel height bounds the list height
+                    let visible_rows = visible_log_rows(
+                        self.log_list_state.offset(),
+                        self.log_list_state.selected(),
+                        chunks[0].height as usize,
+                    );
+
+                    let log_lines: Vec<Line> = log_rows
                         .iter()
                         .enumerate()
-                        .map(|(i, line)| {
-                            let mut line = line.to_owned();
+                        .map(|(row_index, row)| {
+                            if !visible_rows.contains(&row_index) {
+                                return Line::default();
+                            }
+
+                            let i = match row {
+                                LogRow::Line(i) => *i,
+                                LogRow::Folded(count) => {
+                                    // The graph is pre-rendered by jj, so edges across the fold
+                                    // can't be redrawn
+                                    return Line::raw(format!(
+                                        "   … {count} change{} folded, graph edges may be \
+                                        inaccurate …",
+                                        if *count == 1 { "" } else { "s" }
+                                    ))
+                                    .fg(Color::DarkGray)
+                                    .italic();
+                                }
+                            };
+                            let mut line = self.log_output_text.lines[i].to_owned();
 
                             // Add padding at start
                             line.spans.insert(0, Span::from(" "));
@@ -272,17 +1389,109 @@ impl Component for LogTab<'_> {
 
                             match line_head {
                                 Some(line_change) => {
-                                    if line_change == &self.head {
-                                        line = line.bg(self.config.highlight_color());
-
+                                    // Abandoned commits, only shown when referenced directly
+                                    if line_change.hidden {
                                         line.spans = line
                                             .spans
                                             .iter_mut()
-                                            .map(|span| {
-                                                span.to_owned().bg(self.config.highlight_color())
-                                            })
+                                            .map(|span| span.to_owned().dim().crossed_out())
                                             .collect();
                                     }
+
+                                    // Changes use 2 lines, only decorate the first
+                                    let first_line = i == 0
+                                        || log_output.graph_heads.get(i - 1).unwrap_or(&None)
+                                            != line_head;
+
+                                    // In the padding, to keep the graph aligned
+                                    if first_line && line_change.immutable {
+                                        line.spans[0] = Span::raw(lock()).dim();
+                                    }
+
+                                    if first_line && line_change.conflict {
+                                        line.spans.push(Span::raw(" !").fg(Color::Red).bold());
+                                    }
+
+                                    if let Some(pushed_heads) =
+                                        self.pushed_heads.as_ref().filter(|_| first_line)
+                                    {
+                                        let marker = match (
+                                            self.config.push_status(),
+                                            pushed_heads.contains(&line_change.commit_id),
+                                        ) {
+                                            (PushStatus::Unpushed, false) => Some(up_arrow()),
+                                            (PushStatus::Pushed, true) => Some(check_mark()),
+                                            _ => None,
+                                        };
+                                        if let Some(marker) = marker {
+                                            line.spans.push(Span::raw(format!(" {marker}")).dim());
+                                        }
+                                    }
+
+                                    for bookmark in self
+                                        .remote_bookmarks
+                                        .get(&line_change.commit_id)
+                                        .filter(|_| first_line)
+                                        .into_iter()
+                                        .flatten()
+                                    {
+                                        let remote = bookmark.remote.as_deref().unwrap_or("");
+                                        line.spans.push(
+                                            Span::raw(format!(" [{bookmark}]"))
+                                                .fg(remote_color(remote)),
+                                        );
+                                    }
+
+                                    if first_line && self.wip_heads.contains(&line_change.commit_id)
+                                    {
+                                        line.spans.push(Span::raw(" "));
+                                        line.spans.push(Span::raw(" WIP ").black().on_yellow());
+                                    }
+
+                                    if let Some(alias) = self
+                                        .aliases
+                                        .get(&line_change.change_id)
+                                        .filter(|_| first_line)
+                                    {
+                                        line.spans.push(Span::raw(" "));
+                                        line.spans.push(
+                                            Span::raw(format!(" {alias} ")).black().on_yellow(),
+                                        );
+                                    }
+
+                                    for decoration in
+                                        [self.stat_badges.as_ref(), self.decorations.as_ref()]
+                                            .into_iter()
+                                            .flatten()
+                                            .filter(|_| first_line)
+                                            .filter_map(|decorations| {
+                                                decorations.get(&line_change.commit_id)
+                                            })
+                                    {
+                                        let mut span = Span::raw(format!(" {}", decoration.text));
+                                        if let Some(color) = decoration.color {
+                                            span = span.fg(color);
+                                        }
+                                        line.spans.push(span);
+                                    }
+
+                                    if line_change == &self.head {
+                                        // Show live relative committer time, since jj's timestamps
+                                        // are a snapshot from when the log was loaded
+                                        if let Some(Some(timestamp)) =
+                                            log_output.graph_timestamps.get(i)
+                                        {
+                                            line.spans.push(
+                                                Span::raw(format!(
+                                                    " ({})",
+                                                    format_relative_time(*timestamp, Utc::now())
+                                                ))
+                                                .fg(Color::DarkGray),
+                                            );
+                                        }
+
+                                        line = highlight_line(line, &self.config);
+                                    }
                                 }
                                 _ => scroll_offset += 1,
                             };
@@ -291,64 +1500,179 @@ impl Component for LogTab<'_> {
                         })
                         .collect();
 
-                    self.log_list_state
-                        .select(log_lines.iter().enumerate().position(|(i, _)| {
-                            log_output
-                                .graph_heads
-                                .get(i)
-                                .unwrap_or(&None)
-                                .clone()
-                                .map_or(false, |line_change| line_change == self.head)
-                        }));
-
                     log_lines
                 }
                 Err(err) => err.into_text("Error getting log")?.lines,
             };
 
-            let title = match &self.log_revset {
-                Some(log_revset) => &format!(" Log for: {} ", log_revset),
-                None => " Log ",
+            let title = match &self.get_revset() {
+                Some(log_revset) => format!(" Log for: {} ", log_revset),
+                None => " Log ".to_owned(),
+            };
+            let title = if self.log_revset.as_deref() == Some(MERGES_REVSET) && !self.show_all {
+                format!("{title}[merges only] ")
+            } else {
+                title
+            };
+            let title = if self.no_graph {
+                format!("{title}[no graph] ")
+            } else {
+                title
+            };
+            let title = if self.new_no_edit {
+                format!("{title}[no-edit] ")
+            } else {
+                title
+            };
+            let title = match self.log_path.as_ref() {
+                Some(log_path) => format!("{title}[touching: {log_path}, Backspace to clear] "),
+                None => title,
+            };
+
+            let title = match self.rebase_selection.as_ref() {
+                Some(rebase_selection) => format!(
+                    " Select destination for rebase {} of {} (Enter to confirm, Esc to cancel, r/s/b to change mode) ",
+                    rebase_selection.mode.flag(),
+                    rebase_selection.source.change_id.as_str()
+                ),
+                None => title,
             };
 
-            let log_block = Block::bordered()
-                .title(title)
-                .border_type(BorderType::Rounded);
-            self.log_height = log_block.inner(chunks[0]).height;
-            let log = List::new(log_lines).block(log_block).scroll_padding(7);
-            f.render_stateful_widget(log, chunks[0], &mut self.log_list_state);
+            let mut log_block = Block::bordered().title(title).border_set(border_set());
+            if self.rebase_selection.is_some() {
+                log_block = log_block.border_style(Style::new().fg(Color::Magenta));
+            }
+            self.expire_change_search();
+            if let Some(search) = self.change_search.as_ref() {
+                log_block = log_block.title_bottom(
+                    Line::raw(format!(" search: {} ", search.prefix)).fg(if search.found {
+                        Color::Cyan
+                    } else {
+                        Color::Red
+                    }),
+                );
+            }
+            if let Some(notification) = self.notification.as_ref() {
+                log_block = log_block.title_bottom(
+                    Line::raw(format!(" {notification} "))
+                        .fg(Color::Yellow)
+                        .right_aligned(),
+                );
+            } else if self.config.panel_hints() && self.rebase_selection.is_none() {
+                let change_id = self.head.change_id.as_str();
+                if let Some(hint) = panel_hint(
+                    &format!(
+                        "Enter: view files of {}",
+                        &change_id[..change_id.len().min(8)]
+                    ),
+                    chunks[0].width,
+                ) {
+                    log_block = log_block.title_bottom(hint);
+                }
+            }
+            let log_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(if self.log_error.is_some() { 1 } else { 0 }),
+                    Constraint::Fill(1),
+                    Constraint::Length(1),
+                ])
+                .split(log_block.inner(chunks[0]));
+            f.render_widget(log_block, chunks[0]);
+
+            if let Some(log_error) = self.log_error.as_ref() {
+                f.render_widget(
+                    stale_banner(log_error, "Press r to edit the revset"),
+                    log_chunks[0],
+                );
+            }
+
+            self.log_height = log_chunks[1].height;
+            let log = List::new(log_lines).scroll_padding(7);
+            f.render_stateful_widget(log, log_chunks[1], &mut self.log_list_state);
+
+            let stats = Line::from(vec![
+                Span::raw(format!(
+                    " {} changes · {} immutable · {} empty · ",
+                    self.log_stats.total, self.log_stats.immutable, self.log_stats.empty
+                )),
+                Span::raw(format!("{} conflicted", self.log_stats.conflicted)).fg(
+                    if self.log_stats.conflicted > 0 {
+                        Color::Red
+                    } else {
+                        Color::DarkGray
+                    },
+                ),
+            ])
+            .fg(Color::DarkGray);
+            f.render_widget(stats, log_chunks[2]);
+
+            // Fetch decorations for visible changes
+            if let Ok(log_output) = self.log_output.as_ref() {
+                for decorations in [self.decorations.as_mut(), self.stat_badges.as_mut()]
+                    .into_iter()
+                    .flatten()
+                {
+                    decorations.request(
+                        log_rows
+                            .iter()
+                            .skip(self.log_list_state.offset())
+                            .take(self.log_height as usize)
+                            .filter_map(|row| match row {
+                                LogRow::Line(i) => log_output.graph_heads.get(*i)?.as_ref(),
+                                LogRow::Folded(_) => None,
+                            })
+                            .map(|head| &head.commit_id),
+                    );
+                }
+            }
         }
 
         // Draw change details
         {
-            let head_content = match self.head_output.as_ref() {
-                Ok(head_output) => head_output.into_text()?.lines,
-                Err(err) => err.into_text("Error getting head details")?.lines,
+            let alias = match self.aliases.get(&self.head.change_id) {
+                Some(alias) => format!(" [{alias}]"),
+                None => String::new(),
             };
             let head_block = Block::bordered()
-                .title(format!(" Details for {} ", self.head.change_id))
-                .border_type(BorderType::Rounded)
+                .title(if self.log_error.is_some() {
+                    format!(" Details for {}{alias} (stale) ", self.head.change_id)
+                } else {
+                    format!(" Details for {}{alias} ", self.head.change_id)
+                })
+                .border_set(border_set())
                 .padding(Padding::horizontal(1));
-            let head = self
-                .head_panel
-                .render(head_content, head_block.inner(chunks[1]))
-                .block(head_block);
-
-            f.render_widget(head, chunks[1]);
-        }
-
-        // Draw popup
-        if self.popup.is_opened() {
-            let popup = ConfirmDialog::default()
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(Color::Green))
-                .selected_button_style(
-                    Style::default()
-                        .bg(self.config.highlight_color())
-                        .underlined(),
-                );
-            f.render_stateful_widget(popup, area, &mut self.popup);
+            match (&self.diff_format, self.head_output.as_ref()) {
+                (DiffFormat::SideBySide, Ok(head_output)) => {
+                    self.head_panel
+                        .render_side_by_side(f, head_output, head_block, chunks[1]);
+                }
+                _ => {
+                    let head_content = match self.head_output.as_ref() {
+                        Ok(head_output) if self.diff_format == DiffFormat::CharLevel => {
+                            char_diff_text(head_output).lines
+                        }
+                        Ok(head_output) => fit_diff_lines(
+                            head_output,
+                            &self.diff_format,
+                            head_block.inner(chunks[1]).width as usize,
+                            self.config.shorten_paths(),
+                        )?,
+                        Err(err) => err.into_text("Error getting head details")?.lines,
+                    };
+                    let head_content = match self.relatives.as_ref() {
+                        Some(relatives) => [relatives_lines(relatives), head_content].concat(),
+                        None => head_content,
+                    };
+                    let head = self
+                        .head_panel
+                        .render(head_content, head_block.inner(chunks[1]))
+                        .block(head_block);
+
+                    f.render_widget(head, chunks[1]);
+                    self.head_panel.render_h_scrollbar(f, chunks[1]);
+                }
+            }
         }
 
         // Draw describe textarea
@@ -357,7 +1681,7 @@ impl Component for LogTab<'_> {
                 let block = Block::bordered()
                     .title(Span::styled(" Describe ", Style::new().bold().cyan()))
                     .title_alignment(Alignment::Center)
-                    .border_type(BorderType::Rounded)
+                    .border_set(border_set())
                     .border_style(Style::default().fg(Color::Green));
                 let area = centered_rect(area, 50, 50);
                 f.render_widget(Clear, area);
@@ -376,7 +1700,7 @@ impl Component for LogTab<'_> {
                     .block(
                         Block::default()
                             .borders(Borders::TOP)
-                            .border_type(BorderType::Rounded)
+                            .border_set(border_set())
                             .border_style(Style::default().fg(Color::DarkGray)),
                     );
 
@@ -384,36 +1708,187 @@ impl Component for LogTab<'_> {
             }
         }
 
-        // Draw revset textarea
+        // Draw revset popup
         {
-            if let Some(log_revset_textarea) = self.log_revset_textarea.as_mut() {
+            if let Some(revset_popup) = self.log_revset_popup.as_mut() {
+                let tab_title = |tab: RevsetPopupTab, title: &'static str| {
+                    if revset_popup.tab == tab {
+                        Span::styled(title, Style::new().bold().cyan())
+                    } else {
+                        Span::styled(title, Style::new().fg(Color::DarkGray))
+                    }
+                };
                 let block = Block::bordered()
-                    .title(Span::styled(" Revset ", Style::new().bold().cyan()))
+                    .title(Line::from(vec![
+                        tab_title(RevsetPopupTab::Revset, " Revset "),
+                        Span::raw("|"),
+                        tab_title(RevsetPopupTab::Template, " Template "),
+                    ]))
                     .title_alignment(Alignment::Center)
-                    .border_type(BorderType::Rounded)
+                    .border_set(border_set())
                     .border_style(Style::default().fg(Color::Green));
-                let area = centered_rect_line_height(area, 30, 7);
+                // Error message and hint below the revset
+                let error_lines: Vec<Line> = match revset_popup.revset_error.as_ref() {
+                    Some(revset_error) => [
+                        Some(
+                            Line::raw(format!(
+                                "{}:{}: {}",
+                                revset_error.line, revset_error.column, revset_error.message
+                            ))
+                            .fg(Color::Red),
+                        ),
+                        revset_error
+                            .hint
+                            .as_ref()
+                            .map(|hint| Line::raw(format!("Hint: {hint}")).fg(Color::DarkGray)),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+                    None => vec![],
+                };
+                let area = match revset_popup.tab {
+                    RevsetPopupTab::Revset => {
+                        centered_rect_line_height(area, 30, 7 + error_lines.len() as u16)
+                    }
+                    RevsetPopupTab::Template => centered_rect_line_height(area, 50, 18),
+                };
                 f.render_widget(Clear, area);
                 f.render_widget(&block, area);
 
                 let popup_chunks = Layout::default()
                     .direction(Direction::Vertical)
-                    .constraints([Constraint::Fill(1), Constraint::Length(2)])
+                    .constraints(match revset_popup.tab {
+                        RevsetPopupTab::Revset => [
+                            Constraint::Fill(1),
+                            Constraint::Length(error_lines.len() as u16),
+                            Constraint::Length(2),
+                        ],
+                        RevsetPopupTab::Template => [
+                            Constraint::Fill(1),
+                            Constraint::Length(9),
+                            Constraint::Length(2),
+                        ],
+                    })
                     .split(block.inner(area));
 
-                f.render_widget(&*log_revset_textarea, popup_chunks[0]);
+                match revset_popup.tab {
+                    RevsetPopupTab::Revset => {
+                        let log_revset_textarea = &revset_popup.revset_textarea;
+                        f.render_widget(log_revset_textarea, popup_chunks[0]);
+
+                        // Underline the character at the error. The textarea only scrolls for
+                        // long revsets, so the position may be hidden
+                        if let Some(revset_error) = revset_popup.revset_error.as_ref() {
+                            let x =
+                                popup_chunks[0].x + (revset_error.column as u16).saturating_sub(1);
+                            let y =
+                                popup_chunks[0].y + (revset_error.line as u16).saturating_sub(1);
+                            if popup_chunks[0].contains(Position { x, y }) {
+                                if let Some(cell) = f.buffer_mut().cell_mut((x, y)) {
+                                    cell.set_style(
+                                        Style::new()
+                                            .fg(Color::Red)
+                                            .underlined()
+                                            .underline_color(Color::Red),
+                                    );
+                                }
+                            }
+                            f.render_widget(
+                                Paragraph::new(error_lines).wrap(Wrap { trim: false }),
+                                popup_chunks[1],
+                            );
+                        }
 
-                let help = Paragraph::new(vec!["Ctrl+s: save | Escape: cancel".into()])
-                    .fg(Color::DarkGray)
-                    .alignment(Alignment::Center)
-                    .block(
-                        Block::default()
-                            .borders(Borders::TOP)
-                            .border_type(BorderType::Rounded)
-                            .border_style(Style::default().fg(Color::DarkGray)),
-                    );
+                        // Draw completions or argument hint below the cursor
+                        let (row, col) = log_revset_textarea.cursor();
+                        let line = &log_revset_textarea.lines()[row];
+                        let word = get_word_before_cursor(line, col);
+                        let completions = get_completions(word);
+                        let items: Vec<Line> = if !completions.is_empty() {
+                            completions
+                                .iter()
+                                .map(|(name, args)| {
+                                    Line::from(vec![
+                                        Span::raw(*name).fg(Color::Magenta),
+                                        Span::raw(format!("({args})")).fg(Color::DarkGray),
+                                    ])
+                                })
+                                .collect()
+                        } else if let Some((name, args)) = get_argument_hint(line, col) {
+                            vec![Line::from(vec![
+                                Span::raw(format!("{name}(")).fg(Color::DarkGray),
+                                Span::raw(args).fg(Color::Yellow),
+                                Span::raw(")").fg(Color::DarkGray),
+                            ])]
+                        } else {
+                            vec![]
+                        };
+                        if !items.is_empty() {
+                            let width = items.iter().map(Line::width).max().unwrap_or(0) as u16 + 2;
+                            let height = items.len().min(6) as u16 + 2;
+                            let cursor_y = (popup_chunks[0].y + row as u16)
+                                .min(popup_chunks[0].bottom().saturating_sub(1));
+                            let dropdown_area = dropdown_rect(
+                                popup_chunks[0].x + col as u16,
+                                cursor_y,
+                                width,
+                                height,
+                                f.area(),
+                            );
+                            f.render_widget(Clear, dropdown_area);
+                            f.render_widget(
+                                List::new(items).block(
+                                    Block::bordered()
+                                        .border_set(border_set())
+                                        .border_style(Style::default().fg(Color::DarkGray)),
+                                ),
+                                dropdown_area,
+                            );
+                        }
+                    }
+                    RevsetPopupTab::Template => {
+                        f.render_widget(&revset_popup.template_textarea, popup_chunks[0]);
+
+                        let preview = match revset_popup.preview.as_ref() {
+                            Some((_, _, Ok(preview))) => ansi_text(preview)?,
+                            Some((_, _, Err(err))) => err.into_text("Error rendering template")?,
+                            None => Text::from("Empty to use the default template")
+                                .fg(Color::DarkGray)
+                                .italic(),
+                        };
+                        f.render_widget(
+                            Paragraph::new(preview).block(
+                                Block::default()
+                                    .borders(Borders::TOP)
+                                    .border_set(border_set())
+                                    .border_style(Style::default().fg(Color::DarkGray))
+                                    .title(" Preview "),
+                            ),
+                            popup_chunks[1],
+                        );
+                    }
+                }
 
-                f.render_widget(help, popup_chunks[1]);
+                let help = Paragraph::new(vec![match revset_popup.tab {
+                    RevsetPopupTab::Revset => {
+                        "Ctrl+s: save | Tab: complete | Shift+Tab: template | F3: debug | Escape: cancel"
+                    }
+                    RevsetPopupTab::Template => {
+                        "Ctrl+s: save | F5: preview | Shift+Tab: revset | Escape: cancel"
+                    }
+                }
+                .into()])
+                .fg(Color::DarkGray)
+                .alignment(Alignment::Center)
+                .block(
+                    Block::default()
+                        .borders(Borders::TOP)
+                        .border_set(border_set())
+                        .border_style(Style::default().fg(Color::DarkGray)),
+                );
+
+                f.render_widget(help, popup_chunks[2]);
             }
         }
 
@@ -447,28 +1922,82 @@ impl Component for LogTab<'_> {
             return Ok(ComponentInputResult::Handled);
         }
 
-        if let Some(log_revset_textarea) = self.log_revset_textarea.as_mut() {
+        if let Some(revset_popup) = self.log_revset_popup.as_mut() {
             if let Event::Key(key) = event {
                 match key.code {
                     KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        let log_revset = log_revset_textarea.lines().join("\n");
-                        self.log_revset = if log_revset.trim().is_empty() {
-                            None
-                        } else {
-                            Some(log_revset)
-                        };
+                        if !revset_popup.check_revset(commander) {
+                            revset_popup.tab = RevsetPopupTab::Revset;
+                            return Ok(ComponentInputResult::Handled);
+                        }
+                        self.log_revset = revset_popup.get_revset();
+                        self.log_template = revset_popup.get_template();
+                        self.show_all = false;
                         self.refresh_log_output(commander);
-                        self.log_revset_textarea = None;
+                        self.log_revset_popup = None;
                         return Ok(ComponentInputResult::Handled);
                     }
                     KeyCode::Esc => {
-                        self.log_revset_textarea = None;
+                        self.log_revset_popup = None;
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    KeyCode::F(3) => {
+                        let revset = revset_popup
+                            .get_revset()
+                            .unwrap_or_else(|| commander.get_default_log_revset());
+                        let popup: Box<dyn Component> = match commander.debug_revset(&revset) {
+                            Ok(output) => Box::new(OutputPopup::new(
+                                format!("Debug revset {revset}"),
+                                &output,
+                            )),
+                            Err(err) => Box::new(MessagePopup {
+                                title: "Debug revset".into(),
+                                messages: err.into_text("Error debugging revset")?,
+                            }),
+                        };
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetPopup(Some(popup)),
+                        ));
+                    }
+                    KeyCode::BackTab => {
+                        revset_popup.tab = match revset_popup.tab {
+                            RevsetPopupTab::Revset => RevsetPopupTab::Template,
+                            RevsetPopupTab::Template => RevsetPopupTab::Revset,
+                        };
+                        revset_popup.refresh_preview(commander);
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    // Render the preview on demand, as running jj on every key press stalls typing
+                    // on large repositories
+                    KeyCode::F(5) => {
+                        revset_popup.refresh_preview(commander);
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    KeyCode::Tab => {
+                        if revset_popup.tab == RevsetPopupTab::Revset {
+                            let log_revset_textarea = &mut revset_popup.revset_textarea;
+                            let (row, col) = log_revset_textarea.cursor();
+                            let word =
+                                get_word_before_cursor(&log_revset_textarea.lines()[row], col);
+                            if let Some(completion) = complete(word) {
+                                log_revset_textarea.insert_str(completion);
+                            }
+                        }
                         return Ok(ComponentInputResult::Handled);
                     }
                     _ => {}
                 }
             }
-            log_revset_textarea.input(event);
+            match revset_popup.tab {
+                RevsetPopupTab::Revset => {
+                    if revset_popup.revset_textarea.input(event) {
+                        revset_popup.revset_error = None;
+                    }
+                }
+                RevsetPopupTab::Template => {
+                    revset_popup.template_textarea.input(event);
+                }
+            }
             return Ok(ComponentInputResult::Handled);
         }
 
@@ -477,21 +2006,95 @@ impl Component for LogTab<'_> {
                 return Ok(ComponentInputResult::Handled);
             }
 
-            if self.popup.is_opened() {
-                if key.code == KeyCode::Char('q') || key.code == KeyCode::Esc {
-                    self.popup = ConfirmDialogState::default();
-                } else {
-                    self.popup.handle(key);
+            self.notification = None;
+
+            // While searching a change ID, change ID letters extend the prefix. Other keys end the
+            // search, and are handled as usual
+            self.expire_change_search();
+            if let Some(search) = self.change_search.as_mut() {
+                match key.code {
+                    KeyCode::Char(c @ 'k'..='z') if key.modifiers.is_empty() => {
+                        search.prefix.push(c);
+                        self.search_change_id(commander);
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    KeyCode::Backspace => {
+                        search.prefix.pop();
+                        self.search_change_id(commander);
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    KeyCode::Enter | KeyCode::Esc => {
+                        self.change_search = None;
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    _ => self.change_search = None,
                 }
+            }
 
-                return Ok(ComponentInputResult::Handled);
+            // While selecting a rebase destination, only keys moving the selection are handled
+            if let Some(rebase_selection) = self.rebase_selection.as_mut() {
+                match key.code {
+                    KeyCode::Enter => return self.rebase_onto_head(commander),
+                    KeyCode::Esc => {
+                        if let Some(rebase_selection) = self.rebase_selection.take() {
+                            self.head = rebase_selection.source;
+                            self.refresh_head_output(commander);
+                        }
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    KeyCode::Char('r' | 's' | 'b') => {
+                        rebase_selection.mode = match key.code {
+                            KeyCode::Char('s') => RebaseMode::Source,
+                            KeyCode::Char('b') => RebaseMode::Branch,
+                            _ => RebaseMode::Revision,
+                        };
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    KeyCode::Char('j' | 'k' | 'J' | 'K' | '@') | KeyCode::Down | KeyCode::Up => {}
+                    _ => return Ok(ComponentInputResult::Handled),
+                }
             }
 
+            if self.focused_panel == FocusedPanel::Right {
+                // Select a numbered relative
+                if let KeyCode::Char(c @ '1'..='9') = key.code {
+                    let relative = self.relatives.as_ref().and_then(|relatives| {
+                        numbered_relatives(relatives)
+                            .get(c as usize - '1' as usize)
+                            .map(|relative| relative.head.clone())
+                    });
+                    if let Some(head) = relative {
+                        self.select_head(commander, head);
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                }
+            }
             if self.head_panel.input(key) {
+                self.focused_panel = FocusedPanel::Right;
                 return Ok(ComponentInputResult::Handled);
             }
+            if matches!(
+                key.code,
+                KeyCode::Char('j' | 'k' | 'J' | 'K') | KeyCode::Down | KeyCode::Up
+            ) {
+                self.focused_panel = FocusedPanel::Left;
+            }
 
             match key.code {
+                // Before `j`, which would scroll
+                KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        self.open_web_view(commander)?,
+                    ));
+                }
+                KeyCode::Char('j') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(Box::new(LogExportPopup::new(
+                            self.get_revset(),
+                            self.log_path.clone(),
+                        )))),
+                    ));
+                }
                 KeyCode::Char('j') | KeyCode::Down => {
                     self.scroll_log(commander, 1);
                 }
@@ -508,125 +2111,184 @@ impl Component for LogTab<'_> {
                     );
                 }
                 KeyCode::Char('@') => {
-                    self.head = commander.get_current_head()?;
+                    let head = commander.get_current_head()?;
+                    let in_log = self
+                        .log_output
+                        .as_ref()
+                        .is_ok_and(|log_output| log_output.heads.contains(&head));
+                    // Clearing the revset and path filter can't help if @ isn't in the default log
+                    let filtered =
+                        self.log_revset.is_some() || self.show_all || self.log_path.is_some();
+                    if in_log || !filtered {
+                        self.head = head;
+                        self.refresh_head_output(commander);
+                    } else {
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetPopup(Some(Box::new(ConfirmPopup::new(
+                                self.config.clone(),
+                                "Current change",
+                                vec!["@ is not in the current revset. Clear revset?".to_owned()],
+                                ConfirmAction::Log(LogConfirmAction::ClearRevset),
+                            )))),
+                        ));
+                    }
+                }
+                // Before `w`, which would cycle the diff format
+                KeyCode::Char('w')
+                    if key.modifiers.contains(KeyModifiers::CONTROL)
+                        && !self.config.wip_prefix().is_empty() =>
+                {
+                    if self.head.immutable {
+                        return Ok(self.refuse_immutable(
+                            "Toggle WIP",
+                            "The change cannot be described because it is immutable.",
+                        ));
+                    }
+
+                    let description = commander.get_commit_description(&self.head.commit_id)?;
+                    commander.run_describe(
+                        self.head.commit_id.as_str(),
+                        &toggle_prefix(&description, self.config.wip_prefix()),
+                    )?;
+                    self.refresh_log_output(commander);
                     self.refresh_head_output(commander);
                 }
                 KeyCode::Char('w') => {
-                    self.diff_format = match self.diff_format {
-                        DiffFormat::ColorWords => DiffFormat::Git,
-                        _ => DiffFormat::ColorWords,
-                    };
-                    self.refresh_head_output(commander);
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetDiffFormat(next_diff_format(
+                            &self.diff_format,
+                            &commander.available_diff_tools(),
+                        )),
+                    ));
                 }
                 KeyCode::Char('R') | KeyCode::F(5) => {
+                    // Decorations come from outside the repository
+                    for decorations in [self.decorations.as_mut(), self.stat_badges.as_mut()]
+                        .into_iter()
+                        .flatten()
+                    {
+                        decorations.expire();
+                    }
+
+                    // Alt+R refreshes even if nothing changed
+                    let inputs = self.refresh_inputs();
+                    if !key.modifiers.contains(KeyModifiers::ALT)
+                        && LastRefresh::is_up_to_date(
+                            self.last_refresh.as_ref(),
+                            commander,
+                            &inputs,
+                        )
+                    {
+                        self.notification = Some("Up to date".to_owned());
+                        return Ok(ComponentInputResult::Handled);
+                    }
+
+                    self.scroll_memory.clear();
                     self.refresh_log_output(commander);
                     self.refresh_head_output(commander);
+                    self.last_refresh = match (&self.log_error, &self.head_output) {
+                        (None, Ok(_)) => LastRefresh::capture(commander, &inputs),
+                        _ => None,
+                    };
                 }
-                KeyCode::Char('n') | KeyCode::Char('N') => {
-                    self.popup = ConfirmDialogState::new(
-                        NEW_POPUP_ID,
-                        Span::styled(" New ", Style::new().bold().cyan()),
-                        Text::from(vec![
-                            Line::from("Are you sure you want to create a new change?"),
-                            Line::from(format!("New parent: {}", self.head.change_id.as_str())),
-                        ]),
-                    )
-                    .with_yes_button(ButtonLabel::YES.clone())
-                    .with_no_button(ButtonLabel::NO.clone())
-                    .with_listener(Some(self.popup_tx.clone()))
-                    .open();
-
-                    self.describe_after_new = key.code == KeyCode::Char('N');
+                KeyCode::Char('n') | KeyCode::Char('N')
+                    if !key.modifiers.contains(KeyModifiers::CONTROL) =>
+                {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(Box::new(ConfirmPopup::new(
+                            self.config.clone(),
+                            "New",
+                            vec![
+                                "Are you sure you want to create a new change?".to_owned(),
+                                format!("New parent: {}", self.head.change_id.as_str()),
+                            ],
+                            ConfirmAction::Log(LogConfirmAction::New {
+                                describe: key.code == KeyCode::Char('N'),
+                            }),
+                        )))),
+                    ));
                 }
                 KeyCode::Char('e') => {
                     if self.head.immutable {
-                        return Ok(ComponentInputResult::HandledAction(
-                            ComponentAction::SetPopup(Some(Box::new(MessagePopup {
-                                title: "Edit".into(),
-                                messages: vec![
-                                    "The change cannot be edited because it is immutable.".into(),
-                                ]
-                                .into(),
-                            }))),
+                        return Ok(self.refuse_immutable(
+                            "Edit",
+                            "The change cannot be edited because it is immutable.",
                         ));
                     } else {
-                        self.popup = ConfirmDialogState::new(
-                            EDIT_POPUP_ID,
-                            Span::styled(" Edit ", Style::new().bold().cyan()),
-                            Text::from(vec![
-                                Line::from("Are you sure you want to edit an existing change?"),
-                                Line::from(format!("Change: {}", self.head.change_id.as_str())),
-                            ]),
-                        )
-                        .with_yes_button(ButtonLabel::YES.clone())
-                        .with_no_button(ButtonLabel::NO.clone())
-                        .with_listener(Some(self.popup_tx.clone()))
-                        .open();
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetPopup(Some(Box::new(ConfirmPopup::new(
+                                self.config.clone(),
+                                "Edit",
+                                vec![
+                                    "Are you sure you want to edit an existing change?".to_owned(),
+                                    format!("Change: {}", self.head.change_id.as_str()),
+                                ],
+                                ConfirmAction::Log(LogConfirmAction::Edit),
+                            )))),
+                        ));
                     }
                 }
+                KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return self.amend_into_head(commander, true);
+                }
                 KeyCode::Char('a') => {
                     if self.head.immutable {
-                        return Ok(ComponentInputResult::HandledAction(
-                            ComponentAction::SetPopup(Some(Box::new(MessagePopup {
-                                title: "Abandon".into(),
-                                messages: vec![
-                                    "The change cannot be abandoned because it is immutable."
-                                        .into(),
-                                ]
-                                .into(),
-                            }))),
+                        return Ok(self.refuse_immutable(
+                            "Abandon",
+                            "The change cannot be abandoned because it is immutable.",
                         ));
                     } else {
-                        self.popup = ConfirmDialogState::new(
-                            ABANDON_POPUP_ID,
-                            Span::styled(" Abandon ", Style::new().bold().cyan()),
-                            Text::from(vec![
-                                Line::from("Are you sure you want to abandon this change?"),
-                                Line::from(format!("Change: {}", self.head.change_id.as_str())),
-                            ]),
-                        )
-                        .with_yes_button(ButtonLabel::YES.clone())
-                        .with_no_button(ButtonLabel::NO.clone())
-                        .with_listener(Some(self.popup_tx.clone()))
-                        .open();
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetPopup(Some(Box::new(ConfirmPopup::new(
+                                self.config.clone(),
+                                "Abandon",
+                                vec![
+                                    "Are you sure you want to abandon this change?".to_owned(),
+                                    format!("Change: {}", self.head.change_id.as_str()),
+                                ],
+                                ConfirmAction::Log(LogConfirmAction::Abandon),
+                            )))),
+                        ));
                     }
                 }
-                KeyCode::Char('d') => {
+                KeyCode::Char('d') | KeyCode::Char('E') => {
                     if self.head.immutable {
+                        return Ok(self.refuse_immutable(
+                            "Describe",
+                            "The change cannot be described because it is immutable.",
+                        ));
+                    } else if key.code == KeyCode::Char('E') || self.config.use_external_editor() {
+                        // jj opens the editor from `JJ_EDITOR`, `VISUAL`, `EDITOR` or `ui.editor`,
+                        // with the changed files listed in comments
                         return Ok(ComponentInputResult::HandledAction(
-                            ComponentAction::SetPopup(Some(Box::new(MessagePopup {
-                                title: "Describe".into(),
-                                messages: vec![
-                                    "The change cannot be described because it is immutable."
-                                        .into(),
-                                ]
-                                .into(),
-                            }))),
+                            ComponentAction::RunExternal(vec![
+                                "describe".to_owned(),
+                                self.head.commit_id.as_string(),
+                            ]),
                         ));
                     } else {
-                        let mut textarea = TextArea::new(
-                            commander
-                                .get_commit_description(&self.head.commit_id)?
-                                .split("\n")
-                                .map(|line| line.to_string())
-                                .collect(),
-                        );
-                        textarea.move_cursor(CursorMove::End);
-                        self.describe_textarea = Some(textarea);
+                        self.open_describe(commander)?;
                         return Ok(ComponentInputResult::Handled);
                     }
                 }
+                KeyCode::Char('S') => {
+                    let descriptions = commander.get_descriptions(DESCRIBE_STACK_REVSET)?;
+                    let popup: Box<dyn Component> = if descriptions.is_empty() {
+                        Box::new(MessagePopup {
+                            title: "Describe stack".into(),
+                            messages: vec![format!("No changes in {DESCRIBE_STACK_REVSET}").into()]
+                                .into(),
+                        })
+                    } else {
+                        Box::new(DescribeStackPopup::new(descriptions))
+                    };
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(popup)),
+                    ));
+                }
                 KeyCode::Char('r') => {
-                    let mut textarea = TextArea::new(
-                        self.log_revset
-                            .as_ref()
-                            .unwrap_or(&"".to_owned())
-                            .lines()
-                            .map(String::from)
-                            .collect(),
-                    );
-                    textarea.move_cursor(CursorMove::End);
-                    self.log_revset_textarea = Some(textarea);
+                    self.log_revset_popup =
+                        Some(RevsetPopup::new(&self.log_revset, &self.log_template));
                     return Ok(ComponentInputResult::Handled);
                 }
                 KeyCode::Char('b') => {
@@ -640,34 +2302,166 @@ impl Component for LogTab<'_> {
                         )))),
                     ));
                 }
+                KeyCode::Char('H') => {
+                    self.show_all = !self.show_all;
+                    self.refresh_log_output(commander);
+                }
+                KeyCode::Char('M') => {
+                    self.log_revset = if self.log_revset.as_deref() == Some(MERGES_REVSET) {
+                        None
+                    } else {
+                        Some(MERGES_REVSET.to_owned())
+                    };
+                    self.show_all = false;
+                    self.refresh_log_output(commander);
+                }
+                KeyCode::Char('z') if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.toggle_fold(commander)?;
+                }
+                KeyCode::Char('/') => {
+                    self.change_search = Some(ChangeSearch {
+                        prefix: String::new(),
+                        found: true,
+                        last_input: Instant::now(),
+                    });
+                }
+                KeyCode::Backspace if self.log_path.is_some() => {
+                    self.log_path = None;
+                    self.refresh_log_output(commander);
+                }
+                KeyCode::Char('G') => {
+                    self.no_graph = !self.no_graph;
+                    self.refresh_log_output(commander);
+                }
+                KeyCode::Char('O') => {
+                    self.new_no_edit = !self.new_no_edit;
+                }
+                KeyCode::Char('c') => self.select_next_conflict(commander, true)?,
+                KeyCode::Char('C') => self.select_next_conflict(commander, false)?,
+                KeyCode::Char('g') => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(Box::new(BookmarkJumpPopup::new(
+                            commander,
+                            self.config.clone(),
+                            self.bookmark_jump_popup_tx.clone(),
+                        )))),
+                    ));
+                }
+                KeyCode::Char('L') => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(Box::new(AliasPopup::new(
+                            &self.aliases,
+                            self.head.change_id.clone(),
+                            self.config.clone(),
+                            self.alias_popup_tx.clone(),
+                        )))),
+                    ));
+                }
+                KeyCode::Char('X') => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(Box::new(ExecPopup::new(
+                            self.head.commit_id.as_str(),
+                        )))),
+                    ));
+                }
+                KeyCode::Char('A') => return self.amend_into_head(commander, false),
+                KeyCode::Char('m') => {
+                    self.rebase_selection = Some(RebaseSelection {
+                        source: self.head.clone(),
+                        mode: RebaseMode::Revision,
+                    });
+                }
+                KeyCode::Char('x') => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(Box::new(FixPopup::new(
+                            commander,
+                            self.head.commit_id.clone(),
+                        )))),
+                    ));
+                }
+                KeyCode::Char('D') => {
+                    let bookmarks = commander.get_commit_bookmarks(&self.head.commit_id)?;
+                    let remote_bookmark = bookmarks
+                        .iter()
+                        .filter(|bookmark| bookmark.remote.is_none())
+                        .find_map(|local_bookmark| {
+                            bookmarks.iter().find(|bookmark| {
+                                bookmark.name == local_bookmark.name
+                                    && bookmark.present
+                                    && bookmark
+                                        .remote
+                                        .as_ref()
+                                        .is_some_and(|remote| remote != "git")
+                            })
+                        });
+
+                    let popup: Box<dyn Component> = match remote_bookmark {
+                        Some(remote_bookmark) => {
+                            let remote_head = commander.get_bookmark_remote_head(
+                                &remote_bookmark.name,
+                                remote_bookmark.remote.as_deref().unwrap_or_default(),
+                            )?;
+                            let diff_output = commander
+                                .get_diff_between(
+                                    &remote_head.commit_id,
+                                    &self.head.commit_id,
+                                    &self.diff_format,
+                                )
+                                .map(|diff| tabs_to_spaces(&diff));
+                            Box::new(DiffBetweenPopup::new(
+                                format!(
+                                    "Diff {} {} {}",
+                                    remote_bookmark,
+                                    right_arrow(),
+                                    remote_bookmark.name
+                                ),
+                                diff_output,
+                                self.diff_format.clone(),
+                            ))
+                        }
+                        None => Box::new(MessagePopup {
+                            title: "Diff with remote".into(),
+                            messages: vec!["The change has no bookmark with a remote.".into()]
+                                .into(),
+                        }),
+                    };
+
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(popup)),
+                    ));
+                }
                 KeyCode::Enter => {
                     return Ok(ComponentInputResult::HandledAction(
                         ComponentAction::ViewFiles(self.head.clone()),
                     ));
                 }
                 KeyCode::Char('p') | KeyCode::Char('P') => {
-                    match commander.git_push(key.code == KeyCode::Char('P'), &self.head.commit_id) {
-                        Ok(result) if !result.is_empty() => {
-                            return Ok(ComponentInputResult::HandledAction(
-                                ComponentAction::SetPopup(Some(Box::new(MessagePopup {
-                                    title: "Push message".into(),
-                                    messages: result.into_text()?,
-                                }))),
-                            ));
-                        }
-                        Err(err) => {
+                    let all_bookmarks = key.code == KeyCode::Char('P');
+
+                    if self.config.push_empty_description_check() {
+                        // Don't block pushing if the check itself fails
+                        let heads = commander
+                            .get_push_empty_description_heads(
+                                all_bookmarks,
+                                &self.head.commit_id,
+                                self.config.wip_prefix(),
+                            )
+                            .unwrap_or_default();
+                        if !heads.is_empty() {
+                            self.push_all_bookmarks = all_bookmarks;
                             return Ok(ComponentInputResult::HandledAction(
-                                ComponentAction::SetPopup(Some(Box::new(MessagePopup {
-                                    title: "Push error".into(),
-                                    messages: err.into_text("")?,
-                                }))),
+                                ComponentAction::SetPopup(Some(Box::new(PushCheckPopup::new(
+                                    self.config.clone(),
+                                    heads,
+                                    self.push_check_popup_tx.clone(),
+                                )))),
                             ));
                         }
-                        _ => (),
                     }
 
-                    self.refresh_log_output(commander);
-                    self.refresh_head_output(commander);
+                    if let Some(component_action) = self.git_push(commander, all_bookmarks)? {
+                        return Ok(ComponentInputResult::HandledAction(component_action));
+                    }
                 }
                 KeyCode::Char('f') | KeyCode::Char('F') => {
                     match commander.git_fetch(key.code == KeyCode::Char('F')) {
@@ -675,7 +2469,7 @@ impl Component for LogTab<'_> {
                             return Ok(ComponentInputResult::HandledAction(
                                 ComponentAction::SetPopup(Some(Box::new(MessagePopup {
                                     title: "Fetch message".into(),
-                                    messages: result.into_text()?,
+                                    messages: ansi_text(&result)?,
                                 }))),
                             ));
                         }
@@ -694,40 +2488,82 @@ impl Component for LogTab<'_> {
                     self.refresh_head_output(commander);
                 }
                 KeyCode::Char('h') | KeyCode::Char('?') => {
+                    let mut entries = vec![
+                        ("j/k".to_owned(), "scroll down/up".to_owned()),
+                        ("J/K".to_owned(), "scroll down by ½ page".to_owned()),
+                        ("Enter".to_owned(), "see files".to_owned()),
+                        ("@".to_owned(), "current change".to_owned()),
+                        ("r".to_owned(), "revset".to_owned()),
+                        ("d".to_owned(), "describe change".to_owned()),
+                        ("E".to_owned(), "describe change in editor".to_owned()),
+                        (
+                            "Ctrl+w".to_owned(),
+                            "toggle WIP description prefix".to_owned(),
+                        ),
+                        ("S".to_owned(), "describe stack".to_owned()),
+                        ("e".to_owned(), "edit change".to_owned()),
+                        ("n".to_owned(), "new change".to_owned()),
+                        ("N".to_owned(), "new with message".to_owned()),
+                        ("O".to_owned(), "toggle no-edit mode for new".to_owned()),
+                        ("a".to_owned(), "abandon change".to_owned()),
+                        ("b".to_owned(), "set bookmark".to_owned()),
+                        ("D".to_owned(), "diff bookmark with remote".to_owned()),
+                        ("X".to_owned(), "run command on changes".to_owned()),
+                        ("x".to_owned(), "fix change".to_owned()),
+                        ("A".to_owned(), "amend @ into change".to_owned()),
+                        (
+                            "Ctrl+a".to_owned(),
+                            "amend @ into change and describe".to_owned(),
+                        ),
+                        ("m".to_owned(), "rebase change".to_owned()),
+                        ("g".to_owned(), "go to bookmark".to_owned()),
+                        ("L".to_owned(), "alias change/go to alias".to_owned()),
+                        ("Ctrl+o".to_owned(), "open change in browser".to_owned()),
+                        ("Ctrl+j".to_owned(), "export log as JSON".to_owned()),
+                        ("H".to_owned(), "toggle showing all changes".to_owned()),
+                        ("M".to_owned(), "toggle showing only merges".to_owned()),
+                        ("G".to_owned(), "toggle graph".to_owned()),
+                        ("Backspace".to_owned(), "clear path filter".to_owned()),
+                        ("z".to_owned(), "fold/unfold merged branch".to_owned()),
+                        ("c/C".to_owned(), "next/previous conflict".to_owned()),
+                        ("/".to_owned(), "search change ID".to_owned()),
+                        ("f".to_owned(), "git fetch".to_owned()),
+                        ("F".to_owned(), "git fetch all remotes".to_owned()),
+                        ("p".to_owned(), "git push".to_owned()),
+                        ("P".to_owned(), "git push all bookmarks".to_owned()),
+                    ];
+                    if self.head.immutable && self.config.hide_immutable_actions() {
+                        entries.retain(|(key, _)| !IMMUTABLE_ACTION_KEYS.contains(&key.as_str()));
+                    }
+                    let mut details_entries = vec![
+                        ("Ctrl+e/Ctrl+y".to_owned(), "scroll down/up".to_owned()),
+                        (
+                            "Ctrl+Left/Ctrl+Right".to_owned(),
+                            "scroll left/right".to_owned(),
+                        ),
+                        (
+                            "Ctrl+d/Ctrl+u".to_owned(),
+                            "scroll down/up by ½ page".to_owned(),
+                        ),
+                        (
+                            "Ctrl+f/Ctrl+b".to_owned(),
+                            "scroll down/up by page".to_owned(),
+                        ),
+                        (
+                            "w".to_owned(),
+                            "cycle diff format (words/git/summary)".to_owned(),
+                        ),
+                        ("W".to_owned(), "toggle wrapping".to_owned()),
+                    ];
+                    if self.config.show_relatives() {
+                        details_entries.push(("1-9".to_owned(), "select parent/child".to_owned()));
+                    }
                     return Ok(ComponentInputResult::HandledAction(
                         ComponentAction::SetPopup(Some(Box::new(HelpPopup::new(
-                            vec![
-                                ("j/k".to_owned(), "scroll down/up".to_owned()),
-                                ("J/K".to_owned(), "scroll down by ½ page".to_owned()),
-                                ("Enter".to_owned(), "see files".to_owned()),
-                                ("@".to_owned(), "current change".to_owned()),
-                                ("r".to_owned(), "revset".to_owned()),
-                                ("d".to_owned(), "describe change".to_owned()),
-                                ("e".to_owned(), "edit change".to_owned()),
-                                ("n".to_owned(), "new change".to_owned()),
-                                ("N".to_owned(), "new with message".to_owned()),
-                                ("a".to_owned(), "abandon change".to_owned()),
-                                ("b".to_owned(), "set bookmark".to_owned()),
-                                ("f".to_owned(), "git fetch".to_owned()),
-                                ("F".to_owned(), "git fetch all remotes".to_owned()),
-                                ("p".to_owned(), "git push".to_owned()),
-                                ("P".to_owned(), "git push all bookmarks".to_owned()),
-                            ],
-                            vec![
-                                ("Ctrl+e/Ctrl+y".to_owned(), "scroll down/up".to_owned()),
-                                (
-                                    "Ctrl+d/Ctrl+u".to_owned(),
-                                    "scroll down/up by ½ page".to_owned(),
-                                ),
-                                (
-                                    "Ctrl+f/Ctrl+b".to_owned(),
-                                    "scroll down/up by page".to_owned(),
-                                ),
-                                ("w".to_owned(), "toggle diff format".to_owned()),
-                                ("W".to_owned(), "toggle wrapping".to_owned()),
-                            ],
+                            entries,
+                            details_entries,
                         )))),
-                    ))
+                    ));
                 }
                 _ => return Ok(ComponentInputResult::NotHandled),
             };
@@ -736,3 +2572,166 @@ impl Component for LogTab<'_> {
         Ok(ComponentInputResult::Handled)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn change_id_prefix() {
+        let head = |change_id: &str| Head {
+            change_id: ChangeId(change_id.to_owned()),
+            commit_id: CommitId(change_id.to_owned()),
+            divergent: false,
+            immutable: false,
+            hidden: false,
+            conflict: false,
+            empty: false,
+        };
+        let heads = [head("kxryzmor"), head("qpvuntsm"), head("kmqlzyxw")];
+        let folded = HashSet::new();
+
+        assert_eq!(find_change_id_prefix(&heads, &folded, "k"), Some(0));
+        assert_eq!(find_change_id_prefix(&heads, &folded, "km"), Some(2));
+        assert_eq!(find_change_id_prefix(&heads, &folded, "q"), Some(1));
+        assert_eq!(find_change_id_prefix(&heads, &folded, "z"), None);
+
+        // Folded changes are skipped
+        let folded = HashSet::from([ChangeId("kxryzmor".to_owned())]);
+        assert_eq!(find_change_id_prefix(&heads, &folded, "k"), Some(2));
+    }
+
+    #[test]
+    fn next_conflict() {
+        let positions = [2, 5, 9];
+
+        assert_eq!(get_next_conflict(&positions, Some(0), true), Some(0));
+        assert_eq!(get_next_conflict(&positions, Some(2), true), Some(1));
+        assert_eq!(get_next_conflict(&positions, Some(6), true), Some(2));
+        // Wraps around
+        assert_eq!(get_next_conflict(&positions, Some(9), true), Some(0));
+        assert_eq!(get_next_conflict(&positions, Some(5), false), Some(0));
+        assert_eq!(get_next_conflict(&positions, Some(2), false), Some(2));
+        assert_eq!(get_next_conflict(&positions, None, true), Some(0));
+        assert_eq!(get_next_conflict(&positions, None, false), Some(2));
+        assert_eq!(get_next_conflict(&[], Some(0), true), None);
+    }
+
+    #[test]
+    fn visible_rows() {
+        // Selection already visible, the list may still scroll for the padding around it
+        assert_eq!(visible_log_rows(100, Some(110), 20), 91..130);
+        // Selection above the view
+        assert_eq!(visible_log_rows(10, Some(4), 20), 0..30);
+        // Selection below the view
+        assert_eq!(visible_log_rows(10, Some(50), 20), 10..70);
+        assert_eq!(visible_log_rows(0, None, 20), 0..20);
+    }
+
+    #[test]
+    fn fold_lines() {
+        let head = |id: &str| {
+            Some(Head {
+                change_id: ChangeId(id.to_owned()),
+                commit_id: CommitId(id.to_owned()),
+                divergent: false,
+                immutable: false,
+                hidden: false,
+                conflict: false,
+                empty: false,
+            })
+        };
+        // Merge "a" of "b" and side branch "c" and "d"
+        let graph_heads = [
+            head("a"),
+            head("a"),
+            None,
+            head("c"),
+            head("c"),
+            head("d"),
+            head("d"),
+            None,
+            head("b"),
+            head("b"),
+        ];
+
+        assert_eq!(
+            fold_log_lines(graph_heads.len(), &graph_heads, &HashSet::new()),
+            (0..graph_heads.len()).map(LogRow::Line).collect::<Vec<_>>()
+        );
+
+        let folded = HashSet::from([ChangeId("c".to_owned()), ChangeId("d".to_owned())]);
+        assert_eq!(
+            fold_log_lines(graph_heads.len(), &graph_heads, &folded),
+            [
+                LogRow::Line(0),
+                LogRow::Line(1),
+                LogRow::Line(2),
+                LogRow::Folded(2),
+                LogRow::Line(7),
+                LogRow::Line(8),
+                LogRow::Line(9),
+            ]
+        );
+
+        // Extra lines without heads are kept
+        let folded = HashSet::from([ChangeId("b".to_owned())]);
+        assert_eq!(
+            fold_log_lines(graph_heads.len() + 1, &graph_heads, &folded),
+            [
+                LogRow::Line(0),
+                LogRow::Line(1),
+                LogRow::Line(2),
+                LogRow::Line(3),
+                LogRow::Line(4),
+                LogRow::Line(5),
+                LogRow::Line(6),
+                LogRow::Line(7),
+                LogRow::Folded(1),
+                LogRow::Line(10),
+            ]
+        );
+    }
+
+    #[test]
+    fn log_stats() {
+        let head = |id: &str, immutable: bool, empty: bool, conflict: bool| Head {
+            change_id: ChangeId(id.to_owned()),
+            commit_id: CommitId(id.to_owned()),
+            divergent: false,
+            immutable,
+            hidden: false,
+            conflict,
+            empty,
+        };
+        let log_output = Ok(LogOutput {
+            graph: String::new(),
+            graph_heads: vec![],
+            graph_timestamps: vec![],
+            heads: vec![
+                head("a", false, true, false),
+                head("b", false, false, true),
+                head("c", true, false, false),
+                head("d", true, true, false),
+            ],
+        });
+
+        assert_eq!(
+            LogStats::new(&log_output),
+            LogStats {
+                total: 4,
+                immutable: 2,
+                empty: 2,
+                conflicted: 1,
+            }
+        );
+        assert_eq!(
+            LogStats::new(&Err(CommandError::Status(
+                "error".to_owned(),
+                Some(1),
+                None
+            ))),
+            LogStats::default()
+        );
+    }
+}
@@ -4,12 +4,16 @@ use ratatui::{
     layout::{Alignment, Rect},
     style::{Color, Style, Stylize},
     text::{Span, Text},
-    widgets::{block::Title, BorderType, Borders},
+    widgets::{block::Title, Borders},
     Frame,
 };
 use tui_confirm_dialog::PopupMessage;
 
-use crate::{commander::Commander, ui::Component, ComponentInputResult};
+use crate::{
+    commander::Commander,
+    ui::{styles::border_type, Component},
+    ComponentInputResult,
+};
 
 pub struct MessagePopup<'a> {
     pub title: Title<'a>,
@@ -33,7 +37,7 @@ impl Component for MessagePopup<'_> {
             .title_alignment(Alignment::Center)
             .text_alignment(Alignment::Center)
             .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
+            .border_type(border_type())
             .border_style(Style::default().fg(Color::Green));
 
         f.render_widget(popup, area);
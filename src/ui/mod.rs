@@ -1,17 +1,44 @@
+pub mod alias_popup;
+pub mod bookmark_jump_popup;
 pub mod bookmark_set_popup;
 pub mod bookmarks_tab;
 pub mod command_log_tab;
+pub mod command_palette;
+pub mod confirm_popup;
+pub mod describe_stack_popup;
 pub mod details_panel;
+pub mod diff_between_popup;
+pub mod exec_popup;
+pub mod file_compare_popup;
 pub mod files_tab;
+pub mod fix_popup;
+pub mod health_popup;
 pub mod help_popup;
+pub mod log_export_popup;
 pub mod log_tab;
 pub mod message_popup;
+pub mod output_popup;
+pub mod push_check_popup;
+pub mod push_rejected_popup;
+pub mod relatives;
+pub mod scroll_memory;
+pub mod sparse_reset_popup;
+pub mod stack_breadcrumb;
+pub mod stale_view_popup;
+pub mod stale_workspace_popup;
 pub mod styles;
 pub mod utils;
+pub mod web_view_popup;
 
 use crate::{
     app::{App, Tab},
     commander::{log::Head, Commander},
+    env::DiffFormat,
+    ui::{
+        confirm_popup::ConfirmAction,
+        stack_breadcrumb::render_stack_breadcrumb,
+        styles::{border_set, reduce_buffer_colors, vertical_line},
+    },
     ComponentInputResult,
 };
 use anyhow::Result;
@@ -20,7 +47,7 @@ use crossterm::event::Event;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
-    symbols, Frame,
+    Frame,
 };
 use ratatui::{prelude::*, widgets::*};
 
@@ -29,9 +56,34 @@ pub enum ComponentAction {
     ViewLog(Head),
     ChangeHead(Head),
     SetPopup(Option<Box<dyn Component>>),
+    SetTab(Tab),
+    SetRevset(Option<String>),
+    // Only show changes touching a path in the log tab
+    SetLogPath(Option<String>),
+    SetDiffFormat(DiffFormat),
+    // Run the action of an accepted confirmation popup, if the repository didn't change since the
+    // last refresh
+    Confirmed(ConfirmAction),
+    // Run the action of a confirmation popup without checking the repository
+    RunConfirmed(ConfirmAction),
+    // Refresh the current tab
+    Refresh,
+    // Suspend the TUI to run a jj command which uses the terminal, such as an editor
+    RunExternal(Vec<String>),
+    // Suspend the TUI to print output to the terminal, such as JSON for scripts
+    Print(String),
+    Quit,
+    // Actions handled in order, including nested `Multiple`s
     Multiple(Vec<ComponentAction>),
 }
 
+/// Panel of a tab with two panels, which can be maximized with Ctrl+z.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum FocusedPanel {
+    Left,
+    Right,
+}
+
 pub trait Component {
     // Called when switching to tab
     fn switch(&mut self, _commander: &mut Commander) -> Result<()> {
@@ -42,17 +94,47 @@ pub trait Component {
         Ok(None)
     }
 
+    // Called on timer ticks instead of `update`, to pick up the results of work running in the
+    // background. Doesn't run jj, so idle redraws don't snapshot the working copy
+    fn poll_background(&mut self) -> Option<ComponentAction> {
+        None
+    }
+
     fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()>;
 
+    // Panel last interacted with, for tabs with two panels
+    fn focused_panel(&self) -> Option<FocusedPanel> {
+        None
+    }
+
+    // Called before drawing with the panel to show full screen, if any
+    fn set_maximized_panel(&mut self, _maximized_panel: Option<FocusedPanel>) {}
+
+    // Called before drawing with the width of the left panel in percent, for tabs with two panels
+    fn set_split_percent(&mut self, _split_percent: u16) {}
+
+    // Called when the terminal is resized, to forget sizes from the last draw until the next one
+    fn reset_size(&mut self) {}
+
+    // Whether work is running in the background, to redraw more often while it does
+    fn has_background_work(&self) -> bool {
+        false
+    }
+
     fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult>;
 }
 
 pub fn ui(f: &mut Frame, app: &mut App) -> Result<()> {
     let start_time = Utc::now().time();
 
+    let show_stack = app.env.config.stack_breadcrumb();
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(3), Constraint::Min(1)])
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(if show_stack { 1 } else { 0 }),
+            Constraint::Min(1),
+        ])
         .split(f.area());
 
     let header_chunks = Layout::default()
@@ -67,11 +149,7 @@ pub fn ui(f: &mut Frame, app: &mut App) -> Result<()> {
                 .enumerate()
                 .map(|(i, tab)| format!("[{}] {}", i + 1, tab)),
         )
-        .block(
-            Block::bordered()
-                .title(" Tabs ")
-                .border_type(BorderType::Rounded),
-        )
+        .block(Block::bordered().title(" Tabs ").border_set(border_set()))
         .highlight_style(Style::default().bg(app.env.config.highlight_color()))
         .select(
             Tab::VALUES
@@ -79,31 +157,67 @@ pub fn ui(f: &mut Frame, app: &mut App) -> Result<()> {
                 .position(|tab| tab == &app.current_tab)
                 .unwrap_or(0),
         )
-        .divider(symbols::line::VERTICAL);
+        .divider(vertical_line());
 
         f.render_widget(tabs, header_chunks[0]);
     }
     {
-        let tabs = Paragraph::new("q: quit | h: help | R: refresh | 1/2/3/4: change tab")
-            .fg(Color::DarkGray)
-            .block(
-                Block::bordered()
-                    .title(" lazyjj ")
-                    .border_type(BorderType::Rounded)
-                    .fg(Color::default()),
-            );
+        let tabs =
+            Paragraph::new("q: quit | h: help | R: refresh | :: command | 1/2/3/4: change tab")
+                .fg(Color::DarkGray)
+                .block(
+                    Block::bordered()
+                        .title(" lazyjj ")
+                        .title(
+                            Line::raw(format!(
+                                " ]/[: next/prev ({}) | Ctrl+n: mode ",
+                                app.navigation_mode
+                            ))
+                            .right_aligned(),
+                        )
+                        .border_set(border_set())
+                        .fg(Color::default()),
+                );
 
         f.render_widget(tabs, header_chunks[1]);
     }
 
+    if show_stack {
+        let width = chunks[1].width.saturating_sub(1) as usize;
+        let breadcrumb = if app.stack.is_empty() {
+            Line::raw("The working copy isn't a descendant of trunk()").fg(Color::DarkGray)
+        } else {
+            render_stack_breadcrumb(
+                &app.stack,
+                app.active_head.as_ref().map(|head| &head.change_id),
+                width,
+            )
+        };
+        f.render_widget(
+            Paragraph::new(Line::from(
+                [vec![Span::raw(" ")], breadcrumb.spans].concat(),
+            )),
+            chunks[1],
+        );
+    }
+
+    let maximized_panel = app.get_maximized_panel();
+    let split_percent = app.split_percent;
+    app.tab_area = chunks[2];
     if let Some(current_tab) = app.get_current_tab() {
-        current_tab.draw(f, chunks[1])?;
+        current_tab.set_maximized_panel(maximized_panel);
+        current_tab.set_split_percent(split_percent);
+        current_tab.draw(f, chunks[2])?;
     }
 
     if let Some(popup) = app.popup.as_mut() {
         popup.draw(f, f.area())?;
     }
 
+    if app.env.config.reduce_color() {
+        reduce_buffer_colors(f.buffer_mut());
+    }
+
     let end_time = Utc::now().time();
     let diff = end_time - start_time;
 
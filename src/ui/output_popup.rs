@@ -0,0 +1,91 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::{
+    commander::Commander,
+    ui::{
+        details_panel::DetailsPanel,
+        styles::{border_set, create_popup_block},
+        utils::{ansi_text, centered_rect, tabs_to_spaces},
+        Component,
+    },
+    ComponentInputResult,
+};
+
+/// Popup showing long command output which needs scrolling, such as `jj debug revset`.
+pub struct OutputPopup {
+    title: String,
+    output: String,
+    output_panel: DetailsPanel,
+}
+
+impl OutputPopup {
+    pub fn new(title: String, output: &str) -> Self {
+        Self {
+            title,
+            output: tabs_to_spaces(output),
+            output_panel: DetailsPanel::new(),
+        }
+    }
+}
+
+impl Component for OutputPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 80, 80);
+        f.render_widget(Clear, area);
+
+        let block = create_popup_block(&self.title);
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Fill(1), Constraint::Length(2)])
+            .split(block.inner(area));
+        f.render_widget(&block, area);
+
+        let output = self
+            .output_panel
+            .render(ansi_text(&self.output)?, popup_chunks[0]);
+        f.render_widget(output, popup_chunks[0]);
+        self.output_panel.render_h_scrollbar(f, popup_chunks[0]);
+
+        let help = Paragraph::new(vec!["j/k: scroll | Escape: close".into()])
+            .fg(Color::DarkGray)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .border_set(border_set())
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            );
+        f.render_widget(help, popup_chunks[1]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            if self.output_panel.input(key) {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => self.output_panel.scroll(1),
+                KeyCode::Char('k') | KeyCode::Up => self.output_panel.scroll(-1),
+                _ => return Ok(ComponentInputResult::NotHandled),
+            }
+
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        Ok(ComponentInputResult::NotHandled)
+    }
+}
@@ -0,0 +1,160 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Text},
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph},
+};
+
+use crate::{
+    commander::{log::Head, Commander},
+    env::Config,
+    ui::{
+        styles::{border_set, create_popup_block},
+        utils::centered_rect,
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+pub enum PushCheckAction {
+    // Describe the offending head instead of pushing
+    Describe(Head),
+    Push,
+}
+
+/// Popup shown before pushing when changes which would be pushed have an empty description or are
+/// marked as WIP.
+pub struct PushCheckPopup {
+    heads: Vec<Head>,
+    list_state: ListState,
+    list_height: u16,
+    config: Config,
+    tx: std::sync::mpsc::Sender<PushCheckAction>,
+}
+
+impl PushCheckPopup {
+    pub fn new(
+        config: Config,
+        heads: Vec<Head>,
+        tx: std::sync::mpsc::Sender<PushCheckAction>,
+    ) -> Self {
+        Self {
+            heads,
+            list_state: ListState::default().with_selected(Some(0)),
+            list_height: 0,
+            config,
+            tx,
+        }
+    }
+
+    fn scroll(&mut self, scroll: isize) {
+        self.list_state.select(Some(
+            self.list_state
+                .selected()
+                .map(|selected| selected.saturating_add_signed(scroll))
+                .unwrap_or(0)
+                .min(self.heads.len().saturating_sub(1)),
+        ));
+    }
+}
+
+impl Component for PushCheckPopup {
+    fn draw(&mut self, f: &mut ratatui::prelude::Frame<'_>, area: Rect) -> Result<()> {
+        let block = create_popup_block("Push");
+        let area = centered_rect(area, 50, 50);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(2),
+                Constraint::Fill(1),
+                Constraint::Length(2),
+            ])
+            .split(block.inner(area));
+
+        f.render_widget(
+            Paragraph::new(vec![Line::from(
+                "The following changes have no description or are marked as WIP:",
+            )]),
+            popup_chunks[0],
+        );
+
+        let list_items = self
+            .heads
+            .iter()
+            .map(|head| Text::raw(head.change_id.as_string()).fg(Color::Magenta));
+        let list = List::new(list_items)
+            .scroll_padding(3)
+            .highlight_style(Style::default().bg(self.config.highlight_color()));
+
+        f.render_stateful_widget(list, popup_chunks[1], &mut self.list_state);
+        self.list_height = popup_chunks[1].height;
+
+        let help = Paragraph::new(vec![
+            "j/k: scroll down/up | d: describe | p: push anyway | Escape: cancel".into(),
+        ])
+        .fg(Color::DarkGray)
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_set(border_set())
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+
+        f.render_widget(help, popup_chunks[2]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.scroll(1);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.scroll(-1);
+                }
+                KeyCode::Char('J') => {
+                    self.scroll(self.list_height as isize / 2);
+                }
+                KeyCode::Char('K') => {
+                    self.scroll((self.list_height as isize / 2).saturating_neg());
+                }
+                KeyCode::Char('d') | KeyCode::Enter => {
+                    if let Some(head) = self
+                        .list_state
+                        .selected()
+                        .and_then(|index| self.heads.get(index))
+                    {
+                        self.tx.send(PushCheckAction::Describe(head.clone()))?;
+                    }
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                KeyCode::Char('p') => {
+                    self.tx.send(PushCheckAction::Push)?;
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                _ => return Ok(ComponentInputResult::NotHandled),
+            }
+
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        Ok(ComponentInputResult::NotHandled)
+    }
+}
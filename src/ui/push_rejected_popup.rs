@@ -0,0 +1,180 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    commander::{bookmarks::BookmarkTarget, Commander},
+    ui::{
+        message_popup::MessagePopup,
+        styles::{border_set, create_popup_block},
+        utils::centered_rect,
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+/// Revset with both sides of diverged bookmarks: their local and remote targets, and the changes
+/// since the fork point.
+pub fn get_divergence_revset(bookmarks: &[String]) -> Option<String> {
+    if bookmarks.is_empty() {
+        return None;
+    }
+
+    Some(
+        bookmarks
+            .iter()
+            .map(|bookmark| {
+                let local = format!(r#"bookmarks(exact:"{bookmark}")"#);
+                let remote = format!(r#"remote_bookmarks(exact:"{bookmark}")"#);
+                format!("heads(::{local} & ::{remote})::({local} | {remote})")
+            })
+            .collect::<Vec<_>>()
+            .join(" | "),
+    )
+}
+
+/// Popup shown when a push was rejected because remote bookmarks moved since the last fetch,
+/// with the local and remote targets of the bookmarks.
+pub struct PushRejectedPopup {
+    bookmarks: Vec<String>,
+    // Targets as of the last fetch
+    bookmark_targets: Vec<BookmarkTarget>,
+}
+
+impl PushRejectedPopup {
+    pub fn new(commander: &mut Commander, bookmarks: Vec<String>) -> Self {
+        let bookmark_targets = commander
+            .get_bookmark_targets()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|bookmark_target| bookmarks.contains(&bookmark_target.bookmark.name))
+            .collect();
+
+        Self {
+            bookmarks,
+            bookmark_targets,
+        }
+    }
+
+    // Fetch, then show both sides of the bookmarks in the log
+    fn fetch(&self, commander: &mut Commander) -> Result<ComponentAction> {
+        if let Err(err) = commander.git_fetch(false) {
+            return Ok(ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                title: "Fetch error".into(),
+                messages: err.into_text("")?,
+            }))));
+        }
+
+        Ok(ComponentAction::Multiple(vec![
+            ComponentAction::SetPopup(None),
+            match get_divergence_revset(&self.bookmarks) {
+                Some(revset) => ComponentAction::SetRevset(Some(revset)),
+                None => ComponentAction::Refresh,
+            },
+        ]))
+    }
+}
+
+impl Component for PushRejectedPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let block = create_popup_block("Push rejected");
+        let area = centered_rect(area, 60, 50);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Fill(1), Constraint::Length(2)])
+            .split(block.inner(area));
+
+        let mut lines = vec![
+            Line::raw("The remote bookmarks moved since the last fetch, such as when someone else pushed. jj refuses to push over them.").fg(Color::Yellow),
+            Line::default(),
+        ];
+        for bookmark_target in self.bookmark_targets.iter() {
+            lines.push(Line::from(vec![
+                Span::raw(bookmark_target.bookmark.to_string()).fg(
+                    if bookmark_target.bookmark.remote.is_some() {
+                        Color::Red
+                    } else {
+                        Color::Green
+                    },
+                ),
+                Span::raw(" "),
+                Span::raw(bookmark_target.change_id.clone()).fg(Color::Blue),
+                Span::raw(" "),
+                Span::raw(bookmark_target.description.clone()),
+            ]));
+        }
+        if !self.bookmark_targets.is_empty() {
+            lines.push(Line::default());
+        }
+        lines.push(Line::raw(
+            "Fetch to see where the remote bookmarks are now, then move the bookmarks to where they should be, such as by rebasing onto the remote changes, and push again.",
+        ));
+        f.render_widget(
+            Paragraph::new(lines).wrap(Wrap { trim: false }),
+            popup_chunks[0],
+        );
+
+        let help = Paragraph::new(vec![
+            "f/Enter: fetch and show both sides in the log | Escape: close".into(),
+        ])
+        .fg(Color::DarkGray)
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_set(border_set())
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+        f.render_widget(help, popup_chunks[1]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            match key.code {
+                KeyCode::Char('f') | KeyCode::Enter => {
+                    return Ok(ComponentInputResult::HandledAction(self.fetch(commander)?));
+                }
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn divergence_revset() {
+        assert_eq!(get_divergence_revset(&[]), None);
+        assert_eq!(
+            get_divergence_revset(&["main".to_owned()]),
+            Some(
+                r#"heads(::bookmarks(exact:"main") & ::remote_bookmarks(exact:"main"))::(bookmarks(exact:"main") | remote_bookmarks(exact:"main"))"#
+                    .to_owned()
+            )
+        );
+    }
+}
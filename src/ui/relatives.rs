@@ -0,0 +1,133 @@
+use ratatui::{
+    style::{Color, Stylize},
+    text::{Line, Span},
+};
+
+use crate::commander::log::{Relatives, StackEntry};
+
+// Parents or children listed, for merges and changes with many children. Others are counted
+const MAX_RELATIVES: usize = 4;
+
+/// Relatives listed by `relatives_lines`, in the order they're numbered from 1: parents, then
+/// children.
+pub fn numbered_relatives(relatives: &Relatives) -> Vec<&StackEntry> {
+    relatives
+        .parents
+        .iter()
+        .take(MAX_RELATIVES)
+        .chain(relatives.children.iter().take(MAX_RELATIVES))
+        .collect()
+}
+
+/// Lines listing the parents and children of a change, numbered to select them, followed by an
+/// empty line.
+pub fn relatives_lines(relatives: &Relatives) -> Vec<Line<'static>> {
+    let mut lines = vec![];
+    let mut number = 1;
+    for (label, entries) in [
+        ("Parents", &relatives.parents),
+        ("Children", &relatives.children),
+    ] {
+        if entries.is_empty() {
+            continue;
+        }
+
+        lines.push(Line::raw(format!("{label}:")).bold());
+        for entry in entries.iter().take(MAX_RELATIVES) {
+            let change_id =
+                &entry.head.change_id.as_str()[..entry.head.change_id.as_str().len().min(8)];
+            let title = if entry.title.is_empty() {
+                Span::raw("(no description set)").fg(Color::DarkGray)
+            } else {
+                Span::raw(entry.title.clone())
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!("  {number} ")).fg(Color::Yellow),
+                Span::raw(change_id.to_owned()).fg(Color::Magenta),
+                Span::raw(" "),
+                title,
+            ]));
+            number += 1;
+        }
+        if entries.len() > MAX_RELATIVES {
+            lines.push(
+                Line::raw(format!("  (+{} more)", entries.len() - MAX_RELATIVES))
+                    .fg(Color::DarkGray),
+            );
+        }
+    }
+
+    if !lines.is_empty() {
+        lines.push(Line::default());
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::{
+        ids::{ChangeId, CommitId},
+        log::Head,
+    };
+
+    fn entry(change_id: &str, title: &str) -> StackEntry {
+        StackEntry {
+            head: Head {
+                change_id: ChangeId(change_id.to_owned()),
+                commit_id: CommitId(format!("{change_id}-commit")),
+                divergent: false,
+                immutable: false,
+                hidden: false,
+                conflict: false,
+                empty: false,
+            },
+            title: title.to_owned(),
+        }
+    }
+
+    #[test]
+    fn list_relatives() {
+        let relatives = Relatives {
+            parents: vec![entry("kxryzmorwvtz", "Add parser")],
+            children: (0..6)
+                .map(|i| entry(&format!("vruxwmq{i}"), &format!("Child {i}")))
+                .collect(),
+        };
+        let lines: Vec<String> = relatives_lines(&relatives)
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "Parents:",
+                "  1 kxryzmor Add parser",
+                "Children:",
+                "  2 vruxwmq0 Child 0",
+                "  3 vruxwmq1 Child 1",
+                "  4 vruxwmq2 Child 2",
+                "  5 vruxwmq3 Child 3",
+                "  (+2 more)",
+                "",
+            ]
+        );
+
+        // Numbers match the listed changes
+        let numbered = numbered_relatives(&relatives);
+        assert_eq!(numbered.len(), 5);
+        assert_eq!(numbered[0].title, "Add parser");
+        assert_eq!(numbered[4].title, "Child 3");
+
+        // The root has no parents, and nothing is listed without relatives
+        let root = Relatives {
+            parents: vec![],
+            children: vec![entry("zzzzzzzz", "")],
+        };
+        assert_eq!(
+            relatives_lines(&root)[1].to_string(),
+            "  1 zzzzzzzz (no description set)"
+        );
+        assert!(relatives_lines(&Relatives::default()).is_empty());
+    }
+}
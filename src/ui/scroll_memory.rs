@@ -0,0 +1,103 @@
+use std::collections::VecDeque;
+
+// Scroll positions remembered, forgetting the least recently left
+const CAPACITY: usize = 20;
+
+/// Scroll positions of the details panel by what it showed, such as a commit, to return to where
+/// it was when coming back to it.
+#[derive(Debug)]
+pub struct ScrollMemory<K> {
+    // Least recently left first
+    entries: VecDeque<(K, u16)>,
+    capacity: usize,
+}
+
+impl<K: PartialEq> ScrollMemory<K> {
+    pub fn new() -> Self {
+        Self::with_capacity(CAPACITY)
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Remember the scroll position when leaving something. The top isn't remembered, as it's
+    /// where it would be anyway.
+    pub fn remember(&mut self, key: K, scroll: u16) {
+        self.entries.retain(|(entry, _)| entry != &key);
+        if scroll == 0 {
+            return;
+        }
+
+        self.entries.push_back((key, scroll));
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Scroll position to restore when coming back to something, forgotten once restored. Clamped
+    /// to the lines of its content, which may be shorter since, such as after a rewrite.
+    pub fn restore(&mut self, key: &K, lines: usize) -> u16 {
+        let Some((_, scroll)) = self
+            .entries
+            .iter()
+            .position(|(entry, _)| entry == key)
+            .and_then(|index| self.entries.remove(index))
+        else {
+            return 0;
+        };
+        scroll.min(u16::try_from(lines.saturating_sub(1)).unwrap_or(u16::MAX))
+    }
+
+    /// Forget all positions, such as when the content changes format.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forget_least_recently_left() {
+        let mut memory = ScrollMemory::with_capacity(3);
+        for (key, scroll) in [("a", 10), ("b", 20), ("c", 30)] {
+            memory.remember(key, scroll);
+        }
+        // Leaving again moves it to the most recent
+        memory.remember("a", 15);
+        memory.remember("d", 40);
+
+        assert_eq!(memory.restore(&"b", 100), 0);
+        assert_eq!(memory.restore(&"a", 100), 15);
+        assert_eq!(memory.restore(&"c", 100), 30);
+        assert_eq!(memory.restore(&"d", 100), 40);
+
+        // Restored positions are forgotten
+        assert_eq!(memory.restore(&"a", 100), 0);
+
+        // Leaving at the top forgets the previous position
+        memory.remember("a", 10);
+        memory.remember("a", 0);
+        assert_eq!(memory.restore(&"a", 100), 0);
+
+        memory.remember("a", 10);
+        memory.clear();
+        assert_eq!(memory.restore(&"a", 100), 0);
+    }
+
+    #[test]
+    fn clamp_restored_scroll() {
+        let mut memory = ScrollMemory::new();
+        memory.remember("a", 50);
+        // The content got shorter, so the last line is at the top
+        assert_eq!(memory.restore(&"a", 20), 19);
+
+        memory.remember("a", 50);
+        assert_eq!(memory.restore(&"a", 0), 0);
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::{
+    commander::Commander,
+    ui::{
+        message_popup::MessagePopup,
+        styles::{border_set, create_popup_block},
+        utils::centered_rect_line_height,
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+/// Popup to confirm resetting a sparse checkout with `jj sparse set --reset`, opened with
+/// Ctrl+Shift+R.
+pub struct SparseResetPopup;
+
+impl Component for SparseResetPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let block = create_popup_block("Reset sparse checkout");
+        let area = centered_rect_line_height(area, 50, 6);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Fill(1), Constraint::Length(2)])
+            .split(block.inner(area));
+
+        f.render_widget(
+            Paragraph::new(Line::raw(
+                "This will restore all files to the working copy.",
+            ))
+            .fg(Color::Yellow)
+            .alignment(Alignment::Center),
+            popup_chunks[0],
+        );
+
+        let help = Paragraph::new(vec!["y/Enter: reset | n/Escape: cancel".into()])
+            .fg(Color::DarkGray)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .border_set(border_set())
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            );
+        f.render_widget(help, popup_chunks[1]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Enter => {
+                    let action = match commander.reset_sparse() {
+                        Ok(()) => ComponentAction::Multiple(vec![
+                            ComponentAction::SetPopup(None),
+                            ComponentAction::Refresh,
+                        ]),
+                        Err(err) => ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                            title: "Reset sparse checkout error".into(),
+                            messages: err.into_text("")?,
+                        }))),
+                    };
+                    return Ok(ComponentInputResult::HandledAction(action));
+                }
+                KeyCode::Char('n') | KeyCode::Char('q') | KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+}
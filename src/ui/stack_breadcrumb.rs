@@ -0,0 +1,201 @@
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+};
+
+use crate::{
+    commander::{ids::ChangeId, log::StackEntry},
+    ui::styles::left_arrow,
+};
+
+// Words of the description shown for each change
+const TITLE_WORDS: usize = 3;
+
+/// Part of the breadcrumb: a change of the stack by index, or a number of elided changes.
+#[derive(Debug, PartialEq)]
+pub enum Crumb {
+    Entry(usize),
+    Elided(usize),
+}
+
+fn elided_label(count: usize) -> String {
+    format!("… ({count} more) …")
+}
+
+// Short change ID and first words of the description. The first change is trunk
+fn entry_label(entry: &StackEntry, index: usize) -> String {
+    if index == 0 {
+        return "trunk".to_owned();
+    }
+
+    let change_id = &entry.head.change_id.as_str()[..entry.head.change_id.as_str().len().min(8)];
+    let words = entry
+        .title
+        .split_whitespace()
+        .take(TITLE_WORDS)
+        .collect::<Vec<_>>()
+        .join(" ");
+    if words.is_empty() {
+        change_id.to_owned()
+    } else {
+        format!("{change_id} {words}")
+    }
+}
+
+/// Pick which changes to show in a width, keeping both ends of the stack and eliding the middle.
+pub fn elide_crumbs(labels: &[String], separator_width: usize, width: usize) -> Vec<Crumb> {
+    let crumbs_width = |crumbs: &[Crumb]| {
+        crumbs
+            .iter()
+            .map(|crumb| match crumb {
+                Crumb::Entry(index) => labels[*index].chars().count(),
+                Crumb::Elided(count) => elided_label(*count).chars().count(),
+            })
+            .sum::<usize>()
+            + crumbs.len().saturating_sub(1) * separator_width
+    };
+
+    let all: Vec<Crumb> = (0..labels.len()).map(Crumb::Entry).collect();
+    if labels.len() <= 2 || crumbs_width(&all) <= width {
+        return all;
+    }
+
+    // Add changes from the end then the start in turn, while they fit
+    let (mut start, mut end) = (1, labels.len() - 1);
+    let mut from_end = true;
+    let build = |start: usize, end: usize| {
+        (0..start)
+            .map(Crumb::Entry)
+            .chain([Crumb::Elided(end - start)])
+            .chain((end..labels.len()).map(Crumb::Entry))
+            .collect::<Vec<_>>()
+    };
+    while end - start > 1 {
+        let (next_start, next_end) = if from_end {
+            (start, end - 1)
+        } else {
+            (start + 1, end)
+        };
+        if crumbs_width(&build(next_start, next_end)) > width {
+            break;
+        }
+        (start, end) = (next_start, next_end);
+        from_end = !from_end;
+    }
+
+    build(start, end)
+}
+
+/// Render the stack from trunk to the working copy as a breadcrumb, such as
+/// `trunk ← kxryzmor Add parser ← vruxwmqv Fix typo`. The change selected in the log is
+/// highlighted.
+pub fn render_stack_breadcrumb(
+    stack: &[StackEntry],
+    selected: Option<&ChangeId>,
+    width: usize,
+) -> Line<'static> {
+    let labels: Vec<String> = stack
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| entry_label(entry, index))
+        .collect();
+    let separator = format!(" {} ", left_arrow());
+
+    let mut spans = vec![];
+    for (i, crumb) in elide_crumbs(&labels, separator.chars().count(), width)
+        .into_iter()
+        .enumerate()
+    {
+        if i > 0 {
+            spans.push(Span::styled(
+                separator.clone(),
+                Style::new().fg(Color::DarkGray),
+            ));
+        }
+        spans.push(match crumb {
+            Crumb::Entry(index) => {
+                let style = if Some(&stack[index].head.change_id) == selected {
+                    Style::new().bold().cyan()
+                } else if index == stack.len() - 1 {
+                    // Working copy
+                    Style::new().fg(Color::Green)
+                } else {
+                    Style::new()
+                };
+                Span::styled(labels[index].clone(), style)
+            }
+            Crumb::Elided(count) => {
+                Span::styled(elided_label(count), Style::new().fg(Color::DarkGray))
+            }
+        });
+    }
+
+    Line::from(spans)
+}
+
+/// Change next to the selected one in the stack, towards the working copy or towards trunk.
+pub fn get_adjacent_entry<'a>(
+    stack: &'a [StackEntry],
+    selected: Option<&ChangeId>,
+    towards_working_copy: bool,
+) -> Option<&'a StackEntry> {
+    let index = stack
+        .iter()
+        .position(|entry| Some(&entry.head.change_id) == selected);
+    match (index, towards_working_copy) {
+        (Some(index), true) => stack.get(index + 1),
+        (Some(index), false) => index.checked_sub(1).and_then(|index| stack.get(index)),
+        // Start from the working copy when the selected change isn't in the stack
+        (None, _) => stack.last(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("change{i}")).collect()
+    }
+
+    #[test]
+    fn elide_nothing_when_fitting() {
+        assert_eq!(
+            elide_crumbs(&labels(3), 3, 100),
+            vec![Crumb::Entry(0), Crumb::Entry(1), Crumb::Entry(2)]
+        );
+        // Both ends are always shown
+        assert_eq!(
+            elide_crumbs(&labels(2), 3, 1),
+            vec![Crumb::Entry(0), Crumb::Entry(1)]
+        );
+    }
+
+    #[test]
+    fn elide_middle() {
+        // Each label is 7 wide, separators 3, and `… (N more) …` 12
+        assert_eq!(
+            elide_crumbs(&labels(8), 3, 7 + 3 + 12 + 3 + 7),
+            vec![Crumb::Entry(0), Crumb::Elided(6), Crumb::Entry(7)]
+        );
+        assert_eq!(
+            elide_crumbs(&labels(8), 3, 7 + 3 + 12 + 3 + 7 + 3 + 7),
+            vec![
+                Crumb::Entry(0),
+                Crumb::Elided(5),
+                Crumb::Entry(6),
+                Crumb::Entry(7)
+            ]
+        );
+        assert_eq!(
+            elide_crumbs(&labels(8), 3, 7 + 3 + 7 + 3 + 12 + 3 + 7 + 3 + 7),
+            vec![
+                Crumb::Entry(0),
+                Crumb::Entry(1),
+                Crumb::Elided(4),
+                Crumb::Entry(6),
+                Crumb::Entry(7)
+            ]
+        );
+    }
+}
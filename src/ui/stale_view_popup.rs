@@ -0,0 +1,123 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    commander::Commander,
+    ui::{
+        confirm_popup::ConfirmAction,
+        styles::{border_set, create_popup_block, right_arrow},
+        utils::centered_rect_line_height,
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+// Operation IDs are long, and a prefix is enough to tell them apart
+fn short_operation(operation: &str) -> &str {
+    &operation[..operation.len().min(12)]
+}
+
+/// Popup shown before a confirmed mutation when the repository changed since the last refresh,
+/// such as from another terminal, so the mutation may not target what is shown. Runs the
+/// confirmed action when proceeding anyway.
+pub struct StaleViewPopup {
+    before: String,
+    after: String,
+    action: Option<ConfirmAction>,
+}
+
+impl StaleViewPopup {
+    pub fn new((before, after): (String, String), action: ConfirmAction) -> Self {
+        Self {
+            before,
+            after,
+            action: Some(action),
+        }
+    }
+}
+
+impl Component for StaleViewPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let block = create_popup_block("Repository changed");
+        let area = centered_rect_line_height(area, 50, 8);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Fill(1), Constraint::Length(2)])
+            .split(block.inner(area));
+
+        f.render_widget(
+            Paragraph::new(vec![
+                Line::raw(format!(
+                    "Repository changed since last refresh (operation {} {} {}).",
+                    short_operation(&self.before),
+                    right_arrow(),
+                    short_operation(&self.after)
+                )),
+                Line::raw("Refresh and re-check before proceeding?"),
+            ])
+            .fg(Color::Yellow)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true }),
+            popup_chunks[0],
+        );
+
+        let help = Paragraph::new(vec![
+            "r/Enter/Escape: refresh and cancel | p: proceed anyway".into(),
+        ])
+        .fg(Color::DarkGray)
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_set(border_set())
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+        f.render_widget(help, popup_chunks[1]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            match key.code {
+                KeyCode::Char('r') | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::Multiple(vec![
+                            ComponentAction::SetPopup(None),
+                            ComponentAction::Refresh,
+                        ]),
+                    ));
+                }
+                KeyCode::Char('p') => {
+                    let close = ComponentAction::SetPopup(None);
+                    return Ok(ComponentInputResult::HandledAction(
+                        match self.action.take() {
+                            Some(action) => ComponentAction::Multiple(vec![
+                                close,
+                                ComponentAction::RunConfirmed(action),
+                            ]),
+                            None => close,
+                        },
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+}
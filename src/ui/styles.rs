@@ -1,16 +1,170 @@
-use std::sync::LazyLock;
+use std::sync::{LazyLock, OnceLock};
 
 use ratatui::{
+    buffer::Buffer,
     layout::Alignment,
     style::{Color, Style, Stylize},
-    text::Span,
+    symbols::{self, border},
+    text::{Line, Span},
     widgets::{Block, BorderType, Padding},
 };
 
+use crate::env::{Config, GraphStyle, SelectionStyle};
+
+// Set once at startup from the config
+static GRAPH_STYLE: OnceLock<GraphStyle> = OnceLock::new();
+
+pub fn set_graph_style(graph_style: GraphStyle) {
+    let _ = GRAPH_STYLE.set(graph_style);
+}
+
+const ASCII_BORDER_SET: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// Border symbols for all blocks.
+pub fn border_set() -> border::Set {
+    match GRAPH_STYLE.get() {
+        Some(GraphStyle::Ascii) => ASCII_BORDER_SET,
+        _ => border::ROUNDED,
+    }
+}
+
+/// Border type for widgets which don't support border symbols, such as dialogs.
+pub fn border_type() -> BorderType {
+    match GRAPH_STYLE.get() {
+        Some(GraphStyle::Ascii) => BorderType::Plain,
+        _ => BorderType::Rounded,
+    }
+}
+
+pub fn vertical_line() -> &'static str {
+    match GRAPH_STYLE.get() {
+        Some(GraphStyle::Ascii) => "|",
+        _ => symbols::line::VERTICAL,
+    }
+}
+
+pub fn right_arrow() -> &'static str {
+    match GRAPH_STYLE.get() {
+        Some(GraphStyle::Ascii) => "->",
+        _ => "→",
+    }
+}
+
+pub fn up_arrow() -> &'static str {
+    match GRAPH_STYLE.get() {
+        Some(GraphStyle::Ascii) => "^",
+        _ => "⇡",
+    }
+}
+
+pub fn check_mark() -> &'static str {
+    match GRAPH_STYLE.get() {
+        Some(GraphStyle::Ascii) => "*",
+        _ => "✓",
+    }
+}
+
+pub fn lock() -> &'static str {
+    match GRAPH_STYLE.get() {
+        Some(GraphStyle::Ascii) => "#",
+        _ => "⊘",
+    }
+}
+
+pub fn left_arrow() -> &'static str {
+    match GRAPH_STYLE.get() {
+        Some(GraphStyle::Ascii) => "<-",
+        _ => "←",
+    }
+}
+
+fn selection_marker() -> &'static str {
+    match GRAPH_STYLE.get() {
+        Some(GraphStyle::Ascii) => ">",
+        _ => "▶",
+    }
+}
+
+/// Show the selected line of a list with `lazyjj.selection-style`. List lines start with a
+/// padding space, which the arrow replaces to keep the line aligned.
+pub fn highlight_line<'a>(mut line: Line<'a>, config: &Config) -> Line<'a> {
+    let style = match config.selection_style() {
+        SelectionStyle::Background => Style::new().bg(config.highlight_color()),
+        SelectionStyle::Reverse => Style::new().reversed(),
+        SelectionStyle::Arrow => {
+            let marker = Span::raw(selection_marker()).bold();
+            match line.spans.first_mut() {
+                Some(span) if span.content == " " => *span = marker,
+                _ => line.spans.insert(0, marker),
+            }
+            return line;
+        }
+    };
+
+    line = line.patch_style(style);
+    line.spans = line
+        .spans
+        .into_iter()
+        .map(|span| span.patch_style(style))
+        .collect();
+    line
+}
+
+/// Collapse a color to the minimal palette of `lazyjj.reduce-color`. Red, green and yellow have a
+/// meaning, such as removed and added lines, conflicts and warnings, and dark gray de-emphasizes.
+/// Other colors are decorative, and use the terminal's default.
+pub fn reduce_color(color: Color) -> Color {
+    match color {
+        Color::Red | Color::LightRed | Color::Indexed(1 | 9) => Color::Red,
+        Color::Green | Color::LightGreen | Color::Indexed(2 | 10) => Color::Green,
+        Color::Yellow | Color::LightYellow | Color::Indexed(3 | 11) => Color::Yellow,
+        Color::DarkGray | Color::Indexed(8) => Color::DarkGray,
+        _ => Color::Reset,
+    }
+}
+
+/// Apply `lazyjj.reduce-color` to text colors of a drawn frame. Backgrounds are kept, as they show
+/// the selection.
+pub fn reduce_buffer_colors(buffer: &mut Buffer) {
+    for cell in buffer.content.iter_mut() {
+        cell.fg = reduce_color(cell.fg);
+    }
+}
+
+// Colors for remotes, excluding yellow which is used for aliases
+const REMOTE_COLORS: [Color; 8] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Blue,
+    Color::Green,
+    Color::LightCyan,
+    Color::LightMagenta,
+    Color::LightBlue,
+    Color::LightGreen,
+];
+
+/// Color of a remote, from a hash of its name, so it's the same across sessions.
+pub fn remote_color(remote: &str) -> Color {
+    // FNV-1a, as std's hasher isn't stable across Rust versions
+    let hash = remote.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    REMOTE_COLORS[(hash % REMOTE_COLORS.len() as u64) as usize]
+}
+
 pub static POPUP_BLOCK: LazyLock<Block<'static>> = LazyLock::new(|| {
     Block::<'static>::bordered()
         .padding(Padding::horizontal(1))
-        .border_type(BorderType::Rounded)
+        .border_set(border_set())
         .border_style(Style::default().fg(Color::Green))
 });
 pub static POPUP_BLOCK_TITLE_STYLE: LazyLock<Style> = LazyLock::new(|| Style::new().bold().cyan());
@@ -21,3 +175,52 @@ pub fn create_popup_block(title: &str) -> Block {
         .title(Span::styled(format!(" {title} "), *POPUP_BLOCK_TITLE_STYLE))
         .title_alignment(Alignment::Center)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Config in the `jj config list` format read at startup
+    fn config(toml: &str) -> Config {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn highlight_selected_line() {
+        let line = || Line::from(vec![Span::raw(" "), Span::raw("change").fg(Color::Blue)]);
+
+        let highlighted = highlight_line(line(), &config(""));
+        assert_eq!(highlighted.spans.len(), 2);
+        assert_eq!(
+            highlighted.spans[1].style,
+            Style::new().fg(Color::Blue).bg(Color::Rgb(50, 50, 150))
+        );
+
+        let highlighted = highlight_line(line(), &config(r#""lazyjj.selection-style" = "arrow""#));
+        assert_eq!(highlighted.spans[0], Span::raw(selection_marker()).bold());
+        assert_eq!(highlighted.spans[1], Span::raw("change").fg(Color::Blue));
+
+        let highlighted =
+            highlight_line(line(), &config(r#""lazyjj.selection-style" = "reverse""#));
+        assert_eq!(
+            highlighted.spans[1].style,
+            Style::new().fg(Color::Blue).reversed()
+        );
+    }
+
+    #[test]
+    fn remote_colors() {
+        assert_eq!(remote_color("origin"), remote_color("origin"));
+        assert_ne!(remote_color("origin"), remote_color("upstream"));
+    }
+
+    #[test]
+    fn reduce_colors() {
+        assert_eq!(reduce_color(Color::LightRed), Color::Red);
+        assert_eq!(reduce_color(Color::Indexed(2)), Color::Green);
+        assert_eq!(reduce_color(Color::Yellow), Color::Yellow);
+        assert_eq!(reduce_color(Color::DarkGray), Color::DarkGray);
+        assert_eq!(reduce_color(Color::Magenta), Color::Reset);
+        assert_eq!(reduce_color(Color::Rgb(50, 50, 150)), Color::Reset);
+    }
+}
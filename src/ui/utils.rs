@@ -1,4 +1,68 @@
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
+pub mod binary;
+pub mod char_diff;
+pub mod fuzzy;
+pub mod graph;
+pub mod paths;
+pub mod refresh;
+pub mod revset_completions;
+pub mod side_by_side;
+pub mod stat;
+pub mod web_view;
+
+use std::borrow::Cow;
+
+use ansi_to_tui::IntoText;
+use chrono::{DateTime, Utc};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Stylize},
+    text::{Line, Text},
+};
+
+use crate::{commander::CommandError, env::DiffFormat, ui::FocusedPanel};
+
+/// Default width of a tab's left panel, in percent.
+pub const DEFAULT_SPLIT_PERCENT: u16 = 50;
+
+// Narrowest panel hint shown, below which it would be mostly cut
+const MIN_HINT_WIDTH: usize = 8;
+
+/// Get constraints for a tab's left and right panels, with the left panel taking
+/// `split_percent` of the width. A maximized panel takes the full width.
+pub fn panel_constraints(
+    maximized_panel: Option<FocusedPanel>,
+    split_percent: u16,
+) -> [Constraint; 2] {
+    match maximized_panel {
+        Some(FocusedPanel::Left) => [Constraint::Percentage(100), Constraint::Percentage(0)],
+        Some(FocusedPanel::Right) => [Constraint::Percentage(0), Constraint::Percentage(100)],
+        None => [
+            Constraint::Percentage(split_percent),
+            Constraint::Percentage(100 - split_percent),
+        ],
+    }
+}
+
+/// Whether a mouse position is on the border between a tab's panels, give or take a cell.
+pub fn is_on_panel_split(area: Rect, split_percent: u16, column: u16, row: u16) -> bool {
+    let [_, right] = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(panel_constraints(None, split_percent))
+        .areas(area);
+    // The left panel's border is the column before the right panel's
+    (area.top()..area.bottom()).contains(&row)
+        && (right.x.saturating_sub(2)..=right.x + 1).contains(&column)
+}
+
+/// Width of a tab's left panel to put the border between panels at a column, in percent. Kept
+/// between 20% and 80%, so neither panel gets too narrow.
+pub fn split_percent_at(area: Rect, column: u16) -> u16 {
+    if area.width == 0 {
+        return DEFAULT_SPLIT_PERCENT;
+    }
+    let offset = u32::from(column.saturating_sub(area.x));
+    ((offset * 100 / u32::from(area.width)) as u16).clamp(20, 80)
+}
 
 pub fn centered_rect(r: Rect, percent_x: u16, percent_y: u16) -> Rect {
     let popup_layout = Layout::default()
@@ -102,3 +166,388 @@ pub fn tabs_to_spaces(line: &str) -> String {
     }
     out
 }
+
+/// Replace a command's output after a refresh, keeping the previous output if the refresh failed
+/// and the previous output succeeded. Returns the refresh error in that case, to show the kept
+/// output as stale.
+pub fn keep_last_ok<T>(
+    output: &mut Result<T, CommandError>,
+    new_output: Result<T, CommandError>,
+) -> Option<CommandError> {
+    match new_output {
+        Err(err) if output.is_ok() => Some(err),
+        new_output => {
+            *output = new_output;
+            None
+        }
+    }
+}
+
+/// Index to select in a list of `len` entries after a refresh removed the selected entry: the same
+/// position clamped to the list, so the selection stays next to where it was, or the first entry
+/// without a previous selection. `None` if the list is now empty.
+pub fn recover_selection(previous_index: Option<usize>, len: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    Some(previous_index.unwrap_or(0).min(len - 1))
+}
+
+/// Toggle a prefix on a description, such as `wip: `: removed if the description starts with it,
+/// added otherwise. The rest of the description is kept as is.
+pub fn toggle_prefix(description: &str, prefix: &str) -> String {
+    match description.strip_prefix(prefix) {
+        Some(rest) => rest.to_owned(),
+        None => format!("{prefix}{description}"),
+    }
+}
+
+// Control characters which would reach the terminal. Tabs and new lines are laid out instead
+fn is_escaped_control(c: char) -> bool {
+    c.is_control() && c != '\n' && c != '\t'
+}
+
+/// Show control characters in caret notation, such as `^[` for escape, so text from users such as
+/// descriptions, file contents and command output can't style or move the terminal.
+pub fn escape_control_chars(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(is_escaped_control) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            c if !is_escaped_control(c) => escaped.push(c),
+            '\x7f' => escaped.push_str("^?"),
+            '\0'..='\x1f' => {
+                escaped.push('^');
+                escaped.push((c as u8 + b'@') as char);
+            }
+            // C1 controls have no caret notation
+            c => escaped.push_str(&format!("<U+{:04X}>", c as u32)),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Escape control characters left in text after parsing its ANSI colors, such as escapes which
+/// aren't colors.
+pub fn escape_text_control_chars(mut text: Text<'static>) -> Text<'static> {
+    for span in text.lines.iter_mut().flat_map(|line| line.spans.iter_mut()) {
+        if let Cow::Owned(escaped) = escape_control_chars(&span.content) {
+            span.content = escaped.into();
+        }
+    }
+    text
+}
+
+/// Parse jj's colored output into text, with any other control characters escaped.
+pub fn ansi_text(output: &str) -> Result<Text<'static>, ansi_to_tui::Error> {
+    output.into_text().map(escape_text_control_chars)
+}
+
+/// Parse jj's colored diff output into lines fitted to a panel width: `--stat` graphs are redrawn
+/// for the width, and paths are shortened if `lazyjj.shorten-paths` is set.
+pub fn fit_diff_lines(
+    output: &str,
+    diff_format: &DiffFormat,
+    width: usize,
+    shorten_paths: bool,
+) -> Result<Vec<Line<'static>>, ansi_to_tui::Error> {
+    let mut lines = ansi_text(output)?.lines;
+    if *diff_format == DiffFormat::Stat {
+        lines = stat::fit_stat_graphs(lines, width);
+    }
+    if shorten_paths {
+        lines = paths::shorten_diff_paths(lines, diff_format, width);
+    }
+    Ok(lines)
+}
+
+/// Banner shown above stale output, with the first line of the refresh error.
+pub fn stale_banner(err: &CommandError, hint: &str) -> Line<'static> {
+    let message = err
+        .to_string()
+        .into_text()
+        .ok()
+        .and_then(|text| {
+            text.lines
+                .iter()
+                .map(ToString::to_string)
+                .find(|line| !line.trim().is_empty())
+        })
+        .unwrap_or_default();
+
+    Line::raw(format!(
+        " Refresh failed, showing stale results: {} {hint}",
+        message.trim()
+    ))
+    .fg(Color::White)
+    .bg(Color::Red)
+    .bold()
+}
+
+/// Dim hint in a panel's bottom border, such as what Enter does for the selection. Cut with `…`
+/// to fit half of the panel's width, so it doesn't cover titles on the left. `None` if too narrow
+/// to be readable.
+pub fn panel_hint(hint: &str, width: u16) -> Option<Line<'static>> {
+    // Padding spaces around the hint
+    let text_width = usize::from(width / 2).saturating_sub(2);
+    if text_width < MIN_HINT_WIDTH {
+        return None;
+    }
+
+    let text = if hint.chars().count() <= text_width {
+        hint.to_owned()
+    } else {
+        hint.chars().take(text_width - 1).collect::<String>() + "…"
+    };
+    Some(
+        Line::raw(format!(" {text} "))
+            .fg(Color::DarkGray)
+            .right_aligned(),
+    )
+}
+
+/// Diff format cycled to with `w`: the builtin formats, then the diff tools, then back to the
+/// first builtin format.
+fn cycle_diff_format(
+    diff_format: &DiffFormat,
+    builtin: &[DiffFormat],
+    tools: &[String],
+) -> DiffFormat {
+    let formats: Vec<DiffFormat> = builtin
+        .iter()
+        .cloned()
+        .chain(tools.iter().cloned().map(DiffFormat::DiffTool))
+        .collect();
+    formats
+        .iter()
+        .position(|format| format == diff_format)
+        .and_then(|index| formats.get(index + 1))
+        .unwrap_or(&builtin[0])
+        .clone()
+}
+
+/// Diff format cycled to with `w` in all tabs: color words, Git, a summary, then the diff tools.
+pub fn next_diff_format(diff_format: &DiffFormat, tools: &[String]) -> DiffFormat {
+    cycle_diff_format(
+        diff_format,
+        &[DiffFormat::ColorWords, DiffFormat::Git, DiffFormat::Summary],
+        tools,
+    )
+}
+
+/// Format a time relative to now, such as "3 minutes ago".
+pub fn format_relative_time(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let seconds = (now - time).num_seconds();
+    if seconds < 60 {
+        return "less than a minute ago".to_owned();
+    }
+
+    let (value, unit) = match seconds {
+        60..3600 => (seconds / 60, "minute"),
+        3600..86400 => (seconds / 3600, "hour"),
+        86400..604800 => (seconds / 86400, "day"),
+        604800..2592000 => (seconds / 604800, "week"),
+        2592000..31536000 => (seconds / 2592000, "month"),
+        _ => (seconds / 31536000, "year"),
+    };
+
+    if value == 1 {
+        format!("1 {unit} ago")
+    } else {
+        format!("{value} {unit}s ago")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    #[test]
+    fn drag_panel_split() {
+        let area = Rect::new(10, 3, 100, 20);
+
+        // The right panel starts at column 60, after its left panel's border at 59
+        assert!(is_on_panel_split(area, 50, 59, 10));
+        assert!(is_on_panel_split(area, 50, 60, 10));
+        assert!(is_on_panel_split(area, 50, 58, 10));
+        assert!(is_on_panel_split(area, 50, 61, 10));
+        assert!(!is_on_panel_split(area, 50, 62, 10));
+        assert!(!is_on_panel_split(area, 50, 60, 23));
+        assert!(is_on_panel_split(area, 30, 40, 3));
+
+        assert_eq!(split_percent_at(area, 45), 35);
+        assert_eq!(split_percent_at(area, 12), 20);
+        assert_eq!(split_percent_at(area, 105), 80);
+        assert_eq!(split_percent_at(Rect::default(), 5), DEFAULT_SPLIT_PERCENT);
+    }
+
+    #[test]
+    fn keep_last_ok_output() {
+        let error =
+            || CommandError::Status("Error: Revision doesn't exist".to_owned(), Some(1), None);
+
+        let mut output = Ok(1);
+        assert!(keep_last_ok(&mut output, Ok(2)).is_none());
+        assert_eq!(output.as_ref().ok(), Some(&2));
+
+        // Keeps the previous output
+        assert!(keep_last_ok(&mut output, Err(error())).is_some());
+        assert_eq!(output.as_ref().ok(), Some(&2));
+
+        // No previous output to keep
+        let mut output: Result<i32, CommandError> = Err(error());
+        assert!(keep_last_ok(&mut output, Err(error())).is_none());
+        assert!(output.is_err());
+        assert!(keep_last_ok(&mut output, Ok(3)).is_none());
+        assert_eq!(output.as_ref().ok(), Some(&3));
+    }
+
+    #[test]
+    fn fit_panel_hint() {
+        let hint =
+            |width| panel_hint("Enter: view files of kxryzmor", width).map(|line| line.to_string());
+        assert_eq!(hint(80).as_deref(), Some(" Enter: view files of kxryzmor "));
+        assert_eq!(hint(40).as_deref(), Some(" Enter: view files… "));
+        assert_eq!(hint(19), None);
+    }
+
+    #[test]
+    fn cycle_diff_tools() {
+        let builtin = [DiffFormat::ColorWords, DiffFormat::Git];
+        let tools = ["difft".to_owned(), "delta".to_owned()];
+        let tool = |name: &str| DiffFormat::DiffTool(name.to_owned());
+
+        assert_eq!(
+            cycle_diff_format(&DiffFormat::Git, &builtin, &tools),
+            tool("difft")
+        );
+        assert_eq!(
+            cycle_diff_format(&tool("difft"), &builtin, &tools),
+            tool("delta")
+        );
+        assert_eq!(
+            cycle_diff_format(&tool("delta"), &builtin, &tools),
+            DiffFormat::ColorWords
+        );
+        // Unavailable tools are skipped
+        assert_eq!(
+            cycle_diff_format(&tool("difft"), &builtin, &tools[1..]),
+            DiffFormat::ColorWords
+        );
+    }
+
+    #[test]
+    fn recover_selections() {
+        // Removed the first of [a, b, c] while selected, now [b, c]
+        assert_eq!(recover_selection(Some(0), 2), Some(0));
+        // Removed the middle, now [a, c]
+        assert_eq!(recover_selection(Some(1), 2), Some(1));
+        // Removed the last, now [a, b]
+        assert_eq!(recover_selection(Some(2), 2), Some(1));
+        // Removed the only one
+        assert_eq!(recover_selection(Some(0), 0), None);
+        assert_eq!(recover_selection(None, 0), None);
+        // Nothing was selected
+        assert_eq!(recover_selection(None, 3), Some(0));
+    }
+
+    #[test]
+    fn escape_control_characters() {
+        assert!(matches!(
+            escape_control_chars("plain\ttext\n"),
+            Cow::Borrowed("plain\ttext\n")
+        ));
+        assert_eq!(
+            escape_control_chars("\x1b[31mred\x1b[0m\r\x07\x7f\u{9b}"),
+            "^[[31mred^[[0m^M^G^?<U+009B>"
+        );
+
+        // Colors are parsed, and what's left is shown
+        let text = ansi_text("\x1b[31mred\x1b[0m \x1b]0;title\x07\x08").unwrap();
+        assert_eq!(text.lines[0].spans[0].style.fg, Some(Color::Red));
+        assert!(text
+            .lines
+            .iter()
+            .flat_map(|line| line.spans.iter())
+            .all(|span| !span.content.chars().any(is_escaped_control)));
+    }
+
+    #[test]
+    fn toggle_prefixes() {
+        assert_eq!(toggle_prefix("Add parser", "wip: "), "wip: Add parser");
+        assert_eq!(toggle_prefix("wip: Add parser", "wip: "), "Add parser");
+        assert_eq!(toggle_prefix("", "wip: "), "wip: ");
+        assert_eq!(toggle_prefix("wip: ", "wip: "), "");
+        // Only a prefix of the first line counts
+        assert_eq!(
+            toggle_prefix("Add parser\n\nwip: body\n", "wip: "),
+            "wip: Add parser\n\nwip: body\n"
+        );
+        // Toggling twice gives back the same description
+        let description = "WIP: Fix  typo \r\n\n  trailing\n\n";
+        assert_eq!(
+            toggle_prefix(&toggle_prefix(description, "wip: "), "wip: "),
+            description
+        );
+    }
+
+    #[test]
+    fn next_diff_formats() {
+        // Without diff tools
+        assert_eq!(
+            next_diff_format(&DiffFormat::ColorWords, &[]),
+            DiffFormat::Git
+        );
+        assert_eq!(next_diff_format(&DiffFormat::Git, &[]), DiffFormat::Summary);
+        assert_eq!(
+            next_diff_format(&DiffFormat::Summary, &[]),
+            DiffFormat::ColorWords
+        );
+        // Formats outside of the cycle go back to the start
+        assert_eq!(
+            next_diff_format(&DiffFormat::Stat, &[]),
+            DiffFormat::ColorWords
+        );
+        assert_eq!(
+            next_diff_format(&DiffFormat::SideBySide, &[]),
+            DiffFormat::ColorWords
+        );
+
+        // With a diff tool
+        let tools = ["difft".to_owned()];
+        assert_eq!(
+            next_diff_format(&DiffFormat::Git, &tools),
+            DiffFormat::Summary
+        );
+        assert_eq!(
+            next_diff_format(&DiffFormat::Summary, &tools),
+            DiffFormat::DiffTool("difft".to_owned())
+        );
+        assert_eq!(
+            next_diff_format(&DiffFormat::DiffTool("difft".to_owned()), &tools),
+            DiffFormat::ColorWords
+        );
+    }
+
+    #[test]
+    fn relative_time() {
+        let now = Utc::now();
+        let format = |delta: TimeDelta| format_relative_time(now - delta, now);
+
+        assert_eq!(format(TimeDelta::seconds(-5)), "less than a minute ago");
+        assert_eq!(format(TimeDelta::seconds(59)), "less than a minute ago");
+        assert_eq!(format(TimeDelta::seconds(60)), "1 minute ago");
+        assert_eq!(format(TimeDelta::minutes(59)), "59 minutes ago");
+        assert_eq!(format(TimeDelta::hours(1)), "1 hour ago");
+        assert_eq!(format(TimeDelta::hours(23)), "23 hours ago");
+        assert_eq!(format(TimeDelta::days(2)), "2 days ago");
+        assert_eq!(format(TimeDelta::weeks(3)), "3 weeks ago");
+        assert_eq!(format(TimeDelta::days(65)), "2 months ago");
+        assert_eq!(format(TimeDelta::days(800)), "2 years ago");
+    }
+}
@@ -0,0 +1,189 @@
+use ratatui::{
+    style::{Color, Stylize},
+    text::Line,
+};
+
+use crate::commander::{files::DiffType, log::ANSI_REGEX};
+
+// Extensions of files which are usually binary, for diff tools which print them as is
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "bmp", "ico", "tiff", "pdf", "zip", "gz", "tgz", "xz",
+    "zst", "bz2", "7z", "jar", "class", "wasm", "exe", "dll", "so", "dylib", "o", "a", "woff",
+    "woff2", "ttf", "otf", "mp3", "mp4", "mov", "wav", "ogg", "sqlite",
+];
+
+/// Whether a file diff is of a binary file: jj's `(binary)` notice, Git's `Binary files ...
+/// differ`, or raw bytes printed by a diff tool.
+pub fn is_binary_diff(diff: &str) -> bool {
+    diff.contains('\0')
+        || ANSI_REGEX.replace_all(diff, "").lines().any(|line| {
+            let line = line.trim();
+            line == "(binary)" || (line.starts_with("Binary files ") && line.ends_with(" differ"))
+        })
+}
+
+/// Whether a path is of a file which is usually binary, by its extension.
+pub fn is_binary_path(path: &str) -> bool {
+    path.rsplit_once('.').is_some_and(|(_, extension)| {
+        BINARY_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+    })
+}
+
+/// Width and height of a PNG or GIF image, from its header.
+pub fn image_dimensions(content: &[u8]) -> Option<(u32, u32)> {
+    if content.starts_with(b"\x89PNG\r\n\x1a\n") && content.get(12..16) == Some(b"IHDR") {
+        let width = u32::from_be_bytes(content.get(16..20)?.try_into().ok()?);
+        let height = u32::from_be_bytes(content.get(20..24)?.try_into().ok()?);
+        return Some((width, height));
+    }
+    if content.starts_with(b"GIF87a") || content.starts_with(b"GIF89a") {
+        let width = u16::from_le_bytes(content.get(6..8)?.try_into().ok()?);
+        let height = u16::from_le_bytes(content.get(8..10)?.try_into().ok()?);
+        return Some((width.into(), height.into()));
+    }
+    None
+}
+
+/// Size in bytes, or in KiB/MiB with one decimal when larger.
+pub fn format_size(bytes: usize) -> String {
+    match bytes {
+        0..1024 => format!("{bytes} bytes"),
+        1024..1_048_576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// Size of a version of a binary file, and its dimensions if it's an image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BinaryVersion {
+    pub size: usize,
+    pub dimensions: Option<(u32, u32)>,
+}
+
+impl BinaryVersion {
+    pub fn new(content: &[u8]) -> Self {
+        Self {
+            size: content.len(),
+            dimensions: image_dimensions(content),
+        }
+    }
+}
+
+/// Binary file of a change, shown instead of its diff. Versions are missing if the file doesn't
+/// exist on that side, or couldn't be read.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BinaryFile {
+    pub path: String,
+    pub diff_type: Option<DiffType>,
+    pub before: Option<BinaryVersion>,
+    pub after: Option<BinaryVersion>,
+}
+
+impl BinaryFile {
+    pub fn lines(&self) -> Vec<Line<'static>> {
+        let change = match self.diff_type {
+            Some(DiffType::Added) => "added",
+            Some(DiffType::Modified) => "modified",
+            Some(DiffType::Deleted) => "deleted",
+            Some(DiffType::Renamed) => "renamed",
+            Some(DiffType::Copied) => "copied",
+            None => "changed",
+        };
+        let version = |label: &str, version: Option<&BinaryVersion>| {
+            let text = match version {
+                Some(BinaryVersion {
+                    size,
+                    dimensions: Some((width, height)),
+                }) => format!("{}, {width}×{height} px", format_size(*size)),
+                Some(version) => format_size(version.size),
+                None => "-".to_owned(),
+            };
+            Line::raw(format!("{label}: {text}"))
+        };
+
+        vec![
+            Line::raw(self.path.clone()).bold(),
+            Line::raw(format!("Binary file {change}, so no diff is shown")).fg(Color::DarkGray),
+            Line::default(),
+            version("Before", self.before.as_ref()),
+            version("After", self.after.as_ref()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 2×3 PNG, up to its header
+    const PNG: &[u8] =
+        b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x02\0\0\0\x03\x08\x06\0\0\0\x72\x86\x9d\x1b";
+
+    #[test]
+    fn detect_binary_diffs() {
+        // Captured from `jj diff` of an added image
+        assert!(is_binary_diff(
+            "\x1b[38;5;3mAdded regular file logo.png:\x1b[39m\n    \x1b[2m(binary)\x1b[0m\n"
+        ));
+        assert!(is_binary_diff(
+            "diff --git a/logo.png b/logo.png\nnew file mode 100644\nindex 0000000000..c7d4e5f6a7\nBinary files /dev/null and b/logo.png differ\n"
+        ));
+        // Colon separated SGR parameters, such as for true color
+        assert!(is_binary_diff(
+            "Added regular file logo.png:\n    \x1b[38:2::255:0:0m(binary)\x1b[0m\n"
+        ));
+        assert!(is_binary_diff("\u{89}PNG\r\n\u{1a}\n\0\0\0\rIHDR"));
+
+        assert!(!is_binary_diff(
+            "Modified regular file README.md:\n   1    1: Binary files are (binary)"
+        ));
+        assert!(!is_binary_diff("+Binary files a and b differ in size"));
+    }
+
+    #[test]
+    fn detect_binary_paths() {
+        assert!(is_binary_path("assets/logo.png"));
+        assert!(is_binary_path("Photo.JPG"));
+        assert!(!is_binary_path("src/main.rs"));
+        assert!(!is_binary_path("png"));
+    }
+
+    #[test]
+    fn read_image_dimensions() {
+        assert_eq!(image_dimensions(PNG), Some((2, 3)));
+        assert_eq!(
+            image_dimensions(b"GIF89a\x40\x01\xf0\x00"),
+            Some((320, 240))
+        );
+        assert_eq!(image_dimensions(&PNG[..20]), None);
+        assert_eq!(image_dimensions(b"\0\x01\x02"), None);
+    }
+
+    #[test]
+    fn binary_file_lines() {
+        let file = BinaryFile {
+            path: "assets/logo.png".to_owned(),
+            diff_type: Some(DiffType::Modified),
+            before: Some(BinaryVersion {
+                size: 512,
+                dimensions: None,
+            }),
+            after: Some(BinaryVersion::new(&[PNG, &[0; 2048]].concat())),
+        };
+        assert_eq!(
+            file.lines()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "assets/logo.png",
+                "Binary file modified, so no diff is shown",
+                "",
+                "Before: 512 bytes",
+                "After: 2.0 KiB, 2×3 px",
+            ]
+        );
+
+        assert_eq!(format_size(5 * 1_048_576 + 1), "5.0 MiB");
+    }
+}
@@ -0,0 +1,245 @@
+use ratatui::{
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span, Text},
+};
+
+use crate::ui::utils::escape_control_chars;
+
+// Longest lines compared character by character, as the comparison is quadratic. Longer lines
+// are highlighted as a whole
+const MAX_LINE_LENGTH: usize = 500;
+
+/// Mark which characters of `old` and `new` are unchanged, from their longest common subsequence.
+pub fn diff_chars(old: &[char], new: &[char]) -> (Vec<bool>, Vec<bool>) {
+    // Common prefix and suffix don't need the table
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut old_unchanged = vec![true; old.len()];
+    let mut new_unchanged = vec![true; new.len()];
+    old_unchanged[prefix..old.len() - suffix].fill(false);
+    new_unchanged[prefix..new.len() - suffix].fill(false);
+
+    if old_middle.len() > MAX_LINE_LENGTH || new_middle.len() > MAX_LINE_LENGTH {
+        return (old_unchanged, new_unchanged);
+    }
+
+    // Length of the longest common subsequence of the ends of both
+    let mut lengths = vec![vec![0usize; new_middle.len() + 1]; old_middle.len() + 1];
+    for i in (0..old_middle.len()).rev() {
+        for j in (0..new_middle.len()).rev() {
+            lengths[i][j] = if old_middle[i] == new_middle[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < old_middle.len() && j < new_middle.len() {
+        if old_middle[i] == new_middle[j] {
+            old_unchanged[prefix + i] = true;
+            new_unchanged[prefix + j] = true;
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    (old_unchanged, new_unchanged)
+}
+
+// Line with changed characters bold and underlined, after the `-`/`+` marker
+fn char_diff_line(marker: char, chars: &[char], unchanged: &[bool], color: Color) -> Line<'static> {
+    let changed_style = Style::new()
+        .fg(color)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut spans = vec![Span::raw(marker.to_string()).fg(color)];
+    for (is_unchanged, group) in chars
+        .iter()
+        .zip(unchanged)
+        .collect::<Vec<_>>()
+        .chunk_by(|(_, a), (_, b)| a == b)
+        .map(|group| {
+            (
+                *group[0].1,
+                group.iter().map(|(c, _)| **c).collect::<String>(),
+            )
+        })
+    {
+        spans.push(if is_unchanged {
+            Span::raw(group).fg(color)
+        } else {
+            Span::styled(group, changed_style)
+        });
+    }
+    Line::from(spans)
+}
+
+// Push removed and added lines, comparing them pairwise by position
+fn push_changes(lines: &mut Vec<Line<'static>>, removed: &mut Vec<&str>, added: &mut Vec<&str>) {
+    let mut removed_lines = vec![];
+    let mut added_lines = vec![];
+    for i in 0..removed.len().max(added.len()) {
+        let old: Vec<char> = removed.get(i).map_or(vec![], |text| text.chars().collect());
+        let new: Vec<char> = added.get(i).map_or(vec![], |text| text.chars().collect());
+        let (old_unchanged, new_unchanged) = match (removed.get(i), added.get(i)) {
+            (Some(_), Some(_)) => diff_chars(&old, &new),
+            // Nothing to compare with, so the whole line changed
+            _ => (vec![false; old.len()], vec![false; new.len()]),
+        };
+        if i < removed.len() {
+            removed_lines.push(char_diff_line('-', &old, &old_unchanged, Color::Red));
+        }
+        if i < added.len() {
+            added_lines.push(char_diff_line('+', &new, &new_unchanged, Color::Green));
+        }
+    }
+
+    lines.append(&mut removed_lines);
+    lines.append(&mut added_lines);
+    removed.clear();
+    added.clear();
+}
+
+/// Render a `--git` diff without color, highlighting the characters which changed within each
+/// changed line. Removed and added lines of a hunk are compared in order.
+pub fn char_diff_text(diff: &str) -> Text<'static> {
+    let mut lines = vec![];
+    let mut in_hunk = false;
+    let mut removed: Vec<&str> = vec![];
+    let mut added: Vec<&str> = vec![];
+
+    // The diff is uncolored, so file contents are escaped as a whole
+    let diff = escape_control_chars(diff);
+    for line in diff.lines() {
+        if in_hunk {
+            if let Some(text) = line.strip_prefix('-') {
+                removed.push(text);
+                continue;
+            }
+            if let Some(text) = line.strip_prefix('+') {
+                added.push(text);
+                continue;
+            }
+            push_changes(&mut lines, &mut removed, &mut added);
+
+            if line.starts_with(' ') || line.is_empty() {
+                lines.push(Line::raw(line.to_owned()));
+                continue;
+            }
+            if line.starts_with('\\') {
+                // No newline at end of file
+                lines.push(Line::raw(line.to_owned()).fg(Color::DarkGray));
+                continue;
+            }
+            in_hunk = false;
+        }
+
+        if line.starts_with("@@ ") {
+            in_hunk = true;
+            lines.push(Line::raw(line.to_owned()).fg(Color::Cyan));
+        } else if line.starts_with("diff --git ") {
+            lines.push(Line::raw(line.to_owned()).fg(Color::Yellow).bold());
+        } else {
+            lines.push(Line::raw(line.to_owned()));
+        }
+    }
+    push_changes(&mut lines, &mut removed, &mut added);
+
+    Text::from(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    // Changed characters of a line, in brackets
+    fn changed(chars: &[char], unchanged: &[bool]) -> String {
+        chars
+            .iter()
+            .zip(unchanged)
+            .map(|(c, unchanged)| {
+                if *unchanged {
+                    c.to_string()
+                } else {
+                    format!("[{c}]")
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diff_characters() {
+        let (old, new) = (chars("let colour = 1;"), chars("let color = 10;"));
+        let (old_unchanged, new_unchanged) = diff_chars(&old, &new);
+        assert_eq!(changed(&old, &old_unchanged), "let colo[u]r = 1;");
+        assert_eq!(changed(&new, &new_unchanged), "let color = 1[0];");
+
+        let (old, new) = (chars("same"), chars("same"));
+        let (old_unchanged, new_unchanged) = diff_chars(&old, &new);
+        assert!(old_unchanged.iter().chain(&new_unchanged).all(|u| *u));
+
+        let (old, new) = (chars(""), chars("new"));
+        let (_, new_unchanged) = diff_chars(&old, &new);
+        assert_eq!(changed(&new, &new_unchanged), "[n][e][w]");
+    }
+
+    #[test]
+    fn char_diff() {
+        let diff = "\
+diff --git a/README b/README
+index 1111111..2222222 100644
+--- a/README
++++ b/README
+@@ -1,2 +1,3 @@
+ unchanged
+-hello world
++hello wordl
++added
+";
+        let text = char_diff_text(diff);
+        let lines: Vec<String> = text.lines.iter().map(|line| line.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "diff --git a/README b/README",
+                "index 1111111..2222222 100644",
+                "--- a/README",
+                "+++ b/README",
+                "@@ -1,2 +1,3 @@",
+                " unchanged",
+                "-hello world",
+                "+hello wordl",
+                "+added",
+            ]
+        );
+
+        // Only the swapped characters are highlighted
+        let highlighted = |line: &Line| {
+            line.spans
+                .iter()
+                .filter(|span| span.style.add_modifier.contains(Modifier::UNDERLINED))
+                .map(|span| span.content.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(highlighted(&text.lines[6]), ["l"]);
+        assert_eq!(highlighted(&text.lines[7]), ["l"]);
+        assert_eq!(highlighted(&text.lines[8]), ["added"]);
+    }
+}
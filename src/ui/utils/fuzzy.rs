@@ -0,0 +1,109 @@
+// Scores used by fuzzy_score, similar to a Smith-Waterman local alignment where every character
+// of the pattern must be matched
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 8;
+const BONUS_BOUNDARY: i64 = 8;
+const PENALTY_GAP: i64 = 1;
+
+fn is_boundary(previous: Option<char>, current: char) -> bool {
+    match previous {
+        None => true,
+        Some(previous) => {
+            matches!(previous, '-' | '_' | '/' | '.' | ' ' | '@')
+                || (previous.is_lowercase() && current.is_uppercase())
+        }
+    }
+}
+
+/// Score how well the pattern fuzzy-matches the text, case-insensitively. Higher is better.
+/// Returns `None` if the text doesn't contain every character of the pattern in order.
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let to_lower = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let pattern: Vec<char> = pattern.chars().map(to_lower).collect();
+    let text_chars: Vec<char> = text.chars().collect();
+    let text_lower: Vec<char> = text_chars.iter().copied().map(to_lower).collect();
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    // Best score for the previous pattern character being matched at each text position
+    let mut previous_row: Vec<Option<i64>> = vec![None; text_lower.len()];
+    for (i, pattern_char) in pattern.iter().enumerate() {
+        let mut row: Vec<Option<i64>> = vec![None; text_lower.len()];
+        // Best score of the previous row before the current position, with gaps penalized
+        let mut best_before: Option<i64> = None;
+
+        for (j, text_char) in text_lower.iter().enumerate() {
+            if text_char == pattern_char {
+                let score = if i == 0 {
+                    Some(0)
+                } else {
+                    let consecutive = j
+                        .checked_sub(1)
+                        .and_then(|j| previous_row[j])
+                        .map(|score| score + BONUS_CONSECUTIVE);
+                    consecutive.max(best_before)
+                };
+
+                let boundary = is_boundary(j.checked_sub(1).map(|j| text_chars[j]), text_chars[j]);
+                row[j] = score
+                    .map(|score| score + SCORE_MATCH + if boundary { BONUS_BOUNDARY } else { 0 });
+            }
+
+            best_before = best_before
+                .map(|score| score - PENALTY_GAP)
+                .max(previous_row[j]);
+        }
+
+        previous_row = row;
+    }
+
+    previous_row.into_iter().flatten().max()
+}
+
+/// Filter texts by fuzzy-matching the pattern. Returns indexes of matching texts, best first.
+/// Ties keep their original order.
+pub fn fuzzy_filter<'a>(pattern: &str, texts: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+    let mut scores: Vec<(usize, i64)> = texts
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, text)| fuzzy_score(pattern, text).map(|score| (i, score)))
+        .collect();
+    scores.sort_by_key(|(_, score)| -score);
+    scores.into_iter().map(|(i, _)| i).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_score_matches() {
+        assert_eq!(fuzzy_score("", "main"), Some(0));
+        assert!(fuzzy_score("mn", "main").is_some());
+        assert!(fuzzy_score("MAIN", "main").is_some());
+        assert!(fuzzy_score("nm", "main").is_none());
+        assert!(fuzzy_score("mainx", "main").is_none());
+    }
+
+    #[test]
+    fn fuzzy_score_ranking() {
+        // Consecutive matches are better than scattered matches
+        assert!(fuzzy_score("foo", "foo-bar") > fuzzy_score("foo", "f-o-o"));
+        // Matches at word boundaries are better
+        assert!(fuzzy_score("fb", "feature-bar") > fuzzy_score("fb", "xfxbx"));
+        assert!(fuzzy_score("fb", "fooBar") > fuzzy_score("fb", "foobar"));
+        // Smaller gaps are better
+        assert!(fuzzy_score("ab", "axb") > fuzzy_score("ab", "axxxxb"));
+    }
+
+    #[test]
+    fn fuzzy_filter_sorts() {
+        let texts = ["push-abc", "feature-bar", "fix-bug", "main"];
+
+        assert_eq!(fuzzy_filter("", texts), [0, 1, 2, 3]);
+        assert_eq!(fuzzy_filter("fb", texts), [2, 1]);
+        assert_eq!(fuzzy_filter("bug", texts), [2]);
+        assert!(fuzzy_filter("xyz", texts).is_empty());
+    }
+}
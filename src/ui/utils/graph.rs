@@ -0,0 +1,281 @@
+use crate::{commander::log::Head, env::GraphNodes};
+
+// ASCII graph symbols from jj's `ascii` and `ascii-large` graph styles, and their Unicode
+// replacements
+const GRAPH_SYMBOLS: [(char, char); 6] = [
+    ('|', '│'),
+    ('/', '╯'),
+    ('\\', '╮'),
+    ('-', '─'),
+    ('o', '◉'),
+    ('@', '◎'),
+];
+
+fn is_graph_symbol(c: char) -> bool {
+    GRAPH_SYMBOLS.iter().any(|(ascii, _)| *ascii == c)
+}
+
+// Number of visible characters at the start of a line which are part of the graph. The graph is
+// separated from the change by 2 spaces, so stop after a symbol followed by 2 spaces, or at the
+// first character which isn't a graph symbol
+fn graph_width(chars: &[char]) -> usize {
+    for (i, c) in chars.iter().enumerate() {
+        if *c == ' ' {
+            continue;
+        }
+        if !is_graph_symbol(*c) {
+            return i;
+        }
+        if chars.get(i + 1) == Some(&' ') && chars.get(i + 2) == Some(&' ') {
+            return i + 1;
+        }
+    }
+    chars.len()
+}
+
+/// Replace ASCII graph symbols of `jj log` output with Unicode box-drawing characters. Only the
+/// graph at the start of each line is changed, not the changes' text. ANSI escape codes are kept.
+pub fn unicode_graph(graph: &str) -> String {
+    graph
+        .split_inclusive('\n')
+        .map(|line| {
+            // Visible characters, skipping escape codes
+            let mut visible = vec![];
+            let mut in_escape = false;
+            for c in line.chars() {
+                if in_escape {
+                    in_escape = !c.is_ascii_alphabetic();
+                } else if c == '\x1b' {
+                    in_escape = true;
+                } else {
+                    visible.push(c);
+                }
+            }
+            let mut remaining = graph_width(&visible);
+
+            let mut in_escape = false;
+            line.chars()
+                .map(|c| {
+                    if in_escape {
+                        in_escape = !c.is_ascii_alphabetic();
+                        return c;
+                    }
+                    if c == '\x1b' {
+                        in_escape = true;
+                        return c;
+                    }
+                    if remaining == 0 {
+                        return c;
+                    }
+                    remaining -= 1;
+                    GRAPH_SYMBOLS
+                        .iter()
+                        .find(|(ascii, _)| *ascii == c)
+                        .map_or(c, |(_, unicode)| *unicode)
+                })
+                .collect::<String>()
+        })
+        .collect()
+}
+
+// Nodes of the working copy, as rendered by jj and after `unicode_graph`
+const WORKING_COPY_NODES: [char; 2] = ['@', '◎'];
+
+// Characters of the graph's edges, and of elided changes, in both graph styles
+fn is_graph_edge(c: char) -> bool {
+    matches!(
+        c,
+        '│' | '|'
+            | '╯'
+            | '╮'
+            | '╭'
+            | '╰'
+            | '─'
+            | '-'
+            | '/'
+            | '\\'
+            | '.'
+            | '├'
+            | '┤'
+            | '┬'
+            | '┴'
+            | '┼'
+            | '~'
+            | ':'
+            | '┆'
+    )
+}
+
+// Replace the node of a change's first line: the first visible character which isn't part of an
+// edge
+fn replace_node(line: &str, head: &Head, nodes: &GraphNodes) -> String {
+    let mut replaced = false;
+    let mut in_escape = false;
+    let mut output = String::with_capacity(line.len());
+    for c in line.chars() {
+        if replaced || in_escape {
+            in_escape = in_escape && !c.is_ascii_alphabetic();
+            output.push(c);
+            continue;
+        }
+        if c == '\x1b' {
+            in_escape = true;
+            output.push(c);
+            continue;
+        }
+        if c == ' ' || is_graph_edge(c) {
+            output.push(c);
+            continue;
+        }
+
+        replaced = true;
+        let node = if WORKING_COPY_NODES.contains(&c) {
+            nodes.working_copy.as_ref()
+        } else if head.conflict {
+            nodes.conflicted.as_ref()
+        } else if head.immutable {
+            nodes.immutable.as_ref()
+        } else {
+            nodes.normal.as_ref()
+        };
+        match node {
+            Some(node) => output.push_str(node),
+            None => output.push(c),
+        }
+    }
+    output
+}
+
+/// Replace the node of each change in the `jj log` graph with the symbol for its kind, from
+/// `lazyjj.graph-node-*`. Heads map each line of the graph to its change, and the node is on the
+/// first line of each change. ANSI escape codes are kept.
+pub fn replace_graph_nodes(
+    graph: &str,
+    graph_heads: &[Option<Head>],
+    nodes: &GraphNodes,
+) -> String {
+    graph
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            let head = graph_heads.get(i).and_then(Option::as_ref);
+            let previous_head = i
+                .checked_sub(1)
+                .and_then(|i| graph_heads.get(i))
+                .and_then(Option::as_ref);
+            match head {
+                Some(head) if previous_head != Some(head) => replace_node(line, head, nodes),
+                _ => line.to_owned(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::ids::{ChangeId, CommitId};
+
+    #[test]
+    fn convert_graph() {
+        let graph = "\
+@  kxryzmor 2 minutes ago 4e9b3b2c
+|  (no description set)
+| o  vruxwmqv 1 hour ago 5c0a7a1d
+|/   oops - fix typo
+o  zzzzzzzz root() 00000000
+";
+        assert_eq!(
+            unicode_graph(graph),
+            "\
+◎  kxryzmor 2 minutes ago 4e9b3b2c
+│  (no description set)
+│ ◉  vruxwmqv 1 hour ago 5c0a7a1d
+│╯   oops - fix typo
+◉  zzzzzzzz root() 00000000
+"
+        );
+    }
+
+    #[test]
+    fn convert_graph_merge() {
+        assert_eq!(unicode_graph("o-.\n|\\ \\\n"), "◉─.\n│╮ ╮\n");
+    }
+
+    #[test]
+    fn convert_graph_keeps_escape_codes() {
+        assert_eq!(
+            unicode_graph("\x1b[1m\x1b[38;5;2m@\x1b[0m  \x1b[1m\x1b[38;5;5mk\x1b[0m o"),
+            "\x1b[1m\x1b[38;5;2m◎\x1b[0m  \x1b[1m\x1b[38;5;5mk\x1b[0m o"
+        );
+    }
+
+    fn head(id: &str, immutable: bool, conflict: bool) -> Option<Head> {
+        Some(Head {
+            change_id: ChangeId(id.to_owned()),
+            commit_id: CommitId(id.to_owned()),
+            divergent: false,
+            immutable,
+            hidden: false,
+            conflict,
+            empty: false,
+        })
+    }
+
+    #[test]
+    fn replace_nodes() {
+        let graph = "\
+@  kxryzmor
+│  (no description set)
+│ ○  vruxwmqv
+├─╯  conflicted
+│ ○  wqnwkozp
+├─╯  other
+◆  zzzzzzzz root()
+";
+        let graph_heads = [
+            head("kxryzmor", false, false),
+            head("kxryzmor", false, false),
+            head("vruxwmqv", false, true),
+            head("vruxwmqv", false, true),
+            head("wqnwkozp", false, false),
+            head("wqnwkozp", false, false),
+            head("zzzzzzzz", true, false),
+        ];
+        let nodes = GraphNodes {
+            working_copy: Some("●".to_owned()),
+            immutable: Some("◇".to_owned()),
+            normal: None,
+            conflicted: Some("✗".to_owned()),
+        };
+
+        assert_eq!(
+            replace_graph_nodes(graph, &graph_heads, &nodes),
+            "\
+●  kxryzmor
+│  (no description set)
+│ ✗  vruxwmqv
+├─╯  conflicted
+│ ○  wqnwkozp
+├─╯  other
+◇  zzzzzzzz root()
+"
+        );
+    }
+
+    #[test]
+    fn replace_nodes_keeps_escape_codes() {
+        let nodes = GraphNodes {
+            normal: Some("*".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            replace_graph_nodes(
+                "| \x1b[1m\x1b[38;5;2mo\x1b[0m  \x1b[1mk\x1b[0m o\n",
+                &[head("k", false, false)],
+                &nodes
+            ),
+            "| \x1b[1m\x1b[38;5;2m*\x1b[0m  \x1b[1mk\x1b[0m o\n"
+        );
+    }
+}
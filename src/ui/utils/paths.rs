@@ -0,0 +1,272 @@
+use ratatui::text::{Line, Span};
+
+use crate::env::DiffFormat;
+
+const ELLIPSIS: &str = "…";
+
+// Split a path into components. Renames such as `{old => new}` may contain slashes, so are kept
+// as a single component
+fn split_components(path: &str) -> Vec<&str> {
+    let mut components = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in path.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            '/' if depth == 0 => {
+                components.push(&path[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    components.push(&path[start..]);
+    components
+}
+
+// Keep the end of the text, as the file name is the most useful part
+fn truncate_start(text: &str, width: usize) -> String {
+    let length = text.chars().count();
+    if length <= width {
+        text.to_owned()
+    } else if width == 0 {
+        String::new()
+    } else {
+        ELLIPSIS.to_owned() + &text.chars().skip(length - (width - 1)).collect::<String>()
+    }
+}
+
+/// Shorten a path to fit in a width, by replacing middle directories with `…`, such as
+/// `services/…/baz/handler.rs`. The first directory and as many of the last components as fit
+/// are kept. If even the file name doesn't fit, its start is cut.
+pub fn shorten_path(path: &str, width: usize) -> String {
+    if path.chars().count() <= width {
+        return path.to_owned();
+    }
+
+    let components = split_components(path);
+    if let Some((last, rest)) = components.split_last() {
+        if let Some((first, middle)) = rest.split_first() {
+            for keep in (0..middle.len()).rev() {
+                let candidate = [*first, ELLIPSIS]
+                    .into_iter()
+                    .chain(middle[middle.len() - keep..].iter().copied())
+                    .chain([*last])
+                    .collect::<Vec<&str>>()
+                    .join("/");
+                if candidate.chars().count() <= width {
+                    return candidate;
+                }
+            }
+
+            let candidate = format!("{ELLIPSIS}/{last}");
+            if candidate.chars().count() <= width {
+                return candidate;
+            }
+        }
+
+        return truncate_start(last, width);
+    }
+
+    truncate_start(path, width)
+}
+
+// Replace the characters from start to end of a line, keeping the style of the replaced text
+fn replace_chars<'a>(line: &Line<'a>, start: usize, end: usize, replacement: String) -> Line<'a> {
+    let mut spans = vec![];
+    let mut offset = 0;
+    let mut replacement = Some(replacement);
+    for span in &line.spans {
+        let length = span.content.chars().count();
+        let before: String = span
+            .content
+            .chars()
+            .take(start.saturating_sub(offset))
+            .collect();
+        let after: String = span
+            .content
+            .chars()
+            .skip(end.saturating_sub(offset))
+            .collect();
+
+        if !before.is_empty() {
+            spans.push(Span::styled(before, span.style));
+        }
+        if offset + length > start {
+            if let Some(replacement) = replacement.take() {
+                spans.push(Span::styled(replacement, span.style));
+            }
+        }
+        if !after.is_empty() {
+            spans.push(Span::styled(after, span.style));
+        }
+
+        offset += length;
+    }
+    if let Some(replacement) = replacement {
+        spans.push(Span::raw(replacement));
+    }
+
+    Line::from(spans).style(line.style)
+}
+
+/// Shorten paths of `--summary` and `--stat` diff output to fit in a width. Other formats are
+/// returned as is. `--stat` paths are padded to keep the graphs aligned.
+pub fn shorten_diff_paths<'a>(
+    lines: Vec<Line<'a>>,
+    diff_format: &DiffFormat,
+    width: usize,
+) -> Vec<Line<'a>> {
+    let line_text = |line: &Line| {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect::<String>()
+    };
+
+    match diff_format {
+        DiffFormat::Summary => lines
+            .into_iter()
+            .map(|line| {
+                let text = line_text(&line);
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(status), Some(' ')) if status.is_ascii_uppercase() => {
+                        let path = chars.as_str();
+                        let length = path.chars().count();
+                        replace_chars(
+                            &line,
+                            2,
+                            2 + length,
+                            shorten_path(path, width.saturating_sub(2)),
+                        )
+                    }
+                    _ => line,
+                }
+            })
+            .collect(),
+        DiffFormat::Stat => {
+            // Path column width, and the widest text after it
+            let columns: Vec<Option<(usize, usize)>> = lines
+                .iter()
+                .map(|line| {
+                    let text = line_text(line);
+                    text.find(" | ")
+                        .map(|index| (text[..index].chars().count(), text[index..].chars().count()))
+                })
+                .collect();
+            let Some(path_width) = columns.iter().flatten().map(|column| column.0).max() else {
+                return lines;
+            };
+            let rest_width = columns
+                .iter()
+                .flatten()
+                .map(|column| column.1)
+                .max()
+                .unwrap_or(0);
+            let target_width = width.saturating_sub(rest_width).min(path_width);
+
+            lines
+                .into_iter()
+                .zip(columns)
+                .map(|(line, column)| match column {
+                    Some((column_width, _)) => {
+                        let text = line_text(&line);
+                        let path: String = text.chars().take(column_width).collect();
+                        replace_chars(
+                            &line,
+                            0,
+                            column_width,
+                            format!(
+                                "{:target_width$}",
+                                shorten_path(path.trim_end(), target_width)
+                            ),
+                        )
+                    }
+                    None => line,
+                })
+                .collect()
+        }
+        _ => lines,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::{Color, Stylize};
+
+    #[test]
+    fn shorten_paths() {
+        let path = "services/api/v2/foo/baz/handler.rs";
+        assert_eq!(shorten_path(path, 100), path);
+        assert_eq!(shorten_path(path, path.len()), path);
+        assert_eq!(shorten_path(path, 30), "services/…/foo/baz/handler.rs");
+        assert_eq!(shorten_path(path, 25), "services/…/baz/handler.rs");
+        assert_eq!(shorten_path(path, 21), "services/…/handler.rs");
+        assert_eq!(shorten_path(path, 15), "…/handler.rs");
+    }
+
+    #[test]
+    fn shorten_paths_short_width() {
+        let path = "services/api/handler.rs";
+        assert_eq!(shorten_path(path, 8), "…dler.rs");
+        assert_eq!(shorten_path(path, 1), "…");
+        assert_eq!(shorten_path(path, 0), "");
+    }
+
+    #[test]
+    fn shorten_paths_single_component() {
+        assert_eq!(shorten_path("README.md", 9), "README.md");
+        assert_eq!(shorten_path("README.md", 6), "…ME.md");
+        assert_eq!(shorten_path("src/main.rs", 8), "main.rs");
+    }
+
+    #[test]
+    fn shorten_paths_renamed() {
+        let path = "src/ui/{old_tab.rs => new_tab.rs}";
+        assert_eq!(shorten_path(path, 32), "src/…/{old_tab.rs => new_tab.rs}");
+        assert_eq!(shorten_path(path, 30), "…/{old_tab.rs => new_tab.rs}");
+
+        // Slashes in a rename aren't split
+        let path = "{src/ui/old.rs => lib/new.rs}";
+        assert_eq!(shorten_path(path, 20), "…d.rs => lib/new.rs}");
+    }
+
+    #[test]
+    fn shorten_summary_paths() {
+        let lines = vec![
+            Line::raw("Commit ID: abc"),
+            Line::from("M services/api/v2/handler.rs".fg(Color::Cyan)),
+        ];
+        let lines = shorten_diff_paths(lines, &DiffFormat::Summary, 24);
+        assert_eq!(lines[0], Line::raw("Commit ID: abc"));
+        assert_eq!(
+            lines[1],
+            Line::from(vec![
+                "M ".fg(Color::Cyan),
+                "services/…/handler.rs".fg(Color::Cyan)
+            ])
+        );
+    }
+
+    #[test]
+    fn shorten_stat_paths() {
+        let lines = vec![
+            Line::raw("services/api/v2/handler.rs | 2 +-"),
+            Line::raw("README.md                  | 1 +"),
+            Line::raw("2 files changed, 2 insertions(+), 1 deletion(-)"),
+        ];
+        let lines = shorten_diff_paths(lines, &DiffFormat::Stat, 28);
+        let text: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        assert_eq!(
+            text,
+            vec![
+                "services/…/handler.rs | 2 +-".to_owned(),
+                format!("{:21} | 1 +", "README.md"),
+                "2 files changed, 2 insertions(+), 1 deletion(-)".to_owned(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::{commander::Commander, env::DiffFormat};
+
+/// Inputs of a tab's refresh besides the repository itself. A refresh with the same inputs at the
+/// same operation shows the same output, so can be skipped. New inputs of a refresh must be added
+/// here, or they won't be refreshed when only they changed.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct RefreshInputs {
+    pub revset: Option<String>,
+    pub diff_format: DiffFormat,
+    pub show_all: bool,
+    // Other inputs of the tab, such as a path filter or template
+    pub filters: Vec<Option<String>>,
+}
+
+impl RefreshInputs {
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Operation and input fingerprint of a tab's last refresh.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LastRefresh {
+    operation: String,
+    fingerprint: u64,
+}
+
+impl LastRefresh {
+    /// Record a refresh at the latest operation. `None` if the operation can't be read, so the
+    /// next refresh isn't skipped.
+    pub fn capture(commander: &mut Commander, inputs: &RefreshInputs) -> Option<Self> {
+        commander
+            .get_operation_id()
+            .ok()
+            .map(|operation| Self::new(operation, inputs))
+    }
+
+    fn new(operation: String, inputs: &RefreshInputs) -> Self {
+        Self {
+            operation,
+            fingerprint: inputs.fingerprint(),
+        }
+    }
+
+    /// Whether a refresh would show the same as the last one. Reading the operation snapshots the
+    /// working copy, so edited files count as a change.
+    pub fn is_up_to_date(
+        last_refresh: Option<&LastRefresh>,
+        commander: &mut Commander,
+        inputs: &RefreshInputs,
+    ) -> bool {
+        let Some(last_refresh) = last_refresh else {
+            return false;
+        };
+        commander
+            .get_operation_id()
+            .is_ok_and(|operation| *last_refresh == Self::new(operation, inputs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refresh_fingerprints() {
+        let inputs = RefreshInputs {
+            revset: Some("::@".to_owned()),
+            diff_format: DiffFormat::ColorWords,
+            show_all: false,
+            filters: vec![Some("src".to_owned()), None],
+        };
+        assert_eq!(inputs.fingerprint(), inputs.clone().fingerprint());
+        let last_refresh = LastRefresh::new("a1".to_owned(), &inputs);
+        assert_eq!(last_refresh, LastRefresh::new("a1".to_owned(), &inputs));
+        assert_ne!(last_refresh, LastRefresh::new("b2".to_owned(), &inputs));
+
+        // Changing any input changes the fingerprint
+        for changed in [
+            RefreshInputs {
+                revset: None,
+                ..inputs.clone()
+            },
+            RefreshInputs {
+                diff_format: DiffFormat::Git,
+                ..inputs.clone()
+            },
+            RefreshInputs {
+                show_all: true,
+                ..inputs.clone()
+            },
+            RefreshInputs {
+                filters: vec![None, Some("src".to_owned())],
+                ..inputs.clone()
+            },
+        ] {
+            assert_ne!(inputs.fingerprint(), changed.fingerprint(), "{changed:?}");
+            assert_ne!(last_refresh, LastRefresh::new("a1".to_owned(), &changed));
+        }
+    }
+}
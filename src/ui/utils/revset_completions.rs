@@ -0,0 +1,223 @@
+use ratatui::layout::Rect;
+
+/// Revset functions known by jj, with a hint of their expected arguments.
+/// Functions without arguments have an empty hint.
+pub const REVSET_FUNCTIONS: &[(&str, &str)] = &[
+    ("all", ""),
+    ("ancestors", "x, [depth]"),
+    ("at_operation", "op, x"),
+    ("author", "pattern"),
+    ("author_date", "pattern"),
+    ("bookmarks", "[pattern]"),
+    ("children", "x"),
+    ("coalesce", "revsets..."),
+    ("committer", "pattern"),
+    ("committer_date", "pattern"),
+    ("conflicts", ""),
+    ("connected", "x"),
+    ("descendants", "x, [depth]"),
+    ("description", "pattern"),
+    ("diff_contains", "text, [files]"),
+    ("empty", ""),
+    ("files", "expression"),
+    ("fork_point", "x"),
+    ("git_head", ""),
+    ("git_refs", ""),
+    ("heads", "x"),
+    ("immutable", ""),
+    ("immutable_heads", ""),
+    ("latest", "x, [count]"),
+    ("merges", ""),
+    ("mine", ""),
+    ("mutable", ""),
+    ("none", ""),
+    ("parents", "x"),
+    ("present", "x"),
+    ("reachable", "srcs, domain"),
+    (
+        "remote_bookmarks",
+        "[bookmark_pattern], [[remote=]remote_pattern]",
+    ),
+    ("root", ""),
+    ("roots", "x"),
+    ("subject", "pattern"),
+    ("tags", "[pattern]"),
+    (
+        "tracked_remote_bookmarks",
+        "[bookmark_pattern], [[remote=]remote_pattern]",
+    ),
+    ("trunk", ""),
+    (
+        "untracked_remote_bookmarks",
+        "[bookmark_pattern], [[remote=]remote_pattern]",
+    ),
+    ("visible_heads", ""),
+    ("working_copies", ""),
+];
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Get the identifier being typed before the cursor. The cursor is a character index in the line.
+pub fn get_word_before_cursor(line: &str, cursor: usize) -> &str {
+    let before: &str = match line.char_indices().nth(cursor) {
+        Some((index, _)) => &line[..index],
+        None => line,
+    };
+    let start = before
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_identifier_char(*c))
+        .last()
+        .map_or(before.len(), |(index, _)| index);
+    &before[start..]
+}
+
+/// Get revset functions starting with the word.
+pub fn get_completions(word: &str) -> Vec<(&'static str, &'static str)> {
+    if word.is_empty() {
+        return vec![];
+    }
+
+    REVSET_FUNCTIONS
+        .iter()
+        .filter(|(name, _)| name.starts_with(word))
+        .copied()
+        .collect()
+}
+
+/// Get text to insert after the word to complete it. A single match is completed with its
+/// parentheses, otherwise the longest common prefix of the matches is completed.
+pub fn complete(word: &str) -> Option<String> {
+    let completions = get_completions(word);
+    let completion = match completions.as_slice() {
+        [] => return None,
+        [(name, args)] => {
+            if args.is_empty() {
+                format!("{name}()")
+            } else {
+                format!("{name}(")
+            }
+        }
+        [(first, _), rest @ ..] => {
+            let mut prefix = first.to_string();
+            for (name, _) in rest {
+                while !name.starts_with(&prefix) {
+                    prefix.pop();
+                }
+            }
+            prefix
+        }
+    };
+
+    let completion = completion[word.len()..].to_owned();
+    if completion.is_empty() {
+        None
+    } else {
+        Some(completion)
+    }
+}
+
+/// Get the function whose arguments are being typed at the cursor, with its argument hint.
+pub fn get_argument_hint(line: &str, cursor: usize) -> Option<(&'static str, &'static str)> {
+    let before: String = line.chars().take(cursor).collect();
+
+    // Find the innermost unclosed parenthesis
+    let mut depth = 0;
+    let open_index = before.char_indices().rev().find_map(|(index, c)| {
+        match c {
+            ')' => depth += 1,
+            '(' if depth == 0 => return Some(index),
+            '(' => depth -= 1,
+            _ => {}
+        }
+        None
+    })?;
+
+    let name = get_word_before_cursor(&before[..open_index], before[..open_index].chars().count());
+    REVSET_FUNCTIONS
+        .iter()
+        .find(|(function, args)| *function == name && !args.is_empty())
+        .copied()
+}
+
+/// Get the area of a dropdown shown below the cursor, kept within the bounds.
+pub fn dropdown_rect(cursor_x: u16, cursor_y: u16, width: u16, height: u16, bounds: Rect) -> Rect {
+    let width = width.min(bounds.width);
+    let height = height.min(bounds.height);
+
+    let x = cursor_x
+        .min(bounds.right().saturating_sub(width))
+        .max(bounds.x);
+    // Show above the cursor if there isn't enough space below
+    let y = if cursor_y.saturating_add(1).saturating_add(height) <= bounds.bottom() {
+        cursor_y + 1
+    } else {
+        cursor_y.saturating_sub(height).max(bounds.y)
+    };
+
+    Rect::new(x, y, width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_before_cursor() {
+        assert_eq!(get_word_before_cursor("trunk", 5), "trunk");
+        assert_eq!(get_word_before_cursor("@ | tru", 7), "tru");
+        assert_eq!(get_word_before_cursor("@ | tru", 5), "t");
+        assert_eq!(get_word_before_cursor("heads(", 6), "");
+        assert_eq!(get_word_before_cursor("", 0), "");
+    }
+
+    #[test]
+    fn complete_functions() {
+        assert_eq!(complete("trunk"), Some("()".to_owned()));
+        assert_eq!(complete("tru"), Some("nk()".to_owned()));
+        assert_eq!(complete("descr"), Some("iption(".to_owned()));
+        // Common prefix of `descendants` and `description` is already typed
+        assert_eq!(complete("desc"), None);
+        assert_eq!(complete("descendants"), Some("(".to_owned()));
+        // Common prefix of `author` and `author_date`
+        assert_eq!(complete("au"), Some("thor".to_owned()));
+        assert_eq!(complete("author"), None);
+        assert_eq!(complete("xyz"), None);
+        assert_eq!(complete(""), None);
+    }
+
+    #[test]
+    fn argument_hint() {
+        assert_eq!(
+            get_argument_hint("description(", 12),
+            Some(("description", "pattern"))
+        );
+        assert_eq!(
+            get_argument_hint("@ | ancestors(trunk(), 2", 24),
+            Some(("ancestors", "x, [depth]"))
+        );
+        assert_eq!(
+            get_argument_hint("heads(trunk(", 12),
+            None,
+            "functions without arguments have no hint"
+        );
+        assert_eq!(get_argument_hint("description(x)", 14), None);
+        assert_eq!(get_argument_hint("description", 11), None);
+    }
+
+    #[test]
+    fn dropdown_within_bounds() {
+        let bounds = Rect::new(0, 0, 80, 24);
+
+        assert_eq!(dropdown_rect(10, 5, 20, 6, bounds), Rect::new(10, 6, 20, 6));
+        // Shifted left at the right edge
+        assert_eq!(dropdown_rect(70, 5, 20, 6, bounds), Rect::new(60, 6, 20, 6));
+        // Shown above at the bottom edge
+        assert_eq!(
+            dropdown_rect(10, 20, 20, 6, bounds),
+            Rect::new(10, 14, 20, 6)
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use std::sync::LazyLock;
+
+use ratatui::{
+    style::{Color, Stylize},
+    text::{Line, Text},
+};
+use regex::Regex;
+
+// Regex to parse hunk headers, such as `@@ -1,2 +1,3 @@`
+static HUNK_HEADER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^@@ -(\d+)(?:,\d+)? \+(\d+)(?:,\d+)? @@").unwrap());
+
+/// Git diff split into left (old) and right (new) columns. Both columns always have the same
+/// number of lines, so they can be scrolled together.
+pub struct SideBySideDiff {
+    pub left: Text<'static>,
+    pub right: Text<'static>,
+}
+
+impl SideBySideDiff {
+    /// Parse a `--git` diff without color. Lines outside of diffs, such as the commit details from
+    /// `jj show`, are shown in the left column.
+    pub fn new(diff: &str) -> Self {
+        let mut side_by_side = Self {
+            left: Text::default(),
+            right: Text::default(),
+        };
+
+        let mut in_file_header = false;
+        // Line numbers of the next old and new lines, when in a hunk
+        let mut line_numbers: Option<(usize, usize)> = None;
+        let mut removed: Vec<&str> = vec![];
+        let mut added: Vec<&str> = vec![];
+
+        for line in diff.lines() {
+            if let Some((old_line_number, new_line_number)) = line_numbers.as_mut() {
+                if let Some(text) = line.strip_prefix('-') {
+                    removed.push(text);
+                    continue;
+                }
+                if let Some(text) = line.strip_prefix('+') {
+                    added.push(text);
+                    continue;
+                }
+
+                side_by_side.push_changes(
+                    old_line_number,
+                    new_line_number,
+                    &mut removed,
+                    &mut added,
+                );
+
+                if let Some(text) = line.strip_prefix(' ').or(line.is_empty().then_some("")) {
+                    side_by_side.push_row(
+                        Line::raw(format!("{:>4} {}", old_line_number, text)),
+                        Line::raw(format!("{:>4} {}", new_line_number, text)),
+                    );
+                    *old_line_number += 1;
+                    *new_line_number += 1;
+                    continue;
+                }
+                if line.starts_with('\\') {
+                    // No newline at end of file
+                    let line = Line::raw(line.to_owned()).fg(Color::DarkGray);
+                    side_by_side.push_row(line.clone(), line);
+                    continue;
+                }
+
+                line_numbers = None;
+            }
+
+            if let Some(captured) = HUNK_HEADER_REGEX.captures(line) {
+                in_file_header = false;
+                line_numbers = Some((
+                    captured[1].parse().unwrap_or(1),
+                    captured[2].parse().unwrap_or(1),
+                ));
+                let line = Line::raw(line.to_owned()).fg(Color::Cyan);
+                side_by_side.push_row(line.clone(), line);
+            } else if line.starts_with("diff --git ") {
+                in_file_header = true;
+                let line = Line::raw(line.to_owned()).fg(Color::Yellow).bold();
+                side_by_side.push_row(line.clone(), line);
+            } else if in_file_header {
+                let line = Line::raw(line.to_owned()).bold();
+                side_by_side.push_row(line.clone(), line);
+            } else {
+                side_by_side.push_row(Line::raw(line.to_owned()), Line::default());
+            }
+        }
+
+        if let Some((old_line_number, new_line_number)) = line_numbers.as_mut() {
+            side_by_side.push_changes(old_line_number, new_line_number, &mut removed, &mut added);
+        }
+
+        side_by_side
+    }
+
+    fn push_row(&mut self, left: Line<'static>, right: Line<'static>) {
+        self.left.lines.push(left);
+        self.right.lines.push(right);
+    }
+
+    // Push removed and added lines, pairing them up by position
+    fn push_changes(
+        &mut self,
+        old_line_number: &mut usize,
+        new_line_number: &mut usize,
+        removed: &mut Vec<&str>,
+        added: &mut Vec<&str>,
+    ) {
+        for i in 0..removed.len().max(added.len()) {
+            let left = match removed.get(i) {
+                Some(text) => {
+                    *old_line_number += 1;
+                    Line::raw(format!("{:>4} {}", *old_line_number - 1, text)).fg(Color::Red)
+                }
+                None => Line::default(),
+            };
+            let right = match added.get(i) {
+                Some(text) => {
+                    *new_line_number += 1;
+                    Line::raw(format!("{:>4} {}", *new_line_number - 1, text)).fg(Color::Green)
+                }
+                None => Line::default(),
+            };
+            self.push_row(left, right);
+        }
+
+        removed.clear();
+        added.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_strings(text: &Text) -> Vec<String> {
+        text.lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn side_by_side_diff() {
+        let diff = "\
+Commit ID: abc
+diff --git a/README b/README
+index 1111111..2222222 100644
+--- a/README
++++ b/README
+@@ -1,4 +1,4 @@
+ first
+-old 1
+-old 2
++new 1
+ last
++added
+\\ No newline at end of file";
+
+        let side_by_side = SideBySideDiff::new(diff);
+
+        assert_eq!(
+            to_strings(&side_by_side.left),
+            [
+                "Commit ID: abc",
+                "diff --git a/README b/README",
+                "index 1111111..2222222 100644",
+                "--- a/README",
+                "+++ b/README",
+                "@@ -1,4 +1,4 @@",
+                "   1 first",
+                "   2 old 1",
+                "   3 old 2",
+                "   4 last",
+                "",
+                "\\ No newline at end of file",
+            ]
+        );
+        assert_eq!(
+            to_strings(&side_by_side.right),
+            [
+                "",
+                "diff --git a/README b/README",
+                "index 1111111..2222222 100644",
+                "--- a/README",
+                "+++ b/README",
+                "@@ -1,4 +1,4 @@",
+                "   1 first",
+                "   2 new 1",
+                "",
+                "   3 last",
+                "   4 added",
+                "\\ No newline at end of file",
+            ]
+        );
+    }
+
+    #[test]
+    fn side_by_side_diff_multiple_hunks() {
+        let diff = "\
+diff --git a/a b/a
+@@ -1 +1 @@
+-a
++b
+@@ -10,0 +11 @@
++c";
+
+        let side_by_side = SideBySideDiff::new(diff);
+
+        assert_eq!(
+            to_strings(&side_by_side.left),
+            [
+                "diff --git a/a b/a",
+                "@@ -1 +1 @@",
+                "   1 a",
+                "@@ -10,0 +11 @@",
+                ""
+            ]
+        );
+        assert_eq!(
+            to_strings(&side_by_side.right),
+            [
+                "diff --git a/a b/a",
+                "@@ -1 +1 @@",
+                "   1 b",
+                "@@ -10,0 +11 @@",
+                "  11 c"
+            ]
+        );
+    }
+}
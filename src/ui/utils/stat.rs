@@ -0,0 +1,282 @@
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+
+// Narrowest graph drawn, so counts stay readable in a narrow panel. Wider lines can be scrolled
+const MIN_GRAPH_WIDTH: usize = 5;
+
+/// A file line of `--stat` output, such as `src/main.rs | 12 +++++++---`.
+#[derive(Debug, PartialEq)]
+pub struct StatLine {
+    /// Characters before the graph: the padded path, separator and count
+    pub prefix_width: usize,
+    /// Changed lines, as shown by the count
+    pub count: usize,
+    /// `+` and `-` of the graph. They can be scaled down from the count
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+/// Parse a file line of `--stat` output. Lines without a count, such as the totals line or binary
+/// files, aren't parsed.
+pub fn parse_stat_line(text: &str) -> Option<StatLine> {
+    let index = text.rfind(" | ")?;
+    let rest = &text[index + 3..];
+    let count_text = rest.trim_start();
+    let digits = count_text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(count_text.len());
+    let count = count_text[..digits].parse().ok()?;
+    let graph = count_text[digits..].strip_prefix(' ').unwrap_or("");
+    if !count_text[digits..].is_empty()
+        && (graph.is_empty() || !graph.chars().all(|c| c == '+' || c == '-'))
+    {
+        return None;
+    }
+
+    Some(StatLine {
+        prefix_width: text[..index + 3].chars().count() + rest.len() - count_text.len() + digits,
+        count,
+        additions: graph.chars().filter(|c| *c == '+').count(),
+        deletions: graph.chars().filter(|c| *c == '-').count(),
+    })
+}
+
+// Length of a graph and how many of it are `+`, to fit a count in a width where the largest
+// count fills it. Changed files always get at least one character
+fn graph_lengths(stat: &StatLine, max_count: usize, width: usize) -> (usize, usize) {
+    let length = if max_count <= width {
+        stat.count
+    } else {
+        (stat.count * width / max_count).max(usize::from(stat.count > 0))
+    };
+    let total = stat.additions + stat.deletions;
+    if total == 0 {
+        return (length, 0);
+    }
+
+    let mut additions = (length * stat.additions + total / 2) / total;
+    if stat.additions > 0 && additions == 0 {
+        additions = 1;
+    }
+    if stat.deletions > 0 && additions == length && length > 1 {
+        additions = length - 1;
+    }
+    (length, additions.min(length))
+}
+
+// First characters of a line, keeping their style
+fn take_chars<'a>(line: &Line<'a>, width: usize) -> Vec<Span<'a>> {
+    let mut spans = vec![];
+    let mut remaining = width;
+    for span in &line.spans {
+        if remaining == 0 {
+            break;
+        }
+        let length = span.content.chars().count();
+        if length <= remaining {
+            spans.push(span.clone());
+            remaining -= length;
+        } else {
+            spans.push(Span::styled(
+                span.content.chars().take(remaining).collect::<String>(),
+                span.style,
+            ));
+            remaining = 0;
+        }
+    }
+    spans
+}
+
+// Style of the first character of a line which is `c`, after the graph start
+fn graph_style(line: &Line, start: usize, c: char, default: Color) -> Style {
+    let mut offset = 0;
+    for span in &line.spans {
+        let length = span.content.chars().count();
+        if offset + length > start
+            && span
+                .content
+                .chars()
+                .skip(start.saturating_sub(offset))
+                .any(|span_char| span_char == c)
+        {
+            return span.style;
+        }
+        offset += length;
+    }
+    Style::default().fg(default)
+}
+
+/// Redraw the graphs of `--stat` output to fill a width, as jj draws them for the terminal width
+/// instead of the panel's. The largest change fills the width, and others are scaled to it. Other
+/// lines, such as the totals line or binary files, are kept as is.
+pub fn fit_stat_graphs<'a>(lines: Vec<Line<'a>>, width: usize) -> Vec<Line<'a>> {
+    let stats: Vec<Option<StatLine>> = lines
+        .iter()
+        .map(|line| {
+            let text: String = line
+                .spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect();
+            parse_stat_line(&text)
+        })
+        .collect();
+    let Some(prefix_width) = stats.iter().flatten().map(|stat| stat.prefix_width).max() else {
+        return lines;
+    };
+    let max_count = stats
+        .iter()
+        .flatten()
+        .map(|stat| stat.count)
+        .max()
+        .unwrap_or(0);
+    let graph_width = width.saturating_sub(prefix_width + 1).max(MIN_GRAPH_WIDTH);
+
+    lines
+        .into_iter()
+        .zip(stats)
+        .map(|(line, stat)| match stat {
+            Some(stat) => {
+                let (length, additions) = graph_lengths(&stat, max_count, graph_width);
+                let mut spans = take_chars(&line, stat.prefix_width);
+                if length > 0 {
+                    spans.push(Span::raw(" "));
+                }
+                if additions > 0 {
+                    spans.push(Span::styled(
+                        "+".repeat(additions),
+                        graph_style(&line, stat.prefix_width, '+', Color::Green),
+                    ));
+                }
+                if length > additions {
+                    spans.push(Span::styled(
+                        "-".repeat(length - additions),
+                        graph_style(&line, stat.prefix_width, '-', Color::Red),
+                    ));
+                }
+                Line::from(spans).style(line.style)
+            }
+            None => line,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Stylize;
+
+    // Captured from `jj diff --stat` in an 80 column terminal
+    const STAT: &str = "\
+src/ui/log_tab.rs                  | 120 +++++++++++++++++++++++++++-------------
+src/{old_name.rs => new_name.rs}   |   4 ++--
+assets/logo.png                    | Bin 0 -> 2048 bytes
+src/empty.rs                       |   0
+3 files changed, 84 insertions(+), 40 deletions(-)";
+
+    fn stat_lines() -> Vec<Line<'static>> {
+        STAT.lines()
+            .map(|line| Line::raw(line.to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_stat_lines() {
+        let lines: Vec<Option<StatLine>> = STAT.lines().map(parse_stat_line).collect();
+        assert_eq!(
+            lines[0],
+            Some(StatLine {
+                prefix_width: 40,
+                count: 120,
+                additions: 27,
+                deletions: 13,
+            })
+        );
+        assert_eq!(
+            lines[1],
+            Some(StatLine {
+                prefix_width: 40,
+                count: 4,
+                additions: 2,
+                deletions: 2,
+            })
+        );
+        assert_eq!(lines[2], None);
+        assert_eq!(
+            lines[3],
+            Some(StatLine {
+                prefix_width: 40,
+                count: 0,
+                additions: 0,
+                deletions: 0,
+            })
+        );
+        assert_eq!(lines[4], None);
+
+        assert_eq!(parse_stat_line("Commit ID: abc"), None);
+        assert_eq!(parse_stat_line("a | b"), None);
+    }
+
+    #[test]
+    fn fit_stat_graphs_to_width() {
+        let lines = fit_stat_graphs(stat_lines(), 51);
+        let text: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        assert_eq!(
+            text,
+            [
+                "src/ui/log_tab.rs                  | 120 +++++++---",
+                "src/{old_name.rs => new_name.rs}   |   4 +",
+                "assets/logo.png                    | Bin 0 -> 2048 bytes",
+                "src/empty.rs                       |   0",
+                "3 files changed, 84 insertions(+), 40 deletions(-)",
+            ]
+        );
+
+        // Wide enough for every changed line
+        let lines = fit_stat_graphs(stat_lines(), 200);
+        assert_eq!(lines[0].to_string().chars().count(), 41 + 120);
+        assert_eq!(
+            lines[1].to_string(),
+            "src/{old_name.rs => new_name.rs}   |   4 ++--"
+        );
+
+        // Too narrow, so the graphs keep a minimum width
+        let lines = fit_stat_graphs(stat_lines(), 20);
+        assert_eq!(
+            lines[0].to_string(),
+            "src/ui/log_tab.rs                  | 120 +++--"
+        );
+        assert_eq!(
+            lines[1].to_string(),
+            "src/{old_name.rs => new_name.rs}   |   4 +"
+        );
+    }
+
+    #[test]
+    fn fit_stat_graphs_keeps_colors() {
+        let lines = vec![
+            Line::from(vec![
+                "README.md | 3 ".into(),
+                "++".fg(Color::LightGreen),
+                "-".fg(Color::LightRed),
+            ]),
+            Line::raw("1 file changed, 2 insertions(+), 1 deletion(-)"),
+        ];
+        let lines = fit_stat_graphs(lines, 80);
+        assert_eq!(
+            lines[0],
+            Line::from(vec![
+                "README.md | 3".into(),
+                " ".into(),
+                "++".fg(Color::LightGreen),
+                "-".fg(Color::LightRed),
+            ])
+        );
+        assert_eq!(
+            lines[1],
+            Line::raw("1 file changed, 2 insertions(+), 1 deletion(-)")
+        );
+    }
+}
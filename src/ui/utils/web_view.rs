@@ -0,0 +1,89 @@
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+
+/// Fill a URL template from `lazyjj.web-view-url-templates`, replacing `{commit_id}`,
+/// `{change_id}` and `{bookmark}`. `None` if the template uses `{bookmark}` and the change has no
+/// bookmark.
+pub fn expand_url_template(
+    template: &str,
+    commit_id: &str,
+    change_id: &str,
+    bookmark: Option<&str>,
+) -> Option<String> {
+    let url = template
+        .replace("{commit_id}", commit_id)
+        .replace("{change_id}", change_id);
+    match bookmark {
+        Some(bookmark) => Some(url.replace("{bookmark}", bookmark)),
+        None if url.contains("{bookmark}") => None,
+        None => Some(url),
+    }
+}
+
+/// Open a URL in the default browser, without waiting for it.
+pub fn open_url(url: &str) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("open");
+        command.arg(url);
+        command
+    };
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", "", url]);
+        command
+    };
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let mut command = {
+        let mut command = Command::new("xdg-open");
+        command.arg(url);
+        command
+    };
+
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to open {url}"))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_url_templates() {
+        assert_eq!(
+            expand_url_template(
+                "https://github.com/owner/repo/commit/{commit_id}",
+                "0123abcd",
+                "kxryzmor",
+                None
+            ),
+            Some("https://github.com/owner/repo/commit/0123abcd".to_owned())
+        );
+        assert_eq!(
+            expand_url_template(
+                "https://example.com/{change_id}/{bookmark}",
+                "0123abcd",
+                "kxryzmor",
+                Some("main")
+            ),
+            Some("https://example.com/kxryzmor/main".to_owned())
+        );
+        assert_eq!(
+            expand_url_template(
+                "https://github.com/owner/repo/tree/{bookmark}",
+                "0123abcd",
+                "kxryzmor",
+                None
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph},
+    Frame,
+};
+
+use crate::{
+    commander::Commander,
+    env::Config,
+    ui::{
+        message_popup::MessagePopup,
+        styles::{border_set, create_popup_block},
+        utils::{centered_rect_line_height, web_view::open_url},
+        Component, ComponentAction,
+    },
+    ComponentInputResult,
+};
+
+/// Open a URL, with a popup on failure.
+pub fn open_web_view(url: &str) -> ComponentAction {
+    match open_url(url) {
+        Ok(()) => ComponentAction::SetPopup(None),
+        Err(err) => ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+            title: "Open in browser".into(),
+            messages: format!("{err:?}").into(),
+        }))),
+    }
+}
+
+/// Popup to pick which web view to open the selected change in, when several URL templates are
+/// configured in `lazyjj.web-view-url-templates`.
+pub struct WebViewPopup {
+    urls: Vec<String>,
+    list_state: ListState,
+    config: Config,
+}
+
+impl WebViewPopup {
+    pub fn new(urls: Vec<String>, config: Config) -> Self {
+        Self {
+            urls,
+            list_state: ListState::default().with_selected(Some(0)),
+            config,
+        }
+    }
+
+    fn scroll(&mut self, scroll: isize) {
+        self.list_state.select(Some(
+            self.list_state
+                .selected()
+                .map(|selected| selected.saturating_add_signed(scroll))
+                .unwrap_or(0)
+                .min(self.urls.len().saturating_sub(1)),
+        ));
+    }
+}
+
+impl Component for WebViewPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let block = create_popup_block("Open in browser");
+        let area = centered_rect_line_height(area, 60, self.urls.len() as u16 + 4);
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Fill(1), Constraint::Length(2)])
+            .split(block.inner(area));
+
+        let list = List::new(self.urls.iter().map(|url| Line::raw(url.clone())))
+            .highlight_style(Style::default().bg(self.config.highlight_color()));
+        f.render_stateful_widget(list, popup_chunks[0], &mut self.list_state);
+
+        let help = Paragraph::new(vec!["j/k: select | Enter: open | Escape: cancel".into()])
+            .fg(Color::DarkGray)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .border_set(border_set())
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            );
+        f.render_widget(help, popup_chunks[1]);
+
+        Ok(())
+    }
+
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => self.scroll(1),
+                KeyCode::Char('k') | KeyCode::Up => self.scroll(-1),
+                KeyCode::Enter => {
+                    if let Some(url) = self
+                        .list_state
+                        .selected()
+                        .and_then(|selected| self.urls.get(selected))
+                    {
+                        return Ok(ComponentInputResult::HandledAction(open_web_view(url)));
+                    }
+                }
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+}